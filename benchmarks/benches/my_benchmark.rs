@@ -8,6 +8,7 @@ extern crate tempfile;
 
 use self::tempfile::tempdir;
 use bencher::Bencher;
+use holochain_core_types::kv::EavKvStorage;
 use holochain_persistence_file::eav::file::EavFileStorage;
 
 use holochain_persistence_api::cas::{content::ExampleAddressableContent, storage::EavTestSuite};
@@ -75,6 +76,26 @@ fn bench_pickle_eav_many_to_one(b: &mut Bencher) {
     })
 }
 
+fn bench_kv_eav_one_to_many(b: &mut Bencher) {
+    b.iter(|| {
+        let temp = tempdir().expect("test was supposed to create temp dir");
+        let eav_storage = EavKvStorage::open(temp.path()).unwrap();
+        EavTestSuite::test_one_to_many::<ExampleAddressableContent, EavKvStorage>(
+            eav_storage.clone(),
+        )
+    })
+}
+
+fn bench_kv_eav_many_to_one(b: &mut Bencher) {
+    b.iter(|| {
+        let temp = tempdir().expect("test was supposed to create temp dir");
+        let eav_storage = EavKvStorage::open(temp.path()).unwrap();
+        EavTestSuite::test_many_to_one::<ExampleAddressableContent, EavKvStorage>(
+            eav_storage.clone(),
+        )
+    })
+}
+
 benchmark_group!(
     benches,
     bench_memory_eav_many_to_one,
@@ -82,6 +103,8 @@ benchmark_group!(
     bench_file_eav_one_to_many,
     bench_file_eav_many_to_one,
     bench_pickle_eav_many_to_one,
-    bench_pickle_eav_one_to_many
+    bench_pickle_eav_one_to_many,
+    bench_kv_eav_one_to_many,
+    bench_kv_eav_many_to_one
 );
 benchmark_main!(benches);
@@ -0,0 +1,191 @@
+use crate::error::DefaultResult;
+use colored::*;
+use holochain_common::paths::DNA_EXTENSION;
+use holochain_conductor_api::{
+    conductor::Conductor,
+    happ_bundle::{resolve_bundle_path, HappBundle, HAPP_MANIFEST_FILE_NAME},
+};
+use holochain_persistence_api::cas::content::AddressableContent;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+pub const HAPP_BUNDLE_EXTENSION: &str = "happ";
+pub const BUNDLE_DNA_DIR_NAME: &str = "dnas";
+pub const BUNDLE_UI_DIR_NAME: &str = "ui";
+
+/// Builds a hApp bundle directory from the given source manifest.
+///
+/// All DNA files and UI directories the manifest references get copied into the bundle,
+/// DNA hashes get calculated (and checked against the ones given in the source manifest,
+/// if any) and the resulting manifest with paths relative to the bundle gets written
+/// next to them. The bundle can then be installed with the `admin/happ/install`
+/// conductor admin function.
+pub fn bundle(manifest_path: &PathBuf, output: Option<PathBuf>) -> DefaultResult<PathBuf> {
+    let mut manifest = HappBundle::from_file(manifest_path)?;
+    manifest
+        .check_consistency()
+        .map_err(|e| format_err!("{}", e))?;
+    let source_dir = manifest_path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_else(PathBuf::new);
+
+    let output = match output {
+        Some(output) => output,
+        None => crate::util::get_dist_path(&source_dir)?
+            .join(format!("{}.{}", manifest.name, HAPP_BUNDLE_EXTENSION)),
+    };
+    let dna_dir = output.join(BUNDLE_DNA_DIR_NAME);
+    fs::create_dir_all(&dna_dir)
+        .map_err(|e| format_err!("Couldn't create path {:?}; {}", dna_dir, e))?;
+
+    for dna in manifest.dnas.iter_mut() {
+        let source = resolve_bundle_path(&source_dir, &dna.file);
+        let loaded = Conductor::load_dna(&source)
+            .map_err(|e| format_err!("Couldn't load DNA {:?}: {}", source, e))?;
+        let hash = loaded.address().to_string();
+        if let Some(ref expected) = dna.hash {
+            ensure!(
+                *expected == hash,
+                "Hash of DNA \"{}\" is {} but the manifest expects {}",
+                dna.id,
+                hash,
+                expected
+            );
+        }
+        dna.hash = Some(hash);
+
+        let file_name = format!("{}.{}", dna.id, DNA_EXTENSION);
+        fs::copy(&source, dna_dir.join(&file_name))?;
+        dna.file = format!("{}/{}", BUNDLE_DNA_DIR_NAME, file_name);
+    }
+
+    for ui in manifest.uis.iter_mut() {
+        let source = resolve_bundle_path(&source_dir, &ui.root_dir);
+        let relative_dest = format!("{}/{}", BUNDLE_UI_DIR_NAME, ui.id);
+        copy_dir(&source, &output.join(&relative_dest))?;
+        ui.root_dir = relative_dest;
+    }
+
+    let mut file = File::create(output.join(HAPP_MANIFEST_FILE_NAME))?;
+    file.write_all(manifest.to_toml()?.as_bytes())?;
+
+    // CLI feedback
+    println!(
+        "{} hApp bundle \"{}\" at {:?}",
+        "Created".green().bold(),
+        manifest.name,
+        output
+    );
+
+    Ok(output)
+}
+
+fn copy_dir(source: &Path, dest: &Path) -> DefaultResult<()> {
+    ensure!(source.is_dir(), "UI directory {:?} does not exist", source);
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cli::init::tests::gen_dir;
+    use holochain_core_types::dna::Dna;
+    use serde_json;
+
+    #[test]
+    fn bundle_copies_files_and_fills_in_hashes() {
+        let dir = gen_dir();
+        let source_dir = dir.path().to_path_buf();
+
+        let dna = Dna::new();
+        fs::create_dir_all(source_dir.join("dist")).unwrap();
+        let dna_file = File::create(source_dir.join("dist").join("app.dna.json")).unwrap();
+        serde_json::to_writer_pretty(&dna_file, &dna).unwrap();
+
+        fs::create_dir_all(source_dir.join("ui").join("js")).unwrap();
+        File::create(source_dir.join("ui").join("index.html")).unwrap();
+        File::create(source_dir.join("ui").join("js").join("app.js")).unwrap();
+
+        let manifest_path = source_dir.join(HAPP_MANIFEST_FILE_NAME);
+        let mut manifest_file = File::create(&manifest_path).unwrap();
+        manifest_file
+            .write_all(
+                br#"name = 'my-happ'
+
+[[dnas]]
+id = 'app'
+file = 'dist/app.dna.json'
+
+[[instances]]
+id = 'app-instance'
+dna = 'app'
+agent = 'agent-1'
+
+[[uis]]
+id = 'app-ui'
+root_dir = 'ui'
+"#,
+            )
+            .unwrap();
+
+        let output = bundle(&manifest_path, None).unwrap();
+        assert_eq!(output, source_dir.join("dist").join("my-happ.happ"));
+        assert!(output.join("dnas").join("app.dna.json").is_file());
+        assert!(output
+            .join("ui")
+            .join("app-ui")
+            .join("index.html")
+            .is_file());
+        assert!(output
+            .join("ui")
+            .join("app-ui")
+            .join("js")
+            .join("app.js")
+            .is_file());
+
+        let built = HappBundle::from_file(&output.join(HAPP_MANIFEST_FILE_NAME)).unwrap();
+        assert_eq!(built.dnas[0].hash, Some(dna.address().to_string()));
+        assert_eq!(built.dnas[0].file, "dnas/app.dna.json");
+        assert_eq!(built.uis[0].root_dir, "ui/app-ui");
+        assert_eq!(built.instances[0].agent, "agent-1");
+    }
+
+    #[test]
+    fn bundle_fails_on_hash_mismatch() {
+        let dir = gen_dir();
+        let source_dir = dir.path().to_path_buf();
+
+        let dna_file = File::create(source_dir.join("app.dna.json")).unwrap();
+        serde_json::to_writer_pretty(&dna_file, &Dna::new()).unwrap();
+
+        let manifest_path = source_dir.join(HAPP_MANIFEST_FILE_NAME);
+        let mut manifest_file = File::create(&manifest_path).unwrap();
+        manifest_file
+            .write_all(
+                br#"name = 'my-happ'
+
+[[dnas]]
+id = 'app'
+file = 'app.dna.json'
+hash = 'QmWrongHash'
+"#,
+            )
+            .unwrap();
+
+        assert!(bundle(&manifest_path, Some(source_dir.join("out"))).is_err());
+    }
+}
@@ -1,11 +1,18 @@
 use crate::{
     cli::{
         package::CODE_DIR_NAME,
-        scaffold::{self, Scaffold},
+        scaffold::{
+            self,
+            rust::LIB_RS_PATH,
+            zome_code::{self, EntryScaffold, Field, LinkScaffold},
+            Scaffold,
+        },
+        test::TEST_DIR_NAME,
     },
     error::DefaultResult,
     util,
 };
+use colored::*;
 use serde_json;
 use std::{
     fs::{self, File},
@@ -75,6 +82,130 @@ fn scaffold<S: Scaffold>(tooling: &S, base_path: PathBuf) -> DefaultResult<()> {
     tooling.gen(base_path)
 }
 
+/// Adds an entry type with the given fields to a Rust zome: a module with the struct,
+/// the `entry!` definition with validation stubs and CRUD handlers, the zome functions
+/// calling them, and a scenario for them in the app's JS tests if there are any.
+pub fn generate_entry(zome: &PathBuf, entry_type: &str, fields: &[String]) -> DefaultResult<()> {
+    let fields = fields
+        .iter()
+        .map(|field| Field::parse(field))
+        .collect::<DefaultResult<Vec<_>>>()?;
+    let entry = EntryScaffold::new(entry_type, fields)?;
+
+    let lib_path = zome_lib_path(zome)?;
+    let module_path = lib_path.with_file_name(format!("{}.rs", entry.name));
+    ensure!(!module_path.exists(), "{:?} already exists", module_path);
+
+    let lib_code = entry.add_to_lib(&fs::read_to_string(&lib_path)?)?;
+    fs::write(&module_path, entry.module_code())?;
+    fs::write(&lib_path, lib_code)?;
+    println!(
+        "{} entry type {} in {:?}",
+        "Generated".green().bold(),
+        entry.name,
+        module_path
+    );
+
+    let tests_path = PathBuf::from(TEST_DIR_NAME).join("index.js");
+    if tests_path.exists() {
+        let zome_name = util::file_name_string(zome)?;
+        let tests = entry.add_scenario(&fs::read_to_string(&tests_path)?, &zome_name)?;
+        fs::write(&tests_path, tests)?;
+        println!("{} scenario to {:?}", "Added".green().bold(), tests_path);
+    }
+
+    Ok(())
+}
+
+/// Adds a link type between two entry types of a Rust zome: a `to!` definition in the
+/// base's `entry!`, handlers to link and to get the linked entries as their type, and
+/// the zome functions calling them.
+pub fn generate_link(
+    zome: &PathBuf,
+    base: &str,
+    target: &str,
+    link_type: &str,
+) -> DefaultResult<()> {
+    let link = LinkScaffold::new(base, target, link_type)?;
+    let lib_path = zome_lib_path(zome)?;
+    let sources = zome_sources(&lib_path)?;
+
+    let base_path = sources
+        .iter()
+        .find(|(_, code)| link.defines_base(code))
+        .map(|(path, _)| path.clone())
+        .ok_or_else(|| format_err!("zome does not define the entry type {}", link.base))?;
+    ensure!(
+        sources.iter().any(|(_, code)| link.defines_target(code)),
+        "zome does not define the entry type {}",
+        link.target
+    );
+    let target_struct_name = zome_code::to_camel_case(&link.target);
+    let target_struct = sources
+        .iter()
+        .find(|(_, code)| code.contains(&format!("pub struct {} ", target_struct_name)))
+        .map(|(path, _)| match module_name(path, &lib_path) {
+            Some(module) => format!("crate::{}::{}", module, target_struct_name),
+            None => format!("crate::{}", target_struct_name),
+        })
+        .ok_or_else(|| format_err!("zome has no struct {}", target_struct_name))?;
+
+    let base_code = link.add_to_base(&fs::read_to_string(&base_path)?, &target_struct)?;
+    fs::write(&base_path, base_code)?;
+    let module = module_name(&base_path, &lib_path);
+    let lib_code = link.add_to_lib(
+        &fs::read_to_string(&lib_path)?,
+        module.as_ref().map(String::as_str),
+        &target_struct,
+    )?;
+    fs::write(&lib_path, lib_code)?;
+
+    println!(
+        "{} link type {} from {} to {}",
+        "Generated".green().bold(),
+        link.link_type,
+        link.base,
+        link.target
+    );
+    Ok(())
+}
+
+fn zome_lib_path(zome: &PathBuf) -> DefaultResult<PathBuf> {
+    let lib_path = zome.join(CODE_DIR_NAME).join(LIB_RS_PATH);
+    ensure!(
+        lib_path.is_file(),
+        "{:?} is not a Rust zome, {:?} is missing",
+        zome,
+        lib_path
+    );
+    Ok(lib_path)
+}
+
+/// The paths and contents of the zome's Rust files next to lib.rs, lib.rs first.
+fn zome_sources(lib_path: &PathBuf) -> DefaultResult<Vec<(PathBuf, String)>> {
+    let mut sources = vec![(lib_path.clone(), fs::read_to_string(lib_path)?)];
+    let src_dir = lib_path.parent().expect("lib.rs is in a directory");
+    for dir_entry in fs::read_dir(src_dir)? {
+        let path = dir_entry?.path();
+        if path != *lib_path && path.extension().map(|e| e == "rs").unwrap_or(false) {
+            let code = fs::read_to_string(&path)?;
+            sources.push((path, code));
+        }
+    }
+    Ok(sources)
+}
+
+/// The name of the module a source file holds, None for lib.rs.
+fn module_name(path: &PathBuf, lib_path: &PathBuf) -> Option<String> {
+    if path == lib_path {
+        None
+    } else {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(String::from)
+    }
+}
+
 #[cfg(test)]
 // too slow!
 #[cfg(feature = "broken-tests")]
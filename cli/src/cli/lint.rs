@@ -0,0 +1,519 @@
+use crate::error::DefaultResult;
+use colored::*;
+use holochain_conductor_api::conductor::Conductor;
+use holochain_core::nucleus::ribosome::{run_dna, WasmCallData};
+use holochain_core_types::{
+    dna::{
+        bridges::{BridgePresence, BridgeReference},
+        entry_types::EntryTypeDef,
+        zome::Zome,
+        Dna,
+    },
+    entry::entry_type::EntryType,
+};
+use serde_json::{self, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+
+/// Callback the HDK exports for validating app entries
+const VALIDATE_APP_ENTRY_CALLBACK: &str = "__hdk_validate_app_entry";
+/// Callback the HDK exports for validating links
+const VALIDATE_LINK_CALLBACK: &str = "__hdk_validate_link";
+/// Callback the HDK exports for listing the entry types a zome defines
+const JSON_DEFINITION_CALLBACK: &str = "__hdk_get_json_definition";
+
+/// A problem found in a DNA by `lint_dna()`
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintIssue {
+    pub zome: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "zome \"{}\": {}", self.zome, self.message)
+    }
+}
+
+/// Statically checks a DNA for definitions that can not work at runtime:
+/// * link definitions (`links_to` / `linked_from`) naming entry types that do not exist
+/// * traits listing functions that are not declared in `fn_declarations`
+/// * declared functions that the zome's WASM does not export
+/// * required bridges that do not name any trait a bridged DNA could match
+/// * entry types and links the zome's WASM does not validate
+pub fn lint_dna(dna: &Dna) -> Vec<LintIssue> {
+    let app_entry_types: BTreeSet<String> = dna
+        .zomes
+        .values()
+        .flat_map(|zome| zome.entry_types.keys())
+        .map(|entry_type| String::from(entry_type.clone()))
+        .collect();
+
+    let mut issues = Vec::new();
+    for (zome_name, zome) in dna.zomes.iter() {
+        let mut issue = |message: String| {
+            issues.push(LintIssue {
+                zome: zome_name.clone(),
+                message,
+            })
+        };
+        for message in lint_zome(zome, &app_entry_types) {
+            issue(message);
+        }
+    }
+    issues
+}
+
+fn entry_type_exists(name: &str, app_entry_types: &BTreeSet<String>) -> bool {
+    match EntryType::from_str(name) {
+        Ok(EntryType::App(_)) => app_entry_types.contains(name),
+        Ok(_) => true,
+        Err(_) => false,
+    }
+}
+
+fn lint_zome(zome: &Zome, app_entry_types: &BTreeSet<String>) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    let mut has_links = false;
+    for (entry_type, entry_type_def) in zome.entry_types.iter() {
+        let entry_type_name = String::from(entry_type.clone());
+        for link in entry_type_def.links_to.iter() {
+            has_links = true;
+            if !entry_type_exists(&link.target_type, app_entry_types) {
+                messages.push(format!(
+                    "entry type \"{}\" links to unknown entry type \"{}\" (link type \"{}\")",
+                    entry_type_name, link.target_type, link.link_type
+                ));
+            }
+        }
+        for link in entry_type_def.linked_from.iter() {
+            has_links = true;
+            if !entry_type_exists(&link.base_type, app_entry_types) {
+                messages.push(format!(
+                    "entry type \"{}\" is linked from unknown entry type \"{}\" (link type \"{}\")",
+                    entry_type_name, link.base_type, link.link_type
+                ));
+            }
+        }
+    }
+
+    let declared: BTreeSet<&String> = zome.fn_declarations.iter().map(|f| &f.name).collect();
+    for (trait_name, trait_fns) in zome.traits.iter() {
+        for function in trait_fns.functions.iter() {
+            if !declared.contains(function) {
+                messages.push(format!(
+                    "trait \"{}\" lists function \"{}\" which is not declared in fn_declarations",
+                    trait_name, function
+                ));
+            }
+        }
+    }
+
+    for bridge in zome.bridges.iter() {
+        if bridge.presence != BridgePresence::Required {
+            continue;
+        }
+        if let BridgeReference::Trait { ref traits } = bridge.reference {
+            if traits.is_empty() || traits.values().all(|t| t.functions.is_empty()) {
+                messages.push(format!(
+                    "required bridge \"{}\" does not define any trait function a bridged DNA could match",
+                    bridge.handle
+                ));
+            }
+        }
+    }
+
+    if zome.code.code.is_empty() {
+        if !zome.fn_declarations.is_empty() || !zome.entry_types.is_empty() {
+            messages.push("zome has declarations but no WASM code".to_string());
+        }
+        return messages;
+    }
+
+    match wasm_exports(&zome.code.code) {
+        Ok(exports) => {
+            for function in zome.fn_declarations.iter() {
+                if !exports.contains(&function.name) {
+                    messages.push(format!(
+                        "declared function \"{}\" is not exported by the zome's WASM",
+                        function.name
+                    ));
+                }
+            }
+            // Zomes built with the HDK export the validation callbacks regardless of what
+            // they define, so ask them which entry types they actually validate.
+            let definitions = if exports.contains(JSON_DEFINITION_CALLBACK) {
+                match wasm_entry_definitions(&zome.code.code) {
+                    Ok(definitions) => Some(definitions),
+                    Err(error) => {
+                        messages.push(format!(
+                            "could not read entry definitions from WASM: {}",
+                            error
+                        ));
+                        return messages;
+                    }
+                }
+            } else {
+                None
+            };
+            messages.extend(lint_validation(
+                zome,
+                &exports,
+                definitions.as_ref(),
+                has_links,
+            ));
+        }
+        Err(error) => messages.push(format!("could not read WASM exports: {}", error)),
+    }
+
+    messages
+}
+
+/// The entry types the zome's WASM defines through the HDK, each together with its
+/// validation and the links it validates.
+fn wasm_entry_definitions(wasm: &Arc<Vec<u8>>) -> Result<BTreeMap<String, EntryTypeDef>, String> {
+    let json_string = run_dna(
+        Some("{}".as_bytes().to_vec()),
+        WasmCallData::DirectCall(JSON_DEFINITION_CALLBACK.to_string(), wasm.clone()),
+    )
+    .map_err(|e| e.to_string())?;
+    let definition: Value =
+        serde_json::from_str(&String::from(json_string)).map_err(|e| e.to_string())?;
+    serde_json::from_value(definition.get("entry_types").cloned().unwrap_or(json!({})))
+        .map_err(|e| e.to_string())
+}
+
+/// Finds entry types and links without validation.
+/// With the entry definitions of an HDK zome, everything that is declared in the DNA
+/// but not defined in the WASM goes unvalidated. Without them, only the presence of the
+/// validation callbacks can be checked.
+fn lint_validation(
+    zome: &Zome,
+    exports: &BTreeSet<String>,
+    definitions: Option<&BTreeMap<String, EntryTypeDef>>,
+    has_links: bool,
+) -> Vec<String> {
+    let mut messages = Vec::new();
+    let definitions = match definitions {
+        Some(definitions) => definitions,
+        None => {
+            if !zome.entry_types.is_empty() && !exports.contains(VALIDATE_APP_ENTRY_CALLBACK) {
+                for entry_type in zome.entry_types.keys() {
+                    messages.push(format!(
+                        "entry type \"{}\" has no validation (WASM does not export {})",
+                        String::from(entry_type.clone()),
+                        VALIDATE_APP_ENTRY_CALLBACK
+                    ));
+                }
+            }
+            if has_links && !exports.contains(VALIDATE_LINK_CALLBACK) {
+                messages.push(format!(
+                    "links are defined but have no validation (WASM does not export {})",
+                    VALIDATE_LINK_CALLBACK
+                ));
+            }
+            return messages;
+        }
+    };
+
+    for (entry_type, entry_type_def) in zome.entry_types.iter() {
+        let entry_type_name = String::from(entry_type.clone());
+        let definition = match definitions.get(&entry_type_name) {
+            Some(definition) => definition,
+            None => {
+                messages.push(format!(
+                    "entry type \"{}\" has no validation (not defined in the zome's WASM)",
+                    entry_type_name
+                ));
+                continue;
+            }
+        };
+        for link in entry_type_def.links_to.iter() {
+            if !definition.links_to.contains(link) {
+                messages.push(format!(
+                    "link \"{}\" from \"{}\" to \"{}\" has no validation (not defined in the zome's WASM)",
+                    link.link_type, entry_type_name, link.target_type
+                ));
+            }
+        }
+        for link in entry_type_def.linked_from.iter() {
+            if !definition.linked_from.contains(link) {
+                messages.push(format!(
+                    "link \"{}\" from \"{}\" to \"{}\" has no validation (not defined in the zome's WASM)",
+                    link.link_type, link.base_type, entry_type_name
+                ));
+            }
+        }
+    }
+    messages
+}
+
+/// Reads an unsigned LEB128 number from the given position, advancing it.
+fn read_leb128(bytes: &[u8], pos: &mut usize) -> Result<u32, String> {
+    let mut result: u32 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or_else(|| "unexpected end of WASM".to_string())?;
+        *pos += 1;
+        result |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+        if shift > 28 {
+            return Err("invalid LEB128 number in WASM".to_string());
+        }
+    }
+}
+
+/// Returns the names of all functions exported by the given WASM binary.
+/// Only the export section is parsed, everything else is skipped.
+pub fn wasm_exports(wasm: &[u8]) -> Result<BTreeSet<String>, String> {
+    const EXPORT_SECTION_ID: u8 = 7;
+    const FUNCTION_EXPORT_KIND: u8 = 0;
+
+    if wasm.len() < 8 || &wasm[0..4] != b"\0asm" {
+        return Err("not a WASM binary".to_string());
+    }
+    let mut exports = BTreeSet::new();
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let section_id = wasm[pos];
+        pos += 1;
+        let size = read_leb128(wasm, &mut pos)? as usize;
+        let section_end = pos + size;
+        if section_end > wasm.len() {
+            return Err("WASM section exceeds binary".to_string());
+        }
+        if section_id == EXPORT_SECTION_ID {
+            let count = read_leb128(wasm, &mut pos)?;
+            for _ in 0..count {
+                let name_length = read_leb128(wasm, &mut pos)? as usize;
+                let name = wasm
+                    .get(pos..pos + name_length)
+                    .ok_or_else(|| "unexpected end of WASM".to_string())?;
+                pos += name_length;
+                let kind = *wasm
+                    .get(pos)
+                    .ok_or_else(|| "unexpected end of WASM".to_string())?;
+                pos += 1;
+                let _index = read_leb128(wasm, &mut pos)?;
+                if kind == FUNCTION_EXPORT_KIND {
+                    exports.insert(String::from_utf8_lossy(name).into_owned());
+                }
+            }
+        }
+        pos = section_end;
+    }
+    Ok(exports)
+}
+
+/// Prints the given lint issues and fails if there are any
+pub fn report_lint_issues(issues: &[LintIssue]) -> DefaultResult<()> {
+    for issue in issues.iter() {
+        eprintln!("{} {}", "Lint:".red().bold(), issue);
+    }
+    ensure!(
+        issues.is_empty(),
+        "DNA failed linting with {} issue(s)",
+        issues.len()
+    );
+    Ok(())
+}
+
+/// Lints the DNA file at the given path
+pub fn lint(dna_path: &PathBuf) -> DefaultResult<()> {
+    let dna = Conductor::load_dna(dna_path)?;
+    report_lint_issues(&lint_dna(&dna))?;
+    println!(
+        "{} No issues found in {:?}",
+        "Lint:".green().bold(),
+        dna_path
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use holochain_core_types::dna::{
+        bridges::Bridge,
+        entry_types::{LinkedFrom, LinksTo},
+        fn_declarations::{FnDeclaration, TraitFns},
+        wasm::DnaWasm,
+    };
+
+    /// Minimal WASM module exporting the given (empty) functions
+    fn wasm_exporting(names: &[&str]) -> Vec<u8> {
+        let mut wasm = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        // type section: one type () -> ()
+        wasm.extend(vec![0x01, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        // function section: one function per export, all of type 0
+        wasm.extend(vec![0x03, names.len() as u8 + 1, names.len() as u8]);
+        wasm.extend(names.iter().map(|_| 0x00));
+        // export section
+        let mut exports = vec![names.len() as u8];
+        for (index, name) in names.iter().enumerate() {
+            exports.push(name.len() as u8);
+            exports.extend(name.as_bytes());
+            exports.push(0x00);
+            exports.push(index as u8);
+        }
+        wasm.push(0x07);
+        wasm.push(exports.len() as u8);
+        wasm.extend(exports);
+        // code section: empty bodies
+        wasm.extend(vec![0x0a, 1 + 3 * names.len() as u8, names.len() as u8]);
+        for _ in names.iter() {
+            wasm.extend(vec![0x02, 0x00, 0x0b]);
+        }
+        wasm
+    }
+
+    fn declaration(name: &str) -> FnDeclaration {
+        FnDeclaration {
+            name: name.to_string(),
+            ..FnDeclaration::new()
+        }
+    }
+
+    fn valid_zome() -> Zome {
+        let mut zome = Zome::default();
+        let mut post = EntryTypeDef::new();
+        post.links_to.push(LinksTo {
+            target_type: "comment".to_string(),
+            link_type: "comments".to_string(),
+        });
+        post.linked_from.push(LinkedFrom {
+            base_type: "%agent_id".to_string(),
+            link_type: "posts".to_string(),
+        });
+        zome.entry_types.insert("post".into(), post);
+        zome.entry_types
+            .insert("comment".into(), EntryTypeDef::new());
+        zome.fn_declarations.push(declaration("create_post"));
+        let mut trait_fns = TraitFns::new();
+        trait_fns.functions.push("create_post".to_string());
+        zome.traits.insert("hc_public".to_string(), trait_fns);
+        zome.code = DnaWasm::from_bytes(wasm_exporting(&[
+            "create_post",
+            VALIDATE_APP_ENTRY_CALLBACK,
+            VALIDATE_LINK_CALLBACK,
+        ]));
+        zome
+    }
+
+    fn dna_with_zome(zome: Zome) -> Dna {
+        let mut dna = Dna::new();
+        dna.zomes.insert("blog".to_string(), zome);
+        dna
+    }
+
+    #[test]
+    fn test_wasm_exports() {
+        let exports = wasm_exporting(&["foo", "bar"]);
+        assert_eq!(
+            wasm_exports(&exports),
+            Ok(vec!["bar".to_string(), "foo".to_string()]
+                .into_iter()
+                .collect())
+        );
+        assert!(wasm_exports(b"not wasm").is_err());
+    }
+
+    #[test]
+    fn test_valid_dna_has_no_issues() {
+        assert_eq!(lint_dna(&dna_with_zome(valid_zome())), vec![]);
+    }
+
+    #[test]
+    fn test_lint_finds_issues() {
+        let mut zome = valid_zome();
+        zome.entry_types
+            .get_mut(&EntryType::from("post"))
+            .unwrap()
+            .links_to
+            .push(LinksTo {
+                target_type: "tag".to_string(),
+                link_type: "tags".to_string(),
+            });
+        zome.traits
+            .get_mut("hc_public")
+            .unwrap()
+            .functions
+            .push("delete_post".to_string());
+        zome.fn_declarations.push(declaration("update_post"));
+        zome.bridges.push(Bridge {
+            presence: BridgePresence::Required,
+            handle: "other".to_string(),
+            reference: BridgeReference::Trait {
+                traits: BTreeMap::new(),
+            },
+        });
+
+        let messages: Vec<String> = lint_dna(&dna_with_zome(zome))
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "entry type \"post\" links to unknown entry type \"tag\" (link type \"tags\")",
+                "trait \"hc_public\" lists function \"delete_post\" which is not declared in fn_declarations",
+                "required bridge \"other\" does not define any trait function a bridged DNA could match",
+                "declared function \"update_post\" is not exported by the zome's WASM",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_checks_validation_defined_by_hdk_zomes() {
+        let zome = valid_zome();
+        let exports = wasm_exports(&zome.code.code).unwrap();
+        let mut definitions = BTreeMap::new();
+        let mut post = zome.entry_types[&EntryType::from("post")].clone();
+        post.links_to.clear();
+        definitions.insert("post".to_string(), post);
+
+        assert_eq!(
+            lint_validation(&zome, &exports, Some(&definitions), true),
+            vec![
+                "entry type \"comment\" has no validation (not defined in the zome's WASM)",
+                "link \"comments\" from \"post\" to \"comment\" has no validation (not defined in the zome's WASM)",
+            ]
+        );
+
+        definitions.insert(
+            "post".to_string(),
+            zome.entry_types[&EntryType::from("post")].clone(),
+        );
+        definitions.insert("comment".to_string(), EntryTypeDef::new());
+        assert!(lint_validation(&zome, &exports, Some(&definitions), true).is_empty());
+    }
+
+    #[test]
+    fn test_lint_finds_missing_validation() {
+        let mut zome = valid_zome();
+        zome.code = DnaWasm::from_bytes(wasm_exporting(&["create_post"]));
+        let messages: Vec<String> = lint_dna(&dna_with_zome(zome))
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "entry type \"comment\" has no validation (WASM does not export __hdk_validate_app_entry)",
+                "entry type \"post\" has no validation (WASM does not export __hdk_validate_app_entry)",
+                "links are defined but have no validation (WASM does not export __hdk_validate_link)",
+            ]
+        );
+    }
+}
@@ -1,20 +1,28 @@
+mod bundle;
 mod chain_log;
 mod generate;
 mod hash_dna;
 mod init;
 mod keygen;
+pub mod lint;
 pub mod package;
+mod replay_journal;
 mod run;
 mod scaffold;
+mod storage;
 pub mod test;
 
 pub use self::{
+    bundle::bundle,
     chain_log::{chain_list, chain_log},
-    generate::generate,
+    generate::{generate, generate_entry, generate_link},
     hash_dna::hash_dna,
     init::init,
     keygen::keygen,
+    lint::lint,
     package::{package, unpack},
+    replay_journal::replay_journal,
     run::{get_interface_type_string, hc_run_configuration, run},
-    test::{test, TEST_DIR_NAME},
+    storage::{storage, StorageConversion},
+    test::{test, test_rust, TEST_DIR_NAME},
 };
@@ -15,7 +15,7 @@ use std::{
     sync::Arc,
 };
 
-use cli::scaffold::rust::CARGO_FILE_NAME;
+use cli::{lint, scaffold::rust::CARGO_FILE_NAME};
 
 use holochain_core_types::hdk_version::{HDKVersion, HDK_VERSION};
 
@@ -58,14 +58,15 @@ fn hdk_version_compare(hdk_version: &HDKVersion, cargo_toml: &str) -> DefaultRes
 
 struct Packager {
     strip_meta: bool,
+    binary: bool,
 }
 
 impl Packager {
-    fn new(strip_meta: bool) -> Packager {
-        Packager { strip_meta }
+    fn new(strip_meta: bool, binary: bool) -> Packager {
+        Packager { strip_meta, binary }
     }
 
-    pub fn package(strip_meta: bool, output: PathBuf) -> DefaultResult<()> {
+    pub fn package(strip_meta: bool, binary: bool, output: PathBuf) -> DefaultResult<()> {
         // First, check whether they have `cargo` installed, since it will be needed for packaging
         // TODO: in the future, don't check for this here, since other build tools and languages
         // could be used
@@ -82,7 +83,7 @@ impl Packager {
             return Ok(());
         }
 
-        Packager::new(strip_meta).run(&output)
+        Packager::new(strip_meta, binary).run(&output)
     }
 
     fn run(&self, output: &PathBuf) -> DefaultResult<()> {
@@ -107,10 +108,18 @@ impl Packager {
             )
         })?;
 
-        let out_file = File::create(&output)
+        // Refuse to write a DNA that is known to fail at runtime
+        lint::report_lint_issues(&lint::lint_dna(&dna))?;
+
+        let mut out_file = File::create(&output)
             .map_err(|e| format_err!("Couldn't create DNA output file {:?}; {}", output, e))?;
 
-        serde_json::to_writer_pretty(&out_file, &(dir_obj_bundle))?;
+        if self.binary {
+            // The binary format has no room for __META__ sections so it can not be unpacked
+            out_file.write_all(&dna.to_binary()?)?;
+        } else {
+            serde_json::to_writer_pretty(&out_file, &(dir_obj_bundle))?;
+        }
 
         // CLI feedback
         println!(
@@ -300,8 +309,8 @@ impl Packager {
     }
 }
 
-pub fn package(strip_meta: bool, output: PathBuf) -> DefaultResult<()> {
-    Packager::package(strip_meta, output)
+pub fn package(strip_meta: bool, binary: bool, output: PathBuf) -> DefaultResult<()> {
+    Packager::package(strip_meta, binary, output)
 }
 
 pub fn unpack(path: &PathBuf, to: &PathBuf) -> DefaultResult<()> {
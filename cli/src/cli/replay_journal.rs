@@ -0,0 +1,35 @@
+use crate::error::DefaultResult;
+use holochain_conductor_api::context_builder::ContextBuilder;
+use holochain_core::action_journal::replay_journal as replay;
+use std::{path::PathBuf, sync::Arc};
+
+/// Replays an instance's action journal into a blank in-memory instance and prints
+/// which of the journal's checkpoints the replayed state did not reproduce.
+pub fn replay_journal(path: &PathBuf) -> DefaultResult<()> {
+    let context = Arc::new(ContextBuilder::new().spawn());
+    let (_, report) = replay(path, context).map_err(|e| format_err!("{}", e))?;
+
+    println!(
+        "Replayed {} actions, {} checkpoints matched",
+        report.actions_replayed, report.checkpoints_matched
+    );
+    for mismatch in report.mismatches.iter() {
+        println!(
+            "Checkpoint after action #{} ({:?}) does not match: expected {}, got {}",
+            mismatch.sequence,
+            mismatch.action_wrapper.action(),
+            mismatch.expected,
+            mismatch.actual
+        );
+    }
+
+    if report.is_consistent() {
+        Ok(())
+    } else {
+        bail!(
+            "{} checkpoints of {} did not match",
+            report.mismatches.len(),
+            path.to_string_lossy()
+        )
+    }
+}
@@ -22,7 +22,7 @@ pub fn run(
     conductor_config: Configuration,
 ) -> DefaultResult<()> {
     if package {
-        cli::package(true, dna_path)?;
+        cli::package(true, false, dna_path)?;
     }
 
     mount_conductor_from_config(conductor_config);
@@ -160,6 +160,9 @@ fn instance_configuration(storage: StorageConfiguration) -> InstanceConfiguratio
         dna: DNA_CONFIG_ID.into(),
         agent: AGENT_CONFIG_ID.into(),
         storage,
+        warrant_policy: None,
+        action_journal: None,
+        encrypt_storage: false,
     }
 }
 
@@ -344,6 +347,9 @@ mod tests {
                 dna: "hc-run-dna".to_string(),
                 agent: "hc-run-agent".to_string(),
                 storage: StorageConfiguration::Memory,
+                warrant_policy: None,
+                action_journal: None,
+                encrypt_storage: false,
             }
         )
     }
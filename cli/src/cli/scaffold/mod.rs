@@ -1,5 +1,6 @@
 pub mod assemblyscript;
 pub mod rust;
+pub mod zome_code;
 
 use crate::error::DefaultResult;
 use std::path::Path;
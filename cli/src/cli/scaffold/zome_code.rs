@@ -0,0 +1,834 @@
+//! Adds entry types, links and the zome functions that use them to the code of existing
+//! Rust zomes. Works with `define_zome!` zomes as well as `#[zome]` zomes.
+//!
+//! Every generated entry type lives in its own module next to the zome's lib.rs, the way
+//! the app_spec zomes are organized. lib.rs only gets the module declaration, the entry
+//! definition and the zome functions.
+
+use crate::{cli::scaffold::rust::HdkMacroStyle, error::DefaultResult};
+
+const DEFINE_ZOME: &str = "define_zome!";
+const ZOME_ATTRIBUTE: &str = "#[zome]";
+
+/// A field of a generated entry struct, given as `name:Type` on the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub field_type: String,
+}
+
+impl Field {
+    pub fn parse(spec: &str) -> DefaultResult<Field> {
+        let mut parts = spec.splitn(2, ':');
+        let name = parts.next().unwrap_or_default().trim();
+        let field_type = parts.next().unwrap_or_default().trim();
+        ensure!(
+            is_identifier(name) && !field_type.is_empty(),
+            "invalid field \"{}\", expected name:Type (e.g. title:String)",
+            spec
+        );
+        // Entries get deserialized into owned structs, which can't borrow
+        ensure!(
+            !field_type.starts_with('&'),
+            "invalid field \"{}\", entries can't hold references, use an owned type (e.g. String instead of &str)",
+            spec
+        );
+        Ok(Field {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+        })
+    }
+
+    /// A JSON value of the field's type for the generated scenario test.
+    /// The `modified` value differs from the other one, for testing updates.
+    fn sample_json(&self, modified: bool) -> String {
+        let pick = |sample: &str, other: &str| if modified { other } else { sample }.to_string();
+        match self.field_type.as_str() {
+            "String" => format!("\"{} {}\"", pick("sample", "modified"), self.name),
+            "bool" => pick("true", "false"),
+            "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" => {
+                pick("1", "2")
+            }
+            "f32" | "f64" => pick("1.5", "2.5"),
+            t if t.starts_with("Vec<") => "[]".to_string(),
+            _ => "null".to_string(),
+        }
+    }
+}
+
+/// Tells which HDK macros the zome code uses.
+pub fn macro_style(code: &str) -> DefaultResult<HdkMacroStyle> {
+    if code.contains(ZOME_ATTRIBUTE) {
+        Ok(HdkMacroStyle::Procedural)
+    } else if code.contains(DEFINE_ZOME) {
+        Ok(HdkMacroStyle::Declarative)
+    } else {
+        bail!(
+            "zome code uses neither {} nor {}, can't add to it",
+            DEFINE_ZOME,
+            ZOME_ATTRIBUTE
+        )
+    }
+}
+
+pub fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else if c == '-' || c == ' ' {
+            snake.push('_');
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+pub fn to_camel_case(name: &str) -> String {
+    to_snake_case(name)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.is_alphabetic() || first == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// The position of the bracket that closes the one at `open`.
+/// Skips string literals and line comments, which is good enough for zome code.
+fn matching_close(code: &str, open: usize) -> DefaultResult<usize> {
+    let bytes = code.as_bytes();
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    bail!("unbalanced brackets in zome code")
+}
+
+/// Finds `keyword` followed by the opening bracket `open` after position `from` and
+/// returns the positions of that bracket and of the one closing it.
+fn find_block(code: &str, from: usize, keyword: &str, open: char) -> DefaultResult<(usize, usize)> {
+    let keyword_pos = code[from..]
+        .find(keyword)
+        .map(|pos| from + pos)
+        .ok_or_else(|| format_err!("could not find \"{}\" in zome code", keyword))?;
+    let open_pos = code[keyword_pos..]
+        .find(open)
+        .map(|pos| keyword_pos + pos)
+        .ok_or_else(|| format_err!("could not find the block of \"{}\"", keyword))?;
+    Ok((open_pos, matching_close(code, open_pos)?))
+}
+
+/// Appends `item` to the comma separated list in the brackets at `open`..`close`.
+/// `indent` is the indentation of the list's items.
+fn append_to_list(code: &str, open: usize, close: usize, item: &str, indent: &str) -> String {
+    let inner = &code[open + 1..close];
+    if inner.trim().is_empty() {
+        let closing_indent = &indent[..indent.len().saturating_sub(4)];
+        format!(
+            "{}\n{}{}\n{}{}",
+            &code[..=open],
+            indent,
+            item,
+            closing_indent,
+            &code[close..]
+        )
+    } else {
+        let position = open + 1 + inner.trim_end().len();
+        format!(
+            "{},\n{}{}{}",
+            &code[..position],
+            indent,
+            item,
+            &code[position..]
+        )
+    }
+}
+
+/// Inserts `text` after the last item of the block at `open`..`close`.
+fn append_to_block(code: &str, open: usize, close: usize, separator: &str, text: &str) -> String {
+    let position = open + 1 + code[open + 1..close].trim_end().len();
+    format!(
+        "{}{}{}{}",
+        &code[..position],
+        separator,
+        text,
+        &code[position..]
+    )
+}
+
+fn zome_marker_line(code: &str, style: &HdkMacroStyle) -> DefaultResult<usize> {
+    let marker = match style {
+        HdkMacroStyle::Declarative => DEFINE_ZOME,
+        HdkMacroStyle::Procedural => ZOME_ATTRIBUTE,
+    };
+    let position = code
+        .find(marker)
+        .ok_or_else(|| format_err!("could not find {} in zome code", marker))?;
+    Ok(code[..position].rfind('\n').map(|pos| pos + 1).unwrap_or(0))
+}
+
+/// Declares the module right after the last module declaration of lib.rs,
+/// or before the first `use` or the zome if there is none.
+pub fn add_module_declaration(code: &str, module: &str) -> DefaultResult<String> {
+    let declaration = format!("pub mod {};\n", module);
+    ensure!(
+        !code
+            .lines()
+            .any(|line| line.trim() == declaration.trim()
+                || line.trim() == format!("mod {};", module)),
+        "module {} is already declared",
+        module
+    );
+    let is_mod_line = |line: &str| {
+        let line = line.trim();
+        (line.starts_with("mod ") || line.starts_with("pub mod ")) && line.ends_with(';')
+    };
+    let mut offset = 0;
+    let mut last_mod = None;
+    let mut first_use = None;
+    for line in code.split('\n') {
+        let end = (offset + line.len() + 1).min(code.len());
+        if is_mod_line(line) {
+            last_mod = Some(end);
+        }
+        if first_use.is_none() && line.trim_start().starts_with("use ") {
+            first_use = Some(offset);
+        }
+        offset = end;
+    }
+    let position = match last_mod.or(first_use) {
+        Some(position) => position,
+        None => zome_marker_line(code, &macro_style(code)?)?,
+    };
+    Ok(format!(
+        "{}{}{}",
+        &code[..position],
+        declaration,
+        &code[position..]
+    ))
+}
+
+/// The path under which the zome functions reach an item given by its path relative to
+/// the crate root. `#[zome]` functions live in a module, so they need `crate::` paths.
+fn item_path(style: &HdkMacroStyle, path: &str) -> String {
+    match style {
+        HdkMacroStyle::Procedural if !path.starts_with("crate::") => format!("crate::{}", path),
+        _ => path.to_string(),
+    }
+}
+
+/// A zome function to add to lib.rs, which calls a handler of a module.
+struct ZomeFunction {
+    name: String,
+    inputs: Vec<(String, String)>,
+    output: String,
+    handler: String,
+}
+
+impl ZomeFunction {
+    fn declarative(&self) -> String {
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|(name, input_type)| format!("{}: {}", name, input_type))
+            .collect();
+        format!(
+            "        {}: {{\n            inputs: |{}|,\n            outputs: |result: {}|,\n            handler: {}\n        }}",
+            self.name,
+            inputs.join(", "),
+            self.output,
+            self.handler
+        )
+    }
+
+    fn procedural(&self) -> String {
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|(name, input_type)| format!("{}: {}", name, input_type))
+            .collect();
+        let args: Vec<&str> = self.inputs.iter().map(|(name, _)| name.as_str()).collect();
+        format!(
+            "    #[zome_fn(\"hc_public\")]\n    fn {}({}) -> {} {{\n        {}({})\n    }}",
+            self.name,
+            inputs.join(", "),
+            self.output,
+            self.handler,
+            args.join(", ")
+        )
+    }
+}
+
+/// Adds the entry definition (if given, as name of the entry type and the expression that
+/// defines it) and zome functions to the zome in lib.rs.
+fn add_to_zome(
+    code: &str,
+    entry_definition: Option<(&str, &str)>,
+    functions: &[ZomeFunction],
+) -> DefaultResult<String> {
+    let style = macro_style(code)?;
+    let zome = code
+        .find(match style {
+            HdkMacroStyle::Declarative => DEFINE_ZOME,
+            HdkMacroStyle::Procedural => ZOME_ATTRIBUTE,
+        })
+        .expect("macro_style() found the zome");
+    let mut code = code.to_string();
+    match style {
+        HdkMacroStyle::Declarative => {
+            if let Some((_, definition)) = entry_definition {
+                let (open, close) = find_block(&code, zome, "entries", '[')?;
+                code = append_to_list(&code, open, close, definition, "        ");
+            }
+            let (open, close) = find_block(&code, zome, "functions", '[')?;
+            let specs: Vec<String> = functions.iter().map(ZomeFunction::declarative).collect();
+            code = append_to_block(&code, open, close, "\n", &specs.join("\n"));
+
+            let names: Vec<&str> = functions.iter().map(|f| f.name.as_str()).collect();
+            let (traits_open, traits_close) = find_block(&code, zome, "traits", '{')?;
+            match code[traits_open..traits_close].find("hc_public") {
+                Some(position) => {
+                    let (open, close) =
+                        find_block(&code, traits_open + position, "hc_public", '[')?;
+                    let inner = code[open + 1..close].trim();
+                    let list = if inner.is_empty() {
+                        names.join(",")
+                    } else {
+                        format!("{},{}", inner, names.join(","))
+                    };
+                    code = format!("{}{}{}", &code[..=open], list, &code[close..]);
+                }
+                None => {
+                    let trait_spec = format!("        hc_public [{}]", names.join(","));
+                    code = append_to_block(&code, traits_open, traits_close, "\n", &trait_spec);
+                }
+            }
+        }
+        HdkMacroStyle::Procedural => {
+            let (open, close) = find_block(&code, zome, "mod", '{')?;
+            let mut items = Vec::new();
+            if let Some((name, definition)) = entry_definition {
+                items.push(format!(
+                    "    #[entry_def]\n    fn {}_entry_def() -> ValidatingEntryType {{\n        {}\n    }}",
+                    name, definition
+                ));
+            }
+            items.extend(functions.iter().map(ZomeFunction::procedural));
+            code = append_to_block(&code, open, close, "\n\n", &items.join("\n\n"));
+        }
+    }
+    Ok(code)
+}
+
+/// An entry type to generate.
+pub struct EntryScaffold {
+    /// Name of the entry type as used in the DNA, e.g. `blog_post`.
+    /// Also the name of the generated module.
+    pub name: String,
+    /// Name of the Rust struct, e.g. `BlogPost`
+    pub struct_name: String,
+    pub fields: Vec<Field>,
+}
+
+impl EntryScaffold {
+    pub fn new(entry_type: &str, fields: Vec<Field>) -> DefaultResult<Self> {
+        let name = to_snake_case(entry_type);
+        ensure!(
+            is_identifier(&name),
+            "invalid entry type name \"{}\"",
+            entry_type
+        );
+        Ok(EntryScaffold {
+            struct_name: to_camel_case(&name),
+            name,
+            fields,
+        })
+    }
+
+    /// The code of the module holding the struct, entry definition and handlers.
+    pub fn module_code(&self) -> String {
+        let fields: String = self
+            .fields
+            .iter()
+            .map(|field| format!("    pub {}: {},\n", field.name, field.field_type))
+            .collect();
+        MODULE_TEMPLATE
+            .replace("<<STRUCT_FIELDS>>", &fields)
+            .replace("<<STRUCT>>", &self.struct_name)
+            .replace("<<NAME>>", &self.name)
+    }
+
+    /// Adds the module, the entry definition and the CRUD zome functions to lib.rs.
+    pub fn add_to_lib(&self, code: &str) -> DefaultResult<String> {
+        ensure!(
+            !code.contains(&format!("name: \"{}\"", self.name)),
+            "entry type {} is already defined",
+            self.name
+        );
+        let style = macro_style(code)?;
+        let path = |item: &str| item_path(&style, &format!("{}::{}", self.name, item));
+        let address = ("address".to_string(), "Address".to_string());
+        let entry = ("entry".to_string(), path(&self.struct_name));
+        let function = |verb: &str, inputs: Vec<(String, String)>, output: &str| ZomeFunction {
+            name: format!("{}_{}", verb, self.name),
+            inputs,
+            output: output.to_string(),
+            handler: path(&format!("handle_{}", verb)),
+        };
+        let functions = vec![
+            function("create", vec![entry.clone()], "ZomeApiResult<Address>"),
+            function(
+                "get",
+                vec![address.clone()],
+                &format!("ZomeApiResult<{}>", path(&self.struct_name)),
+            ),
+            function(
+                "update",
+                vec![address.clone(), entry],
+                "ZomeApiResult<Address>",
+            ),
+            function("remove", vec![address], "ZomeApiResult<Address>"),
+        ];
+        let code = add_module_declaration(code, &self.name)?;
+        let definition = format!("{}()", path("definition"));
+        add_to_zome(&code, Some((&self.name, &definition)), &functions)
+    }
+
+    /// Adds a scenario for the zome functions to the app's JS tests.
+    pub fn add_scenario(&self, tests: &str, zome: &str) -> DefaultResult<String> {
+        let position = tests
+            .rfind("diorama.run()")
+            .ok_or_else(|| format_err!("test file does not call diorama.run()"))?;
+        let entry = |modified| {
+            let fields: Vec<String> = self
+                .fields
+                .iter()
+                .map(|field| format!("\"{}\": {}", field.name, field.sample_json(modified)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        };
+        let scenario = SCENARIO_TEMPLATE
+            .replace("<<ZOME>>", zome)
+            .replace("<<NAME>>", &self.name)
+            .replace("<<ENTRY>>", &entry(false))
+            .replace("<<MODIFIED_ENTRY>>", &entry(true));
+        Ok(format!(
+            "{}{}{}",
+            &tests[..position],
+            scenario,
+            &tests[position..]
+        ))
+    }
+}
+
+/// A link type to generate between two existing entry types.
+pub struct LinkScaffold {
+    pub base: String,
+    pub target: String,
+    pub link_type: String,
+}
+
+impl LinkScaffold {
+    pub fn new(base: &str, target: &str, link_type: &str) -> DefaultResult<Self> {
+        let link_type = to_snake_case(link_type);
+        ensure!(
+            is_identifier(&link_type),
+            "invalid link type \"{}\"",
+            link_type
+        );
+        Ok(LinkScaffold {
+            base: to_snake_case(base),
+            target: to_snake_case(target),
+            link_type,
+        })
+    }
+
+    /// True if the code defines the base entry type.
+    pub fn defines_base(&self, code: &str) -> bool {
+        code.contains(&format!("name: \"{}\"", self.base))
+    }
+
+    pub fn defines_target(&self, code: &str) -> bool {
+        code.contains(&format!("name: \"{}\"", self.target))
+    }
+
+    /// Adds the `to!` link definition to the base's `entry!` definition and the link handlers
+    /// to the end of the code. `target_struct` is the path to the target's struct.
+    pub fn add_to_base(&self, code: &str, target_struct: &str) -> DefaultResult<String> {
+        let name_position = code
+            .find(&format!("name: \"{}\"", self.base))
+            .ok_or_else(|| format_err!("entry type {} is not defined", self.base))?;
+        let entry_position = code[..name_position]
+            .rfind("entry!")
+            .ok_or_else(|| format_err!("could not find the entry! of {}", self.base))?;
+        let (open, close) = find_block(code, entry_position, "entry!", '(')?;
+        let link = LINK_TEMPLATE
+            .replace("<<TARGET>>", &self.target)
+            .replace("<<LINK_TYPE>>", &self.link_type);
+        ensure!(
+            !code[open..close].contains(&format!("link_type: \"{}\"", self.link_type)),
+            "{} already has a link of type {}",
+            self.base,
+            self.link_type
+        );
+
+        let mut code = match code[open..close].find("links") {
+            Some(position) => {
+                let (links_open, links_close) = find_block(code, open + position, "links", '[')?;
+                append_to_list(code, links_open, links_close, &link, "            ")
+            }
+            None => {
+                let position = open + 1 + code[open + 1..close].trim_end().len();
+                format!(
+                    "{},\n\n        links: [\n            {}\n        ]{}",
+                    &code[..position],
+                    link,
+                    &code[position..]
+                )
+            }
+        };
+        if !code.ends_with('\n') {
+            code.push('\n');
+        }
+        code.push_str(
+            &LINK_HANDLERS_TEMPLATE
+                .replace("<<LINK_TYPE>>", &self.link_type)
+                .replace("<<TARGET_STRUCT>>", target_struct),
+        );
+        Ok(code)
+    }
+
+    /// Adds the zome functions for the link to lib.rs.
+    /// `module` is the path of the module with the handlers, `target_struct` the path to the
+    /// target's struct, both relative to the crate root.
+    pub fn add_to_lib(
+        &self,
+        code: &str,
+        module: Option<&str>,
+        target_struct: &str,
+    ) -> DefaultResult<String> {
+        let style = macro_style(code)?;
+        let handler = |name: &str| match module {
+            Some(module) => item_path(&style, &format!("{}::{}", module, name)),
+            None => item_path(&style, name),
+        };
+        let address = |name: &str| (name.to_string(), "Address".to_string());
+        let functions = vec![
+            ZomeFunction {
+                name: format!("link_{}", self.link_type),
+                inputs: vec![address("base"), address("target")],
+                output: "ZomeApiResult<Address>".to_string(),
+                handler: handler(&format!("handle_link_{}", self.link_type)),
+            },
+            ZomeFunction {
+                name: format!("get_{}_links", self.link_type),
+                inputs: vec![address("base")],
+                output: format!("ZomeApiResult<Vec<{}>>", target_struct),
+                handler: handler(&format!("handle_get_{}_links", self.link_type)),
+            },
+        ];
+        add_to_zome(code, None, &functions)
+    }
+}
+
+const MODULE_TEMPLATE: &str = r#"use hdk::{
+    entry_definition::ValidatingEntryType,
+    error::ZomeApiResult,
+    holochain_core_types::{dna::entry_types::Sharing, entry::Entry},
+    holochain_json_api::{error::JsonError, json::JsonString},
+    holochain_persistence_api::cas::content::Address,
+};
+
+#[derive(Serialize, Deserialize, Debug, DefaultJson, Clone)]
+pub struct <<STRUCT>> {
+<<STRUCT_FIELDS>>}
+
+pub fn definition() -> ValidatingEntryType {
+    entry!(
+        name: "<<NAME>>",
+        description: "TODO: describe <<NAME>>",
+        sharing: Sharing::Public,
+
+        validation_package: || {
+            hdk::ValidationPackageDefinition::Entry
+        },
+
+        validation: |validation_data: hdk::EntryValidationData<<<STRUCT>>>| {
+            // TODO: check the entries
+            match validation_data {
+                hdk::EntryValidationData::Create { .. } => Ok(()),
+                hdk::EntryValidationData::Modify { .. } => Ok(()),
+                hdk::EntryValidationData::Delete { .. } => Ok(()),
+            }
+        },
+
+        links: []
+    )
+}
+
+pub fn handle_create(entry: <<STRUCT>>) -> ZomeApiResult<Address> {
+    hdk::commit_entry(&Entry::App("<<NAME>>".into(), entry.into()))
+}
+
+pub fn handle_get(address: Address) -> ZomeApiResult<<<STRUCT>>> {
+    hdk::utils::get_as_type(address)
+}
+
+pub fn handle_update(address: Address, entry: <<STRUCT>>) -> ZomeApiResult<Address> {
+    hdk::update_entry(Entry::App("<<NAME>>".into(), entry.into()), &address)
+}
+
+pub fn handle_remove(address: Address) -> ZomeApiResult<Address> {
+    hdk::remove_entry(&address)
+}
+"#;
+
+const LINK_TEMPLATE: &str = r#"to!(
+                "<<TARGET>>",
+                link_type: "<<LINK_TYPE>>",
+
+                validation_package: || {
+                    hdk::ValidationPackageDefinition::Entry
+                },
+
+                validation: |_validation_data: hdk::LinkValidationData| {
+                    // TODO: check the links
+                    Ok(())
+                }
+            )"#;
+
+const LINK_HANDLERS_TEMPLATE: &str = r#"
+pub fn handle_link_<<LINK_TYPE>>(
+    base: hdk::holochain_persistence_api::cas::content::Address,
+    target: hdk::holochain_persistence_api::cas::content::Address,
+) -> hdk::error::ZomeApiResult<hdk::holochain_persistence_api::cas::content::Address> {
+    hdk::link_entries(&base, &target, "<<LINK_TYPE>>", "")
+}
+
+pub fn handle_get_<<LINK_TYPE>>_links(
+    base: hdk::holochain_persistence_api::cas::content::Address,
+) -> hdk::error::ZomeApiResult<Vec<<<TARGET_STRUCT>>>> {
+    hdk::utils::get_links_and_load_type(
+        &base,
+        hdk::holochain_core_types::link::LinkMatch::Exactly("<<LINK_TYPE>>"),
+        hdk::holochain_core_types::link::LinkMatch::Any,
+    )
+}
+"#;
+
+const SCENARIO_TEMPLATE: &str = r#"diorama.registerScenario("can create, update and remove <<NAME>>", async (s, t, { alice }) => {
+  const entry = <<ENTRY>>
+  const address = await alice.call("<<ZOME>>", "create_<<NAME>>", { entry })
+  t.ok(address.Ok)
+
+  const result = await alice.call("<<ZOME>>", "get_<<NAME>>", { address: address.Ok })
+  t.deepEqual(result, { Ok: entry })
+
+  const modified = <<MODIFIED_ENTRY>>
+  const updated = await alice.call("<<ZOME>>", "update_<<NAME>>", { address: address.Ok, entry: modified })
+  t.ok(updated.Ok)
+
+  const updatedResult = await alice.call("<<ZOME>>", "get_<<NAME>>", { address: updated.Ok })
+  t.deepEqual(updatedResult, { Ok: modified })
+
+  const removed = await alice.call("<<ZOME>>", "remove_<<NAME>>", { address: updated.Ok })
+  t.ok(removed.Ok)
+})
+
+"#;
+
+#[cfg(test)]
+pub mod tests {
+    extern crate syn;
+    use super::*;
+
+    const DECLARATIVE_ZOME: &str = include_str!("rust/lib.rs");
+    const PROCEDURAL_ZOME: &str = include_str!("rust-proc-macro/lib.rs");
+
+    fn blog_post() -> EntryScaffold {
+        EntryScaffold::new(
+            "BlogPost",
+            vec![
+                Field::parse("title:String").unwrap(),
+                Field::parse("likes:u32").unwrap(),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn can_convert_names() {
+        assert_eq!(to_snake_case("BlogPost"), "blog_post");
+        assert_eq!(to_snake_case("blog_post"), "blog_post");
+        assert_eq!(to_camel_case("blog_post"), "BlogPost");
+        assert_eq!(to_camel_case("BlogPost"), "BlogPost");
+        assert!(Field::parse("title").is_err());
+        assert!(Field::parse("1title:String").is_err());
+        assert!(Field::parse("title:&str").is_err());
+    }
+
+    #[test]
+    fn can_add_entries_to_declarative_zomes() {
+        let entry = blog_post();
+        assert!(entry
+            .module_code()
+            .contains("pub struct BlogPost {\n    pub title: String,\n    pub likes: u32,\n}"));
+
+        let code = entry.add_to_lib(DECLARATIVE_ZOME).unwrap();
+        assert!(code.contains("pub mod blog_post;\n"));
+        assert!(code.contains("definition(),\n        blog_post::definition()"));
+        assert!(code.contains("handler: blog_post::handle_update"));
+        assert!(code.contains(
+            "hc_public [create_my_entry,get_my_entry,create_blog_post,get_blog_post,update_blog_post,remove_blog_post]"
+        ));
+        assert!(entry.add_to_lib(&code).is_err());
+    }
+
+    #[test]
+    fn can_add_entries_to_procedural_zomes() {
+        let code = blog_post().add_to_lib(PROCEDURAL_ZOME).unwrap();
+        assert!(code.contains("pub mod blog_post;\n"));
+        assert!(code.contains(
+            "fn blog_post_entry_def() -> ValidatingEntryType {\n        crate::blog_post::definition()"
+        ));
+        assert!(code.contains(
+            "fn create_blog_post(entry: crate::blog_post::BlogPost) -> ZomeApiResult<Address> {"
+        ));
+        assert!(code.contains(
+            "fn get_blog_post(address: Address) -> ZomeApiResult<crate::blog_post::BlogPost> {\n        crate::blog_post::handle_get(address)"
+        ));
+        assert!(code.contains(
+            "hdk::get_entry(&address)\n    }\n\n    #[entry_def]\n    fn blog_post_entry_def()"
+        ));
+        assert!(code.ends_with("crate::blog_post::handle_remove(address)\n    }\n\n}\n"));
+    }
+
+    #[test]
+    fn generates_parseable_procedural_zomes() {
+        let entry = blog_post();
+        let code = entry.add_to_lib(PROCEDURAL_ZOME).unwrap();
+        let code = LinkScaffold::new("blog_post", "BlogPost", "related")
+            .unwrap()
+            .add_to_lib(&code, Some("blog_post"), "crate::blog_post::BlogPost")
+            .unwrap();
+        let file = syn::parse_file(&code).unwrap();
+        let zome = file
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Mod(module) => module.content.as_ref(),
+                _ => None,
+            })
+            .next()
+            .expect("zome module");
+        let functions: Vec<String> = zome
+            .1
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Fn(function) => Some(function.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+        for name in vec![
+            "blog_post_entry_def",
+            "create_blog_post",
+            "remove_blog_post",
+            "link_related",
+            "get_related_links",
+        ] {
+            assert!(functions.contains(&name.to_string()), "missing {}", name);
+        }
+        assert!(code.contains("crate::blog_post::handle_link_related(base, target)"));
+        assert!(syn::parse_file(&entry.module_code()).is_ok());
+    }
+
+    #[test]
+    fn can_add_links() {
+        let link = LinkScaffold::new("blog_post", "Comment", "comments").unwrap();
+        let module = blog_post().module_code();
+        assert!(link.defines_base(&module));
+        assert!(!link.defines_target(&module));
+
+        let module = link
+            .add_to_base(&module, "crate::comment::Comment")
+            .unwrap();
+        assert!(module.contains("links: [\n            to!(\n                \"comment\""));
+        assert!(module.contains("pub fn handle_get_comments_links("));
+        assert!(link
+            .add_to_base(&module, "crate::comment::Comment")
+            .is_err());
+
+        // entry definitions without links get a links section
+        let code = link
+            .add_to_base(
+                &DECLARATIVE_ZOME.replace("\"my_entry\"", "\"blog_post\""),
+                "Comment",
+            )
+            .unwrap();
+        assert!(code.contains("Ok(())\n        },\n\n        links: [\n            to!("));
+
+        let code = link
+            .add_to_lib(
+                DECLARATIVE_ZOME,
+                Some("blog_post"),
+                "crate::comment::Comment",
+            )
+            .unwrap();
+        assert!(code.contains("inputs: |base: Address, target: Address|"));
+        assert!(code.contains("handler: blog_post::handle_link_comments"));
+    }
+
+    #[test]
+    fn can_add_scenarios() {
+        let tests = "const x = 1\n\ndiorama.run()\n";
+        let tests = blog_post().add_scenario(tests, "blog").unwrap();
+        assert!(tests.contains(r#"const entry = {"title": "sample title", "likes": 1}"#));
+        assert!(tests.contains(r#"alice.call("blog", "create_blog_post", { entry })"#));
+        assert!(tests.contains(r#"const modified = {"title": "modified title", "likes": 2}"#));
+        assert!(tests.contains(r#"t.deepEqual(updatedResult, { Ok: modified })"#));
+        assert!(tests.ends_with("})\n\ndiorama.run()\n"));
+    }
+}
@@ -0,0 +1,108 @@
+use error::DefaultResult;
+use holochain_conductor_api::{
+    encrypted_storage::{decrypt_storage_dir, encrypt_storage_dir, StorageKey},
+    key_loaders::mock_passphrase_manager,
+    keystore::Keystore,
+};
+use rpassword;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// Whether `storage` converts a storage directory into its encrypted or its decrypted form.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageConversion {
+    Encrypt,
+    Decrypt,
+}
+
+/// Writes an encrypted or decrypted copy of the file storage at `path` to `output`.
+/// The key gets derived from the root seed in the given keystore,
+/// the same way the conductor does for instances with `encrypt_storage` set.
+pub fn storage(
+    conversion: StorageConversion,
+    path: PathBuf,
+    output: PathBuf,
+    keystore_path: PathBuf,
+    passphrase: Option<String>,
+) -> DefaultResult<()> {
+    if output.exists() && output.read_dir()?.next().is_some() {
+        bail!("Output directory {} is not empty", output.display());
+    }
+
+    let passphrase = passphrase.unwrap_or_else(|| {
+        print!("Passphrase for {}: ", keystore_path.display());
+        io::stdout().flush().expect("Could not flush stdout");
+        rpassword::read_password().unwrap()
+    });
+    let mut keystore =
+        Keystore::new_from_file(keystore_path, mock_passphrase_manager(passphrase), None)?;
+    let key = StorageKey::from_keystore(&mut keystore)?;
+
+    let count = match conversion {
+        StorageConversion::Encrypt => encrypt_storage_dir(&path, &output, &key)?,
+        StorageConversion::Decrypt => decrypt_storage_dir(&path, &output, &key)?,
+    };
+    println!(
+        "{} {} records from {} to {}",
+        match conversion {
+            StorageConversion::Encrypt => "Encrypted",
+            StorageConversion::Decrypt => "Decrypted",
+        },
+        count,
+        path.display(),
+        output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+    use cli::keygen;
+
+    #[test]
+    fn storage_encrypt_decrypt_roundtrip() {
+        let dir = tempdir().unwrap();
+        let keystore_path = dir.path().join("test.key");
+        let passphrase = String::from("secret");
+        keygen(Some(keystore_path.clone()), Some(passphrase.clone()), true)
+            .expect("Keygen should work");
+
+        let plain = dir.path().join("plain");
+        let encrypted = dir.path().join("encrypted");
+        let decrypted = dir.path().join("decrypted");
+        std::fs::create_dir_all(plain.join("cas")).unwrap();
+        std::fs::create_dir_all(plain.join("eav")).unwrap();
+
+        storage(
+            StorageConversion::Encrypt,
+            plain.clone(),
+            encrypted.clone(),
+            keystore_path.clone(),
+            Some(passphrase.clone()),
+        )
+        .expect("Encrypting should work");
+        storage(
+            StorageConversion::Decrypt,
+            encrypted.clone(),
+            decrypted.clone(),
+            keystore_path.clone(),
+            Some(passphrase.clone()),
+        )
+        .expect("Decrypting should work");
+        assert!(decrypted.join("cas").is_dir());
+
+        assert!(storage(
+            StorageConversion::Encrypt,
+            plain,
+            encrypted,
+            keystore_path,
+            Some(passphrase),
+        )
+        .is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{cli::package, error::DefaultResult, util};
 use colored::*;
 use failure::Error;
+use holochain_common::env_vars::EnvVar;
 use std::{
     io::ErrorKind,
     path::PathBuf,
@@ -52,7 +53,7 @@ pub fn test(
             "Packaging".green().bold(),
             &file_path
         );
-        package(true, file_path)?;
+        package(true, false, file_path)?;
     }
 
     // build tests
@@ -90,6 +91,42 @@ pub fn test(
     Ok(())
 }
 
+/// Runs the Rust scenarios in the tests folder, which has to be a Cargo project
+/// using `test_utils::scenario`, against the packaged DNA.
+/// Scenarios are plain `#[test]` functions, so `cargo test` discovers and runs them.
+pub fn test_rust(path: &PathBuf, tests_folder: &str, skip_build: bool) -> DefaultResult<()> {
+    let file_path = util::std_package_path(path)?;
+    if !skip_build {
+        println!(
+            "{} files for testing to file: {:?}",
+            "Packaging".green().bold(),
+            &file_path
+        );
+        package(true, false, file_path.clone())?;
+    }
+
+    let tests_path = path.join(&tests_folder);
+    ensure!(
+        tests_path.join("Cargo.toml").exists(),
+        "Directory {} does not contain a Cargo project with Rust scenarios",
+        tests_folder
+    );
+
+    println!(
+        "{} Rust scenarios in {}",
+        "Running".green().bold(),
+        tests_folder
+    );
+    let status = Command::new("cargo")
+        .arg("test")
+        .env(EnvVar::ScenarioDnaPath.as_str(), &file_path)
+        .current_dir(tests_path)
+        .status()?;
+    ensure!(status.success(), "Rust scenarios failed");
+
+    Ok(())
+}
+
 #[cfg(test)]
 #[cfg(feature = "broken-tests")]
 pub mod tests {
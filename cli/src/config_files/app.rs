@@ -30,7 +30,7 @@ impl Default for App {
                 public_key_source: "".into(),
                 signature: "".into(),
             }],
-            dht: Dht {},
+            dht: Dht::default(),
             properties: Default::default(),
         }
     }
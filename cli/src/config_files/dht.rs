@@ -1,2 +1,3 @@
-#[derive(Serialize, Deserialize, Clone)]
-pub struct Dht {}
+/// DHT parameters of the app, see `DhtConfig` for their meaning.
+/// They end up in the DNA and are therefore part of its hash.
+pub use holochain_core_types::dna::dht::DhtConfig as Dht;
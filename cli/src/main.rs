@@ -31,6 +31,46 @@ use crate::error::{HolochainError, HolochainResult};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
+#[derive(StructOpt)]
+enum Generate {
+    #[structopt(
+        name = "entry",
+        about = "Adds an entry type with CRUD zome functions and a scenario test to a Rust zome"
+    )]
+    Entry {
+        #[structopt(
+            help = "The path to the zome (usually in ./zomes/)",
+            parse(from_os_str)
+        )]
+        zome: PathBuf,
+        #[structopt(help = "The name of the entry type, e.g. BlogPost")]
+        entry_type: String,
+        #[structopt(
+            long,
+            help = "The fields of the entry as name:Type, e.g. --fields title:String,likes:u32",
+            raw(use_delimiter = "true")
+        )]
+        fields: Vec<String>,
+    },
+    #[structopt(
+        name = "link",
+        about = "Adds a link type between two entry types of a Rust zome"
+    )]
+    Link {
+        #[structopt(
+            help = "The path to the zome (usually in ./zomes/)",
+            parse(from_os_str)
+        )]
+        zome: PathBuf,
+        #[structopt(help = "The entry type to link from")]
+        base: String,
+        #[structopt(help = "The entry type to link to")]
+        target: String,
+        #[structopt(help = "The name of the link type")]
+        link_type: String,
+    },
+}
+
 #[derive(StructOpt)]
 #[structopt(about = "A command line for Holochain")]
 enum Cli {
@@ -45,9 +85,33 @@ enum Cli {
             help = "Strips all __META__ sections off the target bundle. Makes unpacking of the bundle impossible"
         )]
         strip_meta: bool,
+        #[structopt(
+            long = "binary",
+            help = "Writes the DNA in the compact binary format instead of JSON. Implies --strip-meta"
+        )]
+        binary: bool,
         #[structopt(long = "output", short = "o", parse(from_os_str))]
         output: Option<PathBuf>,
     },
+    #[structopt(
+        name = "bundle",
+        about = "Builds a hApp bundle from a manifest listing DNAs, instances, bridges and UIs"
+    )]
+    Bundle {
+        #[structopt(
+            help = "Path to the hApp bundle manifest",
+            default_value = "happ.toml",
+            parse(from_os_str)
+        )]
+        manifest: PathBuf,
+        #[structopt(
+            long = "output",
+            short = "o",
+            help = "Directory to create the bundle in [default: ./dist/<happ-name>.happ]",
+            parse(from_os_str)
+        )]
+        output: Option<PathBuf>,
+    },
     #[structopt(
         name = "unpack",
         about = "Unpacks a Holochain bundle into it's original file system structure"
@@ -70,16 +134,20 @@ enum Cli {
     #[structopt(
         name = "generate",
         alias = "g",
-        about = "Generates a new zome and scaffolds the given functions"
+        about = "Generates a new zome, or entry types and links in an existing Rust zome",
+        raw(setting = "structopt::clap::AppSettings::SubcommandsNegateReqs")
     )]
     Generate {
         #[structopt(
             help = "The path to the zome that should be generated (usually in ./zomes/)",
-            parse(from_os_str)
+            parse(from_os_str),
+            raw(required = "true")
         )]
-        zome: PathBuf,
+        zome: Option<PathBuf>,
         #[structopt(help = "The language of the generated zome", default_value = "rust")]
         language: String,
+        #[structopt(subcommand)]
+        code: Option<Generate>,
     },
     #[structopt(
         name = "run",
@@ -148,6 +216,11 @@ enum Cli {
             help = "Show NPM output when installing test dependencies"
         )]
         show_npm_output: bool,
+        #[structopt(
+            long = "rust",
+            help = "Run the Rust scenarios in the test folder with cargo instead of the JavaScript tests"
+        )]
+        rust: bool,
     },
     #[structopt(
         name = "keygen",
@@ -187,6 +260,86 @@ enum Cli {
         )]
         path: Option<PathBuf>,
     },
+    #[structopt(
+        name = "lint",
+        about = "Statically checks a DNA file for definitions that can not work at runtime"
+    )]
+    Lint {
+        #[structopt(
+            long,
+            short,
+            help = "Path to .dna.json file [default: dist/<dna-name>.dna.json]"
+        )]
+        path: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "replay-journal",
+        about = "Replays an instance's action journal into a blank instance and checks that it reproduces the journaled state"
+    )]
+    ReplayJournal {
+        #[structopt(
+            name = "PATH",
+            help = "Action journal file of the instance",
+            parse(from_os_str)
+        )]
+        path: PathBuf,
+    },
+    #[structopt(
+        name = "encrypt-storage",
+        about = "Writes an encrypted copy of an instance's file storage directory, using the storage key derived from the agent's keystore"
+    )]
+    EncryptStorage {
+        #[structopt(
+            name = "PATH",
+            help = "Storage directory to encrypt",
+            parse(from_os_str)
+        )]
+        path: PathBuf,
+        #[structopt(
+            long,
+            short,
+            help = "Directory to write the encrypted storage to",
+            parse(from_os_str)
+        )]
+        output: PathBuf,
+        #[structopt(
+            long,
+            short,
+            help = "Keystore file of the agent owning the storage",
+            parse(from_os_str)
+        )]
+        keystore: PathBuf,
+        #[structopt(long, short, help = "Don't ask for passphrase")]
+        nullpass: bool,
+    },
+    #[structopt(
+        name = "decrypt-storage",
+        about = "Writes a decrypted copy of an instance's file storage directory, using the storage key derived from the agent's keystore"
+    )]
+    DecryptStorage {
+        #[structopt(
+            name = "PATH",
+            help = "Storage directory to decrypt",
+            parse(from_os_str)
+        )]
+        path: PathBuf,
+        #[structopt(
+            long,
+            short,
+            help = "Directory to write the decrypted storage to",
+            parse(from_os_str)
+        )]
+        output: PathBuf,
+        #[structopt(
+            long,
+            short,
+            help = "Keystore file of the agent owning the storage",
+            parse(from_os_str)
+        )]
+        keystore: PathBuf,
+        #[structopt(long, short, help = "Don't ask for passphrase")]
+        nullpass: bool,
+    },
 }
 
 fn main() {
@@ -205,22 +358,53 @@ fn run() -> HolochainResult<()> {
         std::env::current_dir().map_err(|e| HolochainError::Default(format_err!("{}", e)))?;
     match args {
         // If using default path, we'll create if necessary; otherwise, target dir must exist
-        Cli::Package { strip_meta, output } => {
+        Cli::Package {
+            strip_meta,
+            binary,
+            output,
+        } => {
             let output = if output.is_some() {
                 output.unwrap()
+            } else if binary {
+                util::std_binary_package_path(&project_path).map_err(HolochainError::Default)?
             } else {
                 util::std_package_path(&project_path).map_err(HolochainError::Default)?
             };
-            cli::package(strip_meta, output).map_err(HolochainError::Default)?
+            cli::package(strip_meta, binary, output).map_err(HolochainError::Default)?
+        }
+
+        Cli::Bundle { manifest, output } => {
+            cli::bundle(&manifest, output).map_err(HolochainError::Default)?;
         }
 
         Cli::Unpack { path, to } => cli::unpack(&path, &to).map_err(HolochainError::Default)?,
 
         Cli::Init { path } => cli::init(&path).map_err(HolochainError::Default)?,
 
-        Cli::Generate { zome, language } => {
-            cli::generate(&zome, &language).map_err(HolochainError::Default)?
-        }
+        Cli::Generate {
+            zome,
+            language,
+            code,
+        } => match code {
+            Some(Generate::Entry {
+                zome,
+                entry_type,
+                fields,
+            }) => {
+                cli::generate_entry(&zome, &entry_type, &fields).map_err(HolochainError::Default)?
+            }
+            Some(Generate::Link {
+                zome,
+                base,
+                target,
+                link_type,
+            }) => cli::generate_link(&zome, &base, &target, &link_type)
+                .map_err(HolochainError::Default)?,
+            None => {
+                let zome = zome.expect("clap requires the zome without subcommand");
+                cli::generate(&zome, &language).map_err(HolochainError::Default)?
+            }
+        },
 
         Cli::Run {
             package,
@@ -252,10 +436,15 @@ fn run() -> HolochainResult<()> {
             testfile,
             skip_build,
             show_npm_output,
+            rust,
         } => {
             let current_path = std::env::current_dir()
                 .map_err(|e| HolochainError::Default(format_err!("{}", e)))?;
-            cli::test(&current_path, &dir, &testfile, skip_build, show_npm_output)
+            if rust {
+                cli::test_rust(&current_path, &dir, skip_build)
+            } else {
+                cli::test(&current_path, &dir, &testfile, skip_build, show_npm_output)
+            }
         }
         .map_err(HolochainError::Default)?,
 
@@ -273,6 +462,48 @@ fn run() -> HolochainResult<()> {
                 .map_err(|e| HolochainError::Default(format_err!("{}", e)))?
         }
 
+        Cli::EncryptStorage {
+            path,
+            output,
+            keystore,
+            nullpass,
+        } => {
+            let passphrase = if nullpass {
+                Some(String::from(holochain_common::DEFAULT_PASSPHRASE))
+            } else {
+                None
+            };
+            cli::storage(
+                cli::StorageConversion::Encrypt,
+                path,
+                output,
+                keystore,
+                passphrase,
+            )
+            .map_err(|e| HolochainError::Default(format_err!("{}", e)))?
+        }
+
+        Cli::DecryptStorage {
+            path,
+            output,
+            keystore,
+            nullpass,
+        } => {
+            let passphrase = if nullpass {
+                Some(String::from(holochain_common::DEFAULT_PASSPHRASE))
+            } else {
+                None
+            };
+            cli::storage(
+                cli::StorageConversion::Decrypt,
+                path,
+                output,
+                keystore,
+                passphrase,
+            )
+            .map_err(|e| HolochainError::Default(format_err!("{}", e)))?
+        }
+
         Cli::ChainLog {
             instance_id,
             list,
@@ -296,6 +527,17 @@ fn run() -> HolochainResult<()> {
                 .map_err(|e| HolochainError::Default(format_err!("{}", e)))?;
             println!("DNA Hash: {}", dna_hash);
         }
+
+        Cli::ReplayJournal { path } => {
+            cli::replay_journal(&path).map_err(HolochainError::Default)?
+        }
+
+        Cli::Lint { path } => {
+            let dna_path = path
+                .unwrap_or(util::std_package_path(&project_path).map_err(HolochainError::Default)?);
+
+            cli::lint(&dna_path).map_err(HolochainError::Default)?
+        }
     }
 
     Ok(())
@@ -1,6 +1,6 @@
 use crate::error::DefaultResult;
 use colored::*;
-pub use holochain_common::paths::DNA_EXTENSION;
+pub use holochain_common::paths::{DNA_BINARY_EXTENSION, DNA_EXTENSION};
 use std::{
     fs,
     io::ErrorKind,
@@ -51,6 +51,12 @@ pub fn std_package_path(path: &PathBuf) -> DefaultResult<PathBuf> {
     Ok(get_dist_path(path)?.join(std_dna_file_name(path)?))
 }
 
+/// Like `std_package_path()` but for DNAs in the compact binary format
+pub fn std_binary_package_path(path: &PathBuf) -> DefaultResult<PathBuf> {
+    let dir_name = file_name_string(path)?;
+    Ok(get_dist_path(path)?.join(format!("{}.{}", dir_name, DNA_BINARY_EXTENSION)))
+}
+
 /// Helper method for obtaining the file name of a path as a String
 pub fn file_name_string(path: &PathBuf) -> DefaultResult<String> {
     let file_name = path
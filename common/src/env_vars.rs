@@ -18,6 +18,10 @@
 //! - **NETWORKING_CONFIG_FILE** *string* Path to a JSON file containing configuration for the n3h networking module. More on this soon. Recommended to
 //! not use this as this time.
 //!
+//! ### `hc test`
+//! - **HC_SCENARIO_DNA_PATH** *string* Set by `hc test --rust` to the path of the packaged DNA before it runs `cargo test` in the test directory.
+//! Rust scenarios (see `test_utils::scenario`) read it to know which DNA to start their agents with.
+//!
 //! ### `hc generate`
 //! - HC_SCAFFOLD_VERSION allows you to set a string value to be used in the generated Cargo.toml.  We use this override the default which points to the current version tag, which a pointer to the develop branch for our CI tests, so for example in CI we can run our tests with: `HC_SCAFFOLD_VERSION='branch="develop"'` and that overrides the default.
 //!
@@ -33,6 +37,7 @@ pub enum EnvVar {
     N3hLogLevel,
     NetworkingConfigFile,
     ScaffoldVersion,
+    ScenarioDnaPath,
 }
 
 impl EnvVar {
@@ -46,6 +51,7 @@ impl EnvVar {
             EnvVar::N3hLogLevel => "HC_N3H_LOG_LEVEL",
             EnvVar::NetworkingConfigFile => "NETWORKING_CONFIG_FILE",
             EnvVar::ScaffoldVersion => "HC_SCAFFOLD_VERSION",
+            EnvVar::ScenarioDnaPath => "HC_SCENARIO_DNA_PATH",
         }
     }
 
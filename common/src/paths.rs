@@ -6,6 +6,8 @@ pub const APPLICATION: &str = "holochain";
 pub const KEYS_DIRECTORY: &str = "keys";
 pub const N3H_BINARIES_DIRECTORY: &str = "n3h-binaries";
 pub const DNA_EXTENSION: &str = "dna.json";
+/// Extension of DNA files in the compact binary format
+pub const DNA_BINARY_EXTENSION: &str = "dna";
 
 /// Returns the project root builder for holochain directories.
 pub fn project_root() -> Option<directories::ProjectDirs> {
@@ -27,7 +27,7 @@ use holochain_conductor_api::{
 };
 use holochain_core_types::error::HolochainError;
 #[cfg(unix)]
-use signal_hook::{iterator::Signals, SIGINT, SIGTERM};
+use signal_hook::{iterator::Signals, SIGHUP, SIGINT, SIGTERM};
 use std::{fs::File, io::prelude::*, path::PathBuf, sync::Arc};
 use structopt::StructOpt;
 
@@ -89,11 +89,27 @@ fn main() {
             match SignalConfiguration::default() {
                 #[cfg(unix)]
                 SignalConfiguration::Unix => {
-                    let termination_signals =
-                        Signals::new(&[SIGINT, SIGTERM]).expect("Couldn't create signals list");
+                    let signals = Signals::new(&[SIGHUP, SIGINT, SIGTERM])
+                        .expect("Couldn't create signals list");
 
-                    // Wait forever until we get one of the signals defined above
-                    let _sig = termination_signals.forever().next();
+                    // Wait forever until we get one of the termination signals defined above.
+                    // SIGHUP makes the conductor reload its config file and apply the changes.
+                    for signal in signals.forever() {
+                        if signal != SIGHUP {
+                            break;
+                        }
+                        println!("Received SIGHUP, reloading config from {}", config_path_str);
+                        let mut conductor_guard = CONDUCTOR.lock().unwrap();
+                        let conductor =
+                            conductor_guard.as_mut().expect("Conductor must be mounted");
+                        match conductor.reload_config_from_file(Some(config_path.clone())) {
+                            Ok(diff) => println!("Config reloaded: {:?}", diff),
+                            Err(error) => println!(
+                                "Error while trying to reload config, keeping the running one: {}",
+                                error
+                            ),
+                        }
+                    }
 
                     // So we're here because we received a shutdown signal.
                     // Let's shut down.
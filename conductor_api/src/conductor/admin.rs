@@ -7,9 +7,13 @@ use crate::{
     dpki_instance::DpkiInstance,
     keystore::{Keystore, PRIMARY_KEYBUNDLE_ID},
 };
-use holochain_core_types::error::HolochainError;
+use holochain_core::state_dump::PendingValidationDump;
+use holochain_core_types::{dna::Dna, error::HolochainError};
 
-use holochain_persistence_api::{cas::content::AddressableContent, hash::HashString};
+use holochain_persistence_api::{
+    cas::content::{Address, AddressableContent},
+    hash::HashString,
+};
 
 use json_patch;
 use std::{
@@ -33,6 +37,27 @@ pub trait ConductorAdmin {
         properties: Option<&serde_json::Value>,
         uuid: Option<String>,
     ) -> Result<HashString, HolochainError>;
+    fn install_dna_from_bytes(
+        &mut self,
+        bytes: Vec<u8>,
+        id: String,
+        expected_hash: Option<HashString>,
+        properties: Option<&serde_json::Value>,
+        uuid: Option<String>,
+    ) -> Result<HashString, HolochainError>;
+    fn add_dna_upload_chunk(
+        &mut self,
+        upload_id: &String,
+        chunk: Vec<u8>,
+    ) -> Result<usize, HolochainError>;
+    fn install_dna_from_upload(
+        &mut self,
+        upload_id: &String,
+        id: String,
+        expected_hash: Option<HashString>,
+        properties: Option<&serde_json::Value>,
+        uuid: Option<String>,
+    ) -> Result<HashString, HolochainError>;
     fn uninstall_dna(&mut self, id: &String) -> Result<(), HolochainError>;
     fn add_instance(
         &mut self,
@@ -68,6 +93,15 @@ pub trait ConductorAdmin {
         caller_id: &String,
         callee_id: &String,
     ) -> Result<(), HolochainError>;
+    fn pending_validations(
+        &self,
+        instance_id: &String,
+    ) -> Result<Vec<PendingValidationDump>, HolochainError>;
+    fn purge_pending_validations(
+        &mut self,
+        instance_id: &String,
+        address: Option<Address>,
+    ) -> Result<Vec<Address>, HolochainError>;
 }
 
 impl ConductorAdmin for Conductor {
@@ -91,7 +125,7 @@ impl ConductorAdmin for Conductor {
         let path_string = path
             .to_str()
             .ok_or_else(|| HolochainError::ConfigError("invalid path".into()))?;
-        let mut dna =
+        let dna =
             Arc::get_mut(&mut self.dna_loader).unwrap()(&path_string.into()).map_err(|e| {
                 HolochainError::ConfigError(format!(
                     "Could not load DNA file \"{}\", Error: {}",
@@ -100,47 +134,78 @@ impl ConductorAdmin for Conductor {
                 ))
             })?;
 
-        if let Some(provided_hash) = expected_hash {
-            let actual_hash = dna.address();
-            if actual_hash != provided_hash {
-                return Err(HolochainError::DnaHashMismatch(provided_hash, actual_hash));
-            }
-        }
-
-        if let Some(props) = properties {
-            if !copy {
-                return Err(HolochainError::ConfigError(
-                    "Cannot install DNA with properties unless copy flag is true".into(),
-                ));
-            }
-            json_patch::merge(&mut dna.properties, &props);
-        }
-
-        if let Some(uuid) = uuid {
-            dna.uuid = uuid;
+        if properties.is_some() && !copy {
+            return Err(HolochainError::ConfigError(
+                "Cannot install DNA with properties unless copy flag is true".into(),
+            ));
         }
 
-        let config_path = match copy {
-            true => self.save_dna(&dna)?,
-            false => PathBuf::from(path_string),
+        let file = match copy {
+            true => None,
+            false => Some(PathBuf::from(path_string)),
         };
-        let config_path_str = config_path
-            .to_str()
-            .ok_or_else(|| HolochainError::ConfigError("invalid path".into()))?;
+        self.install_dna(dna, id, file, expected_hash, properties, uuid, path_string)
+    }
 
-        let new_dna = DnaConfiguration {
-            id: id.clone(),
-            file: config_path_str.into(),
-            hash: dna.address().to_string(),
-        };
+    /// Installs a DNA package that was handed to the conductor as raw bytes of a `.dna.json`
+    /// file, for instance by a remote management tool through the admin interface.
+    ///
+    /// Hash checking, properties merging and uuid override work the same as with
+    /// `install_dna_from_file()`. Since there is no file to refer to, the DNA always gets
+    /// saved to the conductor's DNA store.
+    fn install_dna_from_bytes(
+        &mut self,
+        bytes: Vec<u8>,
+        id: String,
+        expected_hash: Option<HashString>,
+        properties: Option<&serde_json::Value>,
+        uuid: Option<String>,
+    ) -> Result<HashString, HolochainError> {
+        let dna = Conductor::dna_from_bytes(&bytes).map_err(|e| {
+            HolochainError::ConfigError(format!(
+                "Could not load uploaded DNA, Error: {}",
+                e.to_string()
+            ))
+        })?;
+        self.install_dna(
+            dna,
+            id,
+            None,
+            expected_hash,
+            properties,
+            uuid,
+            "uploaded bytes",
+        )
+    }
 
-        let mut new_config = self.config.clone();
-        new_config.dnas.push(new_dna.clone());
-        new_config.check_consistency(&mut self.dna_loader)?;
-        self.config = new_config;
-        self.save_config()?;
-        notify(format!("Installed DNA from {} as \"{}\"", path_string, id));
-        Ok(dna.address())
+    /// Appends a chunk of bytes to the pending DNA upload with the given ID.
+    /// Creates the upload if it does not exist yet and returns its total size so far.
+    /// Fails if too many uploads are open or they would get too big all together
+    /// (see [DnaUploads](../dna_uploads/struct.DnaUploads.html)).
+    /// Once all chunks are received the upload can be installed with
+    /// `install_dna_from_upload()`.
+    fn add_dna_upload_chunk(
+        &mut self,
+        upload_id: &String,
+        chunk: Vec<u8>,
+    ) -> Result<usize, HolochainError> {
+        self.dna_uploads.add_chunk(upload_id, chunk)
+    }
+
+    /// Installs the DNA from a chunked upload (see `add_dna_upload_chunk()`).
+    /// The upload gets discarded afterwards, regardless of whether the installation succeeded.
+    fn install_dna_from_upload(
+        &mut self,
+        upload_id: &String,
+        id: String,
+        expected_hash: Option<HashString>,
+        properties: Option<&serde_json::Value>,
+        uuid: Option<String>,
+    ) -> Result<HashString, HolochainError> {
+        let bytes = self.dna_uploads.take(upload_id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("No such DNA upload: '{}'", upload_id))
+        })?;
+        self.install_dna_from_bytes(bytes, id, expected_hash, properties, uuid)
     }
 
     /// Removes the DNA given by id from the config.
@@ -208,6 +273,9 @@ impl ConductorAdmin for Conductor {
                     })?
                     .into(),
             },
+            warrant_policy: None,
+            action_journal: None,
+            encrypt_storage: false,
         };
         new_config.instances.push(new_instance_config);
         new_config.check_consistency(&mut self.dna_loader)?;
@@ -244,6 +312,7 @@ impl ConductorAdmin for Conductor {
         if let Some(instance) = self.instances.remove(id) {
             instance.write().unwrap().kill();
         }
+        self.state_subscriptions.write().unwrap().remove(id);
         let _ = self.start_signal_multiplexer();
 
         notify(format!("Removed instance \"{}\".", id));
@@ -585,6 +654,89 @@ impl ConductorAdmin for Conductor {
 
         Ok(())
     }
+
+    /// Lists the validations an instance could not finish yet, with their dependencies,
+    /// the number of attempts so far and when they will be retried next.
+    fn pending_validations(
+        &self,
+        instance_id: &String,
+    ) -> Result<Vec<PendingValidationDump>, HolochainError> {
+        let hc = self.instances.get(instance_id)?;
+        Ok(hc.read().unwrap().pending_validations()?)
+    }
+
+    /// Drops stuck pending validations of an instance, either all of them or only
+    /// the ones for the given entry address.
+    fn purge_pending_validations(
+        &mut self,
+        instance_id: &String,
+        address: Option<Address>,
+    ) -> Result<Vec<Address>, HolochainError> {
+        let hc = self.instances.get(instance_id)?;
+        let purged = hc
+            .read()
+            .unwrap()
+            .purge_pending_validations(address.as_ref())?;
+        notify(format!(
+            "Purged {} pending validation(s) of instance \"{}\"",
+            purged.len(),
+            instance_id
+        ));
+        Ok(purged)
+    }
+}
+
+impl Conductor {
+    /// Shared tail of all DNA installation functions:
+    /// checks the expected hash, applies properties and uuid, stores the DNA in the
+    /// DNA store if no file is given, and adds it to the config.
+    fn install_dna(
+        &mut self,
+        mut dna: Dna,
+        id: String,
+        file: Option<PathBuf>,
+        expected_hash: Option<HashString>,
+        properties: Option<&serde_json::Value>,
+        uuid: Option<String>,
+        source: &str,
+    ) -> Result<HashString, HolochainError> {
+        if let Some(provided_hash) = expected_hash {
+            let actual_hash = dna.address();
+            if actual_hash != provided_hash {
+                return Err(HolochainError::DnaHashMismatch(provided_hash, actual_hash));
+            }
+        }
+
+        if let Some(props) = properties {
+            json_patch::merge(&mut dna.properties, &props);
+        }
+
+        if let Some(uuid) = uuid {
+            dna.uuid = uuid;
+        }
+
+        let config_path = match file {
+            Some(path) => path,
+            None => self.save_dna(&dna)?,
+        };
+        let config_path_str = config_path
+            .to_str()
+            .ok_or_else(|| HolochainError::ConfigError("invalid path".into()))?;
+
+        let new_dna = DnaConfiguration {
+            id: id.clone(),
+            file: config_path_str.into(),
+            hash: dna.address().to_string(),
+        };
+
+        let mut new_config = self.config.clone();
+        new_config.dnas.push(new_dna.clone());
+        new_config.check_consistency(&mut self.dna_loader)?;
+        self.config = new_config;
+        self.save_config()?;
+        notify(format!("Installed DNA from {} as \"{}\"", source, id));
+        Ok(dna.address())
+    }
 }
 
 #[cfg(test)]
@@ -595,6 +747,7 @@ pub mod tests {
             tests::{example_dna_string, test_key_loader, test_keybundle},
             DnaLoader,
         },
+        conductor::debug::ConductorDebug,
         config::{load_configuration, Configuration, InterfaceConfiguration, InterfaceDriver},
         key_loaders::mock_passphrase_manager,
         keystore::test_hash_config,
@@ -1089,6 +1242,119 @@ id = 'new-dna'"#,
         assert!(output_dna_file.is_file())
     }
 
+    #[test]
+    fn test_install_dna_from_bytes() {
+        let test_name = "test_install_dna_from_bytes";
+        let mut conductor = create_test_conductor(test_name, 3000);
+
+        let bytes = example_dna_string().into_bytes();
+        let dna = Conductor::dna_from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            conductor.install_dna_from_bytes(
+                bytes.clone(),
+                String::from("new-dna"),
+                Some("wrong-address".into()),
+                None,
+                None,
+            ),
+            Err(HolochainError::DnaHashMismatch(
+                "wrong-address".into(),
+                dna.address(),
+            )),
+        );
+
+        assert_eq!(
+            conductor.install_dna_from_bytes(
+                bytes.clone(),
+                String::from("new-dna"),
+                Some(dna.address()),
+                None,
+                None,
+            ),
+            Ok(dna.address()),
+        );
+
+        // Installing the same DNA again under a different ID re-uses the stored file
+        assert_eq!(
+            conductor.install_dna_from_bytes(
+                bytes.clone(),
+                String::from("new-dna-again"),
+                None,
+                None,
+                None,
+            ),
+            Ok(dna.address()),
+        );
+
+        let mut output_dna_file = current_dir()
+            .expect("Could not get current dir")
+            .join("tmp-test")
+            .join(test_name)
+            .join("dna");
+        output_dna_file.push(dna.address().to_string());
+        output_dna_file.set_extension(DNA_EXTENSION);
+
+        let dnas = conductor.config().dnas;
+        assert_eq!(dnas.len(), 3);
+        assert_eq!(dnas[1].file, output_dna_file.to_str().unwrap().to_string());
+        assert_eq!(dnas[2].file, dnas[1].file);
+        assert!(output_dna_file.is_file());
+        assert_eq!(
+            fs::read_dir(output_dna_file.parent().unwrap())
+                .unwrap()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_install_dna_from_chunked_upload() {
+        let test_name = "test_install_dna_from_chunked_upload";
+        let mut conductor = create_test_conductor(test_name, 3000);
+
+        let bytes = example_dna_string().into_bytes();
+        let dna = Conductor::dna_from_bytes(&bytes).unwrap();
+        let upload_id = String::from("upload-1");
+        let (first, second) = bytes.split_at(bytes.len() / 2);
+
+        assert_eq!(
+            conductor.add_dna_upload_chunk(&upload_id, first.to_vec()),
+            Ok(first.len()),
+        );
+        assert_eq!(
+            conductor.add_dna_upload_chunk(&upload_id, second.to_vec()),
+            Ok(bytes.len()),
+        );
+
+        let uuid = String::from("uuid");
+        let result = conductor.install_dna_from_upload(
+            &upload_id,
+            String::from("new-dna"),
+            None,
+            None,
+            Some(uuid.clone()),
+        );
+        let mut expected_dna = dna.clone();
+        expected_dna.uuid = uuid;
+        assert_eq!(result, Ok(expected_dna.address()));
+        assert_eq!(conductor.config().dnas.len(), 2);
+
+        // The upload is consumed by installing it
+        assert_eq!(
+            conductor.install_dna_from_upload(
+                &upload_id,
+                String::from("new-dna-2"),
+                None,
+                None,
+                None,
+            ),
+            Err(HolochainError::ErrorGeneric(
+                "No such DNA upload: 'upload-1'".into()
+            )),
+        );
+    }
+
     #[test]
     fn test_add_instance() {
         let test_name = "test_add_instance";
@@ -1365,6 +1631,51 @@ type = 'http'"#,
             .is_none());
     }
 
+    #[test]
+    fn test_remove_interface_removes_state_subscriptions() {
+        let test_name = "test_remove_interface_removes_state_subscriptions";
+        let mut conductor = create_test_conductor(test_name, 3012);
+        let interface_id = String::from("websocket interface");
+        let instance_id = String::from("test-instance-1");
+
+        conductor.start_all_interfaces();
+        conductor
+            .subscribe_state(&instance_id, &interface_id)
+            .expect("Could not subscribe to state changes");
+        assert!(conductor
+            .state_subscriptions
+            .read()
+            .unwrap()
+            .get(&instance_id)
+            .unwrap()
+            .contains(&interface_id));
+
+        assert_eq!(conductor.remove_interface(&interface_id), Ok(()));
+
+        assert!(conductor.state_subscriptions.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pending_validations_of_instances() {
+        let test_name = "test_pending_validations_of_instances";
+        let mut conductor = create_test_conductor(test_name, 3013);
+        let instance_id = String::from("test-instance-1");
+
+        assert_eq!(
+            conductor
+                .pending_validations(&instance_id)
+                .map(|pending| pending.len()),
+            Ok(0)
+        );
+        assert_eq!(
+            conductor.purge_pending_validations(&instance_id, None),
+            Ok(Vec::new())
+        );
+        assert!(conductor
+            .pending_validations(&String::from("no-such-instance"))
+            .is_err());
+    }
+
     #[test]
     fn test_add_instance_to_interface() {
         let test_name = "test_add_instance_to_interface";
@@ -1,21 +1,28 @@
 use crate::{
-    conductor::broadcaster::Broadcaster,
+    conductor::{broadcaster::Broadcaster, dna_uploads::DnaUploads},
     config::{
         serialize_configuration, Configuration, InterfaceConfiguration, InterfaceDriver,
         NetworkConfig, StorageConfiguration,
     },
     context_builder::ContextBuilder,
     dpki_instance::DpkiInstance,
+    encrypted_storage::StorageKey,
     error::HolochainInstanceError,
     keystore::{Keystore, PRIMARY_KEYBUNDLE_ID},
     Holochain,
 };
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use holochain_common::paths::DNA_EXTENSION;
-use holochain_core::{logger::Logger, signal::Signal};
+use holochain_core::{
+    action_journal::DEFAULT_CHECKPOINT_INTERVAL,
+    consistency::{await_consistency, ConsistencyTracker},
+    logger::Logger,
+    signal::Signal,
+    state_dump::StateChange,
+};
 use holochain_core_types::{
     agent::AgentId,
-    dna::Dna,
+    dna::{binary::is_binary_dna, Dna},
     error::{HcResult, HolochainError},
 };
 use key_loaders::test_keystore;
@@ -28,7 +35,7 @@ use jsonrpc_ws_server::jsonrpc_core::IoHandler;
 use logging::{rule::RuleFilter, FastLogger, FastLoggerBuilder};
 use std::{
     clone::Clone,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     fs::{self, File},
     io::prelude::*,
@@ -54,7 +61,7 @@ use holochain_net::{
     p2p_network::P2pNetwork,
 };
 use interface::{ConductorApiBuilder, InstanceMap, Interface};
-use signal_wrapper::SignalWrapper;
+use signal_wrapper::{SignalWrapper, StateChangeWrapper};
 use static_file_server::ConductorStaticFileServer;
 use static_server_impls::NickelStaticServer as StaticServer;
 
@@ -99,10 +106,17 @@ pub struct Conductor {
     pub(in crate::conductor) static_servers: HashMap<String, StaticServer>,
     pub(in crate::conductor) interface_threads: HashMap<String, Sender<()>>,
     pub(in crate::conductor) interface_broadcasters: Arc<RwLock<HashMap<String, Broadcaster>>>,
+    /// IDs of the interfaces that subscribed to state changes via `debug/subscribe_state`,
+    /// keyed by instance ID
+    pub(in crate::conductor) state_subscriptions: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+    /// Matches the consistency signals of all instances, keyed by instance ID
+    consistency_tracker: Arc<Mutex<ConsistencyTracker>>,
     signal_multiplexer_kill_switch: Option<Sender<()>>,
     pub key_loader: KeyLoader,
     pub(in crate::conductor) dna_loader: DnaLoader,
     pub(in crate::conductor) ui_dir_copier: UiDirCopier,
+    /// Partial DNA uploads received through `admin/dna/upload_chunk`
+    pub(in crate::conductor) dna_uploads: DnaUploads,
     signal_tx: Option<SignalSender>,
     logger: FastLogger,
     p2p_config: Option<P2pConfig>,
@@ -216,11 +230,14 @@ impl Conductor {
             interface_threads: HashMap::new(),
             static_servers: HashMap::new(),
             interface_broadcasters: Arc::new(RwLock::new(HashMap::new())),
+            state_subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            consistency_tracker: Arc::new(Mutex::new(ConsistencyTracker::new())),
             signal_multiplexer_kill_switch: None,
             config,
             key_loader: Arc::new(Box::new(Self::load_key)),
             dna_loader: Arc::new(Box::new(Self::load_dna)),
             ui_dir_copier: Arc::new(Box::new(Self::copy_ui_dir)),
+            dna_uploads: DnaUploads::default(),
             signal_tx: None,
             logger,
             p2p_config: None,
@@ -262,6 +279,8 @@ impl Conductor {
     pub fn start_signal_multiplexer(&mut self) -> thread::JoinHandle<()> {
         self.stop_signal_multiplexer();
         let broadcasters = self.interface_broadcasters.clone();
+        let state_subscriptions = self.state_subscriptions.clone();
+        let consistency_tracker = self.consistency_tracker.clone();
         let instance_signal_receivers = self.instance_signal_receivers.clone();
         let signal_tx = self.signal_tx.clone();
         let config = self.config.clone();
@@ -278,6 +297,39 @@ impl Conductor {
                         if let Ok(signal) = receiver.try_recv() {
                             signal_tx.clone().map(|s| s.send(signal.clone()));
                             let broadcasters = broadcasters.read().unwrap();
+
+                            // Send the state changes an action made to all interfaces
+                            // that subscribed to them:
+                            if let Signal::Trace(ref action_wrapper) = signal {
+                                if let Some(interface_ids) =
+                                    state_subscriptions.read().unwrap().get(instance_id)
+                                {
+                                    for state_change in StateChange::from_action(action_wrapper) {
+                                        for interface_id in interface_ids {
+                                            if let Some(broadcaster) =
+                                                broadcasters.get(interface_id)
+                                            {
+                                                if let Err(error) =
+                                                    broadcaster.send(StateChangeWrapper {
+                                                        state_change: state_change.clone(),
+                                                        instance_id: instance_id.clone(),
+                                                    })
+                                                {
+                                                    notify(error.to_string());
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+
+                            if let Signal::Consistency(ref consistency_signal) = signal {
+                                consistency_tracker
+                                    .lock()
+                                    .unwrap()
+                                    .observe(instance_id, consistency_signal);
+                            }
+
                             let interfaces_with_instance: Vec<&InterfaceConfiguration> =
                                 match signal {
                                     // Send internal signals only to admin interfaces, if signals.trace is set:
@@ -349,6 +401,28 @@ impl Conductor {
             .expect("Must be able to spawn thread")
     }
 
+    /// The tracker behind `await_consistency`, for waiting without holding on to the conductor.
+    pub fn consistency_tracker(&self) -> Arc<Mutex<ConsistencyTracker>> {
+        self.consistency_tracker.clone()
+    }
+
+    /// Blocks until every event that actions of the given instance (or of all instances if
+    /// `None`) caused has been observed on the instances of this conductor.
+    /// Calling this right after a zome call returned waits for all of that call's effects.
+    /// Needs the signal multiplexer to be running.
+    pub fn await_consistency(
+        &self,
+        instance_id: Option<&str>,
+        timeout: Duration,
+    ) -> Result<(), HolochainError> {
+        await_consistency(&self.consistency_tracker, instance_id, timeout).map_err(|pending| {
+            HolochainError::ErrorGeneric(format!(
+                "Instances not consistent after {:?}, still waiting for: {:?}",
+                timeout, pending
+            ))
+        })
+    }
+
     pub fn stop_signal_multiplexer(&self) {
         self.signal_multiplexer_kill_switch
             .as_ref()
@@ -387,9 +461,21 @@ impl Conductor {
             })?;
         }
         self.interface_threads.remove(id);
+        self.remove_state_subscriptions_of_interface(id);
         Ok(())
     }
 
+    /// Stops sending state changes to the given interface.
+    /// Subscriptions belong to an interface, so they must not outlive it
+    /// or get inherited by a new interface with the same ID.
+    fn remove_state_subscriptions_of_interface(&self, interface_id: &String) {
+        let mut subscriptions = self.state_subscriptions.write().unwrap();
+        for interface_ids in subscriptions.values_mut() {
+            interface_ids.remove(interface_id);
+        }
+        subscriptions.retain(|_, interface_ids| !interface_ids.is_empty());
+    }
+
     pub fn start_interface_by_id(&mut self, id: &String) -> Result<(), String> {
         self.config
             .interface_by_id(id)
@@ -743,6 +829,21 @@ impl Conductor {
                                     format!("Error creating context: {}", hc_err.to_string())
                                 })?
                     }
+                    StorageConfiguration::Kv { path } => {
+                        context_builder =
+                            context_builder.with_kv_storage(path).map_err(|hc_err| {
+                                format!("Error creating context: {}", hc_err.to_string())
+                            })?
+                    }
+                }
+
+                if instance_config.encrypt_storage {
+                    let keystore = self.get_keystore_for_agent(&instance_config.agent)?;
+                    let mut keystore = keystore.lock().unwrap();
+                    let key = StorageKey::from_keystore(&mut keystore).map_err(|hc_err| {
+                        format!("Error unlocking storage key: {}", hc_err.to_string())
+                    })?;
+                    context_builder = context_builder.with_encrypted_storage(key);
                 }
 
                 let instance_name = instance_config.id.clone();
@@ -754,6 +855,23 @@ impl Conductor {
                     context_builder = context_builder.with_state_dump_logging();
                 }
 
+                if let Some(warrant_policy) = instance_config.warrant_policy.clone() {
+                    context_builder = context_builder.with_warrant_policy(warrant_policy);
+                }
+
+                if let Some(journal_config) = instance_config.action_journal.clone() {
+                    context_builder = context_builder
+                        .with_action_journal(
+                            journal_config.path,
+                            journal_config
+                                .checkpoint_interval
+                                .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL),
+                        )
+                        .map_err(|hc_err| {
+                            format!("Error creating action journal: {}", hc_err.to_string())
+                        })?;
+                }
+
                 // Spawn context
                 let context = context_builder.with_instance_name(&instance_name).spawn();
 
@@ -1115,8 +1233,19 @@ impl Conductor {
     pub fn load_dna(file: &PathBuf) -> HcResult<Dna> {
         notify(format!("Reading DNA from {}", file.display()));
         let mut f = File::open(file)?;
-        let mut contents = String::new();
-        f.read_to_string(&mut contents)?;
+        let mut contents = Vec::new();
+        f.read_to_end(&mut contents)?;
+        Self::dna_from_bytes(&contents)
+    }
+
+    /// Parses the contents of a DNA file, which can either be a `.dna.json` file or
+    /// a DNA in the compact binary format (see `holochain_core_types::dna::binary`).
+    pub fn dna_from_bytes(bytes: &[u8]) -> HcResult<Dna> {
+        if is_binary_dna(bytes) {
+            return Dna::from_binary(bytes);
+        }
+        let contents = String::from_utf8(bytes.to_vec())
+            .map_err(|e| HolochainError::ErrorGeneric(format!("DNA is not valid UTF-8: {}", e)))?;
         Dna::try_from(JsonString::from_json(&contents)).map_err(|err| err.into())
     }
 
@@ -1171,7 +1300,8 @@ impl Conductor {
                 .with_admin_dna_functions()
                 .with_admin_ui_functions()
                 .with_test_admin_functions()
-                .with_debug_functions();
+                .with_debug_functions(interface_config.id.clone())
+                .with_consistency_functions(self.consistency_tracker.clone());
         }
 
         conductor_api_builder.spawn()
@@ -1241,11 +1371,17 @@ impl Conductor {
         Ok(())
     }
 
+    /// Saves the given DNA to the conductor's DNA store (see `dna_dir_path()`).
+    /// Files in the store are named after the DNA's address, so installing the same DNA
+    /// twice will not write a second copy but return the path of the already stored one.
     pub fn save_dna(&self, dna: &Dna) -> Result<PathBuf, HolochainError> {
         let file_path = self
             .dna_dir_path()
             .join(dna.address().to_string())
             .with_extension(DNA_EXTENSION);
+        if file_path.is_file() {
+            return Ok(file_path);
+        }
         fs::create_dir_all(&self.dna_dir_path())?;
         self.save_dna_to(dna, file_path)
     }
@@ -1631,6 +1767,19 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_default_dna_loader_reads_binary_dna() {
+        let json_dna = Conductor::dna_from_bytes(example_dna_string().as_bytes()).unwrap();
+        let tempdir = tempdir().unwrap();
+        let file_path = tempdir.path().join("test.dna");
+        let mut tmp_file = File::create(file_path.clone()).unwrap();
+        tmp_file.write_all(&json_dna.to_binary().unwrap()).unwrap();
+
+        let binary_dna = Conductor::load_dna(&file_path).unwrap();
+        assert_eq!(binary_dna.name, "my dna");
+        assert_eq!(binary_dna.address(), json_dna.address());
+    }
+
     #[test]
     fn test_conductor_boot_from_config() {
         let mut conductor = test_conductor(10001, 10002);
@@ -1,16 +1,36 @@
 use conductor::Conductor;
 use holochain_core::state_dump::StateDump;
-use holochain_core_types::error::HolochainError;
+use holochain_core_types::{chain_header::ChainHeader, error::HolochainError};
 use holochain_persistence_api::cas::content::Address;
 
 pub trait ConductorDebug {
     fn running_instances(&self) -> Result<Vec<String>, HolochainError>;
-    fn state_dump_for_instance(&self, instance_id: &String) -> Result<StateDump, HolochainError>;
+    fn state_dump_for_instance(
+        &self,
+        instance_id: &String,
+        include_source_chain: bool,
+    ) -> Result<StateDump, HolochainError>;
+    fn source_chain_for_instance(
+        &self,
+        instance_id: &String,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ChainHeader>, HolochainError>;
     fn get_type_and_content_from_cas(
         &self,
         address: &Address,
         instance_id: &String,
     ) -> Result<(String, String), HolochainError>;
+    fn subscribe_state(
+        &self,
+        instance_id: &String,
+        interface_id: &String,
+    ) -> Result<(), HolochainError>;
+    fn unsubscribe_state(
+        &self,
+        instance_id: &String,
+        interface_id: &String,
+    ) -> Result<(), HolochainError>;
 }
 
 impl ConductorDebug for Conductor {
@@ -18,9 +38,23 @@ impl ConductorDebug for Conductor {
         Ok(self.instances.keys().cloned().collect())
     }
 
-    fn state_dump_for_instance(&self, instance_id: &String) -> Result<StateDump, HolochainError> {
+    fn state_dump_for_instance(
+        &self,
+        instance_id: &String,
+        include_source_chain: bool,
+    ) -> Result<StateDump, HolochainError> {
         let hc = self.instances.get(instance_id)?;
-        Ok(hc.read().unwrap().get_state_dump()?)
+        Ok(hc.read().unwrap().get_state_dump(include_source_chain)?)
+    }
+
+    fn source_chain_for_instance(
+        &self,
+        instance_id: &String,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ChainHeader>, HolochainError> {
+        let hc = self.instances.get(instance_id)?;
+        Ok(hc.read().unwrap().get_source_chain_page(offset, limit)?)
     }
 
     fn get_type_and_content_from_cas(
@@ -31,4 +65,38 @@ impl ConductorDebug for Conductor {
         let hc = self.instances.get(instance_id)?;
         Ok(hc.read().unwrap().get_type_and_content_from_cas(address)?)
     }
+
+    fn subscribe_state(
+        &self,
+        instance_id: &String,
+        interface_id: &String,
+    ) -> Result<(), HolochainError> {
+        self.instances.get(instance_id)?;
+        self.state_subscriptions
+            .write()
+            .unwrap()
+            .entry(instance_id.clone())
+            .or_insert_with(Default::default)
+            .insert(interface_id.clone());
+        Ok(())
+    }
+
+    fn unsubscribe_state(
+        &self,
+        instance_id: &String,
+        interface_id: &String,
+    ) -> Result<(), HolochainError> {
+        let mut subscriptions = self.state_subscriptions.write().unwrap();
+        let now_empty = match subscriptions.get_mut(instance_id) {
+            Some(interface_ids) => {
+                interface_ids.remove(interface_id);
+                interface_ids.is_empty()
+            }
+            None => false,
+        };
+        if now_empty {
+            subscriptions.remove(instance_id);
+        }
+        Ok(())
+    }
 }
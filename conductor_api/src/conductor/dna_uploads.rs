@@ -0,0 +1,121 @@
+use holochain_core_types::error::HolochainError;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// How many uploads can be open at the same time
+pub const MAX_OPEN_DNA_UPLOADS: usize = 8;
+/// How many bytes all open uploads can hold together
+pub const MAX_DNA_UPLOADS_SIZE: usize = 64 * 1024 * 1024;
+/// Uploads that didn't receive a chunk for this long are discarded
+pub const DNA_UPLOAD_EXPIRY: Duration = Duration::from_secs(10 * 60);
+
+struct DnaUpload {
+    bytes: Vec<u8>,
+    last_chunk: Instant,
+}
+
+/// Partial DNA uploads received through `admin/dna/upload_chunk`, keyed by upload ID.
+/// Their number and total size are limited and abandoned uploads expire, so admin clients
+/// can't exhaust the conductor's memory.
+pub struct DnaUploads {
+    uploads: HashMap<String, DnaUpload>,
+    max_open: usize,
+    max_size: usize,
+    expiry: Duration,
+}
+
+impl Default for DnaUploads {
+    fn default() -> Self {
+        DnaUploads::new(
+            MAX_OPEN_DNA_UPLOADS,
+            MAX_DNA_UPLOADS_SIZE,
+            DNA_UPLOAD_EXPIRY,
+        )
+    }
+}
+
+impl DnaUploads {
+    pub fn new(max_open: usize, max_size: usize, expiry: Duration) -> Self {
+        DnaUploads {
+            uploads: HashMap::new(),
+            max_open,
+            max_size,
+            expiry,
+        }
+    }
+
+    fn total_size(&self) -> usize {
+        self.uploads.values().map(|upload| upload.bytes.len()).sum()
+    }
+
+    fn remove_expired(&mut self) {
+        let expiry = self.expiry;
+        self.uploads
+            .retain(|_, upload| upload.last_chunk.elapsed() < expiry);
+    }
+
+    /// Appends the chunk to the upload, creating it if it does not exist yet,
+    /// and returns the upload's size so far.
+    pub fn add_chunk(&mut self, upload_id: &str, chunk: Vec<u8>) -> Result<usize, HolochainError> {
+        self.remove_expired();
+        if !self.uploads.contains_key(upload_id) && self.uploads.len() >= self.max_open {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Too many open DNA uploads (at most {} allowed)",
+                self.max_open
+            )));
+        }
+        if self.total_size() + chunk.len() > self.max_size {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "DNA uploads exceed the maximum size of {} bytes",
+                self.max_size
+            )));
+        }
+        let upload = self
+            .uploads
+            .entry(upload_id.to_string())
+            .or_insert_with(|| DnaUpload {
+                bytes: Vec::new(),
+                last_chunk: Instant::now(),
+            });
+        upload.bytes.extend(chunk);
+        upload.last_chunk = Instant::now();
+        Ok(upload.bytes.len())
+    }
+
+    /// Removes the upload and returns its bytes.
+    pub fn take(&mut self, upload_id: &str) -> Option<Vec<u8>> {
+        self.remove_expired();
+        self.uploads.remove(upload_id).map(|upload| upload.bytes)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    #[test]
+    fn limits_open_uploads_and_their_size() {
+        let mut uploads = DnaUploads::new(2, 10, DNA_UPLOAD_EXPIRY);
+        assert_eq!(uploads.add_chunk("a", vec![0; 4]), Ok(4));
+        assert_eq!(uploads.add_chunk("b", vec![0; 4]), Ok(4));
+        assert!(uploads.add_chunk("c", vec![0; 1]).is_err());
+        assert!(uploads.add_chunk("a", vec![0; 3]).is_err());
+        assert_eq!(uploads.add_chunk("a", vec![0; 2]), Ok(6));
+
+        // Taking an upload frees its slot and its bytes
+        assert_eq!(uploads.take("b"), Some(vec![0; 4]));
+        assert_eq!(uploads.add_chunk("c", vec![0; 4]), Ok(4));
+    }
+
+    #[test]
+    fn abandoned_uploads_expire() {
+        let mut uploads = DnaUploads::new(1, 10, Duration::from_millis(10));
+        assert_eq!(uploads.add_chunk("a", vec![0; 4]), Ok(4));
+        sleep(Duration::from_millis(20));
+        assert_eq!(uploads.add_chunk("b", vec![0; 10]), Ok(10));
+        assert_eq!(uploads.take("a"), None);
+    }
+}
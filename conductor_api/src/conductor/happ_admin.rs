@@ -0,0 +1,454 @@
+use crate::{
+    conductor::{base::notify, Conductor},
+    config::{
+        Configuration, DnaConfiguration, InstanceConfiguration, StorageConfiguration,
+        UiBundleConfiguration, UiInterfaceConfiguration,
+    },
+    happ_bundle::{resolve_bundle_path, HappBundle},
+    static_server_impls::NickelStaticServer as StaticServer,
+};
+use holochain_core_types::error::HolochainError;
+use holochain_persistence_api::{cas::content::AddressableContent, hash::HashString};
+use std::{
+    collections::HashMap,
+    fs,
+    net::Ipv4Addr,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+pub trait ConductorHappAdmin {
+    fn install_happ_bundle(
+        &mut self,
+        bundle: HappBundle,
+        bundle_dir: PathBuf,
+    ) -> Result<HashMap<String, HashString>, HolochainError>;
+    fn install_happ_bundle_from_file(
+        &mut self,
+        manifest_path: PathBuf,
+    ) -> Result<HashMap<String, HashString>, HolochainError>;
+}
+
+impl ConductorHappAdmin for Conductor {
+    /// Installs all DNAs, instances, bridges and UI bundles of the given hApp bundle at once.
+    ///
+    /// In contrast to calling the separate admin functions in sequence, the resulting
+    /// config is built and checked as a whole before anything is applied. If copying the
+    /// UI directories, creating the instances, saving the config or restarting interfaces
+    /// fails, the copied UI directories and new instances are removed again and the
+    /// previous config and interfaces are restored.
+    /// The only side effects that remain are files in the DNA store that are not
+    /// referenced by the config.
+    ///
+    /// Returns the hashes of the installed DNAs, keyed by DNA ID.
+    /// Instances are created but not started.
+    fn install_happ_bundle(
+        &mut self,
+        bundle: HappBundle,
+        bundle_dir: PathBuf,
+    ) -> Result<HashMap<String, HashString>, HolochainError> {
+        bundle.check_consistency()?;
+        let mut new_config = self.config.clone();
+        let mut dna_hashes = HashMap::new();
+
+        for bundle_dna in bundle.dnas.iter() {
+            let path = resolve_bundle_path(&bundle_dir, &bundle_dna.file);
+            let mut dna = Arc::get_mut(&mut self.dna_loader).unwrap()(&path).map_err(|e| {
+                HolochainError::ConfigError(format!(
+                    "Could not load DNA file \"{}\", Error: {}",
+                    path.display(),
+                    e.to_string()
+                ))
+            })?;
+
+            if let Some(ref hash) = bundle_dna.hash {
+                let provided_hash = HashString::from(hash.clone());
+                let actual_hash = dna.address();
+                if actual_hash != provided_hash {
+                    return Err(HolochainError::DnaHashMismatch(provided_hash, actual_hash));
+                }
+            }
+
+            if let Some(ref props) = bundle_dna.properties {
+                json_patch::merge(&mut dna.properties, props);
+            }
+            if let Some(ref uuid) = bundle_dna.uuid {
+                dna.uuid = uuid.clone();
+            }
+
+            let file = self.save_dna(&dna)?;
+            new_config.dnas.push(DnaConfiguration {
+                id: bundle_dna.id.clone(),
+                file: file
+                    .to_str()
+                    .ok_or_else(|| HolochainError::ConfigError("invalid path".into()))?
+                    .into(),
+                hash: dna.address().to_string(),
+            });
+            dna_hashes.insert(bundle_dna.id.clone(), dna.address());
+        }
+
+        for bundle_instance in bundle.instances.iter() {
+            let storage_path = self
+                .instance_storage_dir_path()
+                .join(bundle_instance.id.clone());
+            new_config.instances.push(InstanceConfiguration {
+                id: bundle_instance.id.clone(),
+                dna: bundle_instance.dna.clone(),
+                agent: bundle_instance.agent.clone(),
+                storage: StorageConfiguration::Pickle {
+                    path: storage_path
+                        .to_str()
+                        .ok_or_else(|| {
+                            HolochainError::ConfigError(format!("invalid path {:?}", storage_path))
+                        })?
+                        .into(),
+                },
+                warrant_policy: None,
+                action_journal: None,
+                encrypt_storage: false,
+            });
+
+            for interface_ref in bundle_instance.interfaces.iter() {
+                let interface = new_config
+                    .interfaces
+                    .iter_mut()
+                    .find(|interface| interface.id == interface_ref.id)
+                    .ok_or_else(|| {
+                        HolochainError::ConfigError(format!(
+                            "Interface \"{}\" not found, mentioned in instance \"{}\"",
+                            interface_ref.id, bundle_instance.id
+                        ))
+                    })?;
+                interface
+                    .instances
+                    .push(interface_ref.instance_reference(&bundle_instance.id));
+            }
+        }
+
+        new_config.bridges.extend(bundle.bridges.iter().cloned());
+
+        let mut ui_dirs = Vec::new();
+        for bundle_ui in bundle.uis.iter() {
+            let source = resolve_bundle_path(&bundle_dir, &bundle_ui.root_dir);
+            let dest = self
+                .config
+                .persistence_dir
+                .join("static")
+                .join(&bundle_ui.id);
+            new_config.ui_bundles.push(UiBundleConfiguration {
+                id: bundle_ui.id.clone(),
+                root_dir: dest
+                    .to_str()
+                    .ok_or_else(|| HolochainError::ConfigError("invalid path".into()))?
+                    .into(),
+                hash: None,
+            });
+            if let Some(ref ui_interface) = bundle_ui.interface {
+                new_config.ui_interfaces.push(UiInterfaceConfiguration {
+                    id: ui_interface.id.clone(),
+                    bundle: bundle_ui.id.clone(),
+                    port: ui_interface.port,
+                    dna_interface: ui_interface.dna_interface.clone(),
+                    reroute_to_root: true,
+                    bind_address: Ipv4Addr::LOCALHOST.to_string(),
+                    tls: None,
+                    compression: true,
+                });
+            }
+            ui_dirs.push((source, dest));
+        }
+
+        new_config.check_consistency(&mut self.dna_loader)?;
+
+        let old_config = self.config.clone();
+        let new_instance_ids: Vec<String> = bundle.instances.iter().map(|i| i.id.clone()).collect();
+        let mut changed_interfaces: Vec<String> = bundle
+            .instances
+            .iter()
+            .flat_map(|instance| instance.interfaces.iter().map(|i| i.id.clone()))
+            .collect();
+        changed_interfaces.sort();
+        changed_interfaces.dedup();
+
+        // Only directories that did not exist before get removed on failure
+        let new_ui_dirs: Vec<PathBuf> = ui_dirs
+            .iter()
+            .map(|(_, dest)| dest.clone())
+            .filter(|dest| !dest.exists())
+            .collect();
+        if let Err(error) =
+            self.apply_happ_bundle(new_config, &ui_dirs, &new_instance_ids, &changed_interfaces)
+        {
+            self.roll_back_happ_bundle(
+                old_config,
+                &new_ui_dirs,
+                &new_instance_ids,
+                &changed_interfaces,
+            );
+            return Err(error);
+        }
+
+        for bundle_ui in bundle.uis.iter() {
+            if let Some(ref ui_interface) = bundle_ui.interface {
+                let ui_interface_config = self
+                    .config
+                    .ui_interfaces
+                    .iter()
+                    .find(|i| i.id == ui_interface.id)
+                    .cloned()
+                    .unwrap();
+                let connected_dna_interface = ui_interface
+                    .dna_interface
+                    .as_ref()
+                    .and_then(|interface_id| self.config.interface_by_id(interface_id));
+                self.static_servers.insert(
+                    ui_interface.id.clone(),
+                    StaticServer::from_configs(
+                        ui_interface_config,
+                        self.config.ui_bundle_by_id(&bundle_ui.id).unwrap(),
+                        connected_dna_interface,
+                    ),
+                );
+            }
+        }
+
+        let _ = self.start_signal_multiplexer();
+        notify(format!("Installed hApp bundle \"{}\"", bundle.name));
+        Ok(dna_hashes)
+    }
+
+    /// Reads the hApp bundle manifest at the given path and installs the bundle.
+    /// Files referenced in the manifest are resolved relative to the manifest's directory.
+    fn install_happ_bundle_from_file(
+        &mut self,
+        manifest_path: PathBuf,
+    ) -> Result<HashMap<String, HashString>, HolochainError> {
+        let bundle = HappBundle::from_file(&manifest_path)?;
+        let bundle_dir = manifest_path
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_else(PathBuf::new);
+        self.install_happ_bundle(bundle, bundle_dir)
+    }
+}
+
+impl Conductor {
+    /// Copies the UI directories (given as source and destination) and creates the new
+    /// instances of a hApp bundle, switches to the new config and restarts the interfaces
+    /// that got new instances.
+    /// Instances are added to the conductor in the order of their bridge dependencies,
+    /// so callees exist by the time their callers' bridges are built.
+    fn apply_happ_bundle(
+        &mut self,
+        mut new_config: Configuration,
+        ui_dirs: &[(PathBuf, PathBuf)],
+        new_instance_ids: &[String],
+        changed_interfaces: &[String],
+    ) -> Result<(), HolochainError> {
+        for (source, dest) in ui_dirs.iter() {
+            Arc::get_mut(&mut self.ui_dir_copier).unwrap()(source, dest).map_err(|e| {
+                HolochainError::ErrorGeneric(format!(
+                    "Error copying UI from {} to {}: {}",
+                    source.display(),
+                    dest.display(),
+                    e
+                ))
+            })?;
+        }
+
+        for id in new_config.instance_ids_sorted_by_bridge_dependencies()? {
+            if !new_instance_ids.contains(&id) {
+                continue;
+            }
+            fs::create_dir_all(self.instance_storage_dir_path().join(&id))?;
+            // (Instantiating test agents updates the config, hence passing the new one.)
+            let instance = self
+                .instantiate_from_config(&id, Some(&mut new_config))
+                .map_err(|error| {
+                    HolochainError::ConfigError(format!(
+                        "Error while trying to create instance \"{}\": {}",
+                        id, error
+                    ))
+                })?;
+            self.instances.insert(id, Arc::new(RwLock::new(instance)));
+        }
+
+        self.config = new_config;
+        self.save_config()?;
+
+        // Restart interfaces that got new instances so they pick them up
+        for interface_id in changed_interfaces.iter() {
+            let _ = self.stop_interface_by_id(interface_id);
+            self.start_interface_by_id(interface_id)
+                .map_err(HolochainError::ErrorGeneric)?;
+        }
+        Ok(())
+    }
+
+    /// Undoes whatever `apply_happ_bundle()` got to before it failed.
+    fn roll_back_happ_bundle(
+        &mut self,
+        old_config: Configuration,
+        new_ui_dirs: &[PathBuf],
+        new_instance_ids: &[String],
+        changed_interfaces: &[String],
+    ) {
+        for id in new_instance_ids.iter() {
+            if let Some(instance) = self.instances.remove(id) {
+                instance.write().unwrap().kill();
+            }
+        }
+        for dir in new_ui_dirs.iter().filter(|dir| dir.exists()) {
+            if let Err(error) = fs::remove_dir_all(dir) {
+                notify(format!(
+                    "Could not remove UI directory {}: {}",
+                    dir.display(),
+                    error
+                ));
+            }
+        }
+        self.config = old_config;
+        if let Err(error) = self.save_config() {
+            notify(format!("Could not restore config: {}", error));
+        }
+        for interface_id in changed_interfaces.iter() {
+            let _ = self.stop_interface_by_id(interface_id);
+            if self.config.interface_by_id(interface_id).is_some() {
+                if let Err(error) = self.start_interface_by_id(interface_id) {
+                    notify(format!(
+                        "Could not restart interface \"{}\": {}",
+                        interface_id, error
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        conductor::{admin::tests::*, base::UiDirCopier, ui_admin::tests::test_ui_copier},
+        config::load_configuration,
+        happ_bundle::tests::example_happ_toml,
+    };
+
+    fn example_bundle() -> HappBundle {
+        load_configuration::<HappBundle>(&example_happ_toml()).unwrap()
+    }
+
+    #[test]
+    fn test_install_happ_bundle() {
+        let test_name = "test_install_happ_bundle";
+        let mut conductor = create_test_conductor(test_name, 3010);
+        conductor.ui_dir_copier = test_ui_copier();
+
+        let hashes = conductor
+            .install_happ_bundle(example_bundle(), PathBuf::from("."))
+            .unwrap();
+        assert_eq!(hashes.len(), 2);
+
+        let config = conductor.config();
+        assert_eq!(config.dnas.len(), 3);
+        assert_eq!(config.dnas[1].hash, hashes["chat-dna"].to_string());
+        assert_ne!(hashes["chat-dna"], hashes["profiles-dna"]);
+        assert!(config.instance_by_id("chat").is_some());
+        assert!(config.instance_by_id("profiles").is_some());
+        assert!(conductor.instances().contains_key("chat"));
+        assert!(conductor.instances().contains_key("profiles"));
+        assert_eq!(config.bridges.len(), 1);
+        assert!(config
+            .interface_by_id("websocket interface")
+            .unwrap()
+            .instances
+            .iter()
+            .any(|i| i.id == "chat" && i.alias == Some("chat-instance".to_string())));
+        assert!(config.ui_bundle_by_id("chat-ui").is_some());
+        assert_eq!(config.ui_interfaces.len(), 1);
+    }
+
+    /// A UI copier that only creates the destination directory
+    fn dir_creating_ui_copier() -> UiDirCopier {
+        let copier = Box::new(|_source: &PathBuf, dest: &PathBuf| {
+            fs::create_dir_all(dest)?;
+            Ok(())
+        })
+            as Box<dyn FnMut(&PathBuf, &PathBuf) -> Result<(), HolochainError> + Send + Sync>;
+        Arc::new(copier)
+    }
+
+    #[test]
+    fn test_install_happ_bundle_without_hashes() {
+        let test_name = "test_install_happ_bundle_without_hashes";
+        let mut conductor = create_test_conductor(test_name, 3014);
+        conductor.ui_dir_copier = test_ui_copier();
+
+        let mut bundle = example_bundle();
+        for dna in bundle.dnas.iter_mut() {
+            dna.hash = None;
+        }
+        let hashes = conductor
+            .install_happ_bundle(bundle, PathBuf::from("."))
+            .unwrap();
+        assert_eq!(hashes.len(), 2);
+        assert!(conductor.instances().contains_key("chat"));
+    }
+
+    #[test]
+    fn test_install_happ_bundle_leaves_config_untouched_on_failure() {
+        let test_name = "test_install_happ_bundle_leaves_config_untouched_on_failure";
+        let mut conductor = create_test_conductor(test_name, 3011);
+        conductor.ui_dir_copier = dir_creating_ui_copier();
+        let config_before = serialize_config(&conductor);
+        let ui_dir = conductor
+            .config()
+            .persistence_dir
+            .join("static")
+            .join("chat-ui");
+
+        // Rejected by the consistency check before anything gets copied
+        let mut bundle = example_bundle();
+        bundle.instances[1].agent = "unknown-agent".to_string();
+        assert!(conductor
+            .install_happ_bundle(bundle, PathBuf::from("."))
+            .is_err());
+        assert_eq!(serialize_config(&conductor), config_before);
+        assert!(!conductor.instances().contains_key("chat"));
+        assert!(!ui_dir.exists());
+
+        // A file in place of the caller's storage directory makes creating it fail
+        // after the UI got copied and the callee got created, so all of that has
+        // to be undone.
+        let blocked_storage = conductor.instance_storage_dir_path().join("chat");
+        fs::create_dir_all(conductor.instance_storage_dir_path()).unwrap();
+        fs::write(&blocked_storage, "not a directory").unwrap();
+        assert!(conductor
+            .install_happ_bundle(example_bundle(), PathBuf::from("."))
+            .is_err());
+        assert_eq!(serialize_config(&conductor), config_before);
+        assert!(!conductor.instances().contains_key("profiles"));
+        assert!(!conductor.instances().contains_key("chat"));
+        assert!(conductor.instances().contains_key("test-instance-1"));
+        assert!(!ui_dir.exists());
+        fs::remove_file(&blocked_storage).unwrap();
+
+        let mut bundle = example_bundle();
+        bundle.dnas[1].hash = Some("wrong-hash".to_string());
+        assert!(conductor
+            .install_happ_bundle(bundle, PathBuf::from("."))
+            .is_err());
+        assert_eq!(serialize_config(&conductor), config_before);
+
+        // Succeeds once the storage directory can be created
+        assert!(conductor
+            .install_happ_bundle(example_bundle(), PathBuf::from("."))
+            .is_ok());
+        assert!(ui_dir.is_dir());
+    }
+
+    fn serialize_config(conductor: &Conductor) -> String {
+        crate::config::serialize_configuration(&conductor.config()).unwrap()
+    }
+}
@@ -2,7 +2,10 @@ pub mod admin;
 pub mod base;
 pub mod broadcaster;
 pub mod debug;
+pub mod dna_uploads;
+pub mod happ_admin;
 pub mod passphrase_manager;
+pub mod reload;
 pub mod test_admin;
 pub mod ui_admin;
 
@@ -10,6 +13,8 @@ pub use self::{
     admin::ConductorAdmin,
     base::{mount_conductor_from_config, Conductor, CONDUCTOR},
     debug::ConductorDebug,
+    happ_admin::ConductorHappAdmin,
+    reload::ConfigDiff,
     test_admin::ConductorTestAdmin,
     ui_admin::ConductorUiAdmin,
 };
@@ -0,0 +1,635 @@
+use crate::{
+    conductor::{base::notify, Conductor},
+    config::{load_configuration, Bridge, Configuration},
+    static_file_server::ConductorStaticFileServer,
+    static_server_impls::NickelStaticServer as StaticServer,
+    Holochain,
+};
+use holochain_core_types::error::HolochainError;
+use std::{
+    fs::File,
+    io::prelude::*,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+/// The difference between two conductor configurations, expressed in terms of the
+/// parts of a running conductor that need to be started, stopped or rebuilt to get
+/// from the first to the second.
+///
+/// An instance counts as changed if its own config, its DNA config or its agent config
+/// changed. Interfaces count as changed if their config changed or if they expose an
+/// instance that gets rebuilt, since they hold on to the instance object.
+/// Instances whose outgoing bridges changed, or that bridge to a rebuilt instance, only
+/// need their conductor API to be rebuilt (unless they get rebuilt themselves).
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ConfigDiff {
+    pub added_instances: Vec<String>,
+    pub removed_instances: Vec<String>,
+    pub changed_instances: Vec<String>,
+    pub changed_bridges: Vec<String>,
+    pub added_interfaces: Vec<String>,
+    pub removed_interfaces: Vec<String>,
+    pub changed_interfaces: Vec<String>,
+    pub added_ui_interfaces: Vec<String>,
+    pub removed_ui_interfaces: Vec<String>,
+    pub changed_ui_interfaces: Vec<String>,
+    /// Top-level settings that changed but only take effect after a restart
+    /// (e.g. network, logger, DPKI)
+    pub requires_restart: Vec<String>,
+}
+
+/// Splits the IDs of two lists into added, removed and changed ones.
+fn diff_by_id<T, F>(old: &[T], new: &[T], id: F) -> (Vec<String>, Vec<String>, Vec<String>)
+where
+    T: PartialEq,
+    F: Fn(&T) -> &String,
+{
+    let added = new
+        .iter()
+        .filter(|n| !old.iter().any(|o| id(o) == id(n)))
+        .map(|n| id(n).clone())
+        .collect();
+    let removed = old
+        .iter()
+        .filter(|o| !new.iter().any(|n| id(o) == id(n)))
+        .map(|o| id(o).clone())
+        .collect();
+    let changed = new
+        .iter()
+        .filter(|n| old.iter().any(|o| id(o) == id(n) && o != *n))
+        .map(|n| id(n).clone())
+        .collect();
+    (added, removed, changed)
+}
+
+fn bridges_of<'a>(config: &'a Configuration, caller_id: &String) -> Vec<&'a Bridge> {
+    config
+        .bridges
+        .iter()
+        .filter(|bridge| bridge.caller_id == *caller_id)
+        .collect()
+}
+
+fn push_unique(list: &mut Vec<String>, id: &String) {
+    if !list.contains(id) {
+        list.push(id.clone());
+    }
+}
+
+impl ConfigDiff {
+    pub fn between(old: &Configuration, new: &Configuration) -> ConfigDiff {
+        let mut diff = ConfigDiff::default();
+
+        let (added, removed, mut changed) = diff_by_id(&old.instances, &new.instances, |i| &i.id);
+        diff.added_instances = added;
+        diff.removed_instances = removed;
+        for instance in new.instances.iter() {
+            if diff.added_instances.contains(&instance.id) {
+                continue;
+            }
+            if old.dna_by_id(&instance.dna) != new.dna_by_id(&instance.dna)
+                || old.agent_by_id(&instance.agent) != new.agent_by_id(&instance.agent)
+            {
+                push_unique(&mut changed, &instance.id);
+            }
+        }
+        diff.changed_instances = changed;
+
+        for instance in new.instances.iter() {
+            let rebuilt = diff.added_instances.contains(&instance.id)
+                || diff.changed_instances.contains(&instance.id);
+            // Callers hold on to their callees, so they need new bridges if one is rebuilt
+            let callee_rebuilt = bridges_of(new, &instance.id).iter().any(|bridge| {
+                diff.added_instances.contains(&bridge.callee_id)
+                    || diff.changed_instances.contains(&bridge.callee_id)
+            });
+            if !rebuilt
+                && (callee_rebuilt
+                    || bridges_of(old, &instance.id) != bridges_of(new, &instance.id))
+            {
+                diff.changed_bridges.push(instance.id.clone());
+            }
+        }
+
+        let (added, removed, mut changed) = diff_by_id(&old.interfaces, &new.interfaces, |i| &i.id);
+        for interface in new.interfaces.iter() {
+            if added.contains(&interface.id) {
+                continue;
+            }
+            if interface.instances.iter().any(|i| {
+                diff.changed_instances.contains(&i.id) || diff.added_instances.contains(&i.id)
+            }) {
+                push_unique(&mut changed, &interface.id);
+            }
+        }
+        diff.added_interfaces = added;
+        diff.removed_interfaces = removed;
+        diff.changed_interfaces = changed;
+
+        let (added, removed, mut changed) =
+            diff_by_id(&old.ui_interfaces, &new.ui_interfaces, |i| &i.id);
+        for ui_interface in new.ui_interfaces.iter() {
+            if added.contains(&ui_interface.id) {
+                continue;
+            }
+            let dna_interface_changed = ui_interface
+                .dna_interface
+                .as_ref()
+                .map(|id| diff.changed_interfaces.contains(id))
+                .unwrap_or(false);
+            if dna_interface_changed
+                || old.ui_bundle_by_id(&ui_interface.bundle)
+                    != new.ui_bundle_by_id(&ui_interface.bundle)
+            {
+                push_unique(&mut changed, &ui_interface.id);
+            }
+        }
+        diff.added_ui_interfaces = added;
+        diff.removed_ui_interfaces = removed;
+        diff.changed_ui_interfaces = changed;
+
+        let settings = vec![
+            ("logger", json!(old.logger) != json!(new.logger)),
+            ("network", json!(old.network) != json!(new.network)),
+            (
+                "persistence_dir",
+                old.persistence_dir != new.persistence_dir,
+            ),
+            (
+                "signing_service_uri",
+                old.signing_service_uri != new.signing_service_uri,
+            ),
+            (
+                "encryption_service_uri",
+                old.encryption_service_uri != new.encryption_service_uri,
+            ),
+            (
+                "decryption_service_uri",
+                old.decryption_service_uri != new.decryption_service_uri,
+            ),
+            ("dpki", old.dpki != new.dpki),
+            ("signals", json!(old.signals) != json!(new.signals)),
+            (
+                "passphrase_service",
+                json!(old.passphrase_service) != json!(new.passphrase_service),
+            ),
+        ];
+        diff.requires_restart = settings
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        diff
+    }
+
+    /// True if applying this diff does not change anything in the running conductor
+    pub fn is_empty(&self) -> bool {
+        let mut without_settings = self.clone();
+        without_settings.requires_restart.clear();
+        without_settings == ConfigDiff::default()
+    }
+}
+
+impl Conductor {
+    /// Re-reads the conductor config from the given TOML file (or the conductor's own
+    /// config file if none is given) and applies it with `reload_config()`.
+    pub fn reload_config_from_file(
+        &mut self,
+        path: Option<PathBuf>,
+    ) -> Result<ConfigDiff, HolochainError> {
+        let path = path.unwrap_or_else(|| self.config_path());
+        let mut f = File::open(&path).map_err(|e| {
+            HolochainError::ConfigError(format!(
+                "Could not open config file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)?;
+        let new_config = load_configuration::<Configuration>(&contents)?;
+        self.reload_config(new_config)
+    }
+
+    /// Replaces the running configuration with the given one.
+    ///
+    /// Only the instances, interfaces, bridges and UI servers that differ between the two
+    /// (see `ConfigDiff`) get stopped, rebuilt and (re)started; everything else keeps running.
+    /// If the new config is invalid, an error is returned and the running setup stays
+    /// untouched. If applying it fails part way, the previous config, instances, interfaces
+    /// and UI servers are restored before the error is returned.
+    pub fn reload_config(
+        &mut self,
+        new_config: Configuration,
+    ) -> Result<ConfigDiff, HolochainError> {
+        new_config
+            .check_consistency(&mut self.dna_loader)
+            .map_err(HolochainError::ConfigError)?;
+        let diff = ConfigDiff::between(&self.config, &new_config);
+        for setting in diff.requires_restart.iter() {
+            notify(format!(
+                "Config reload: change of \"{}\" will only take effect after a restart",
+                setting
+            ));
+        }
+        if diff.is_empty() {
+            // Nothing to restart, but settings that only take effect after a restart
+            // still have to end up in the persisted config.
+            self.config = new_config;
+            self.save_config()?;
+            return Ok(diff);
+        }
+
+        let old_config = self.config.clone();
+        let mut reload = Reload::default();
+        match self.apply_config_diff(new_config, &diff, &mut reload) {
+            Ok(()) => {
+                for (id, instance) in reload.detached {
+                    if diff.removed_instances.contains(&id) {
+                        notify(format!("Removing instance \"{}\"...", id));
+                    }
+                    instance.write().unwrap().kill();
+                }
+                let _ = self.start_signal_multiplexer();
+                notify(format!("Reloaded config: {:?}", diff));
+                Ok(diff)
+            }
+            Err(error) => {
+                notify(format!(
+                    "Config reload failed, restoring previous config: {}",
+                    error
+                ));
+                self.roll_back_config_reload(old_config, &diff, reload);
+                Err(error)
+            }
+        }
+    }
+
+    /// Switches the running conductor over to the new config.
+    /// Everything needed to undo it is recorded in `reload`.
+    fn apply_config_diff(
+        &mut self,
+        mut new_config: Configuration,
+        diff: &ConfigDiff,
+        reload: &mut Reload,
+    ) -> Result<(), HolochainError> {
+        // UI servers and interfaces first since they reference instances
+        for id in diff
+            .removed_ui_interfaces
+            .iter()
+            .chain(diff.changed_ui_interfaces.iter())
+        {
+            if let Some(mut server) = self.static_servers.remove(id) {
+                notify(format!("Stopping UI interface \"{}\"...", id));
+                let _ = server.stop();
+            }
+        }
+        for id in diff
+            .removed_interfaces
+            .iter()
+            .chain(diff.changed_interfaces.iter())
+        {
+            let _ = self.stop_interface_by_id(id);
+        }
+
+        // Replaced and removed instances are stopped and taken out, but only killed once
+        // the reload succeeded. Replacements must not run alongside them since both
+        // would access the same storage.
+        for id in diff
+            .removed_instances
+            .iter()
+            .chain(diff.changed_instances.iter())
+        {
+            if let Some(instance) = self.instances.remove(id) {
+                {
+                    let mut instance = instance.write().unwrap();
+                    if instance.active() {
+                        instance.stop()?;
+                        reload.stopped.push(id.clone());
+                    }
+                }
+                reload.detached.push((id.clone(), instance));
+            }
+        }
+
+        // New instances are added right away in the order of their bridge dependencies,
+        // so callees exist by the time their callers' bridges are built.
+        for id in new_config.instance_ids_sorted_by_bridge_dependencies()? {
+            if !diff.added_instances.contains(&id) && !diff.changed_instances.contains(&id) {
+                continue;
+            }
+            // (Instantiating test agents updates the config, hence passing the new one.)
+            let instance = self
+                .instantiate_from_config(&id, Some(&mut new_config))
+                .map_err(|error| {
+                    HolochainError::ConfigError(format!(
+                        "Error while trying to create instance \"{}\": {}",
+                        id, error
+                    ))
+                })?;
+            self.instances
+                .insert(id.clone(), Arc::new(RwLock::new(instance)));
+            reload.created.push(id);
+        }
+
+        self.config = new_config;
+        self.save_config()?;
+
+        for id in reload.created.clone() {
+            if diff.added_instances.contains(&id) || reload.stopped.contains(&id) {
+                self.start_instance(&id)?;
+            }
+        }
+
+        for id in diff.changed_bridges.iter() {
+            let conductor_api = self.build_conductor_api(id.clone(), &self.config.clone())?;
+            self.instances
+                .get(id)?
+                .write()
+                .unwrap()
+                .set_conductor_api(conductor_api)?;
+        }
+
+        for id in diff
+            .added_interfaces
+            .iter()
+            .chain(diff.changed_interfaces.iter())
+        {
+            self.start_interface_by_id(id)
+                .map_err(HolochainError::ErrorGeneric)?;
+        }
+
+        for id in diff
+            .added_ui_interfaces
+            .iter()
+            .chain(diff.changed_ui_interfaces.iter())
+        {
+            self.start_ui_server(id)?;
+        }
+        Ok(())
+    }
+
+    /// Restores the previous config and the instances, interfaces and UI servers
+    /// `apply_config_diff()` touched before it failed.
+    /// Errors are only reported since there is nothing left to fall back to.
+    fn roll_back_config_reload(
+        &mut self,
+        old_config: Configuration,
+        diff: &ConfigDiff,
+        reload: Reload,
+    ) {
+        let ui_interfaces: Vec<&String> = diff
+            .added_ui_interfaces
+            .iter()
+            .chain(diff.removed_ui_interfaces.iter())
+            .chain(diff.changed_ui_interfaces.iter())
+            .collect();
+        for id in ui_interfaces.iter() {
+            if let Some(mut server) = self.static_servers.remove(*id) {
+                let _ = server.stop();
+            }
+        }
+        let interfaces: Vec<&String> = diff
+            .added_interfaces
+            .iter()
+            .chain(diff.removed_interfaces.iter())
+            .chain(diff.changed_interfaces.iter())
+            .collect();
+        for id in interfaces.iter() {
+            let _ = self.stop_interface_by_id(id);
+        }
+
+        for id in reload.created.iter() {
+            if let Some(instance) = self.instances.remove(id) {
+                instance.write().unwrap().kill();
+            }
+        }
+        for (id, instance) in reload.detached {
+            self.instances.insert(id, instance);
+        }
+
+        self.config = old_config;
+        if let Err(error) = self.save_config() {
+            notify(format!("Could not restore config: {}", error));
+        }
+
+        for id in reload.stopped.iter() {
+            if let Err(error) = self.start_instance(id) {
+                notify(format!(
+                    "Could not restart instance \"{}\": {:?}",
+                    id, error
+                ));
+            }
+        }
+        for id in diff.changed_bridges.iter() {
+            let config = self.config.clone();
+            let rebuilt = self
+                .build_conductor_api(id.clone(), &config)
+                .and_then(|conductor_api| {
+                    let instance = self.instances.get(id).ok_or_else(|| {
+                        HolochainError::ErrorGeneric(format!("Instance {} not found", id))
+                    })?;
+                    let result = instance.write().unwrap().set_conductor_api(conductor_api);
+                    result.map_err(HolochainError::from)
+                });
+            if let Err(error) = rebuilt {
+                notify(format!(
+                    "Could not restore bridges of \"{}\": {}",
+                    id, error
+                ));
+            }
+        }
+        for id in interfaces {
+            if self.config.interface_by_id(id).is_some() {
+                if let Err(error) = self.start_interface_by_id(id) {
+                    notify(format!("Could not restart interface \"{}\": {}", id, error));
+                }
+            }
+        }
+        for id in ui_interfaces {
+            let exists = self.config.ui_interfaces.iter().any(|i| i.id == *id);
+            if exists {
+                if let Err(error) = self.start_ui_server(id) {
+                    notify(format!(
+                        "Could not restart UI interface \"{}\": {}",
+                        id, error
+                    ));
+                }
+            }
+        }
+    }
+
+    fn start_ui_server(&mut self, id: &String) -> Result<(), HolochainError> {
+        let ui_interface_config = self
+            .config
+            .ui_interfaces
+            .iter()
+            .find(|ui_interface| ui_interface.id == *id)
+            .cloned()
+            .unwrap();
+        let bundle_config = self
+            .config
+            .ui_bundle_by_id(&ui_interface_config.bundle)
+            .unwrap();
+        let connected_dna_interface = ui_interface_config
+            .dna_interface
+            .as_ref()
+            .and_then(|interface_id| self.config.interface_by_id(interface_id));
+        let mut server =
+            StaticServer::from_configs(ui_interface_config, bundle_config, connected_dna_interface);
+        notify(format!("Starting UI interface \"{}\"...", id));
+        server.start()?;
+        self.static_servers.insert(id.clone(), server);
+        Ok(())
+    }
+}
+
+/// What `apply_config_diff()` changed so far
+#[derive(Default)]
+struct Reload {
+    /// Instances that were running before and got stopped
+    stopped: Vec<String>,
+    /// Removed and replaced instances, taken out of the conductor
+    detached: Vec<(String, Arc<RwLock<Holochain>>)>,
+    /// Added and replacement instances that were added to the conductor
+    created: Vec<String>,
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::conductor::admin::tests::*;
+
+    fn config_from_toml(toml: &str) -> Configuration {
+        load_configuration::<Configuration>(toml).unwrap()
+    }
+
+    #[test]
+    fn test_config_diff_of_equal_configs_is_empty() {
+        let config = config_from_toml(&test_toml("test_config_diff_empty", 3100));
+        let diff = ConfigDiff::between(&config, &config);
+        assert!(diff.is_empty());
+        assert_eq!(diff, ConfigDiff::default());
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let old = config_from_toml(&test_toml("test_config_diff", 3101));
+
+        let mut new = old.clone();
+        new.instances.retain(|i| i.id != "test-instance-2");
+        new.interfaces[0]
+            .instances
+            .retain(|i| i.id != "test-instance-2");
+        new.agents[0].name = "Renamed Holo Tester 1".to_string();
+        new.network = Some(
+            load_configuration::<Configuration>(
+                &crate::config::tests::example_serialized_network_config(),
+            )
+            .unwrap()
+            .network
+            .unwrap(),
+        );
+
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.removed_instances, vec!["test-instance-2".to_string()]);
+        assert_eq!(diff.changed_instances, vec!["test-instance-1".to_string()]);
+        assert!(diff.added_instances.is_empty());
+        assert_eq!(
+            diff.changed_interfaces,
+            vec!["websocket interface".to_string()]
+        );
+        assert_eq!(diff.requires_restart, vec!["network".to_string()]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_config_diff_bridges_only() {
+        let old = config_from_toml(&test_toml("test_config_diff_bridges_only", 3102));
+        let mut new = old.clone();
+        new.bridges.push(Bridge {
+            caller_id: "test-instance-1".to_string(),
+            callee_id: "test-instance-2".to_string(),
+            handle: "test-callee".to_string(),
+        });
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.changed_bridges, vec!["test-instance-1".to_string()]);
+        assert!(diff.changed_instances.is_empty());
+        assert!(diff.changed_interfaces.is_empty());
+    }
+
+    #[test]
+    fn test_config_diff_rebuilds_bridges_to_changed_callees() {
+        let mut old = config_from_toml(&test_toml("test_config_diff_changed_callee", 3105));
+        old.bridges.push(Bridge {
+            caller_id: "test-instance-1".to_string(),
+            callee_id: "test-instance-2".to_string(),
+            handle: "test-callee".to_string(),
+        });
+        let mut new = old.clone();
+        new.instances
+            .iter_mut()
+            .find(|i| i.id == "test-instance-2")
+            .unwrap()
+            .encrypt_storage = true;
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.changed_instances, vec!["test-instance-2".to_string()]);
+        assert_eq!(diff.changed_bridges, vec!["test-instance-1".to_string()]);
+    }
+
+    #[test]
+    fn test_reload_config_rejects_invalid_config() {
+        let test_name = "test_reload_config_rejects_invalid_config";
+        let mut conductor = create_test_conductor(test_name, 3103);
+        let config_before = conductor.config();
+
+        let mut new_config = config_before.clone();
+        new_config.instances[0].agent = "unknown-agent".to_string();
+        assert!(conductor.reload_config(new_config).is_err());
+        assert_eq!(conductor.config().instances, config_before.instances);
+        assert!(conductor.instances().contains_key("test-instance-1"));
+    }
+
+    #[test]
+    fn test_reload_config_removes_instance() {
+        let test_name = "test_reload_config_removes_instance";
+        let mut conductor = create_test_conductor(test_name, 3104);
+
+        let mut new_config = conductor.config();
+        new_config = new_config.save_remove_instance(&"test-instance-2".to_string());
+        let diff = conductor.reload_config(new_config).unwrap();
+
+        assert_eq!(diff.removed_instances, vec!["test-instance-2".to_string()]);
+        assert!(!conductor.instances().contains_key("test-instance-2"));
+        assert!(conductor.instances().contains_key("test-instance-1"));
+        assert!(conductor
+            .config()
+            .instance_by_id("test-instance-2")
+            .is_none());
+    }
+
+    #[test]
+    fn test_reload_config_persists_restart_only_settings() {
+        let test_name = "test_reload_config_persists_restart_only_settings";
+        let mut conductor = create_test_conductor(test_name, 3106);
+
+        let mut new_config = conductor.config();
+        new_config.signals.trace = !new_config.signals.trace;
+        let diff = conductor.reload_config(new_config.clone()).unwrap();
+
+        assert!(diff.is_empty());
+        assert_eq!(diff.requires_restart, vec!["signals".to_string()]);
+        assert_eq!(conductor.config().signals.trace, new_config.signals.trace);
+
+        let mut saved = String::new();
+        File::open(conductor.config_path())
+            .unwrap()
+            .read_to_string(&mut saved)
+            .unwrap();
+        let saved_config = load_configuration::<Configuration>(&saved).unwrap();
+        assert_eq!(saved_config.signals.trace, new_config.signals.trace);
+        assert!(conductor.instances().contains_key("test-instance-1"));
+    }
+}
@@ -330,7 +330,9 @@ id = 'test-bundle-id'"#,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
                 reroute_to_root: true,
-                bind_address: Ipv4Addr::LOCALHOST.to_string()
+                bind_address: Ipv4Addr::LOCALHOST.to_string(),
+                tls: None,
+                compression: true,
             }),
             Err(HolochainError::ErrorGeneric(
                 "UI bundle configuration test-bundle-id not found, mentioned in UI interface test-ui-interface-id".into()
@@ -354,7 +356,9 @@ id = 'test-bundle-id'"#,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
                 reroute_to_root: true,
-                bind_address: Ipv4Addr::LOCALHOST.to_string()
+                bind_address: Ipv4Addr::LOCALHOST.to_string(),
+                tls: None,
+                compression: true,
             }),
             Ok(())
         );
@@ -382,6 +386,7 @@ root_dir = '.'
 [[ui_interfaces]]
 bind_address = '127.0.0.1'
 bundle = 'test-bundle-id'
+compression = true
 id = 'test-ui-interface-id'
 port = 4000
 reroute_to_root = true"#,
@@ -424,7 +429,9 @@ reroute_to_root = true"#,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
                 reroute_to_root: true,
-                bind_address: Ipv4Addr::LOCALHOST.to_string()
+                bind_address: Ipv4Addr::LOCALHOST.to_string(),
+                tls: None,
+                compression: true,
             }),
             Ok(())
         );
@@ -488,7 +495,9 @@ root_dir = '.'"#,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
                 reroute_to_root: true,
-                bind_address: Ipv4Addr::LOCALHOST.to_string()
+                bind_address: Ipv4Addr::LOCALHOST.to_string(),
+                tls: None,
+                compression: true,
             }),
             Ok(())
         );
@@ -522,7 +531,9 @@ root_dir = '.'"#,
                 bundle: "test-bundle-id".into(),
                 dna_interface: None,
                 reroute_to_root: true,
-                bind_address: Ipv4Addr::LOCALHOST.to_string()
+                bind_address: Ipv4Addr::LOCALHOST.to_string(),
+                tls: None,
+                compression: true,
             }),
             Ok(())
         );
@@ -21,6 +21,7 @@ use holochain_core_types::{
     error::{HcResult, HolochainError},
 };
 
+pub use holochain_core::context::WarrantPolicy;
 use holochain_json_api::json::JsonString;
 use holochain_persistence_api::cas::content::AddressableContent;
 use lib3h::engine::RealEngineConfig;
@@ -293,6 +294,15 @@ impl Configuration {
                         )
                     })?;
             }
+
+            if let Some(ref tls) = ui_interface.tls {
+                PathBuf::from(&tls.certificate).is_file().ok_or_else(|| {
+                    format!(
+                        "TLS certificate \"{}\" not found, mentioned in UI interface \"{}\"",
+                        tls.certificate, ui_interface.id,
+                    )
+                })?;
+            }
         }
         if let Some(ref dpki_config) = self.dpki {
             self.instance_by_id(&dpki_config.instance_id)
@@ -607,7 +617,8 @@ impl Configuration {
             .iter()
             .filter_map(|stg_config| match stg_config.storage {
                 StorageConfiguration::File { ref path }
-                | StorageConfiguration::Pickle { ref path } => Some(path.as_str()),
+                | StorageConfiguration::Pickle { ref path }
+                | StorageConfiguration::Kv { ref path } => Some(path.as_str()),
                 _ => None,
             })
             .collect();
@@ -678,13 +689,38 @@ pub struct InstanceConfiguration {
     pub dna: String,
     pub agent: String,
     pub storage: StorageConfiguration,
+    /// How this instance treats agents it knows warrants against.
+    /// Optional, defaults to treating them like any other agent.
+    #[serde(default)]
+    pub warrant_policy: Option<WarrantPolicy>,
+    /// Journal file this instance appends every reduced action to, for replaying them later.
+    /// Optional, no journal gets written if not set.
+    #[serde(default)]
+    pub action_journal: Option<ActionJournalConfiguration>,
+    /// Encrypts everything this instance writes to its storage with a key derived from
+    /// the agent's root seed. The agent's keystore gets unlocked when the instance starts.
+    /// Optional, defaults to false.
+    #[serde(default)]
+    pub encrypt_storage: bool,
+}
+
+/// Configures the append-only action journal of an instance.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ActionJournalConfiguration {
+    pub path: String,
+    /// Number of actions between two state digests in the journal.
+    /// Optional, defaults to 100.
+    #[serde(default)]
+    pub checkpoint_interval: Option<u64>,
 }
 
 /// This configures the Content Addressable Storage (CAS) that
 /// the instance uses to store source chain and DHT shard in.
-/// There are two storage implementations in cas_implementations so far:
+/// There are these storage implementations so far:
 /// * memory
 /// * file
+/// * pickle
+/// * kv: an embedded transactional store holding CAS and EAV in one commit log
 ///
 /// Projected are various DB adapters.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
@@ -693,6 +729,7 @@ pub enum StorageConfiguration {
     Memory,
     File { path: String },
     Pickle { path: String },
+    Kv { path: String },
 }
 
 /// Here, interfaces are user facing and make available zome functions to
@@ -801,12 +838,37 @@ pub struct UiInterfaceConfiguration {
     /// Can be either ip4 of ip6
     /// Default = "127.0.0.1"
     pub bind_address: String,
+
+    /// Serve the UI over HTTPS with this certificate instead of plain HTTP
+    /// (Optional)
+    #[serde(default)]
+    pub tls: Option<UiTlsConfiguration>,
+
+    #[serde(default = "default_compression")]
+    /// Compress static assets with brotli or gzip for clients that accept it
+    /// Default = true
+    pub compression: bool,
+}
+
+/// Certificate of a UI interface that is served over HTTPS.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub struct UiTlsConfiguration {
+    /// Path to a PKCS #12 archive containing the certificate chain and private key
+    pub certificate: String,
+
+    /// Password the archive is encrypted with
+    #[serde(default)]
+    pub password: String,
 }
 
 fn default_reroute() -> bool {
     true
 }
 
+fn default_compression() -> bool {
+    true
+}
+
 fn default_address() -> String {
     Ipv4Addr::LOCALHOST.to_string()
 }
@@ -994,9 +1056,14 @@ pub mod tests {
     id = "app spec instance"
     dna = "app spec rust"
     agent = "test agent"
+    encrypt_storage = true
         [instances.storage]
         type = "file"
         path = "app_spec_storage"
+        [instances.warrant_policy]
+        refuse_direct_messages = true
+        [instances.action_journal]
+        path = "app_spec_journal"
 
     [[interfaces]]
     id = "app spec websocket interface"
@@ -1044,6 +1111,21 @@ pub mod tests {
         assert_eq!(instance_config.id, "app spec instance");
         assert_eq!(instance_config.dna, "app spec rust");
         assert_eq!(instance_config.agent, "test agent");
+        assert_eq!(
+            instance_config.warrant_policy,
+            Some(WarrantPolicy {
+                refuse_direct_messages: true,
+                refuse_gossip: false,
+            })
+        );
+        assert_eq!(
+            instance_config.action_journal,
+            Some(ActionJournalConfiguration {
+                path: String::from("app_spec_journal"),
+                checkpoint_interval: None,
+            })
+        );
+        assert!(instance_config.encrypt_storage);
         assert_eq!(config.logger.logger_level, "debug");
         assert_eq!(
             config.network.unwrap(),
@@ -1140,6 +1222,9 @@ pub mod tests {
         assert_eq!(instance_config.id, "app spec instance");
         assert_eq!(instance_config.dna, "app spec rust");
         assert_eq!(instance_config.agent, "test agent");
+        assert_eq!(instance_config.warrant_policy, None);
+        assert_eq!(instance_config.action_journal, None);
+        assert!(!instance_config.encrypt_storage);
         assert_eq!(config.logger.logger_level, "debug");
         assert_eq!(config.logger.rules.rules.len(), 1);
 
@@ -1548,6 +1633,44 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_ui_interface_tls() {
+        let toml = r#"
+    [[ui_bundles]]
+    id = "bundle1"
+    root_dir = ""
+
+    [[ui_interfaces]]
+    id = "ui-interface-1"
+    bundle = "bundle1"
+    port = 3000
+
+    [[ui_interfaces]]
+    id = "ui-interface-2"
+    bundle = "bundle1"
+    port = 3001
+    compression = false
+        [ui_interfaces.tls]
+        certificate = "<not existant>.p12"
+    "#;
+        let config = load_configuration::<Configuration>(&toml)
+            .expect("Config should be syntactically correct");
+        assert_eq!(config.ui_interfaces[0].tls, None);
+        assert!(config.ui_interfaces[0].compression);
+        assert!(!config.ui_interfaces[1].compression);
+        assert_eq!(
+            config.ui_interfaces[1].tls,
+            Some(UiTlsConfiguration {
+                certificate: "<not existant>.p12".to_string(),
+                password: String::new(),
+            })
+        );
+        assert_eq!(
+            config.check_consistency(&mut test_dna_loader()),
+            Err("TLS certificate \"<not existant>.p12\" not found, mentioned in UI interface \"ui-interface-2\"".to_string())
+        );
+    }
+
     #[test]
     fn test_inconsistent_dpki() {
         let toml = r#"
@@ -1666,4 +1789,43 @@ pub mod tests {
         );
         Ok(())
     }
+    #[test]
+    fn test_kv_storage_shares_path_check() {
+        let toml = r#"
+        [[agents]]
+        id = "test agent 1"
+        keystore_file = "holo_tester.key"
+        name = "Holo Tester 1"
+        public_address = "HoloTester1-----------------------------------------------------------------------AAACZp4xHB"
+
+        [[instances]]
+        agent = "test agent 1"
+        dna = "app spec rust"
+        id = "app spec instance 1"
+
+            [instances.storage]
+            path = "shared-storage-path"
+            type = "kv"
+
+        [[instances]]
+        agent = "test agent 1"
+        dna = "app spec rust"
+        id = "app spec instance 2"
+
+            [instances.storage]
+            path = "shared-storage-path"
+            type = "file"
+        "#;
+
+        let config = load_configuration::<Configuration>(&toml)
+            .expect("Config should be syntactically correct");
+
+        assert_eq!(
+            config.instances[0].storage,
+            StorageConfiguration::Kv {
+                path: "shared-storage-path".to_string()
+            }
+        );
+        assert!(config.check_instances_storage().is_err());
+    }
 }
@@ -1,6 +1,6 @@
 use holochain_persistence_file::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
 
-use holochain_persistence_mem::{cas::memory::MemoryStorage, eav::memory::EavMemoryStorage};
+use holochain_persistence_mem::cas::memory::MemoryStorage;
 
 use holochain_persistence_pickle::{cas::pickle::PickleStorage, eav::pickle::EavPickleStorage};
 
@@ -8,8 +8,19 @@ use holochain_persistence_api::{
     cas::storage::ContentAddressableStorage, eav::EntityAttributeValueStorage,
 };
 
-use holochain_core::{context::Context, persister::SimplePersister, signal::SignalSender};
-use holochain_core_types::{agent::AgentId, eav::Attribute, error::HolochainError};
+use encrypted_storage::{EncryptedEavStorage, EncryptedStorage, StorageKey};
+use holochain_core::{
+    action_journal::ActionJournal,
+    context::{Context, WarrantPolicy},
+    persister::TransactionalPersister,
+    signal::SignalSender,
+};
+use holochain_core_types::{
+    agent::AgentId,
+    eav::{Attribute, EavIndexedMemoryStorage},
+    error::HolochainError,
+    kv::{EavKvStorage, KvStorage, KvStore},
+};
 use holochain_net::p2p_config::P2pConfig;
 use jsonrpc_core::IoHandler;
 use std::{
@@ -39,6 +50,8 @@ pub struct ContextBuilder {
     conductor_api: Option<Arc<RwLock<IoHandler>>>,
     signal_tx: Option<SignalSender>,
     state_dump_logging: bool,
+    warrant_policy: WarrantPolicy,
+    action_journal: Option<ActionJournal>,
 }
 
 impl ContextBuilder {
@@ -53,6 +66,8 @@ impl ContextBuilder {
             conductor_api: None,
             signal_tx: None,
             state_dump_logging: false,
+            warrant_policy: WarrantPolicy::default(),
+            action_journal: None,
         }
     }
 
@@ -64,10 +79,11 @@ impl ContextBuilder {
 
     /// Sets all three storages, chain, DHT and EAV storage, to transient memory implementations.
     /// Chain and DHT storages get set to the same memory CAS.
+    /// The EAV storage keeps indices on entity, attribute and value.
     pub fn with_memory_storage(mut self) -> Self {
         let cas = Arc::new(RwLock::new(MemoryStorage::new()));
         let eav = //Arc<RwLock<holochain_persistence_api::eav::EntityAttributeValueStorage<Attribute>>> =
-            Arc::new(RwLock::new(EavMemoryStorage::new()));
+            Arc::new(RwLock::new(EavIndexedMemoryStorage::new()));
         self.chain_storage = Some(cas.clone());
         self.dht_storage = Some(cas);
         self.eav_storage = Some(eav);
@@ -76,6 +92,8 @@ impl ContextBuilder {
 
     /// Sets all three storages, chain, DHT and EAV storage, to persistent file based implementations.
     /// Chain and DHT storages get set to the same file CAS.
+    /// Unlike the memory and KV storages, the file EAV storage is not indexed,
+    /// so every EAV query reads all of its records.
     /// Returns an error if no file storage could be spawned on the given path.
     pub fn with_file_storage<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HolochainError> {
         let base_path: PathBuf = path.as_ref().into();
@@ -95,6 +113,7 @@ impl ContextBuilder {
 
     /// Sets all three storages, chain, DHT and EAV storage, to persistent pikcle based implementations.
     /// Chain and DHT storages get set to the same pikcle CAS.
+    /// Like the file EAV storage, the pickle EAV storage is not indexed.
     /// Returns an error if no pickle storage could be spawned on the given path.
     pub fn with_pickle_storage<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HolochainError> {
         let base_path: PathBuf = path.as_ref().into();
@@ -111,6 +130,53 @@ impl ContextBuilder {
         Ok(self)
     }
 
+    /// Sets all three storages, chain, DHT and EAV storage, to one embedded transactional
+    /// key-value store that keeps content and meta data in the same commit log.
+    /// Returns an error if the store could not be opened on the given path.
+    pub fn with_kv_storage<P: AsRef<Path>>(mut self, path: P) -> Result<Self, HolochainError> {
+        let store = KvStore::open(path)?;
+        let cas_storage = Arc::new(RwLock::new(KvStorage::new(store.clone())));
+        let eav_storage = Arc::new(RwLock::new(EavKvStorage::new(store)));
+        self.chain_storage = Some(cas_storage.clone());
+        self.dht_storage = Some(cas_storage);
+        self.eav_storage = Some(eav_storage);
+        Ok(self)
+    }
+
+    /// Wraps the storages configured so far so that everything written to them
+    /// gets encrypted with the given key.
+    /// Needs to be called after any of the `with_*_storage` functions.
+    /// Storages that are not configured yet get set to memory implementations first.
+    pub fn with_encrypted_storage(mut self, key: StorageKey) -> Self {
+        let chain_storage = self
+            .chain_storage
+            .take()
+            .unwrap_or_else(|| Arc::new(RwLock::new(MemoryStorage::new())));
+        let dht_storage = self
+            .dht_storage
+            .take()
+            .unwrap_or_else(|| Arc::new(RwLock::new(MemoryStorage::new())));
+        let eav_storage = self
+            .eav_storage
+            .take()
+            .unwrap_or_else(|| Arc::new(RwLock::new(EavIndexedMemoryStorage::new())));
+
+        let encrypted_chain_storage: Arc<RwLock<dyn ContentAddressableStorage>> = Arc::new(
+            RwLock::new(EncryptedStorage::new(chain_storage.clone(), key.clone())),
+        );
+        self.dht_storage = Some(if Arc::ptr_eq(&chain_storage, &dht_storage) {
+            encrypted_chain_storage.clone()
+        } else {
+            Arc::new(RwLock::new(EncryptedStorage::new(dht_storage, key.clone())))
+        });
+        self.chain_storage = Some(encrypted_chain_storage);
+        self.eav_storage = Some(Arc::new(RwLock::new(EncryptedEavStorage::new(
+            eav_storage,
+            key,
+        ))));
+        self
+    }
+
     /// Sets the network config.
     pub fn with_p2p_config(mut self, p2p_config: P2pConfig) -> Self {
         self.p2p_config = Some(p2p_config);
@@ -137,9 +203,30 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets how the instance treats agents it knows warrants against.
+    pub fn with_warrant_policy(mut self, warrant_policy: WarrantPolicy) -> Self {
+        self.warrant_policy = warrant_policy;
+        self
+    }
+
+    /// Makes the instance append every action it reduces to the journal file at the given path.
+    /// A digest of the state gets recorded every `checkpoint_interval` actions.
+    /// Returns an error if the journal file can't be opened.
+    pub fn with_action_journal<P: AsRef<Path>>(
+        mut self,
+        path: P,
+        checkpoint_interval: u64,
+    ) -> Result<Self, HolochainError> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.action_journal = Some(ActionJournal::new(path, checkpoint_interval)?);
+        Ok(self)
+    }
+
     /// Actually creates the context.
     /// Defaults to memory storages, an in-memory network config and a fake agent called "alice".
-    /// The persister gets set to SimplePersister based on the chain storage.
+    /// The persister gets set to TransactionalPersister based on the chain storage.
     pub fn spawn(self) -> Context {
         let chain_storage = self
             .chain_storage
@@ -149,15 +236,17 @@ impl ContextBuilder {
             .unwrap_or_else(|| Arc::new(RwLock::new(MemoryStorage::new())));
         let eav_storage = self
             .eav_storage
-            .unwrap_or_else(|| Arc::new(RwLock::new(EavMemoryStorage::new())));
+            .unwrap_or_else(|| Arc::new(RwLock::new(EavIndexedMemoryStorage::new())));
 
-        Context::new(
+        let mut context = Context::new(
             &self
                 .instance_name
                 .unwrap_or_else(|| "Anonymous-instance".to_string()),
             self.agent_id
                 .unwrap_or_else(|| AgentId::generate_fake("alice")),
-            Arc::new(Mutex::new(SimplePersister::new(chain_storage.clone()))),
+            Arc::new(Mutex::new(TransactionalPersister::new(
+                chain_storage.clone(),
+            ))),
             chain_storage,
             dht_storage,
             eav_storage,
@@ -166,7 +255,12 @@ impl ContextBuilder {
             self.conductor_api,
             self.signal_tx,
             self.state_dump_logging,
-        )
+        );
+        context.warrant_policy = self.warrant_policy;
+        context.action_journal = self
+            .action_journal
+            .map(|journal| Arc::new(Mutex::new(journal)));
+        context
     }
 }
 
@@ -0,0 +1,746 @@
+//! Encryption at rest for instance storage.
+//!
+//! [EncryptedStorage] and [EncryptedEavStorage] wrap any CAS or EAV storage and encrypt
+//! what gets written to it with a [StorageKey] that is derived from the agent's root seed
+//! in its [Keystore], which in turn gets unlocked through the keystore's [PassphraseManager].
+//!
+//! Content is encrypted as a whole and stored under its original address.
+//! EAV records keep entity and value addresses in the clear so they can be found,
+//! but link types and tags get encrypted deterministically.
+//! Fetching from an encrypted EAV storage passes the entity, value and index range filters
+//! on to the wrapped storage as they are. Since EAV query filters are opaque predicates on
+//! the clear text, link attributes get decrypted to be checked, but only once per distinct
+//! sealed attribute, which deterministic sealing makes equal for equal clear texts.
+//! Only the records that pass get decrypted as a whole, and the query is run on them.
+
+use holochain_core_types::{
+    eav::{Attribute, EaviQuery, EntityAttributeValueIndex, Index},
+    error::{HcResult, HolochainError},
+};
+use holochain_dpki::{
+    utils::{generate_derived_seed_buf, SeedContext},
+    SEED_SIZE,
+};
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent, Content},
+        storage::ContentAddressableStorage,
+    },
+    eav::{storage::EntityAttributeValueStorage, EavFilter, IndexFilter},
+    error::{PersistenceError, PersistenceResult},
+};
+use holochain_persistence_file::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
+use keystore::{Keystore, Secret, STANDALONE_ROOT_SEED};
+use lib3h_sodium::{
+    aead::{self, ABYTES, NONCEBYTES},
+    hash::sha256,
+    secbuf::SecBuf,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryFrom,
+    fmt, fs,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+};
+use uuid::Uuid;
+
+/// Context used to derive storage keys from the root seed.
+pub const STORAGE_KEY_CONTEXT: [u8; 8] = *b"HCSTORAG";
+pub const STORAGE_KEY_INDEX: u64 = 1;
+
+/// Prefixed to every plain text before sealing so that we can change the format later
+/// and never have to seal empty buffers.
+const SEALED_FORMAT_VERSION: u8 = 1;
+
+/// Symmetric key that instance storage gets encrypted with.
+#[derive(Clone)]
+pub struct StorageKey {
+    key: Arc<Mutex<SecBuf>>,
+}
+
+impl fmt::Debug for StorageKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StorageKey(..)")
+    }
+}
+
+impl StorageKey {
+    pub fn new(key: SecBuf) -> Self {
+        StorageKey {
+            key: Arc::new(Mutex::new(key)),
+        }
+    }
+
+    /// Derives the storage key from the keystore's root seed.
+    /// This decrypts the seed and thus asks the keystore's PassphraseManager for the passphrase.
+    pub fn from_keystore(keystore: &mut Keystore) -> HcResult<Self> {
+        let seed = keystore.get(STANDALONE_ROOT_SEED)?;
+        let mut seed = seed.lock()?;
+        match *seed {
+            Secret::Seed(ref mut seed_buf) => Ok(StorageKey::new(generate_derived_seed_buf(
+                seed_buf,
+                &SeedContext::new(STORAGE_KEY_CONTEXT),
+                STORAGE_KEY_INDEX,
+                SEED_SIZE,
+            )?)),
+            _ => Err(HolochainError::ErrorGeneric(format!(
+                "Keystore secret '{}' is not a seed",
+                STANDALONE_ROOT_SEED
+            ))),
+        }
+    }
+
+    fn seal_with_nonce(&self, data: &[u8], mut nonce: SecBuf) -> HcResult<Vec<u8>> {
+        let mut message = SecBuf::with_insecure(data.len() + 1);
+        message.write(0, &[SEALED_FORMAT_VERSION])?;
+        message.write(1, data)?;
+        let mut cipher = SecBuf::with_insecure(message.len() + ABYTES);
+        {
+            let mut key = self.key.lock()?;
+            aead::enc(&mut message, &mut *key, None, &mut nonce, &mut cipher)?;
+        }
+        let cipher_slice = &**cipher.read_lock();
+        let nonce_slice = &**nonce.read_lock();
+        Ok(cipher_slice
+            .iter()
+            .cloned()
+            .chain(nonce_slice.iter().cloned())
+            .collect())
+    }
+
+    /// Encrypts the data with a random nonce, which is appended to the cipher text.
+    pub fn seal(&self, data: &[u8]) -> HcResult<Vec<u8>> {
+        let mut nonce = SecBuf::with_insecure(NONCEBYTES);
+        nonce.randomize();
+        self.seal_with_nonce(data, nonce)
+    }
+
+    /// Encrypts the data with a nonce derived from key and data,
+    /// so that equal clear texts give equal cipher texts.
+    pub fn seal_deterministic(&self, data: &[u8]) -> HcResult<Vec<u8>> {
+        let mut hash = SecBuf::with_secure(32);
+        {
+            let mut key = self.key.lock()?;
+            // Holds a copy of the key, so it has to be protected like the key itself
+            let mut input = SecBuf::with_secure(key.len() + data.len() + 1);
+            input.write(0, &**key.read_lock())?;
+            input.write(key.len(), &[SEALED_FORMAT_VERSION])?;
+            input.write(key.len() + 1, data)?;
+            sha256(&mut input, &mut hash)?;
+        }
+        let mut nonce = SecBuf::with_insecure(NONCEBYTES);
+        nonce.write(0, &hash.read_lock()[..NONCEBYTES])?;
+        self.seal_with_nonce(data, nonce)
+    }
+
+    /// Decrypts data sealed with either `seal` or `seal_deterministic`.
+    pub fn open(&self, sealed: &[u8]) -> HcResult<Vec<u8>> {
+        if sealed.len() < NONCEBYTES + ABYTES + 1 {
+            return Err(HolochainError::ErrorGeneric(
+                "Encrypted data is too short".to_string(),
+            ));
+        }
+        let cipher_length = sealed.len() - NONCEBYTES;
+        let mut nonce = SecBuf::with_insecure(NONCEBYTES);
+        nonce.write(0, &sealed[cipher_length..])?;
+        let mut cipher = SecBuf::with_insecure(cipher_length);
+        cipher.write(0, &sealed[..cipher_length])?;
+        let mut message = SecBuf::with_insecure(cipher_length - ABYTES);
+        {
+            let mut key = self.key.lock()?;
+            aead::dec(&mut message, &mut *key, None, &mut nonce, &mut cipher)?;
+        }
+        let message = &**message.read_lock();
+        match message.split_first() {
+            Some((&SEALED_FORMAT_VERSION, data)) => Ok(data.to_vec()),
+            _ => Err(HolochainError::ErrorGeneric(
+                "Unknown encrypted data format".to_string(),
+            )),
+        }
+    }
+
+    fn seal_string(&self, data: &str) -> HcResult<String> {
+        Ok(base64::encode(&self.seal(data.as_bytes())?))
+    }
+
+    fn open_string(&self, sealed: &str) -> HcResult<String> {
+        let data = self.open(&base64::decode(sealed)?)?;
+        String::from_utf8(data)
+            .map_err(|_| HolochainError::ErrorGeneric("Decrypted data is not UTF-8".to_string()))
+    }
+
+    /// Hex keeps encrypted attribute parts usable in file names and attribute strings.
+    fn seal_name(&self, name: &str) -> HcResult<String> {
+        Ok(self
+            .seal_deterministic(name.as_bytes())?
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    fn open_name(&self, sealed: &str) -> HcResult<String> {
+        let invalid = || HolochainError::ErrorGeneric("Invalid encrypted name".to_string());
+        if sealed.len() % 2 != 0 || !sealed.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..sealed.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&sealed[i..i + 2], 16).map_err(|_| invalid()))
+            .collect::<HcResult<Vec<u8>>>()?;
+        String::from_utf8(self.open(&bytes)?).map_err(|_| invalid())
+    }
+
+    fn seal_attribute(&self, attribute: &Attribute) -> HcResult<Attribute> {
+        Ok(match attribute {
+            Attribute::LinkTag(link_type, tag) => {
+                Attribute::LinkTag(self.seal_name(link_type)?, self.seal_name(tag)?)
+            }
+            Attribute::RemovedLink(link_type, tag) => {
+                Attribute::RemovedLink(self.seal_name(link_type)?, self.seal_name(tag)?)
+            }
+            other => other.clone(),
+        })
+    }
+
+    /// True for attributes that get stored sealed.
+    fn is_sealed(attribute: &Attribute) -> bool {
+        match attribute {
+            Attribute::LinkTag(_, _) | Attribute::RemovedLink(_, _) => true,
+            _ => false,
+        }
+    }
+
+    fn open_attribute(&self, attribute: &Attribute) -> HcResult<Attribute> {
+        Ok(match attribute {
+            Attribute::LinkTag(link_type, tag) => {
+                Attribute::LinkTag(self.open_name(link_type)?, self.open_name(tag)?)
+            }
+            Attribute::RemovedLink(link_type, tag) => {
+                Attribute::RemovedLink(self.open_name(link_type)?, self.open_name(tag)?)
+            }
+            other => other.clone(),
+        })
+    }
+
+    fn seal_eavi(&self, eavi: &EntityAttributeValueIndex) -> HcResult<EntityAttributeValueIndex> {
+        Ok(EntityAttributeValueIndex::new_with_index(
+            &eavi.entity(),
+            &self.seal_attribute(&eavi.attribute())?,
+            &eavi.value(),
+            eavi.index(),
+        )?)
+    }
+
+    fn open_eavi(&self, eavi: &EntityAttributeValueIndex) -> HcResult<EntityAttributeValueIndex> {
+        Ok(EntityAttributeValueIndex::new_with_index(
+            &eavi.entity(),
+            &self.open_attribute(&eavi.attribute())?,
+            &eavi.value(),
+            eavi.index(),
+        )?)
+    }
+}
+
+fn storage_error(error: HolochainError) -> PersistenceError {
+    PersistenceError::ErrorGeneric(format!("Encrypted storage: {}", error))
+}
+
+/// What an encrypted storage actually writes to the wrapped CAS.
+#[derive(Clone, Debug, Serialize, Deserialize, DefaultJson)]
+struct EncryptedContent {
+    address: Address,
+    encrypted: String,
+}
+
+impl AddressableContent for EncryptedContent {
+    fn content(&self) -> Content {
+        self.to_owned().into()
+    }
+
+    fn try_from_content(content: &Content) -> Result<Self, JsonError> {
+        Self::try_from(content.to_owned())
+    }
+
+    fn address(&self) -> Address {
+        self.address.clone()
+    }
+}
+
+/// Content as read from a storage, to be written to another one under the same address.
+struct StoredContent {
+    address: Address,
+    content: Content,
+}
+
+impl AddressableContent for StoredContent {
+    fn content(&self) -> Content {
+        self.content.clone()
+    }
+
+    fn try_from_content(_: &Content) -> Result<Self, JsonError> {
+        Err(JsonError::ErrorGeneric(
+            "StoredContent needs its address to be given".to_string(),
+        ))
+    }
+
+    fn address(&self) -> Address {
+        self.address.clone()
+    }
+}
+
+/// CAS wrapper that encrypts all content before it reaches the wrapped storage.
+#[derive(Clone, Debug)]
+pub struct EncryptedStorage {
+    storage: Arc<RwLock<dyn ContentAddressableStorage>>,
+    key: StorageKey,
+}
+
+impl EncryptedStorage {
+    pub fn new(storage: Arc<RwLock<dyn ContentAddressableStorage>>, key: StorageKey) -> Self {
+        EncryptedStorage { storage, key }
+    }
+}
+
+impl ContentAddressableStorage for EncryptedStorage {
+    fn add(&mut self, content: &dyn AddressableContent) -> PersistenceResult<()> {
+        let encrypted = EncryptedContent {
+            address: content.address(),
+            encrypted: self
+                .key
+                .seal_string(&String::from(content.content()))
+                .map_err(storage_error)?,
+        };
+        self.storage
+            .write()
+            .map_err(|_| storage_error(HolochainError::new("Could not lock storage")))?
+            .add(&encrypted)
+    }
+
+    fn contains(&self, address: &Address) -> PersistenceResult<bool> {
+        self.storage
+            .read()
+            .map_err(|_| storage_error(HolochainError::new("Could not lock storage")))?
+            .contains(address)
+    }
+
+    fn fetch(&self, address: &Address) -> PersistenceResult<Option<Content>> {
+        let stored = self
+            .storage
+            .read()
+            .map_err(|_| storage_error(HolochainError::new("Could not lock storage")))?
+            .fetch(address)?;
+        match stored {
+            Some(content) => {
+                let encrypted = EncryptedContent::try_from_content(&content).map_err(|_| {
+                    storage_error(HolochainError::ErrorGeneric(format!(
+                        "Content at {} is not encrypted",
+                        address
+                    )))
+                })?;
+                let content = self
+                    .key
+                    .open_string(&encrypted.encrypted)
+                    .map_err(storage_error)?;
+                Ok(Some(JsonString::from_json(&content)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get_id(&self) -> Uuid {
+        self.storage.read().unwrap().get_id()
+    }
+}
+
+/// EAV wrapper that encrypts link types and tags before they reach the wrapped storage.
+#[derive(Clone, Debug)]
+pub struct EncryptedEavStorage {
+    storage: Arc<RwLock<dyn EntityAttributeValueStorage<Attribute>>>,
+    key: StorageKey,
+}
+
+impl EncryptedEavStorage {
+    pub fn new(
+        storage: Arc<RwLock<dyn EntityAttributeValueStorage<Attribute>>>,
+        key: StorageKey,
+    ) -> Self {
+        EncryptedEavStorage { storage, key }
+    }
+}
+
+fn all_eavis_query<'a>() -> EaviQuery<'a> {
+    EaviQuery::new(
+        None.into(),
+        None.into(),
+        None.into(),
+        IndexFilter::Range(None, None),
+        None,
+    )
+}
+
+impl EntityAttributeValueStorage<Attribute> for EncryptedEavStorage {
+    fn add_eavi(
+        &mut self,
+        eavi: &EntityAttributeValueIndex,
+    ) -> PersistenceResult<Option<EntityAttributeValueIndex>> {
+        let encrypted = self.key.seal_eavi(eavi).map_err(storage_error)?;
+        let stored = self
+            .storage
+            .write()
+            .map_err(|_| storage_error(HolochainError::new("Could not lock storage")))?
+            .add_eavi(&encrypted)?;
+        match stored {
+            Some(stored) => Ok(Some(self.key.open_eavi(&stored).map_err(storage_error)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn fetch_eavi(
+        &self,
+        query: &EaviQuery,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex>> {
+        // Picking the latest record needs to see tombstones, so only index ranges get
+        // passed on and the query's own index filter is applied to the decrypted candidates.
+        let index = match query.index() {
+            IndexFilter::Range(start, end) => IndexFilter::Range(
+                Some(start.unwrap_or(Index::min_value())),
+                Some(end.unwrap_or(Index::max_value())),
+            ),
+            _ => IndexFilter::Range(None, None),
+        };
+        let opened: Mutex<HashMap<Attribute, bool>> = Mutex::new(HashMap::new());
+        let attribute_matches = |attribute: Attribute| {
+            query.attribute().check(attribute.clone())
+                || query
+                    .tombstone()
+                    .map(|tombstone| tombstone.check(attribute))
+                    .unwrap_or(false)
+        };
+        let candidates_query = EaviQuery::new(
+            EavFilter::predicate(|entity| query.entity().check(entity)),
+            EavFilter::predicate(|attribute: Attribute| {
+                if !StorageKey::is_sealed(&attribute) {
+                    return attribute_matches(attribute);
+                }
+                let mut opened = opened.lock().unwrap();
+                if let Some(matches) = opened.get(&attribute) {
+                    return *matches;
+                }
+                // Records that can't be decrypted are passed on to fail below
+                let matches = self
+                    .key
+                    .open_attribute(&attribute)
+                    .map(&attribute_matches)
+                    .unwrap_or(true);
+                opened.insert(attribute, matches);
+                matches
+            }),
+            EavFilter::predicate(|value| query.value().check(value)),
+            index,
+            None,
+        );
+
+        let decrypted = self
+            .storage
+            .read()
+            .map_err(|_| storage_error(HolochainError::new("Could not lock storage")))?
+            .fetch_eavi(&candidates_query)?
+            .iter()
+            .map(|eavi| self.key.open_eavi(eavi))
+            .collect::<HcResult<Vec<_>>>()
+            .map_err(storage_error)?;
+        Ok(query.run(decrypted.into_iter()))
+    }
+}
+
+type StoragePair = (
+    Arc<RwLock<dyn ContentAddressableStorage>>,
+    Arc<RwLock<dyn EntityAttributeValueStorage<Attribute>>>,
+);
+
+/// Opens the file storage layout that ContextBuilder::with_file_storage creates.
+fn open_file_storage(path: &Path) -> HcResult<StoragePair> {
+    let cas_path = path.join("cas");
+    let eav_path = path.join("eav");
+    fs::create_dir_all(&cas_path)?;
+    fs::create_dir_all(&eav_path)?;
+    Ok((
+        Arc::new(RwLock::new(FilesystemStorage::new(&cas_path)?)),
+        Arc::new(RwLock::new(EavFileStorage::new(eav_path)?)),
+    ))
+}
+
+/// File storage keeps every content in a file named after its address.
+fn stored_addresses(cas_path: &Path) -> HcResult<Vec<Address>> {
+    let mut addresses = Vec::new();
+    for entry in fs::read_dir(cas_path)? {
+        let path = entry?.path();
+        if path.is_file() {
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                addresses.push(Address::from(stem));
+            }
+        }
+    }
+    Ok(addresses)
+}
+
+fn copy_storage(from: StoragePair, to: StoragePair, addresses: Vec<Address>) -> HcResult<usize> {
+    let mut copied = 0;
+    for address in addresses {
+        if let Some(content) = from.0.read()?.fetch(&address)? {
+            to.0.write()?.add(&StoredContent { address, content })?;
+            copied += 1;
+        }
+    }
+    for eavi in from.1.read()?.fetch_eavi(&all_eavis_query())? {
+        to.1.write()?.add_eavi(&eavi)?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+fn encrypted(storage: StoragePair, key: &StorageKey) -> StoragePair {
+    (
+        Arc::new(RwLock::new(EncryptedStorage::new(storage.0, key.clone()))),
+        Arc::new(RwLock::new(EncryptedEavStorage::new(
+            storage.1,
+            key.clone(),
+        ))),
+    )
+}
+
+/// Writes an encrypted copy of the file storage at `source` to `target`.
+/// Returns the number of records copied.
+pub fn encrypt_storage_dir(source: &Path, target: &Path, key: &StorageKey) -> HcResult<usize> {
+    let addresses = stored_addresses(&source.join("cas"))?;
+    copy_storage(
+        open_file_storage(source)?,
+        encrypted(open_file_storage(target)?, key),
+        addresses,
+    )
+}
+
+/// Writes a decrypted copy of the encrypted file storage at `source` to `target`.
+/// Returns the number of records copied.
+pub fn decrypt_storage_dir(source: &Path, target: &Path, key: &StorageKey) -> HcResult<usize> {
+    let addresses = stored_addresses(&source.join("cas"))?;
+    copy_storage(
+        encrypted(open_file_storage(source)?, key),
+        open_file_storage(target)?,
+        addresses,
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::entry::{test_entry, test_entry_b};
+    use holochain_persistence_mem::{cas::memory::MemoryStorage, eav::memory::EavMemoryStorage};
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+    use key_loaders::test_keystore;
+
+    fn test_key() -> StorageKey {
+        StorageKey::from_keystore(&mut test_keystore(&"alice".to_string())).unwrap()
+    }
+
+    fn test_link_eavi() -> EntityAttributeValueIndex {
+        EntityAttributeValueIndex::new(
+            &test_entry().address(),
+            &Attribute::LinkTag("comments".to_string(), "".to_string()),
+            &test_entry_b().address(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn storage_key_is_derived_deterministically() {
+        let key = test_key();
+        let other = test_key();
+        let sealed = key.seal(b"secret").unwrap();
+        assert_eq!(other.open(&sealed).unwrap(), b"secret".to_vec());
+        assert_ne!(key.seal(b"secret").unwrap(), sealed);
+        assert_eq!(
+            key.seal_deterministic(b"tag").unwrap(),
+            other.seal_deterministic(b"tag").unwrap()
+        );
+
+        let bob = StorageKey::from_keystore(&mut test_keystore(&"bob".to_string())).unwrap();
+        assert!(bob.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn encrypted_cas_round_trip() {
+        let inner = Arc::new(RwLock::new(MemoryStorage::new()));
+        let mut storage = EncryptedStorage::new(inner.clone(), test_key());
+        let entry = test_entry();
+        storage.add(&entry).unwrap();
+
+        assert!(storage.contains(&entry.address()).unwrap());
+        assert_eq!(
+            storage.fetch(&entry.address()).unwrap(),
+            Some(entry.content())
+        );
+        let raw = inner
+            .read()
+            .unwrap()
+            .fetch(&entry.address())
+            .unwrap()
+            .unwrap();
+        assert!(!String::from(raw).contains(&String::from(entry.content())));
+    }
+
+    #[test]
+    fn encrypted_eav_round_trip() {
+        let inner = Arc::new(RwLock::new(EavMemoryStorage::new()));
+        let mut storage = EncryptedEavStorage::new(inner.clone(), test_key());
+        let eavi = test_link_eavi();
+        let stored = storage.add_eavi(&eavi).unwrap().unwrap();
+        assert_eq!(stored.attribute(), eavi.attribute());
+
+        let query = EaviQuery::new(
+            Some(eavi.entity()).into(),
+            Some(eavi.attribute()).into(),
+            None.into(),
+            IndexFilter::LatestByAttribute,
+            None,
+        );
+        let fetched = storage.fetch_eavi(&query).unwrap();
+        assert_eq!(fetched.into_iter().collect::<Vec<_>>(), vec![stored]);
+
+        let raw = inner
+            .read()
+            .unwrap()
+            .fetch_eavi(&all_eavis_query())
+            .unwrap();
+        assert_eq!(raw.len(), 1);
+        assert_ne!(raw.iter().next().unwrap().attribute(), eavi.attribute());
+    }
+
+    #[test]
+    fn encrypted_eav_queries_only_decrypt_candidates() {
+        let inner = Arc::new(RwLock::new(EavMemoryStorage::new()));
+        let mut storage = EncryptedEavStorage::new(inner.clone(), test_key());
+        let mut example = EavMemoryStorage::new();
+        let link = test_link_eavi();
+        let removed = EntityAttributeValueIndex::new_with_index(
+            &link.entity(),
+            &Attribute::RemovedLink("comments".to_string(), "".to_string()),
+            &link.value(),
+            link.index() + 1,
+        )
+        .unwrap();
+        for eavi in vec![link.clone(), removed.clone()] {
+            assert_eq!(
+                storage.add_eavi(&eavi).unwrap(),
+                example.add_eavi(&eavi).unwrap()
+            );
+        }
+        // A record that can't be decrypted, which only queries for its entity may touch
+        let other_entity = test_entry_b().address();
+        inner
+            .write()
+            .unwrap()
+            .add_eavi(
+                &EntityAttributeValueIndex::new(
+                    &other_entity,
+                    &Attribute::LinkTag("00".to_string(), "00".to_string()),
+                    &link.value(),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        let links_of = |entity: Address, tombstone: bool| {
+            EaviQuery::new(
+                Some(entity).into(),
+                EavFilter::predicate(|attribute| match attribute {
+                    Attribute::LinkTag(link_type, _) | Attribute::RemovedLink(link_type, _) => {
+                        link_type == "comments"
+                    }
+                    _ => false,
+                }),
+                None.into(),
+                IndexFilter::LatestByAttribute,
+                if tombstone {
+                    Some(EavFilter::single(Attribute::RemovedLink(
+                        "comments".to_string(),
+                        "".to_string(),
+                    )))
+                } else {
+                    None
+                },
+            )
+        };
+        let queries = vec![
+            links_of(link.entity(), false),
+            links_of(link.entity(), true),
+            EaviQuery::new(
+                Some(link.entity()).into(),
+                Some(link.attribute()).into(),
+                None.into(),
+                IndexFilter::LatestByAttribute,
+                None,
+            ),
+            EaviQuery::new(
+                Some(link.entity()).into(),
+                None.into(),
+                None.into(),
+                IndexFilter::Range(Some(removed.index()), None),
+                None,
+            ),
+        ];
+        for query in queries {
+            let fetched = storage.fetch_eavi(&query).unwrap();
+            assert!(!fetched.is_empty());
+            assert_eq!(fetched, example.fetch_eavi(&query).unwrap());
+        }
+
+        assert!(storage.fetch_eavi(&links_of(other_entity, false)).is_err());
+    }
+
+    #[test]
+    fn storage_dir_encrypt_decrypt_round_trip() {
+        let plain = tempdir().unwrap();
+        let encrypted_dir = tempdir().unwrap();
+        let decrypted_dir = tempdir().unwrap();
+        let entry = test_entry();
+        let eavi = test_link_eavi();
+        {
+            let (cas, eav) = open_file_storage(plain.path()).unwrap();
+            cas.write().unwrap().add(&entry).unwrap();
+            eav.write().unwrap().add_eavi(&eavi).unwrap();
+        }
+
+        let key = test_key();
+        assert_eq!(
+            encrypt_storage_dir(plain.path(), encrypted_dir.path(), &key).unwrap(),
+            2
+        );
+        let (cas, _) = encrypted(open_file_storage(encrypted_dir.path()).unwrap(), &key);
+        assert_eq!(
+            cas.read().unwrap().fetch(&entry.address()).unwrap(),
+            Some(entry.content())
+        );
+
+        assert_eq!(
+            decrypt_storage_dir(encrypted_dir.path(), decrypted_dir.path(), &key).unwrap(),
+            2
+        );
+        let (cas, eav) = open_file_storage(decrypted_dir.path()).unwrap();
+        assert_eq!(
+            cas.read().unwrap().fetch(&entry.address()).unwrap(),
+            Some(entry.content())
+        );
+        assert_eq!(
+            eav.read()
+                .unwrap()
+                .fetch_eavi(&all_eavis_query())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,313 @@
+/// hApp bundles
+/// A hApp bundle describes everything a multi-DNA application needs to get installed
+/// into a conductor in one go:
+/// * the DNAs (with their expected hashes and optional properties / uuid overrides),
+/// * the instances to create from these DNAs, per agent,
+/// * the bridges between those instances,
+/// * and the UI bundles to serve.
+///
+/// The manifest is a TOML file (usually called `happ.toml`) and all file paths in it
+/// are relative to the directory the manifest resides in.
+/// `hc bundle` builds such a bundle directory from a source manifest and
+/// `ConductorHappAdmin::install_happ_bundle()` applies it to a running conductor.
+use crate::config::{load_configuration, Bridge, InstanceReferenceConfiguration};
+use holochain_core_types::error::{HcResult, HolochainError};
+use std::{
+    fs::File,
+    io::prelude::*,
+    path::{Path, PathBuf},
+};
+
+/// Default file name of a hApp bundle manifest
+pub const HAPP_MANIFEST_FILE_NAME: &str = "happ.toml";
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HappBundle {
+    /// Name of the hApp. Used to name the bundle directory that `hc bundle` creates.
+    pub name: String,
+    /// DNAs this hApp consists of.
+    #[serde(default)]
+    pub dnas: Vec<HappBundleDna>,
+    /// Instances to create from the DNAs above, each run by an agent that has to be
+    /// configured in the conductor already.
+    #[serde(default)]
+    pub instances: Vec<HappBundleInstance>,
+    /// Bridges between the instances above.
+    #[serde(default)]
+    pub bridges: Vec<Bridge>,
+    /// Static UI bundles belonging to this hApp.
+    #[serde(default)]
+    pub uis: Vec<HappBundleUi>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HappBundleDna {
+    /// ID of the DNA as it will be used in the conductor config
+    pub id: String,
+    /// Path to the .dna.json file, relative to the manifest
+    pub file: String,
+    /// Hash of the DNA file (before properties and uuid are applied).
+    /// Installation fails if it is given and does not match.
+    /// Can be left out in source manifests, `hc bundle` fills it in.
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Properties to merge into the DNA's properties on installation
+    #[serde(default)]
+    pub properties: Option<serde_json::Value>,
+    /// Value to override the DNA's uuid with on installation
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HappBundleInstance {
+    pub id: String,
+    /// ID of a DNA listed in this bundle
+    pub dna: String,
+    /// ID of an agent configured in the conductor
+    pub agent: String,
+    /// Existing conductor interfaces this instance should be made available through
+    #[serde(default)]
+    pub interfaces: Vec<HappBundleInterfaceReference>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HappBundleInterfaceReference {
+    /// ID of the conductor interface
+    pub id: String,
+    /// Name of the instance within that interface
+    #[serde(default)]
+    pub alias: Option<String>,
+}
+
+impl HappBundleInterfaceReference {
+    pub fn instance_reference(&self, instance_id: &String) -> InstanceReferenceConfiguration {
+        InstanceReferenceConfiguration {
+            id: instance_id.clone(),
+            alias: self.alias.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HappBundleUi {
+    /// ID of the UI bundle as it will be used in the conductor config
+    pub id: String,
+    /// Directory holding the static UI files, relative to the manifest
+    pub root_dir: String,
+    /// Optional UI interface to serve this UI bundle through
+    #[serde(default)]
+    pub interface: Option<HappBundleUiInterface>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct HappBundleUiInterface {
+    pub id: String,
+    pub port: u16,
+    /// ID of an existing conductor interface the UI should make its calls to
+    #[serde(default)]
+    pub dna_interface: Option<String>,
+}
+
+impl HappBundle {
+    /// Reads a manifest file
+    pub fn from_file(path: &PathBuf) -> HcResult<HappBundle> {
+        let mut f = File::open(path).map_err(|e| {
+            HolochainError::ConfigError(format!(
+                "Could not open hApp bundle manifest {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let mut contents = String::new();
+        f.read_to_string(&mut contents)?;
+        load_configuration::<HappBundle>(&contents)
+    }
+
+    /// Serializes the manifest to TOML
+    pub fn to_toml(&self) -> HcResult<String> {
+        // see https://github.com/alexcrichton/toml-rs/issues/142
+        let bundle_toml = toml::Value::try_from(self).map_err(|e| {
+            HolochainError::IoError(format!(
+                "Could not serialize hApp bundle manifest: {}",
+                e.to_string()
+            ))
+        })?;
+        toml::to_string_pretty(&bundle_toml).map_err(|e| {
+            HolochainError::IoError(format!(
+                "Could not convert toml to string: {}",
+                e.to_string()
+            ))
+        })
+    }
+
+    /// Checks that all references within the bundle are resolvable and that there are no
+    /// duplicate IDs. References to things outside of the bundle (agents and interfaces)
+    /// get checked when the bundle is installed.
+    pub fn check_consistency(&self) -> Result<(), String> {
+        let mut dna_ids: Vec<&String> = self.dnas.iter().map(|dna| &dna.id).collect();
+        let mut instance_ids: Vec<&String> = self.instances.iter().map(|i| &i.id).collect();
+        let mut ui_ids: Vec<&String> = self.uis.iter().map(|ui| &ui.id).collect();
+        for (kind, ids) in vec![
+            ("DNA", &mut dna_ids),
+            ("instance", &mut instance_ids),
+            ("UI", &mut ui_ids),
+        ] {
+            let count = ids.len();
+            ids.sort();
+            ids.dedup();
+            if ids.len() != count {
+                return Err(format!("Duplicate {} IDs in hApp bundle", kind));
+            }
+        }
+
+        for instance in self.instances.iter() {
+            if !dna_ids.contains(&&instance.dna) {
+                return Err(format!(
+                    "DNA \"{}\" not found in hApp bundle, mentioned in instance \"{}\"",
+                    instance.dna, instance.id
+                ));
+            }
+        }
+
+        for bridge in self.bridges.iter() {
+            for id in vec![&bridge.caller_id, &bridge.callee_id] {
+                if !instance_ids.contains(&id) {
+                    return Err(format!(
+                        "Instance \"{}\" not found in hApp bundle, mentioned in bridge \"{}\"",
+                        id, bridge.handle
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves a path given in a manifest relative to the manifest's directory
+pub fn resolve_bundle_path(bundle_dir: &Path, path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        path
+    } else {
+        bundle_dir.join(path)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn example_happ_toml() -> String {
+        r#"name = 'chat'
+
+[[dnas]]
+id = 'chat-dna'
+file = 'dnas/chat.dna.json'
+hash = 'QmaJiTs75zU7kMFYDkKgrCYaH8WtnYNkmYX3tPt7ycbtRq'
+uuid = 'chat-1'
+
+[dnas.properties]
+room = 'lobby'
+
+[[dnas]]
+id = 'profiles-dna'
+file = 'dnas/profiles.dna.json'
+hash = 'QmaJiTs75zU7kMFYDkKgrCYaH8WtnYNkmYX3tPt7ycbtRq'
+
+[[instances]]
+id = 'chat'
+dna = 'chat-dna'
+agent = 'test-agent-1'
+
+[[instances.interfaces]]
+id = 'websocket interface'
+alias = 'chat-instance'
+
+[[instances]]
+id = 'profiles'
+dna = 'profiles-dna'
+agent = 'test-agent-1'
+
+[[bridges]]
+caller_id = 'chat'
+callee_id = 'profiles'
+handle = 'profiles'
+
+[[uis]]
+id = 'chat-ui'
+root_dir = 'ui'
+
+[uis.interface]
+id = 'chat-ui-interface'
+port = 3500
+dna_interface = 'websocket interface'
+"#
+        .to_string()
+    }
+
+    #[test]
+    fn test_load_happ_bundle() {
+        let bundle = load_configuration::<HappBundle>(&example_happ_toml()).unwrap();
+        assert_eq!(bundle.name, "chat");
+        assert_eq!(bundle.dnas.len(), 2);
+        assert_eq!(bundle.dnas[0].properties, Some(json!({"room": "lobby"})));
+        assert_eq!(bundle.dnas[0].uuid, Some("chat-1".to_string()));
+        assert_eq!(bundle.dnas[1].properties, None);
+        assert_eq!(
+            bundle.instances[0].interfaces,
+            vec![HappBundleInterfaceReference {
+                id: "websocket interface".to_string(),
+                alias: Some("chat-instance".to_string()),
+            }]
+        );
+        assert!(bundle.instances[1].interfaces.is_empty());
+        assert_eq!(bundle.bridges[0].handle, "profiles");
+        assert_eq!(
+            bundle.uis[0].interface,
+            Some(HappBundleUiInterface {
+                id: "chat-ui-interface".to_string(),
+                port: 3500,
+                dna_interface: Some("websocket interface".to_string()),
+            })
+        );
+        assert_eq!(bundle.check_consistency(), Ok(()));
+
+        let reloaded = load_configuration::<HappBundle>(&bundle.to_toml().unwrap()).unwrap();
+        assert_eq!(reloaded, bundle);
+    }
+
+    #[test]
+    fn test_happ_bundle_consistency() {
+        let mut bundle = load_configuration::<HappBundle>(&example_happ_toml()).unwrap();
+        bundle.instances[1].dna = "missing-dna".to_string();
+        assert_eq!(
+            bundle.check_consistency(),
+            Err(
+                "DNA \"missing-dna\" not found in hApp bundle, mentioned in instance \"profiles\""
+                    .to_string()
+            )
+        );
+
+        let mut bundle = load_configuration::<HappBundle>(&example_happ_toml()).unwrap();
+        bundle.dnas[1].id = "chat-dna".to_string();
+        assert_eq!(
+            bundle.check_consistency(),
+            Err("Duplicate DNA IDs in hApp bundle".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_bundle_path() {
+        let dir = PathBuf::from("/bundles/chat");
+        assert_eq!(
+            resolve_bundle_path(&dir, "dnas/chat.dna.json"),
+            PathBuf::from("/bundles/chat/dnas/chat.dna.json")
+        );
+        assert_eq!(
+            resolve_bundle_path(&dir, "/other/chat.dna.json"),
+            PathBuf::from("/other/chat.dna.json")
+        );
+    }
+}
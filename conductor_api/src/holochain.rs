@@ -94,13 +94,15 @@ use holochain_core::{
     context::Context,
     instance::Instance,
     nucleus::{
+        actions::remove_pending_validation::remove_pending_validation,
         call_zome_function,
         ribosome::{run_dna, WasmCallData},
+        state::PendingValidationKey,
         ZomeFnCall,
     },
-    persister::{Persister, SimplePersister},
 };
 use holochain_core_types::{
+    chain_header::ChainHeader,
     dna::{capabilities::CapabilityRequest, Dna},
     error::HolochainError,
 };
@@ -109,7 +111,9 @@ use holochain_json_api::json::JsonString;
 
 use holochain_core::{
     state::StateWrapper,
-    state_dump::{address_to_content_and_type, StateDump},
+    state_dump::{
+        address_to_content_and_type, source_chain_page, PendingValidationDump, StateDump,
+    },
 };
 use holochain_persistence_api::cas::content::Address;
 use jsonrpc_core::IoHandler;
@@ -169,10 +173,16 @@ impl Holochain {
     }
 
     pub fn load(context: Arc<Context>) -> Result<Self, HolochainError> {
-        let persister = SimplePersister::new(context.dht_storage.clone());
-        let loaded_state = persister.load(context.clone())?.ok_or_else(|| {
-            HolochainError::ErrorGeneric("State could not be loaded due to NoneError".to_string())
-        })?;
+        let loaded_state = context
+            .persister
+            .lock()
+            .map_err(|_| HolochainError::new("Could not get lock on persister"))?
+            .load(context.clone())?
+            .ok_or_else(|| {
+                HolochainError::ErrorGeneric(
+                    "State could not be loaded due to NoneError".to_string(),
+                )
+            })?;
         let mut instance = Instance::from_state(loaded_state.clone(), context.clone());
         let new_context = instance.initialize(None, context.clone())?;
         Ok(Holochain {
@@ -266,11 +276,67 @@ impl Holochain {
         Ok(())
     }
 
-    pub fn get_state_dump(&self) -> Result<StateDump, HolochainInstanceError> {
-        self.check_instance()?;
-        Ok(StateDump::from(self.context.clone().expect(
-            "Context must be Some since we've checked it with check_instance()? above",
-        )))
+    pub fn get_state_dump(
+        &self,
+        include_source_chain: bool,
+    ) -> Result<StateDump, HolochainInstanceError> {
+        Ok(StateDump::new(self.context()?, include_source_chain))
+    }
+
+    /// Returns up to `limit` headers of this instance's source chain, newest first,
+    /// skipping the `offset` newest ones.
+    pub fn get_source_chain_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ChainHeader>, HolochainInstanceError> {
+        Ok(source_chain_page(&self.context()?, offset, limit)?)
+    }
+
+    /// Lists the validations of this instance that are waiting to be retried.
+    pub fn pending_validations(
+        &self,
+    ) -> Result<Vec<PendingValidationDump>, HolochainInstanceError> {
+        let context = self.context()?;
+        let state = context
+            .state()
+            .ok_or_else(|| HolochainError::ErrorGeneric("Instance has no state".to_string()))?;
+        Ok(state
+            .nucleus()
+            .pending_validations
+            .iter()
+            .map(|(key, pending_validation)| PendingValidationDump::new(key, pending_validation))
+            .collect())
+    }
+
+    /// Drops pending validations so they are not retried anymore.
+    /// Only drops the ones for the given entry address if one is given, otherwise all of them.
+    /// Returns the addresses of the entries whose validations were dropped.
+    pub fn purge_pending_validations(
+        &self,
+        address: Option<&Address>,
+    ) -> Result<Vec<Address>, HolochainInstanceError> {
+        let context = self.context()?;
+        let keys: Vec<PendingValidationKey> = context
+            .state()
+            .ok_or_else(|| HolochainError::ErrorGeneric("Instance has no state".to_string()))?
+            .nucleus()
+            .pending_validations
+            .keys()
+            .filter(|key| {
+                address
+                    .map(|address| key.address == *address)
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect();
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                remove_pending_validation(key.address.clone(), key.workflow, &context);
+                key.address
+            })
+            .collect())
     }
 
     pub fn get_type_and_content_from_cas(
@@ -297,16 +363,25 @@ mod tests {
         action::Action,
         context::Context,
         logger::{test_logger, TestLogger},
-        nucleus::actions::call_zome_function::make_cap_request_for_call,
+        network::entry_with_header::EntryWithHeader,
+        nucleus::actions::{
+            add_pending_validation::add_pending_validation,
+            call_zome_function::make_cap_request_for_call,
+        },
+        scheduled_jobs::pending_validations::ValidatingWorkflow,
         signal::{signal_channel, SignalReceiver},
     };
-    use holochain_core_types::dna::capabilities::CapabilityRequest;
+    use holochain_core_types::{
+        chain_header::test_chain_header, dna::capabilities::CapabilityRequest, entry::test_entry,
+    };
     use holochain_json_api::json::RawString;
     use holochain_persistence_api::cas::content::{Address, AddressableContent};
     use holochain_wasm_utils::wasm_target_dir;
     use std::{
         path::PathBuf,
         sync::{Arc, Mutex},
+        thread,
+        time::Duration,
     };
     use test_utils::{
         create_arbitrary_test_dna, create_test_defs_with_fn_name, create_test_dna_with_defs,
@@ -557,6 +632,51 @@ mod tests {
         assert_eq!(result.unwrap().nucleus().dna(), Some(dna));
     }
 
+    fn wait_for_pending_validations(hc: &Holochain, count: usize) -> Vec<PendingValidationDump> {
+        for _ in 0..100 {
+            let pending = hc.pending_validations().unwrap();
+            if pending.len() == count {
+                return pending;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Instance did not reach {} pending validation(s)", count);
+    }
+
+    #[test]
+    fn can_list_and_purge_pending_validations() {
+        let dna = create_arbitrary_test_dna();
+        let (context, _, _) = test_context("bob");
+        let hc = Holochain::new(dna, context.clone()).unwrap();
+        let entry = test_entry();
+        let dependency = Address::from("missing dependency");
+
+        add_pending_validation(
+            EntryWithHeader {
+                entry: entry.clone(),
+                header: test_chain_header(),
+            },
+            vec![dependency.clone()],
+            ValidatingWorkflow::HoldEntry,
+            context.clone(),
+        );
+
+        let pending = wait_for_pending_validations(&hc, 1);
+        assert_eq!(pending[0].address, entry.address());
+        assert_eq!(pending[0].dependencies, vec![dependency]);
+        assert_eq!(pending[0].attempts, 1);
+        assert!(pending[0].next_retry.is_some());
+
+        let other_address = Address::from("other entry");
+        assert_eq!(
+            hc.purge_pending_validations(Some(&other_address)).unwrap(),
+            Vec::<Address>::new()
+        );
+        let purged = hc.purge_pending_validations(Some(&entry.address()));
+        assert_eq!(purged, Ok(vec![entry.address()]));
+        wait_for_pending_validations(&hc, 0);
+    }
+
     #[test]
     fn can_call_test() {
         let wasm = example_api_wasm();
@@ -2,7 +2,12 @@ use crate::holo_signing_service::request_service;
 use base64;
 use conductor::broadcaster::Broadcaster;
 use crossbeam_channel::Receiver;
-use holochain_core::nucleus::actions::call_zome_function::make_cap_request_for_call;
+use holochain_core::{
+    consistency::{
+        await_consistency, ConsistencyTracker, DEFAULT_CONSISTENCY_TIMEOUT, MAX_CONSISTENCY_TIMEOUT,
+    },
+    nucleus::actions::call_zome_function::make_cap_request_for_call,
+};
 
 use holochain_core_types::{
     agent::AgentId, dna::capabilities::CapabilityRequest, signature::Provenance,
@@ -20,9 +25,13 @@ use std::{
     path::PathBuf,
     sync::{Arc, Mutex, RwLock},
     thread,
+    time::Duration,
 };
 
-use conductor::{ConductorAdmin, ConductorDebug, ConductorTestAdmin, ConductorUiAdmin, CONDUCTOR};
+use conductor::{
+    ConductorAdmin, ConductorDebug, ConductorHappAdmin, ConductorTestAdmin, ConductorUiAdmin,
+    CONDUCTOR,
+};
 use config::{
     AgentConfiguration, Bridge, DnaConfiguration, InstanceConfiguration, InterfaceConfiguration,
     InterfaceDriver, UiBundleConfiguration, UiInterfaceConfiguration,
@@ -34,6 +43,9 @@ use serde_json::{self, map::Map};
 pub type InterfaceError = String;
 pub type InstanceMap = HashMap<String, Arc<RwLock<Holochain>>>;
 
+/// Number of source chain headers `debug/source_chain` returns if no limit is given
+const DEFAULT_SOURCE_CHAIN_PAGE_SIZE: i64 = 100;
+
 /// An identifier for an instance that is usable by UI in making calls to the conductor
 /// this type allows us to implement this identifier differently, i.e. as a DNA/agent ID pair, etc
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Hash, Eq)]
@@ -331,6 +343,36 @@ impl ConductorApiBuilder {
             })?)
     }
 
+    fn get_as_base64<T: Into<String>>(
+        key: T,
+        params_map: &Map<String, Value>,
+    ) -> Result<Vec<u8>, jsonrpc_core::Error> {
+        let key = key.into();
+        let encoded = Self::get_as_string(key.clone(), params_map)?;
+        base64::decode(&encoded).map_err(|_| {
+            jsonrpc_core::Error::invalid_params(format!("`{}` is not valid base64", &key))
+        })
+    }
+
+    fn get_expected_hash(
+        params_map: &Map<String, Value>,
+    ) -> Result<Option<Address>, jsonrpc_core::Error> {
+        match params_map.get("expected_hash") {
+            Some(value) => Ok(Some(
+                value
+                    .as_str()
+                    .ok_or_else(|| {
+                        jsonrpc_core::Error::invalid_params(format!(
+                            "`{}` is not a valid json string",
+                            &value
+                        ))
+                    })?
+                    .into(),
+            )),
+            None => Ok(None),
+        }
+    }
+
     /// This adds functions to remotely change any aspect of the conductor config.
     /// After any change the conductor's config file gets saved.
     /// It is guaranteed that the config is either valid after the change or the change
@@ -348,6 +390,32 @@ impl ConductorApiBuilder {
     ///     * `uuid`: [string] (optional) value to override "uuid" section of the DNA
     ///     * `copy`: [bool] (optional) copy DNA file to storage directory
     ///
+    ///  * `admin/dna/install_from_bytes`:
+    ///     Installs a DNA from the contents of a DNA file that are sent along with the call,
+    ///     either in one piece or previously uploaded in chunks with `admin/dna/upload_chunk`.
+    ///     The DNA gets stored in the conductor's DNA directory, named by its hash.
+    ///     Params:
+    ///     * `id`: [string] internal handle/name of the newly created DNA config
+    ///     * `data`: [string] base64 encoded contents of the DNA file (if no `upload_id` is given)
+    ///     * `upload_id`: [string] (optional) ID of a chunked upload to install instead of `data`
+    ///     * `expected_hash`: [string] (optional) the hash of this DNA. If this does not match the actual hash, installation will fail.
+    ///     * `properties`: [object] (optional) extra data to include in the "properties" section of the DNA
+    ///     * `uuid`: [string] (optional) value to override "uuid" section of the DNA
+    ///
+    ///  * `admin/dna/upload_chunk`:
+    ///     Appends a chunk to a DNA upload, creating the upload if needed.
+    ///     Returns the number of bytes received so far for that upload.
+    ///     Params:
+    ///     * `upload_id`: [string] client chosen ID of the upload
+    ///     * `chunk`: [string] base64 encoded next part of the DNA file
+    ///
+    ///  * `admin/happ/install`
+    ///     Installs a hApp bundle (as built by `hc bundle`), i.e. all of its DNAs, instances,
+    ///     bridges and UI bundles at once. Either everything gets installed or, if anything
+    ///     fails, nothing is changed. Instances are created but not started.
+    ///     Params:
+    ///     * `path`: [string] local file path to the bundle's manifest (`happ.toml`)
+    ///
     ///  * `admin/dna/uninstall`
     ///     Uninstalls a DNA from the conductor config. Recursively also removes (and stops)
     ///     all instances this DNA is used in.
@@ -387,6 +455,20 @@ impl ConductorApiBuilder {
     ///  * `admin/instance/running`
     ///     Returns an array of all instances that are running.
     ///
+    ///  * `admin/instance/pending_validations`
+    ///     Returns an array of the validations a running instance could not finish yet,
+    ///     with their missing dependencies, the number of attempts so far and the time of
+    ///     the next retry.
+    ///     Params:
+    ///     * `id`: [string] Which instance to list pending validations of?
+    ///
+    ///  * `admin/instance/purge_pending_validations`
+    ///     Drops pending validations of a running instance so they are not retried anymore.
+    ///     Returns the entry addresses of the dropped validations.
+    ///     Params:
+    ///     * `id`: [string] Which instance to purge pending validations of?
+    ///     * `address`: [string] (optional) only drop the validations of this entry
+    ///
     ///  * `admin/interface/add`
     ///     Adds a new DNA / zome / conductor interface (that provides access to zome functions
     ///     of selected instances and conductor functions, depending on the interfaces config).
@@ -456,6 +538,14 @@ impl ConductorApiBuilder {
     ///  * `admin/bridge/list`
     ///     Returns an array of all bridges.
     ///
+    ///  * `admin/config/reload`
+    ///     Re-reads the conductor config file and applies the differences to the running
+    ///     conductor, i.e. only starts, stops or rebuilds the instances, interfaces, bridges and
+    ///     UI servers that changed. An invalid config is rejected without changing anything.
+    ///     Returns the applied differences.
+    ///     Params:
+    ///     * `path`: [string] (optional) config file to load instead of the conductor's own
+    ///
     pub fn with_admin_dna_functions(mut self) -> Self {
         self.io
             .add_method("admin/dna/install_from_file", move |params| {
@@ -463,20 +553,7 @@ impl ConductorApiBuilder {
                 let id = Self::get_as_string("id", &params_map)?;
                 let path = Self::get_as_string("path", &params_map)?;
                 let copy = Self::get_as_bool("copy", &params_map).unwrap_or(false);
-                let expected_hash = match params_map.get("expected_hash") {
-                    Some(value) => Some(
-                        value
-                            .as_str()
-                            .ok_or_else(|| {
-                                jsonrpc_core::Error::invalid_params(format!(
-                                    "`{}` is not a valid json string",
-                                    &value
-                                ))
-                            })?
-                            .into(),
-                    ),
-                    None => None,
-                };
+                let expected_hash = Self::get_expected_hash(&params_map)?;
                 let properties = params_map.get("properties");
                 let uuid = params_map
                     .get("uuid")
@@ -493,6 +570,57 @@ impl ConductorApiBuilder {
                 Ok(json!({ "success": true, "dna_hash": dna_hash }))
             });
 
+        self.io
+            .add_method("admin/dna/install_from_bytes", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                let expected_hash = Self::get_expected_hash(&params_map)?;
+                let properties = params_map.get("properties");
+                let uuid = params_map
+                    .get("uuid")
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_string());
+                let dna_hash = match params_map.get("upload_id") {
+                    Some(_) => {
+                        let upload_id = Self::get_as_string("upload_id", &params_map)?;
+                        conductor_call!(|c| c.install_dna_from_upload(
+                            &upload_id,
+                            id.to_string(),
+                            expected_hash,
+                            properties,
+                            uuid,
+                        ))?
+                    }
+                    None => {
+                        let bytes = Self::get_as_base64("data", &params_map)?;
+                        conductor_call!(|c| c.install_dna_from_bytes(
+                            bytes,
+                            id.to_string(),
+                            expected_hash,
+                            properties,
+                            uuid,
+                        ))?
+                    }
+                };
+                Ok(json!({ "success": true, "dna_hash": dna_hash }))
+            });
+
+        self.io.add_method("admin/dna/upload_chunk", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let upload_id = Self::get_as_string("upload_id", &params_map)?;
+            let chunk = Self::get_as_base64("chunk", &params_map)?;
+            let size = conductor_call!(|c| c.add_dna_upload_chunk(&upload_id, chunk))?;
+            Ok(json!({ "success": true, "size": size }))
+        });
+
+        self.io.add_method("admin/happ/install", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let path = Self::get_as_string("path", &params_map)?;
+            let dna_hashes =
+                conductor_call!(|c| c.install_happ_bundle_from_file(PathBuf::from(path)))?;
+            Ok(json!({ "success": true, "dna_hashes": dna_hashes }))
+        });
+
         self.io.add_method("admin/dna/uninstall", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let id = Self::get_as_string("id", &params_map)?;
@@ -587,6 +715,27 @@ impl ConductorApiBuilder {
                 ))
             });
 
+        self.io
+            .add_method("admin/instance/pending_validations", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                let pending_validations = conductor_call!(|c| c.pending_validations(&id))?;
+                Ok(serde_json::to_value(pending_validations)
+                    .map_err(|_| jsonrpc_core::Error::internal_error())?)
+            });
+
+        self.io
+            .add_method("admin/instance/purge_pending_validations", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let id = Self::get_as_string("id", &params_map)?;
+                let address = params_map
+                    .get("address")
+                    .and_then(|v| v.as_str())
+                    .map(Address::from);
+                let purged = conductor_call!(|c| c.purge_pending_validations(&id, address))?;
+                Ok(json!({ "success": true, "purged": purged }))
+            });
+
         self.io.add_method("admin/interface/add", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
 
@@ -717,6 +866,16 @@ impl ConductorApiBuilder {
             Ok(serde_json::to_value(bridges).map_err(|_| jsonrpc_core::Error::internal_error())?)
         });
 
+        self.io.add_method("admin/config/reload", move |params| {
+            let params_map = Self::unwrap_params_map(params).unwrap_or_default();
+            let path = params_map
+                .get("path")
+                .and_then(|v| v.as_str())
+                .map(PathBuf::from);
+            let diff = conductor_call!(|c| c.reload_config_from_file(path))?;
+            Ok(json!({ "success": true, "diff": diff }))
+        });
+
         self
     }
 
@@ -730,6 +889,30 @@ impl ConductorApiBuilder {
     ///   Returns a JSON object with all relevant fields of an instance's state.
     ///   Params:
     ///   - `instance_id` ID of the instance of which the state is requested
+    ///   - `include_source_chain` Whether to include the whole source chain (Optional, default: false)
+    ///
+    /// - `debug/source_chain`
+    ///   Returns a page of an instance's source chain headers, newest first.
+    ///   Params:
+    ///   - `instance_id` ID of the instance of which the source chain is requested
+    ///   - `offset` Number of newest headers to skip (Optional, default: 0)
+    ///   - `limit` Maximum number of headers to return (Optional, default: 100)
+    ///
+    /// - `debug/subscribe_state`
+    ///   Subscribes this interface to incremental state changes of an instance.
+    ///   Every action the instance reduces gets sent to all clients of this interface
+    ///   as {state_change: {change_type, data}, instance_id}, followed by
+    ///   the committed and held entries, pending validation changes and
+    ///   opened or closed query flows it caused.
+    ///   The subscription belongs to the interface, not to the calling connection.
+    ///   It ends when the interface gets stopped or removed, or the instance gets removed.
+    ///   Params:
+    ///   - `instance_id` ID of the instance to follow
+    ///
+    /// - `debug/unsubscribe_state`
+    ///   Stops sending state changes of an instance to this interface.
+    ///   Params:
+    ///   - `instance_id` ID of the instance to stop following
     ///
     /// - `debug/fetch_cas`
     ///   Returns content of a given instance's CAS.
@@ -738,7 +921,7 @@ impl ConductorApiBuilder {
     ///   - `address` Address (hash) of the content that is requests
     ///   Returns an object of the form: {type:"<entry type>", content: "<content>"}
     ///
-    pub fn with_debug_functions(mut self) -> Self {
+    pub fn with_debug_functions(mut self, interface_id: String) -> Self {
         self.io
             .add_method("debug/running_instances", move |_params| {
                 let running_instances_ids = conductor_call!(|c| c.running_instances())?;
@@ -750,11 +933,45 @@ impl ConductorApiBuilder {
             let params_map = Self::unwrap_params_map(params)?;
             let instance_id = Self::get_as_string("instance_id", &params_map)?;
 
-            let dump = conductor_call!(|c| c.state_dump_for_instance(&instance_id))?;
+            let include_source_chain =
+                Self::get_as_bool("include_source_chain", &params_map).unwrap_or(false);
+
+            let dump =
+                conductor_call!(|c| c.state_dump_for_instance(&instance_id, include_source_chain))?;
 
             Ok(serde_json::to_value(dump).map_err(|_| jsonrpc_core::Error::internal_error())?)
         });
 
+        self.io.add_method("debug/source_chain", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let instance_id = Self::get_as_string("instance_id", &params_map)?;
+            let offset = Self::get_as_int("offset", &params_map).unwrap_or(0).max(0) as usize;
+            let limit = Self::get_as_int("limit", &params_map)
+                .unwrap_or(DEFAULT_SOURCE_CHAIN_PAGE_SIZE)
+                .max(0) as usize;
+
+            let headers =
+                conductor_call!(|c| c.source_chain_for_instance(&instance_id, offset, limit))?;
+
+            Ok(serde_json::to_value(headers).map_err(|_| jsonrpc_core::Error::internal_error())?)
+        });
+
+        let subscribing_interface_id = interface_id.clone();
+        self.io.add_method("debug/subscribe_state", move |params| {
+            let params_map = Self::unwrap_params_map(params)?;
+            let instance_id = Self::get_as_string("instance_id", &params_map)?;
+            conductor_call!(|c| c.subscribe_state(&instance_id, &subscribing_interface_id))?;
+            Ok(json!({ "success": true }))
+        });
+
+        self.io
+            .add_method("debug/unsubscribe_state", move |params| {
+                let params_map = Self::unwrap_params_map(params)?;
+                let instance_id = Self::get_as_string("instance_id", &params_map)?;
+                conductor_call!(|c| c.unsubscribe_state(&instance_id, &interface_id))?;
+                Ok(json!({ "success": true }))
+            });
+
         self.io.add_method("debug/fetch_cas", move |params| {
             let params_map = Self::unwrap_params_map(params)?;
             let instance_id = Self::get_as_string("instance_id", &params_map)?;
@@ -773,6 +990,76 @@ impl ConductorApiBuilder {
         self
     }
 
+    /// Adds a `consistency/await` method to the Conductor RPC with which clients and tests can
+    /// wait for the effects of their zome calls (entries published and held, links added etc.)
+    /// instead of retrying or sleeping. Since it blocks a conductor thread, the conductor only
+    /// adds it to admin interfaces.
+    /// Only instances added to the builder before calling this can be referred to.
+    ///
+    /// - `consistency/await`
+    ///   Blocks until every event that actions of the given instance caused has been observed
+    ///   on the instances of this conductor. Calling it right after a zome call returned
+    ///   waits for all of that call's effects.
+    ///   Effects expected from the validators of an entry (e.g. holding it) are only observed
+    ///   if those validators are instances of this conductor. Otherwise this times out and
+    ///   reports them with `from_validators: true`.
+    ///   Params:
+    ///   - `instance_id` ID of the instance whose actions' effects to wait for (optional,
+    ///     waits for the effects of all instances if not given)
+    ///   - `timeout` Milliseconds after which to give up (optional, defaults to 30 seconds,
+    ///     at most 5 minutes)
+    ///   Returns `{success: true}`, or fails with the still missing events as error data.
+    pub fn with_consistency_functions(mut self, tracker: Arc<Mutex<ConsistencyTracker>>) -> Self {
+        let instance_configs = self.instance_configs.clone();
+        self.io.add_method("consistency/await", move |params| {
+            let params_map = match params {
+                Params::None => Map::new(),
+                params => Self::unwrap_params_map(params)?,
+            };
+            let instance_id = match Self::get_as_string("instance_id", &params_map) {
+                Ok(name) => Some(
+                    instance_configs
+                        .get(&name)
+                        .map(|config| config.id.clone())
+                        .ok_or_else(|| jsonrpc_core::Error::invalid_params("unknown instance"))?,
+                ),
+                Err(_) => None,
+            };
+            let timeout = Self::get_as_int("timeout", &params_map)
+                .map(|millis| Duration::from_millis(millis.max(0) as u64))
+                .unwrap_or(DEFAULT_CONSISTENCY_TIMEOUT)
+                .min(MAX_CONSISTENCY_TIMEOUT);
+
+            await_consistency(&tracker, instance_id.as_ref().map(String::as_str), timeout)
+                .map_err(|pending| {
+                    let from_validators = tracker.lock().unwrap().pending_validator_events();
+                    let mut error = jsonrpc_core::Error::internal_error();
+                    error.message = format!("Not consistent after {:?}", timeout);
+                    if pending.iter().any(|event| from_validators.contains(event)) {
+                        error.message.push_str(
+                            ", still waiting for validators which are only observed \
+                             if they are instances of this conductor",
+                        );
+                    }
+                    error.data = Some(json!(pending
+                        .into_iter()
+                        .map(|(instance_id, event)| {
+                            let is_from_validators =
+                                from_validators.contains(&(instance_id.clone(), event.clone()));
+                            json!({
+                                "instance_id": instance_id,
+                                "event": event,
+                                "from_validators": is_from_validators,
+                            })
+                        })
+                        .collect::<Vec<_>>()));
+                    error
+                })?;
+            Ok(json!({ "success": true }))
+        });
+        self
+    }
+
     /// Adds a further set of functions to the Conductor RPC for managing
     /// static UI bundles and HTTP interfaces to these.
     /// This adds the following RPC endpoints:
@@ -868,6 +1155,8 @@ impl ConductorApiBuilder {
                 dna_interface,
                 reroute_to_root: true,
                 bind_address: "127.0.0.1".to_string(),
+                tls: None,
+                compression: true,
             }))?;
             Ok(json!({"success": true}))
         });
@@ -1296,6 +1585,71 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_rpc_consistency_await() {
+        use holochain_core::consistency::{ConsistencyEvent, ConsistencyGroup, ConsistencySignal};
+
+        let (config, instances) = example_config_and_instances();
+        let tracker = Arc::new(Mutex::new(ConsistencyTracker::new()));
+        let handler = ConductorApiBuilder::new()
+            .with_instances(instances.clone())
+            .with_instance_configs(config.instances)
+            .with_consistency_functions(tracker.clone())
+            .spawn();
+
+        let response_str = handler
+            .handle_request_sync(&create_call_str(
+                "consistency/await",
+                Some(json!({"instance_id": "test-instance-1"})),
+            ))
+            .expect("Invalid call to handler");
+        assert_eq!(
+            response_str,
+            r#"{"jsonrpc":"2.0","result":{"success":true},"id":"0"}"#
+        );
+
+        let response_str = handler
+            .handle_request_sync(&create_call_str(
+                "consistency/await",
+                Some(json!({"instance_id": "bad instance id"})),
+            ))
+            .expect("Invalid call to handler");
+        assert_eq!(
+            response_str,
+            r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"unknown instance"},"id":"0"}"#
+        );
+
+        let address = Address::from("Qmentry");
+        tracker.lock().unwrap().observe(
+            "test-instance-1",
+            &ConsistencySignal::new_pending(
+                ConsistencyEvent::Publish(address.clone()),
+                ConsistencyGroup::Validators,
+                vec![ConsistencyEvent::Hold(address)],
+            )
+            .into(),
+        );
+        let response_str = handler
+            .handle_request_sync(&create_call_str(
+                "consistency/await",
+                Some(json!({"instance_id": "test-instance-1", "timeout": 100})),
+            ))
+            .expect("Invalid call to handler");
+        let response: serde_json::Value = serde_json::from_str(&response_str).unwrap();
+        assert_eq!(
+            response["error"]["data"],
+            json!([{
+                "instance_id": "test-instance-1",
+                "event": r#"{"Hold":"Qmentry"}"#,
+                "from_validators": true,
+            }])
+        );
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("still waiting for validators"));
+    }
+
     #[test]
     fn test_rpc_call_method() {
         let (config, instances) = example_config_and_instances();
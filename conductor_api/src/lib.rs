@@ -139,12 +139,18 @@ extern crate base64;
 extern crate lib3h;
 #[macro_use]
 extern crate nickel;
+extern crate brotli;
+extern crate flate2;
+extern crate native_tls;
+extern crate uuid;
 
 pub mod conductor;
 pub mod config;
 pub mod context_builder;
 pub mod dpki_instance;
+pub mod encrypted_storage;
 pub mod error;
+pub mod happ_bundle;
 pub mod holo_signing_service;
 pub mod holochain;
 pub mod interface;
@@ -1,4 +1,4 @@
-use holochain_core::signal::Signal;
+use holochain_core::{signal::Signal, state_dump::StateChange};
 use holochain_json_api::{error::JsonError, json::JsonString};
 
 /// This struct wraps a Signal from core before serializing and sending over
@@ -8,3 +8,19 @@ pub struct SignalWrapper {
     pub signal: Signal,
     pub instance_id: String,
 }
+
+/// Wraps an incremental state change of an instance before sending it to
+/// the interfaces that subscribed to it via `debug/subscribe_state`.
+#[derive(Serialize, Debug)]
+pub struct StateChangeWrapper {
+    pub state_change: StateChange,
+    pub instance_id: String,
+}
+
+impl From<StateChangeWrapper> for JsonString {
+    fn from(wrapper: StateChangeWrapper) -> JsonString {
+        JsonString::from_json(
+            &serde_json::to_string(&wrapper).expect("StateChangeWrapper must serialize"),
+        )
+    }
+}
@@ -4,18 +4,34 @@ use hyper::{http::uri, Request};
 
 pub const DNA_CONFIG_ROUTE: &str = "/_dna_connections.json";
 
+/// Websocket connections to this route are passed on to the UI's DNA interface
+/// if it is a websocket interface.
+pub const DNA_INTERFACE_ROUTE: &str = "/_dna_interface";
+
 pub fn redirect_request_to_root<T>(req: &mut Request<T>) {
     let mut original_parts: uri::Parts = req.uri().to_owned().into();
     original_parts.path_and_query = Some("/".parse().unwrap());
     *req.uri_mut() = uri::Uri::from_parts(original_parts).unwrap();
 }
 
-pub fn dna_connections_response(config: &Option<InterfaceConfiguration>) -> serde_json::Value {
+/// `proxied_url` is where the UI reaches its DNA interface through the UI interface's own
+/// origin, if the UI interface proxies it.
+pub fn dna_connections_response(
+    config: &Option<InterfaceConfiguration>,
+    proxied_url: Option<String>,
+) -> serde_json::Value {
     let interface = match config {
         Some(config) => json!(config),
         None => serde_json::Value::Null,
     };
-    json!({ "dna_interface": interface })
+    json!({ "dna_interface": interface, "dna_interface_url": proxied_url })
+}
+
+/// URL of the proxied DNA interface for a UI that got loaded from the given host,
+/// which includes the port if it is not the default one.
+pub fn proxied_dna_interface_url(tls: bool, host: &str) -> String {
+    let scheme = if tls { "wss" } else { "ws" };
+    format!("{}://{}{}", scheme, host, DNA_INTERFACE_ROUTE)
 }
 
 pub trait ConductorStaticFileServer {
@@ -0,0 +1,305 @@
+use brotli;
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use nickel::{
+    hyper::method::Method, status::StatusCode, MediaType, Middleware, MiddlewareResult, Request,
+    Response,
+};
+
+/// Files smaller than this are sent as they are since compressing them saves next to nothing.
+const MIN_COMPRESSED_SIZE: usize = 1024;
+
+const COMPRESSIBLE_EXTENSIONS: &[&str] = &[
+    "css", "csv", "htm", "html", "js", "json", "map", "mjs", "svg", "txt", "wasm", "xml",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+impl Encoding {
+    /// The best encoding the client accepts according to its Accept-Encoding header.
+    pub fn negotiate(accept_encoding: &str) -> Encoding {
+        let accepted: Vec<&str> = accept_encoding
+            .split(',')
+            .filter_map(|coding| {
+                let mut parts = coding.split(';').map(str::trim);
+                let name = parts.next()?;
+                let refused =
+                    parts.any(|param| param.starts_with("q=") && param[2..].parse() == Ok(0.0f32));
+                if refused || name.is_empty() {
+                    None
+                } else {
+                    Some(name)
+                }
+            })
+            .collect();
+        if accepted.contains(&"br") {
+            Encoding::Brotli
+        } else if accepted.contains(&"gzip") {
+            Encoding::Gzip
+        } else {
+            Encoding::Identity
+        }
+    }
+
+    fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Encoding::Identity => None,
+            Encoding::Gzip => Some("gzip"),
+            Encoding::Brotli => Some("br"),
+        }
+    }
+
+    pub fn encode(self, content: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Encoding::Identity => Ok(content.to_vec()),
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(content)?;
+                encoder.finish()
+            }
+            Encoding::Brotli => {
+                let mut compressed = Vec::new();
+                {
+                    let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 9, 22);
+                    writer.write_all(content)?;
+                }
+                Ok(compressed)
+            }
+        }
+    }
+}
+
+/// The ETag of a file of a UI bundle.
+/// Bundles with a hash don't change, so the tag only has to tell apart the files and
+/// their encodings.
+pub fn entity_tag(bundle_hash: &str, file: &Path, encoding: Encoding) -> String {
+    let mut hasher = DefaultHasher::new();
+    file.hash(&mut hasher);
+    match encoding.content_encoding() {
+        Some(coding) => format!("\"{}-{:x}-{}\"", bundle_hash, hasher.finish(), coding),
+        None => format!("\"{}-{:x}\"", bundle_hash, hasher.finish()),
+    }
+}
+
+/// The path of the requested file relative to the bundle root.
+/// Paths that would leave the bundle are refused.
+pub fn relative_path(request_path: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(request_path.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Serves the files of a UI bundle.
+/// Text assets are compressed with brotli or gzip if the client accepts it and, for bundles
+/// with a hash, get an ETag so browsers only download them again once the bundle changes.
+/// Requests for files that don't exist are passed on to the next middleware.
+#[derive(Clone)]
+pub struct BundleFilesHandler {
+    root_dir: PathBuf,
+    bundle_hash: Option<String>,
+    compression: bool,
+    cache: Arc<Mutex<HashMap<(PathBuf, Encoding), Arc<Vec<u8>>>>>,
+}
+
+impl BundleFilesHandler {
+    pub fn new(root_dir: &str, bundle_hash: Option<String>, compression: bool) -> Self {
+        Self {
+            root_dir: PathBuf::from(root_dir),
+            bundle_hash,
+            compression,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn resolve(&self, request_path: &str) -> Option<PathBuf> {
+        let mut file = relative_path(request_path)?;
+        if self.root_dir.join(&file).is_dir() {
+            file.push("index.html");
+        }
+        if self.root_dir.join(&file).is_file() {
+            Some(file)
+        } else {
+            None
+        }
+    }
+
+    fn content(&self, file: &Path, encoding: Encoding) -> io::Result<Arc<Vec<u8>>> {
+        let key = (file.to_path_buf(), encoding);
+        if let Some(content) = self.cache.lock().unwrap().get(&key) {
+            return Ok(content.clone());
+        }
+        let content = Arc::new(encoding.encode(&fs::read(self.root_dir.join(file))?)?);
+        // Only bundles with a hash are known not to change while being served
+        if self.bundle_hash.is_some() {
+            self.cache.lock().unwrap().insert(key, content.clone());
+        }
+        Ok(content)
+    }
+
+    fn encoding_for(&self, file: &Path, accept_encoding: Option<&str>) -> io::Result<Encoding> {
+        let compressible = file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| COMPRESSIBLE_EXTENSIONS.contains(&extension))
+            .unwrap_or(false);
+        if !self.compression || !compressible {
+            return Ok(Encoding::Identity);
+        }
+        if fs::metadata(self.root_dir.join(file))?.len() < MIN_COMPRESSED_SIZE as u64 {
+            return Ok(Encoding::Identity);
+        }
+        Ok(accept_encoding
+            .map(Encoding::negotiate)
+            .unwrap_or(Encoding::Identity))
+    }
+}
+
+fn raw_header<D>(req: &Request<D>, name: &str) -> Option<String> {
+    req.origin.headers.get_raw(name).map(|values| {
+        values
+            .iter()
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+            .collect::<Vec<_>>()
+            .join(",")
+    })
+}
+
+impl<D> Middleware<D> for BundleFilesHandler {
+    fn invoke<'mw, 'conn>(
+        &'mw self,
+        req: &mut Request<'mw, 'conn, D>,
+        mut res: Response<'mw, D>,
+    ) -> MiddlewareResult<'mw, D> {
+        match req.origin.method {
+            Method::Get | Method::Head => {}
+            _ => return res.next_middleware(),
+        }
+        let file = match req.path_without_query().and_then(|path| self.resolve(path)) {
+            Some(file) => file,
+            None => return res.next_middleware(),
+        };
+        let accept_encoding = raw_header(req, "Accept-Encoding");
+        let encoding = match self.encoding_for(&file, accept_encoding.as_ref().map(String::as_str))
+        {
+            Ok(encoding) => encoding,
+            Err(error) => return Err(res.error(StatusCode::InternalServerError, error.to_string())),
+        };
+
+        let media_type = file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+            .unwrap_or(MediaType::Bin);
+        res.set(media_type);
+        if self.compression {
+            res.headers_mut()
+                .set_raw("Vary", vec![b"Accept-Encoding".to_vec()]);
+        }
+        if let Some(coding) = encoding.content_encoding() {
+            res.headers_mut()
+                .set_raw("Content-Encoding", vec![coding.as_bytes().to_vec()]);
+        }
+
+        if let Some(ref bundle_hash) = self.bundle_hash {
+            let tag = entity_tag(bundle_hash, &file, encoding);
+            res.headers_mut()
+                .set_raw("Cache-Control", vec![b"public, no-cache".to_vec()]);
+            res.headers_mut()
+                .set_raw("ETag", vec![tag.as_bytes().to_vec()]);
+            let not_modified = raw_header(req, "If-None-Match")
+                .map(|tags| tags.split(',').any(|candidate| candidate.trim() == tag))
+                .unwrap_or(false);
+            if not_modified {
+                res.set(StatusCode::NotModified);
+                return res.send("");
+            }
+        } else {
+            res.headers_mut()
+                .set_raw("Cache-Control", vec![b"no-cache".to_vec()]);
+        }
+
+        match self.content(&file, encoding) {
+            Ok(content) => res.send(content.to_vec()),
+            Err(error) => Err(res.error(StatusCode::InternalServerError, error.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn negotiates_the_best_accepted_encoding() {
+        assert_eq!(Encoding::negotiate("gzip, deflate, br"), Encoding::Brotli);
+        assert_eq!(Encoding::negotiate("gzip;q=1.0, br;q=0"), Encoding::Gzip);
+        assert_eq!(Encoding::negotiate("deflate"), Encoding::Identity);
+        assert_eq!(Encoding::negotiate(""), Encoding::Identity);
+    }
+
+    #[test]
+    fn compressed_content_can_be_decoded() {
+        let content = "<html></html>".repeat(200).into_bytes();
+
+        let gzipped = Encoding::Gzip.encode(&content).unwrap();
+        assert!(gzipped.len() < content.len());
+        let mut decoded = Vec::new();
+        GzDecoder::new(&gzipped[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, content);
+
+        let brotli_compressed = Encoding::Brotli.encode(&content).unwrap();
+        let mut decoded = Vec::new();
+        brotli::Decompressor::new(&brotli_compressed[..], 4096)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, content);
+    }
+
+    #[test]
+    fn entity_tags_depend_on_file_and_encoding() {
+        let index = PathBuf::from("index.html");
+        let tag = entity_tag("Qm000", &index, Encoding::Identity);
+        assert!(tag.starts_with("\"Qm000-"));
+        assert_eq!(tag, entity_tag("Qm000", &index, Encoding::Identity));
+        assert_ne!(tag, entity_tag("Qm000", &index, Encoding::Gzip));
+        assert_ne!(tag, entity_tag("Qm001", &index, Encoding::Identity));
+        assert_ne!(
+            tag,
+            entity_tag("Qm000", &PathBuf::from("main.js"), Encoding::Identity)
+        );
+    }
+
+    #[test]
+    fn refuses_paths_outside_of_the_bundle() {
+        assert_eq!(
+            relative_path("/js/./main.js"),
+            Some(PathBuf::from("js/main.js"))
+        );
+        assert_eq!(relative_path("/"), Some(PathBuf::new()));
+        assert_eq!(relative_path("/../secret"), None);
+        assert_eq!(relative_path("/js/../../secret"), None);
+    }
+}
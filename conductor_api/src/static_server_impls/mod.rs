@@ -1,2 +1,4 @@
+pub mod bundle_files;
 pub mod nickel_static_server;
+pub mod ui_proxy;
 pub use self::nickel_static_server::NickelStaticServer;
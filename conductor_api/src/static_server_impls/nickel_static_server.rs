@@ -1,18 +1,27 @@
 use conductor::base::notify;
-use config::{InterfaceConfiguration, UiBundleConfiguration, UiInterfaceConfiguration};
+use config::{
+    InterfaceConfiguration, InterfaceDriver, UiBundleConfiguration, UiInterfaceConfiguration,
+};
 use error::HolochainResult;
 use holochain_core_types::error::HolochainError;
-use static_file_server::{dna_connections_response, ConductorStaticFileServer, DNA_CONFIG_ROUTE};
+use static_file_server::{
+    dna_connections_response, proxied_dna_interface_url, ConductorStaticFileServer,
+    DNA_CONFIG_ROUTE,
+};
+use static_server_impls::{
+    bundle_files::BundleFilesHandler,
+    ui_proxy::{tls_acceptor, UiProxy},
+};
 
 use std::{
-    net::SocketAddr,
+    net::{Ipv4Addr, SocketAddr},
     sync::mpsc::{self, Sender},
     thread,
 };
 
 use nickel::{
-    hyper::uri::RequestUri, HttpRouter, Middleware, MiddlewareResult, Mountable, Nickel, Request,
-    Response, StaticFilesHandler,
+    hyper::{header::Host, uri::RequestUri},
+    HttpRouter, Middleware, MiddlewareResult, Mountable, Nickel, Request, Response,
 };
 
 pub struct NickelStaticServer {
@@ -39,6 +48,52 @@ impl ConductorStaticFileServer for NickelStaticServer {
     }
 
     fn start(&mut self) -> HolochainResult<()> {
+        let addr: SocketAddr = format!("{}:{}", self.config.bind_address, self.config.port)
+            .parse()
+            .expect("invalid address or port");
+
+        let tls = match self.config.tls {
+            Some(ref tls) => Some(
+                tls_acceptor(&tls.certificate, &tls.password)
+                    .map_err(HolochainError::ConfigError)?,
+            ),
+            None => None,
+        };
+        let scheme = if tls.is_some() { "https" } else { "http" };
+
+        // UIs can reach a websocket DNA interface through the UI's own origin.
+        // Admin interfaces are not exposed like that, since UI interfaces are meant to be
+        // reachable by anyone who may load the UI.
+        let dna_websocket =
+            self.connected_dna_interface
+                .as_ref()
+                .and_then(|interface| match interface.driver {
+                    InterfaceDriver::Websocket { .. } if interface.admin => {
+                        notify(format!(
+                            "Not proxying admin interface \"{}\" through UI interface \"{}\"",
+                            interface.id, self.config.id
+                        ));
+                        None
+                    }
+                    InterfaceDriver::Websocket { port } => {
+                        Some(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+                    }
+                    _ => None,
+                });
+        let tls_enabled = tls.is_some();
+        let proxying = dna_websocket.is_some();
+
+        // Without TLS or a DNA interface to proxy, nickel can be reached directly.
+        // Otherwise it only listens locally behind the proxy, on a port it picks itself.
+        let (server_addr, proxy) = if tls.is_some() || dna_websocket.is_some() {
+            let proxy = UiProxy::bind(addr, tls, dna_websocket).map_err(|e| {
+                HolochainError::ErrorGeneric(format!("Could not listen on {}: {}", addr, e))
+            })?;
+            (SocketAddr::from((Ipv4Addr::LOCALHOST, 0)), Some(proxy))
+        } else {
+            (addr, None)
+        };
+
         let (tx, rx) = mpsc::channel();
 
         self.shutdown_signal = Some(tx);
@@ -47,49 +102,78 @@ impl ConductorStaticFileServer for NickelStaticServer {
         {
             let mut server = Nickel::new();
 
-            let static_file_handler =
-                StaticFilesHandler::new(self.bundle_config.root_dir.to_owned());
-            server.mount("/", static_file_handler.clone());
+            let bundle_files_handler = BundleFilesHandler::new(
+                &self.bundle_config.root_dir,
+                self.bundle_config.hash.clone(),
+                self.config.compression,
+            );
+            server.mount("/", bundle_files_handler.clone());
 
             // if required, reroute failed routes to index.html
             // This is required for SPAs with virtual routing
             if self.config.reroute_to_root {
-                server.mount("/", FallbackFileRouteHandler::new(static_file_handler));
+                server.mount("/", FallbackFileRouteHandler::new(bundle_files_handler));
             }
 
             // provide a virtual route for inspecting the configed DNA interfaces for this UI
-            // let connected_dna_interface = ;
             let connected_dna_interface = self.connected_dna_interface.clone();
             server.get(
                 DNA_CONFIG_ROUTE,
-                middleware! { |_|
-                    dna_connections_response(&connected_dna_interface)
+                middleware! { |request|
+                    let proxied_url = if proxying {
+                        let host = match request.origin.headers.get::<Host>() {
+                            Some(Host { hostname, port: Some(port) }) => format!("{}:{}", hostname, port),
+                            Some(Host { hostname, port: None }) => hostname.clone(),
+                            None => addr.to_string(),
+                        };
+                        Some(proxied_dna_interface_url(tls_enabled, &host))
+                    } else {
+                        None
+                    };
+                    dna_connections_response(&connected_dna_interface, proxied_url)
                 },
             );
 
-            let addr: SocketAddr = format!("{}:{}", self.config.bind_address, self.config.port)
-                .parse()
-                .expect("invalid address or port");
-
             notify(format!(
-                "About to serve path \"{}\" at http://{}",
-                &self.bundle_config.root_dir, &addr
+                "About to serve path \"{}\" at {}://{}",
+                &self.bundle_config.root_dir, scheme, &addr
             ));
 
+            // The server thread reports the address nickel bound to, and then keeps serving
+            // for as long as it lives.
+            let (bound_tx, bound_rx) = mpsc::channel();
+            let _server_thread = thread::Builder::new()
+                .name("conductor_api::static_file_server_inner".to_string())
+                .spawn(move || match server.listen(server_addr) {
+                    Ok(listening) => {
+                        let _ = bound_tx.send(Ok(listening.socket()));
+                    }
+                    Err(e) => {
+                        let _ = bound_tx.send(Err(e.to_string()));
+                    }
+                });
+            let bound_addr = bound_rx
+                .recv()
+                .unwrap_or_else(|_| Err("server thread died".to_string()))
+                .map_err(|e| {
+                    self.running = false;
+                    self.shutdown_signal = None;
+                    HolochainError::ErrorGeneric(format!(
+                        "Could not start static file server: {}",
+                        e
+                    ))
+                })?;
+
             thread::Builder::new()
                 .name("conductor_api::static_file_server".to_string())
                 .spawn(move || {
-                    let _server_thread = thread::Builder::new()
-                        .name("conductor_api::static_file_server_inner".to_string())
-                        .spawn(move || {
-                            server
-                                .listen(addr)
-                                .map_err(|e| notify(format!("server error: {}", e)))
-                                .expect("Could not start static file server");
-                        });
-                    notify(format!("Listening on http://{}", addr));
-                    // block waiting for a shutdown signal after which the server thread goes out of scope
-                    rx.recv().unwrap();
+                    notify(format!("Listening on {}://{}", scheme, addr));
+                    match proxy {
+                        // the proxy frees the public port once it gets the shutdown signal
+                        Some(proxy) => proxy.run(bound_addr, rx),
+                        // block waiting for a shutdown signal after which the server thread goes out of scope
+                        None => rx.recv().unwrap(),
+                    }
                 })
                 .unwrap();
         };
@@ -109,11 +193,11 @@ impl ConductorStaticFileServer for NickelStaticServer {
 }
 
 #[derive(Clone)]
-struct FallbackFileRouteHandler(StaticFilesHandler);
+struct FallbackFileRouteHandler(BundleFilesHandler);
 
 impl FallbackFileRouteHandler {
-    pub fn new(static_file_handler: StaticFilesHandler) -> Self {
-        Self(static_file_handler)
+    pub fn new(bundle_files_handler: BundleFilesHandler) -> Self {
+        Self(bundle_files_handler)
     }
 }
 
@@ -0,0 +1,366 @@
+use conductor::base::notify;
+use native_tls::{HandshakeError, Identity, TlsAcceptor, TlsStream};
+use static_file_server::DNA_INTERFACE_ROUTE;
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How often blocked reads and accepts check whether they should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Time a client gets for the TLS handshake and for sending the head of its request,
+/// all together.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections served at once. Each one takes up to two threads, further connections are
+/// closed right away.
+const MAX_CONNECTIONS: usize = 256;
+
+/// Request heads that don't end within this many bytes are refused.
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+trait ClientStream: Read + Write + Send {}
+impl<T: Read + Write + Send> ClientStream for T {}
+
+/// Loads a PKCS #12 archive as used in the `tls` section of a UI interface.
+pub fn tls_acceptor(certificate: &str, password: &str) -> Result<TlsAcceptor, String> {
+    let archive = fs::read(certificate)
+        .map_err(|e| format!("Could not read TLS certificate {}: {}", certificate, e))?;
+    let identity = Identity::from_pkcs12(&archive, password)
+        .map_err(|e| format!("Invalid TLS certificate {}: {}", certificate, e))?;
+    TlsAcceptor::new(identity).map_err(|e| e.to_string())
+}
+
+/// The public side of a UI interface.
+/// It terminates TLS and hands connections on to the static file server, except for
+/// websocket upgrades of `DNA_INTERFACE_ROUTE`, which are piped to the connected
+/// DNA interface so the UI can reach everything through one origin.
+///
+/// Connections are routed by their first request. Keep-alive connections therefore stay
+/// with the static file server, which is fine for browsers as they open a new connection
+/// for every websocket.
+pub struct UiProxy {
+    listener: TcpListener,
+    tls: Option<Arc<TlsAcceptor>>,
+    dna_interface: Option<SocketAddr>,
+    connections: Arc<AtomicUsize>,
+}
+
+/// Counts a connection as open while it lives.
+struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl UiProxy {
+    pub fn bind(
+        addr: SocketAddr,
+        tls: Option<TlsAcceptor>,
+        dna_interface: Option<SocketAddr>,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            tls: tls.map(Arc::new),
+            dna_interface,
+            connections: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Accepts connections and routes them to the given static file server until
+    /// a shutdown signal is received, which frees the port.
+    pub fn run(self, static_server: SocketAddr, shutdown: Receiver<()>) {
+        loop {
+            match shutdown.try_recv() {
+                Err(TryRecvError::Empty) => {}
+                _ => return,
+            }
+            match self.listener.accept() {
+                Ok((stream, peer)) => {
+                    if self.connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        debug!(
+                            "UI proxy refused connection from {}: too many connections",
+                            peer
+                        );
+                        let _ = stream.shutdown(Shutdown::Both);
+                        continue;
+                    }
+                    self.connections.fetch_add(1, Ordering::SeqCst);
+                    let guard = ConnectionGuard(self.connections.clone());
+                    let tls = self.tls.clone();
+                    let dna_interface = self.dna_interface;
+                    let _ = thread::Builder::new()
+                        .name("conductor_api::ui_proxy_connection".to_string())
+                        .spawn(move || {
+                            let _guard = guard;
+                            if let Err(error) =
+                                handle_connection(stream, tls, static_server, dna_interface)
+                            {
+                                debug!("UI proxy connection failed: {}", error);
+                            }
+                        });
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => notify(format!("UI proxy could not accept connection: {}", e)),
+            }
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    tls: Option<Arc<TlsAcceptor>>,
+    static_server: SocketAddr,
+    dna_interface: Option<SocketAddr>,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+    let set_timeout = |timeout: Duration| stream.set_read_timeout(Some(timeout));
+    let mut client: Box<dyn ClientStream> = match tls {
+        Some(acceptor) => Box::new(accept_tls(
+            &acceptor,
+            stream.try_clone()?,
+            deadline,
+            set_timeout,
+        )?),
+        None => Box::new(stream.try_clone()?),
+    };
+
+    let head = read_head(&mut client, deadline, set_timeout)?;
+    let target = if is_websocket_upgrade(&head, DNA_INTERFACE_ROUTE) {
+        dna_interface.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "UI interface has no websocket DNA interface",
+            )
+        })?
+    } else {
+        static_server
+    };
+    let mut backend = TcpStream::connect(target)?;
+    backend.write_all(&head)?;
+
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    pipe(client, stream, backend)
+}
+
+/// Time left until the deadline, or a timeout error if it has passed.
+fn time_left(deadline: Instant) -> io::Result<Duration> {
+    let now = Instant::now();
+    if now >= deadline {
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "client did not finish its request in time",
+        ))
+    } else {
+        Ok(deadline - now)
+    }
+}
+
+/// Does the TLS handshake, giving up at the deadline however slowly the client sends.
+fn accept_tls<F: Fn(Duration) -> io::Result<()>>(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    deadline: Instant,
+    set_timeout: F,
+) -> io::Result<TlsStream<TcpStream>> {
+    set_timeout(time_left(deadline)?)?;
+    let mut handshake = acceptor.accept(stream);
+    loop {
+        match handshake {
+            Ok(stream) => return Ok(stream),
+            // Reads time out with WouldBlock, which leaves the handshake resumable
+            Err(HandshakeError::WouldBlock(mid_handshake)) => {
+                set_timeout(time_left(deadline)?)?;
+                handshake = mid_handshake.handshake();
+            }
+            Err(HandshakeError::Failure(error)) => {
+                return Err(io::Error::new(io::ErrorKind::Other, error.to_string()))
+            }
+        }
+    }
+}
+
+/// Reads until the end of the request head, which has to arrive before the deadline.
+/// `set_timeout` is called with the time left before every read.
+/// Everything read is returned, including the start of the body if it came along.
+fn read_head<R: Read, F: Fn(Duration) -> io::Result<()>>(
+    client: &mut R,
+    deadline: Instant,
+    set_timeout: F,
+) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 4096];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_HEAD_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        set_timeout(time_left(deadline)?)?;
+        let read = client.read(&mut buffer)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    Ok(head)
+}
+
+/// True if the head is a websocket upgrade request for the given path.
+pub fn is_websocket_upgrade(head: &[u8], path: &str) -> bool {
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.split("\r\n");
+    let requested_path = lines
+        .next()
+        .and_then(|request_line| request_line.split(' ').nth(1))
+        .map(|target| target.split('?').next().unwrap_or(target));
+    if requested_path != Some(path) {
+        return false;
+    }
+    lines
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| {
+            let mut header = line.splitn(2, ':');
+            Some((header.next()?.trim(), header.next()?.trim()))
+        })
+        .any(|(name, value)| {
+            name.eq_ignore_ascii_case("upgrade") && value.eq_ignore_ascii_case("websocket")
+        })
+}
+
+/// Copies data both ways until either side closes its connection.
+/// The client stream is shared by both directions. Its socket has a short read timeout,
+/// so reads hold the lock only briefly and writes to the client don't wait for idle reads.
+/// Reading through the (TLS) stream instead of waiting on the socket also picks up data
+/// the stream already buffered.
+fn pipe(
+    client: Box<dyn ClientStream>,
+    client_socket: TcpStream,
+    backend: TcpStream,
+) -> io::Result<()> {
+    let client = Arc::new(Mutex::new(client));
+    let backend_closed = Arc::new(AtomicBool::new(false));
+
+    let to_client = {
+        let client = client.clone();
+        let backend_closed = backend_closed.clone();
+        let mut backend = backend.try_clone()?;
+        thread::spawn(move || {
+            let mut buffer = [0; 16 * 1024];
+            loop {
+                match backend.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => {
+                        let mut client = client.lock().unwrap();
+                        if client.write_all(&buffer[..read]).is_err() || client.flush().is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            backend_closed.store(true, Ordering::SeqCst);
+        })
+    };
+
+    let mut backend_writer = backend;
+    let mut buffer = [0; 16 * 1024];
+    let is_timeout = |e: &io::Error| {
+        e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut
+    };
+    let result = loop {
+        if backend_closed.load(Ordering::SeqCst) {
+            break Ok(());
+        }
+        let read = client.lock().unwrap().read(&mut buffer);
+        match read {
+            Ok(0) => break Ok(()),
+            Ok(read) => {
+                if let Err(error) = backend_writer.write_all(&buffer[..read]) {
+                    break Err(error);
+                }
+            }
+            // Gives the other direction a chance to take the lock
+            Err(ref e) if is_timeout(e) => thread::yield_now(),
+            Err(error) => break Err(error),
+        }
+    };
+
+    let _ = backend_writer.shutdown(Shutdown::Both);
+    let _ = client_socket.shutdown(Shutdown::Both);
+    let _ = to_client.join();
+    result
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_websocket_upgrades_of_the_route() {
+        let upgrade = b"GET /_dna_interface HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\r\n";
+        assert!(is_websocket_upgrade(upgrade, "/_dna_interface"));
+        assert!(!is_websocket_upgrade(upgrade, "/other"));
+
+        let query = b"GET /_dna_interface?token=1 HTTP/1.1\r\nupgrade: WebSocket\r\n\r\n";
+        assert!(is_websocket_upgrade(query, "/_dna_interface"));
+
+        let plain = b"GET /_dna_interface HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        assert!(!is_websocket_upgrade(plain, "/_dna_interface"));
+    }
+
+    #[test]
+    fn reads_until_the_end_of_the_head() {
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        let request = b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\nhi";
+        assert_eq!(
+            read_head(&mut &request[..], deadline, |_| Ok(())).unwrap(),
+            request.to_vec()
+        );
+
+        let mut incomplete: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n";
+        assert!(read_head(&mut incomplete, deadline, |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn gives_up_on_the_head_at_the_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut slow_client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        let socket = stream.try_clone().unwrap();
+
+        // Bytes trickling in keep resetting a per-read timeout, but not the deadline
+        let writer = thread::spawn(move || {
+            for _ in 0..10 {
+                if slow_client.write_all(b"G").is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        });
+        let start = Instant::now();
+        let result = read_head(
+            &mut stream,
+            Instant::now() + Duration::from_millis(200),
+            |timeout| socket.set_read_timeout(Some(timeout)),
+        );
+        assert!(result.is_err());
+        assert!(start.elapsed() < Duration::from_millis(450));
+        writer.join().unwrap();
+    }
+}
@@ -0,0 +1,12 @@
+{ pkgs }:
+let
+  name = "hc-conductor-wasm-check";
+
+  script = pkgs.writeShellScriptBin name
+  ''
+  cargo build -p holochain_conductor_wasm --target wasm32-unknown-unknown
+  '';
+in
+{
+ buildInputs = [ script ];
+}
@@ -1,6 +1,7 @@
 { pkgs }:
 {
  buildInputs = []
+ ++ (pkgs.callPackage ./check { }).buildInputs
  ++ (pkgs.callPackage ./compile { }).buildInputs
  ++ (pkgs.callPackage ./install { }).buildInputs
  ++ (pkgs.callPackage ./test { }).buildInputs
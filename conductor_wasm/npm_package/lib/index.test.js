@@ -2,11 +2,43 @@ const { expect } = require('chai')
 
 const hc = require('./index')
 
+const entry = JSON.stringify({ App: ['post', '{"content":"hi"}'] })
+
 describe('holochain_conductor_wasm Suite', () => {
-  it('should fast_foo', () => {
-    let input = 'bararas'
-    let output = input
-    let res = hc.fast_foo(input)
-    expect(Buffer.from(res).toString()).equals(output)
+  it('should calculate entry addresses', () => {
+    let address = hc.entry_address(entry)
+    expect(address).to.match(/^Qm/)
+    expect(hc.entry_address(entry)).equals(address)
+  })
+
+  it('should throw on invalid entries', () => {
+    expect(() => hc.entry_address('not an entry')).to.throw()
+  })
+
+  it('should reject headers without valid signatures', () => {
+    let header = JSON.stringify({
+      entry_type: { App: 'post' },
+      entry_address: hc.entry_address(entry),
+      provenances: [['HcScIkRaAaaaaaaaaaAaaaAAAAaaaaaaaaAaaaaaaaAaaaaaaaaAaaaaaaaaaaaa', 'bm90IGEgc2lnbmF0dXJl']],
+      link: null,
+      link_same_type: null,
+      link_update_delete: null,
+      timestamp: '1970-01-01T00:00:00+00:00'
+    })
+    expect(() => hc.verify_chain_header(header, entry)).to.throw()
+  })
+
+  it('should build and read conductor requests', () => {
+    let client = new hc.ConductorClient()
+    let request = JSON.parse(client.call('app', 'blog', 'create_post', '{"content":"hi"}'))
+    expect(request.method).equals('call')
+    expect(request.id).equals(client.last_request_id())
+
+    let response = client.response(JSON.stringify({
+      jsonrpc: '2.0',
+      id: request.id,
+      result: '{"Ok":"QmPost"}'
+    }))
+    expect(response).deep.equals({ id: request.id, result: '{"Ok":"QmPost"}' })
   })
 })
@@ -0,0 +1,262 @@
+//! A typed client for the JSON-RPC interface of a conductor.
+//!
+//! The client only builds requests and reads responses. Sending them is left to whatever
+//! transport the browser has at hand (usually a websocket to the conductor's interface),
+//! so the same client works for websocket and HTTP interfaces.
+
+use holochain_core_types::error::{HcResult, HolochainError};
+use serde_json::{self, Value};
+use std::collections::HashMap;
+
+/// The conductor interface methods a light client uses.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConductorRequest {
+    /// Calls a zome function. `args` is the JSON object of the function's arguments.
+    Call {
+        instance_id: String,
+        zome: String,
+        function: String,
+        args: Value,
+    },
+    /// Lists the instances of the interface.
+    InfoInstances,
+    /// Waits until all effects of the actions of the given instance, or of all instances,
+    /// have been observed by the conductor. Only available on admin interfaces.
+    AwaitConsistency {
+        instance_id: Option<String>,
+        timeout_ms: Option<u64>,
+    },
+}
+
+impl ConductorRequest {
+    pub fn method(&self) -> &'static str {
+        match self {
+            ConductorRequest::Call { .. } => "call",
+            ConductorRequest::InfoInstances => "info/instances",
+            ConductorRequest::AwaitConsistency { .. } => "consistency/await",
+        }
+    }
+
+    pub fn params(&self) -> Value {
+        match self {
+            ConductorRequest::Call {
+                instance_id,
+                zome,
+                function,
+                args,
+            } => json!({
+                "instance_id": instance_id,
+                "zome": zome,
+                "function": function,
+                "args": args,
+            }),
+            ConductorRequest::InfoInstances => json!({}),
+            ConductorRequest::AwaitConsistency {
+                instance_id,
+                timeout_ms,
+            } => {
+                let mut params = serde_json::Map::new();
+                if let Some(instance_id) = instance_id {
+                    params.insert("instance_id".into(), json!(instance_id));
+                }
+                if let Some(timeout_ms) = timeout_ms {
+                    params.insert("timeout".into(), json!(timeout_ms));
+                }
+                Value::Object(params)
+            }
+        }
+    }
+
+    pub fn to_json_rpc(&self, id: u64) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": self.method(),
+            "params": self.params(),
+        })
+        .to_string()
+    }
+}
+
+/// An instance as listed by `info/instances`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstanceInfo {
+    pub id: String,
+    pub dna: String,
+    pub agent: String,
+}
+
+/// The result of a request, typed by the method it was made with.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ConductorResponse {
+    /// The JSON the zome function returned
+    Call(String),
+    InfoInstances(Vec<InstanceInfo>),
+    Consistent,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse {
+    id: Option<u64>,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+/// Numbers requests and remembers which method each pending request was made with,
+/// so responses can be matched and typed.
+#[derive(Default)]
+pub struct ConductorClient {
+    next_id: u64,
+    pending: HashMap<u64, ConductorRequest>,
+}
+
+impl ConductorClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the ID of the request and the JSON-RPC message to send for it.
+    pub fn request(&mut self, request: ConductorRequest) -> (u64, String) {
+        self.next_id += 1;
+        let id = self.next_id;
+        let message = request.to_json_rpc(id);
+        self.pending.insert(id, request);
+        (id, message)
+    }
+
+    /// Reads a JSON-RPC message received from the conductor and returns the ID of the
+    /// request it answers together with its typed result.
+    /// Errors returned by the conductor fail with their message and data.
+    pub fn response(&mut self, message: &str) -> HcResult<(u64, ConductorResponse)> {
+        let response: JsonRpcResponse = serde_json::from_str(message)
+            .map_err(|e| HolochainError::SerializationError(e.to_string()))?;
+        let id = response.id.ok_or_else(|| {
+            HolochainError::ErrorGeneric("Response without request ID".to_string())
+        })?;
+        let request = self.pending.remove(&id).ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("No pending request with ID {}", id))
+        })?;
+
+        if let Some(error) = response.error {
+            return Err(HolochainError::ErrorGeneric(match error.data {
+                Some(data) => format!("{} ({}): {}", error.message, error.code, data),
+                None => format!("{} ({})", error.message, error.code),
+            }));
+        }
+        let result = response.result.unwrap_or(Value::Null);
+        let unexpected = |result: &Value| {
+            HolochainError::SerializationError(format!(
+                "Unexpected result of {}: {}",
+                request.method(),
+                result
+            ))
+        };
+
+        let typed = match request {
+            ConductorRequest::Call { .. } => match result {
+                Value::String(ref output) => ConductorResponse::Call(output.clone()),
+                _ => return Err(unexpected(&result)),
+            },
+            ConductorRequest::InfoInstances => ConductorResponse::InfoInstances(
+                serde_json::from_value(result.clone()).map_err(|_| unexpected(&result))?,
+            ),
+            ConductorRequest::AwaitConsistency { .. } => ConductorResponse::Consistent,
+        };
+        Ok((id, typed))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn can_build_requests() {
+        let mut client = ConductorClient::new();
+        let (id, message) = client.request(ConductorRequest::Call {
+            instance_id: "app".to_string(),
+            zome: "blog".to_string(),
+            function: "create_post".to_string(),
+            args: json!({"content": "hi"}),
+        });
+        assert_eq!(id, 1);
+        let message: Value = serde_json::from_str(&message).unwrap();
+        assert_eq!(message["method"], json!("call"));
+        assert_eq!(message["params"]["args"], json!({"content": "hi"}));
+
+        let (id, message) = client.request(ConductorRequest::AwaitConsistency {
+            instance_id: None,
+            timeout_ms: Some(500),
+        });
+        assert_eq!(id, 2);
+        let message: Value = serde_json::from_str(&message).unwrap();
+        assert_eq!(message["params"], json!({"timeout": 500}));
+    }
+
+    #[test]
+    fn can_read_responses() {
+        let mut client = ConductorClient::new();
+        let (call_id, _) = client.request(ConductorRequest::Call {
+            instance_id: "app".to_string(),
+            zome: "blog".to_string(),
+            function: "create_post".to_string(),
+            args: json!({}),
+        });
+        let (info_id, _) = client.request(ConductorRequest::InfoInstances);
+
+        assert_eq!(
+            client.response(&format!(
+                r#"{{"jsonrpc":"2.0","id":{},"result":[{{"id":"app","dna":"dna","agent":"alice"}}]}}"#,
+                info_id
+            )),
+            Ok((
+                info_id,
+                ConductorResponse::InfoInstances(vec![InstanceInfo {
+                    id: "app".to_string(),
+                    dna: "dna".to_string(),
+                    agent: "alice".to_string(),
+                }])
+            ))
+        );
+        assert_eq!(
+            client.response(&format!(
+                r#"{{"jsonrpc":"2.0","id":{},"result":"{{\"Ok\":\"QmPost\"}}"}}"#,
+                call_id
+            )),
+            Ok((
+                call_id,
+                ConductorResponse::Call(r#"{"Ok":"QmPost"}"#.to_string())
+            ))
+        );
+        // Each response is only taken once
+        assert!(client
+            .response(&format!(
+                r#"{{"jsonrpc":"2.0","id":{},"result":null}}"#,
+                call_id
+            ))
+            .is_err());
+
+        let (id, _) = client.request(ConductorRequest::InfoInstances);
+        let error = client.response(&format!(
+            r#"{{"jsonrpc":"2.0","id":{},"error":{{"code":-32602,"message":"unknown instance"}}}}"#,
+            id
+        ));
+        assert_eq!(
+            error,
+            Err(HolochainError::ErrorGeneric(
+                "unknown instance (-32602)".to_string()
+            ))
+        );
+    }
+}
@@ -1,14 +1,167 @@
+//! A light client for Holochain that runs in the browser.
+//!
+//! It lets UIs check the data they get from a conductor instead of trusting it:
+//! DNAs, entries and chain headers can be parsed and their addresses recalculated,
+//! and signatures and chain headers verified with the authors' keys.
+//! The `ConductorClient` builds and reads the JSON-RPC messages of the conductor interface.
+
+#[cfg(target_arch = "wasm32")]
+extern crate base64;
+#[cfg(target_arch = "wasm32")]
+extern crate ed25519_dalek;
+#[cfg(target_arch = "wasm32")]
+extern crate hcid;
 extern crate holochain_core_types;
+#[cfg(not(target_arch = "wasm32"))]
+extern crate holochain_dpki;
+extern crate holochain_json_api;
+extern crate holochain_persistence_api;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+extern crate base64;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+extern crate lib3h_sodium;
 extern crate wasm_bindgen;
 
+pub mod client;
+pub mod verify;
+
+use crate::client::ConductorRequest;
+use holochain_core_types::error::HolochainError;
+use holochain_persistence_api::cas::content::AddressableContent;
 use wasm_bindgen::prelude::*;
 
-/// There isn't really any reason to export this
-/// but we need something to prove out the wasm build
+fn js_error(error: HolochainError) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+/// The address (hash) of the DNA in the given JSON, as the conductor calculates it.
+#[wasm_bindgen]
+pub fn dna_address(dna_json: &str) -> Result<String, JsValue> {
+    let dna = verify::parse_dna(dna_json).map_err(js_error)?;
+    Ok(dna.address().to_string())
+}
+
+/// The name of the DNA in the given JSON.
+#[wasm_bindgen]
+pub fn dna_name(dna_json: &str) -> Result<String, JsValue> {
+    let dna = verify::parse_dna(dna_json).map_err(js_error)?;
+    Ok(dna.name)
+}
+
+/// The names of the zomes of the DNA in the given JSON, as a JSON array.
+#[wasm_bindgen]
+pub fn dna_zome_names(dna_json: &str) -> Result<String, JsValue> {
+    let dna = verify::parse_dna(dna_json).map_err(js_error)?;
+    Ok(json!(dna.zomes.keys().collect::<Vec<_>>()).to_string())
+}
+
+/// The address of the entry in the given JSON.
+#[wasm_bindgen]
+pub fn entry_address(entry_json: &str) -> Result<String, JsValue> {
+    let entry = verify::parse_entry(entry_json).map_err(js_error)?;
+    Ok(entry.address().to_string())
+}
+
+/// The address of the chain header in the given JSON.
+#[wasm_bindgen]
+pub fn chain_header_address(header_json: &str) -> Result<String, JsValue> {
+    let header = verify::parse_chain_header(header_json).map_err(js_error)?;
+    Ok(header.address().to_string())
+}
+
+/// True if `signature` is the signature of `data` by the agent with the address `source`.
+#[wasm_bindgen]
+pub fn verify_signature(source: &str, data: &str, signature: &str) -> Result<bool, JsValue> {
+    verify::verify_signature(source, data, signature).map_err(js_error)
+}
+
+/// Throws if the chain header is not signed by all of its authors or, if the entry
+/// is given, does not belong to it.
 #[wasm_bindgen]
-#[allow(clippy::blacklisted_name)]
-pub fn fast_foo(foo: &str) -> Result<String, JsValue> {
-    // for speed return `foo` without validating that it is "foo"
-    // foo validation is an upstream concern
-    Ok(foo.to_string())
+pub fn verify_chain_header(header_json: &str, entry_json: Option<String>) -> Result<(), JsValue> {
+    let header = verify::parse_chain_header(header_json).map_err(js_error)?;
+    let entry = match entry_json {
+        Some(entry_json) => Some(verify::parse_entry(&entry_json).map_err(js_error)?),
+        None => None,
+    };
+    verify::verify_chain_header(&header, entry.as_ref()).map_err(js_error)
+}
+
+/// JavaScript binding of the [ConductorClient](client/struct.ConductorClient.html).
+/// The request functions return the message to send, the ID of which can be read with
+/// `last_request_id()`. `response()` returns `{id, result}` for a received message.
+#[wasm_bindgen(js_name = ConductorClient)]
+pub struct JsConductorClient {
+    client: client::ConductorClient,
+    last_request_id: u64,
+}
+
+#[wasm_bindgen(js_class = ConductorClient)]
+impl JsConductorClient {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsConductorClient {
+        JsConductorClient {
+            client: client::ConductorClient::new(),
+            last_request_id: 0,
+        }
+    }
+
+    fn request(&mut self, request: ConductorRequest) -> String {
+        let (id, message) = self.client.request(request);
+        self.last_request_id = id;
+        message
+    }
+
+    pub fn last_request_id(&self) -> u32 {
+        self.last_request_id as u32
+    }
+
+    pub fn call(
+        &mut self,
+        instance_id: String,
+        zome: String,
+        function: String,
+        args_json: &str,
+    ) -> Result<String, JsValue> {
+        let args = serde_json::from_str(args_json)
+            .map_err(|e| js_error(HolochainError::SerializationError(e.to_string())))?;
+        Ok(self.request(ConductorRequest::Call {
+            instance_id,
+            zome,
+            function,
+            args,
+        }))
+    }
+
+    pub fn info_instances(&mut self) -> String {
+        self.request(ConductorRequest::InfoInstances)
+    }
+
+    pub fn await_consistency(
+        &mut self,
+        instance_id: Option<String>,
+        timeout_ms: Option<u32>,
+    ) -> String {
+        self.request(ConductorRequest::AwaitConsistency {
+            instance_id,
+            timeout_ms: timeout_ms.map(u64::from),
+        })
+    }
+
+    pub fn response(&mut self, message: &str) -> Result<JsValue, JsValue> {
+        let (id, result) = self.client.response(message).map_err(js_error)?;
+        JsValue::from_serde(&json!({ "id": id, "result": result }))
+            .map_err(|e| js_error(HolochainError::SerializationError(e.to_string())))
+    }
+}
+
+impl Default for JsConductorClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,132 @@
+//! Checks a light client can do on data it got from a conductor, without trusting it.
+
+use holochain_core_types::{
+    chain_header::ChainHeader,
+    dna::Dna,
+    entry::Entry,
+    error::{HcResult, HolochainError},
+    signature::Signature,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use holochain_dpki::utils::verify;
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use std::convert::TryFrom;
+
+pub fn parse_dna(json: &str) -> HcResult<Dna> {
+    Ok(Dna::try_from(JsonString::from_json(json))?)
+}
+
+pub fn parse_entry(json: &str) -> HcResult<Entry> {
+    Ok(Entry::try_from(JsonString::from_json(json))?)
+}
+
+pub fn parse_chain_header(json: &str) -> HcResult<ChainHeader> {
+    Ok(ChainHeader::try_from(JsonString::from_json(json))?)
+}
+
+/// Same as `holochain_dpki::utils::verify`, which can't be used in the browser because
+/// libsodium doesn't build for wasm32.
+#[cfg(target_arch = "wasm32")]
+fn verify(source: Address, data: String, signature: Signature) -> HcResult<bool> {
+    let codec = hcid::HcidEncoding::with_kind("hcs0")?;
+    let public_key = ed25519_dalek::PublicKey::from_bytes(&codec.decode(&source.to_string())?)
+        .map_err(|_| HolochainError::ErrorGeneric(format!("Invalid signing key {}", source)))?;
+    let signature = base64::decode(&String::from(signature))
+        .map_err(|_| HolochainError::ErrorGeneric("Signature syntactically invalid".to_string()))?;
+    Ok(ed25519_dalek::Signature::from_bytes(&signature)
+        .and_then(|signature| public_key.verify(data.as_bytes(), &signature))
+        .is_ok())
+}
+
+/// True if `signature` is the signature of `data` by the agent with the given address.
+pub fn verify_signature(source: &str, data: &str, signature: &str) -> HcResult<bool> {
+    verify(
+        Address::from(source),
+        data.to_string(),
+        Signature::from(signature.to_string()),
+    )
+}
+
+/// Checks that a header was signed by all of its authors and, if given, that it belongs to
+/// the entry. Fails with a description of the first problem found.
+pub fn verify_chain_header(header: &ChainHeader, entry: Option<&Entry>) -> HcResult<()> {
+    if let Some(entry) = entry {
+        if entry.address() != *header.entry_address() {
+            return Err(HolochainError::ValidationFailed(format!(
+                "Header is for entry {}, not for {}",
+                header.entry_address(),
+                entry.address()
+            )));
+        }
+        if entry.entry_type() != *header.entry_type() {
+            return Err(HolochainError::ValidationFailed(format!(
+                "Header is for an entry of type {}, not {}",
+                header.entry_type(),
+                entry.entry_type()
+            )));
+        }
+    }
+
+    if header.provenances().is_empty() {
+        return Err(HolochainError::ValidationFailed(
+            "Header has no provenances".to_string(),
+        ));
+    }
+    for provenance in header.provenances() {
+        // Authors sign the entry address, see core's provenance validation.
+        let signed = verify(
+            provenance.source(),
+            header.entry_address().to_string(),
+            provenance.signature(),
+        )
+        .unwrap_or(false);
+        if !signed {
+            return Err(HolochainError::ValidationFailed(format!(
+                "Signature of entry {} by {} is invalid",
+                header.entry_address(),
+                provenance.source()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::{
+        chain_header::test_chain_header, entry::test_entry, signature::Provenance, time::Iso8601,
+    };
+    use holochain_dpki::{key_bundle::KeyBundle, utils::generate_random_seed_buf};
+    use lib3h_sodium::secbuf::SecBuf;
+
+    fn signed_header(entry: &Entry) -> ChainHeader {
+        let mut seed = generate_random_seed_buf();
+        let mut key_bundle = KeyBundle::new_from_seed_buf(&mut seed).unwrap();
+        let mut data = SecBuf::with_insecure_from_string(entry.address().to_string());
+        let signature = key_bundle.sign(&mut data).unwrap();
+        let signature = base64::encode(&**signature.read_lock());
+        ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &[Provenance::new(
+                Address::from(key_bundle.get_id()),
+                Signature::from(signature),
+            )],
+            &None,
+            &None,
+            &None,
+            &Iso8601::from(0),
+        )
+    }
+
+    #[test]
+    fn can_verify_chain_headers() {
+        let entry = test_entry();
+        let header = signed_header(&entry);
+        let header = parse_chain_header(&String::from(JsonString::from(header))).unwrap();
+        assert_eq!(verify_chain_header(&header, Some(&entry)), Ok(()));
+        assert!(verify_chain_header(&test_chain_header(), None).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    agent::state::AgentState,
+    agent::state::{AgentState, ChainLock},
     network::{
         direct_message::DirectMessage,
         entry_aspect::EntryAspect,
@@ -9,7 +9,7 @@ use crate::{
     },
     nucleus::{
         actions::{call_zome_function::ExecuteZomeFnResponse, initialize::Initialization},
-        state::NucleusState,
+        state::{CachedValidationResult, NucleusState, ValidationCacheKey},
         validation::ValidationResult,
         ZomeFnCall,
     },
@@ -27,17 +27,22 @@ use holochain_persistence_api::cas::content::Address;
 use lib3h_protocol::data_types::{EntryListData, FetchEntryData, QueryEntryData};
 use snowflake;
 use std::{
+    collections::HashMap,
     hash::{Hash, Hasher},
+    time::{Duration, SystemTime},
     vec::Vec,
 };
 
+/// Time after which responses to actions that nobody picked up get dropped
+pub const ACTION_RESPONSE_TTL: Duration = Duration::from_secs(60 * 10);
+
 /// Wrapper for actions that provides a unique ID
 /// The unique ID is needed for state tracking to ensure that we can differentiate between two
 /// Action dispatches containing the same value when doing "time travel debug".
 /// The standard approach is to drop the ActionWrapper into the key of a state history HashMap and
 /// use the convenience unwrap_to! macro to extract the action data in a reducer.
 /// All reducer functions must accept an ActionWrapper so all dispatchers take an ActionWrapper.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ActionWrapper {
     action: Action,
     id: snowflake::ProcessUniqueId,
@@ -83,21 +88,23 @@ impl Hash for ActionWrapper {
 }
 
 ///This describes a key for the actions
-#[derive(Clone, PartialEq, Debug, Serialize, Eq, Hash)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Eq, Hash)]
 pub enum QueryKey {
     Entry(GetEntryKey),
     Links(GetLinksKey),
 }
 
 ///This is a payload for the Get Method
-#[derive(Clone, PartialEq, Debug, Serialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum QueryPayload {
     Entry,
     Links((Option<CrudStatus>, GetLinksNetworkQuery)),
 }
 
 /// All Actions for the Holochain Instance Store, according to Redux pattern.
-#[derive(Clone, PartialEq, Debug, Serialize)]
+/// Converting a reference into a `&'static str` gives the variant's name, which is
+/// the same as the "action_type" tag actions get serialized with.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, IntoStaticStr)]
 #[serde(tag = "action_type", content = "data")]
 #[allow(clippy::large_enum_variant)]
 pub enum Action {
@@ -108,6 +115,20 @@ pub enum Action {
     /// Does not validate, assumes entry is valid.
     Commit((Entry, Option<Address>, Vec<Provenance>)),
 
+    /// Locks the source chain for everything but a commit of the lock's entry,
+    /// while a countersignature of that entry is being negotiated.
+    /// Fails if the chain is already locked.
+    LockChain(ChainLock),
+
+    /// Releases the given chain lock, unless the chain got locked again in the meantime.
+    /// A commit of the locked entry stays on the chain.
+    UnlockChain(ChainLock),
+
+    /// Releases the given chain lock like UnlockChain, but also resets the top of the
+    /// source chain to where it was when the lock was taken. Undoes the commit of a
+    /// countersigned entry that did not get committed by all counterparties.
+    RollbackChain(ChainLock),
+
     // -------------
     // DHT actions:
     // -------------
@@ -239,10 +260,86 @@ pub enum Action {
     /// Clear an entry from the pending validation list
     RemovePendingValidation((Address, ValidatingWorkflow)),
 
+    /// Remember the conclusive outcome of validating an entry, so that the same
+    /// entry/header pair doesn't get validated again.
+    CacheValidationResult((ValidationCacheKey, CachedValidationResult)),
+
+    /// Drops the response to the action with the given ID from network and DHT state.
+    /// Dispatched by the future that waited for the response, once it has read it.
+    ClearActionResponse(snowflake::ProcessUniqueId),
+
+    /// Drops all responses to actions that were stored before the given time,
+    /// so that responses nobody waits for don't pile up.
+    PruneActionResponses(SystemTime),
+
     /// No-op, used to check if an action channel is still open
     Ping,
 }
 
+/// Responses of reducers to the actions they reduced, for futures to pick up.
+/// Every response should get removed with Action::ClearActionResponse by the future
+/// that waited for it. Responses that no future consumes get removed with
+/// Action::PruneActionResponses once they are older than ACTION_RESPONSE_TTL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionResponses<T> {
+    responses: HashMap<snowflake::ProcessUniqueId, (T, SystemTime)>,
+}
+
+impl<T> Default for ActionResponses<T> {
+    fn default() -> Self {
+        ActionResponses {
+            responses: HashMap::new(),
+        }
+    }
+}
+
+impl<T> ActionResponses<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, action_wrapper: ActionWrapper, response: T) {
+        self.responses
+            .insert(*action_wrapper.id(), (response, SystemTime::now()));
+    }
+
+    pub fn get(&self, action_wrapper: &ActionWrapper) -> Option<&T> {
+        self.responses
+            .get(action_wrapper.id())
+            .map(|(response, _)| response)
+    }
+
+    pub fn contains(&self, action_id: &snowflake::ProcessUniqueId) -> bool {
+        self.responses.contains_key(action_id)
+    }
+
+    pub fn remove(&mut self, action_id: &snowflake::ProcessUniqueId) -> Option<T> {
+        self.responses
+            .remove(action_id)
+            .map(|(response, _)| response)
+    }
+
+    pub fn has_older_than(&self, time: SystemTime) -> bool {
+        self.responses
+            .values()
+            .any(|(_, inserted_at)| *inserted_at < time)
+    }
+
+    /// Removes all responses that were inserted before the given time.
+    pub fn remove_older_than(&mut self, time: SystemTime) {
+        self.responses
+            .retain(|_, (_, inserted_at)| *inserted_at >= time);
+    }
+
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+}
+
 /// function signature for action handler functions
 // @TODO merge these into a single signature
 // @see https://github.com/holochain/holochain-rust/issues/194
@@ -253,7 +350,7 @@ pub type ReduceFn<S> = fn(&mut S, &State, &ActionWrapper);
 
 /// The unique key that represents a GetLinks request, used to associate the eventual
 /// response with this GetLinks request
-#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct GetLinksKey {
     /// The address of the Link base
     pub base_address: Address,
@@ -270,7 +367,7 @@ pub struct GetLinksKey {
 
 /// The unique key that represents a Get request, used to associate the eventual
 /// response with this Get request
-#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct GetEntryKey {
     /// The address of the entry to get
     pub address: Address,
@@ -281,7 +378,7 @@ pub struct GetEntryKey {
 
 /// Everything the network module needs to know in order to send a
 /// direct message.
-#[derive(Clone, PartialEq, Debug, Serialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DirectMessageData {
     /// The address of the node to send a message to
     pub address: Address,
@@ -299,7 +396,7 @@ pub struct DirectMessageData {
 }
 
 /// Everything the network needs to initialize
-#[derive(Clone, PartialEq, Debug, Serialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct NetworkSettings {
     /// P2pConfig that gets passed to [P2pNetwork](struct.P2pNetwork.html)
     /// determines how to connect to the network module.
@@ -314,18 +411,27 @@ pub struct NetworkSettings {
     pub agent_id: String,
 
     /// This is a closure of the code that gets called by the network
-    /// module to have us process incoming messages
+    /// module to have us process incoming messages.
+    /// Closures can't be deserialized, so a deserialized NetworkSettings
+    /// comes with a handler that drops all messages.
+    #[serde(skip_deserializing, default = "ignoring_net_handler")]
     pub handler: NetHandler,
 }
 
+/// A NetHandler that ignores all incoming messages
+pub fn ignoring_net_handler() -> NetHandler {
+    NetHandler::new(Box::new(|_| Ok(())))
+}
+
 #[cfg(test)]
 pub mod tests {
 
     use crate::{
-        action::{Action, ActionWrapper, GetEntryKey, QueryKey, QueryPayload},
+        action::{Action, ActionResponses, ActionWrapper, GetEntryKey, QueryKey, QueryPayload},
         nucleus::tests::test_call_response,
     };
     use holochain_core_types::entry::{expected_entry_address, test_entry};
+    use std::time::{Duration, SystemTime};
     use test_utils::calculate_hash;
 
     /// dummy action
@@ -415,4 +521,24 @@ pub mod tests {
         assert_ne!(calculate_hash(&aw1), calculate_hash(&aw2));
     }
 
+    #[test]
+    fn action_responses_remove_and_prune() {
+        let aw1 = test_action_wrapper();
+        let aw2 = test_action_wrapper();
+        let mut responses = ActionResponses::new();
+        responses.insert(aw1.clone(), 1);
+        assert_eq!(responses.get(&aw1), Some(&1));
+        assert_eq!(responses.get(&aw2), None);
+
+        assert_eq!(responses.remove(aw1.id()), Some(1));
+        assert!(responses.is_empty());
+
+        responses.insert(aw1.clone(), 1);
+        let cutoff = SystemTime::now() + Duration::from_millis(1);
+        std::thread::sleep(Duration::from_millis(2));
+        responses.insert(aw2.clone(), 2);
+        responses.remove_older_than(cutoff);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses.get(&aw2), Some(&2));
+    }
 }
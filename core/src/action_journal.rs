@@ -0,0 +1,517 @@
+//! An opt-in, append-only log of all actions an instance reduces,
+//! and the tooling to replay such a journal into a blank instance.
+//!
+//! Every action gets written as one JSON line. Every `checkpoint_interval` actions the
+//! record also carries a digest of the state after reducing the action. Replaying a journal
+//! re-reduces all actions in order and compares these digests, which makes it possible to
+//! reproduce reducer bugs from a journal written by a production node.
+//! `hc replay-journal` replays a journal file from the command line.
+//!
+//! Records get written, and digests computed, by a background thread so that journaling
+//! does not slow down the reduction of actions.
+use crate::{
+    action::{ignoring_net_handler, Action, ActionWrapper, NetworkSettings},
+    context::Context,
+    instance::Instance,
+    state::StateWrapper,
+};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use holochain_core_types::error::HolochainError;
+use holochain_persistence_api::cas::content::Address;
+use multihash::Hash;
+use serde_json::{self, Value};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Number of actions between two state digests if not configured otherwise
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 100;
+
+/// One line of the journal
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalRecord {
+    /// Position of this action in the journal, starting at 0
+    pub sequence: u64,
+    pub action_wrapper: ActionWrapper,
+    /// Digest of the state after reducing this action, see state_digest()
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<Address>,
+}
+
+enum JournalWrite {
+    /// `state` is only given for records that carry a checkpoint
+    Record {
+        sequence: u64,
+        action_wrapper: ActionWrapper,
+        state: Option<StateWrapper>,
+    },
+    Flush(Sender<()>),
+}
+
+/// Appends the actions an instance reduces to a file.
+/// Gets set on the context and is written to from Instance::process_action().
+pub struct ActionJournal {
+    path: PathBuf,
+    writer: Sender<JournalWrite>,
+    /// Set by the writer thread if it had to stop
+    failure: Arc<Mutex<Option<String>>>,
+    next_sequence: u64,
+    checkpoint_interval: u64,
+}
+
+impl ActionJournal {
+    /// Opens the journal at the given path, creating it if it does not exist yet.
+    /// Records get appended after the ones already in the file,
+    /// a torn last record gets cut off first.
+    pub fn new<P: AsRef<Path>>(path: P, checkpoint_interval: u64) -> Result<Self, HolochainError> {
+        let path: PathBuf = path.as_ref().into();
+        let (records, valid_length) = if path.exists() {
+            parse_journal(&fs::read(&path)?)?
+        } else {
+            (Vec::new(), 0)
+        };
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        file.set_len(valid_length as u64)?;
+        if valid_length > 0 {
+            file.write_all(b"\n")?;
+        }
+
+        let (writer, receiver) = unbounded();
+        let failure = Arc::new(Mutex::new(None));
+        let writer_failure = failure.clone();
+        thread::Builder::new()
+            .name("action_journal".to_string())
+            .spawn(move || write_journal(file, receiver, writer_failure))?;
+        Ok(ActionJournal {
+            path,
+            writer,
+            failure,
+            next_sequence: records
+                .last()
+                .map(|record| record.sequence + 1)
+                .unwrap_or(0),
+            checkpoint_interval: checkpoint_interval.max(1),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queues the given action for writing to the journal.
+    /// `state` has to be the state after reducing that action, so that checkpoints can be taken.
+    pub fn append(
+        &mut self,
+        action_wrapper: &ActionWrapper,
+        state: &StateWrapper,
+    ) -> Result<(), HolochainError> {
+        self.check_writer()?;
+        let sequence = self.next_sequence;
+        let state = if (sequence + 1) % self.checkpoint_interval == 0 {
+            Some(state.clone())
+        } else {
+            None
+        };
+        self.writer
+            .send(JournalWrite::Record {
+                sequence,
+                action_wrapper: action_wrapper.clone(),
+                state,
+            })
+            .map_err(|_| self.writer_stopped())?;
+        self.next_sequence += 1;
+        Ok(())
+    }
+
+    /// Blocks until all actions appended so far are written to the file.
+    pub fn flush(&self) -> Result<(), HolochainError> {
+        let (ack_tx, ack_rx) = unbounded();
+        let _ = self.writer.send(JournalWrite::Flush(ack_tx));
+        let written = ack_rx.recv();
+        self.check_writer()?;
+        written.map_err(|_| self.writer_stopped())
+    }
+
+    fn check_writer(&self) -> Result<(), HolochainError> {
+        match *self.failure.lock().unwrap() {
+            Some(ref error) => Err(HolochainError::ErrorGeneric(format!(
+                "Action journal {} stopped: {}",
+                self.path.display(),
+                error
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn writer_stopped(&self) -> HolochainError {
+        HolochainError::ErrorGeneric(format!("Action journal {} stopped", self.path.display()))
+    }
+}
+
+/// Runs on the journal's writer thread until the journal gets dropped.
+fn write_journal(
+    mut file: File,
+    writes: Receiver<JournalWrite>,
+    failure: Arc<Mutex<Option<String>>>,
+) {
+    for write in writes {
+        let result = match write {
+            JournalWrite::Record {
+                sequence,
+                action_wrapper,
+                state,
+            } => state
+                .map(|state| state_digest(&state))
+                .transpose()
+                .and_then(|checkpoint| {
+                    let mut line = serde_json::to_string(&JournalRecord {
+                        sequence,
+                        action_wrapper,
+                        checkpoint,
+                    })?;
+                    line.push('\n');
+                    file.write_all(line.as_bytes())?;
+                    Ok(file.flush()?)
+                }),
+            JournalWrite::Flush(ack) => {
+                let _ = ack.send(());
+                Ok(())
+            }
+        };
+        if let Err(error) = result {
+            *failure.lock().unwrap() = Some(error.to_string());
+            return;
+        }
+    }
+}
+
+/// Parses the lines of a journal. A last line that can't be parsed is the torn write
+/// of a crashed node and gets dropped, any other broken line is an error.
+/// Also returns the length of the journal up to the end of its last record.
+fn parse_journal(raw: &[u8]) -> Result<(Vec<JournalRecord>, usize), HolochainError> {
+    let is_blank = |line: &&[u8]| line.iter().all(u8::is_ascii_whitespace);
+    let lines: Vec<&[u8]> = raw.split(|byte| *byte == b'\n').collect();
+    let mut records = Vec::new();
+    let mut valid_length = 0;
+    let mut offset = 0;
+    for (index, line) in lines.iter().enumerate() {
+        let line_end = offset + line.len();
+        offset = line_end + 1;
+        if is_blank(line) {
+            continue;
+        }
+        match serde_json::from_slice(line) {
+            Ok(record) => {
+                records.push(record);
+                valid_length = line_end;
+            }
+            Err(_) if lines[index + 1..].iter().all(is_blank) => break,
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok((records, valid_length))
+}
+
+/// Reads all records of the journal at the given path, dropping a torn last record.
+pub fn read_journal<P: AsRef<Path>>(path: P) -> Result<Vec<JournalRecord>, HolochainError> {
+    Ok(parse_journal(&fs::read(path)?)?.0)
+}
+
+/// Computes a digest of those parts of the state that replaying a journal has to reproduce:
+/// the entries (not headers) of the source chain, the held entries, the nucleus status,
+/// pending validations with their attempt counts and the validation cache.
+/// Chain headers are left out since they contain the time of the commit.
+/// This walks the whole chain and all held entries, so it should not run while reducing.
+pub fn state_digest(state: &StateWrapper) -> Result<Address, HolochainError> {
+    let agent = state.agent();
+    let nucleus = state.nucleus();
+    let dht = state.dht();
+
+    let chain_entries: Vec<Address> = agent
+        .iter_chain()
+        .map(|header| header.entry_address().clone())
+        .collect();
+
+    let mut held_entries: Vec<String> = dht
+        .get_all_held_entry_addresses()
+        .iter()
+        .map(|address| address.to_string())
+        .collect();
+    held_entries.sort();
+
+    // Both maps are HashMaps, so their entries have to be sorted to get a stable digest
+    let mut pending_validations: Vec<(String, u32)> = nucleus
+        .pending_validations
+        .iter()
+        .map(|(key, pending)| Ok((serde_json::to_string(key)?, pending.attempts)))
+        .collect::<Result<_, HolochainError>>()?;
+    pending_validations.sort();
+
+    let mut validation_cache: Vec<(String, Value)> = nucleus
+        .validation_cache
+        .iter()
+        .map(|(key, result)| Ok((serde_json::to_string(key)?, serde_json::to_value(result)?)))
+        .collect::<Result<_, HolochainError>>()?;
+    validation_cache.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let digest = serde_json::json!({
+        "chain_entries": chain_entries,
+        "held_entries": held_entries,
+        "nucleus_status": nucleus.status,
+        "pending_validations": pending_validations,
+        "validation_cache": validation_cache,
+    });
+    Ok(Address::encode_from_str(&digest.to_string(), Hash::SHA2256))
+}
+
+/// A checkpoint of the journal whose state digest did not match the replayed state
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CheckpointMismatch {
+    pub sequence: u64,
+    pub action_wrapper: ActionWrapper,
+    pub expected: Address,
+    pub actual: Address,
+}
+
+/// Outcome of replaying a journal
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ReplayReport {
+    pub actions_replayed: u64,
+    pub checkpoints_matched: u64,
+    pub mismatches: Vec<CheckpointMismatch>,
+}
+
+impl ReplayReport {
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Replays the journal at the given path into a new, blank instance built from the given context.
+///
+/// The network is stubbed: network initializations get the context's P2pConfig
+/// (which should be an in-memory backend) and a handler that drops all incoming messages
+/// instead of the journaled ones.
+/// No action loop or scheduled jobs get started, so only the journaled actions change the state.
+/// Returns the context of the replayed instance together with the report of all checkpoints.
+pub fn replay_journal<P: AsRef<Path>>(
+    path: P,
+    context: Arc<Context>,
+) -> Result<(Arc<Context>, ReplayReport), HolochainError> {
+    let records = read_journal(path)?;
+
+    let mut context = (*context).clone();
+    // The replay must not journal into the context's journal again
+    context.action_journal = None;
+    let context = Arc::new(context);
+
+    let mut instance = Instance::new(context.clone());
+    let context = instance.setup_for_replay(context);
+    let (_observer_tx, observer_rx) = unbounded();
+
+    let mut report = ReplayReport::default();
+    for record in records {
+        let action_wrapper = stub_network(record.action_wrapper, &context);
+        instance.process_action(&action_wrapper, Vec::new(), &observer_rx, &context);
+        report.actions_replayed += 1;
+
+        if let Some(expected) = record.checkpoint {
+            let actual = state_digest(&*instance.state())?;
+            if actual == expected {
+                report.checkpoints_matched += 1;
+            } else {
+                report.mismatches.push(CheckpointMismatch {
+                    sequence: record.sequence,
+                    action_wrapper,
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    let network_initialized = instance.state().network().initialized().is_ok();
+    if network_initialized {
+        instance.process_action(
+            &ActionWrapper::new(Action::ShutdownNetwork),
+            Vec::new(),
+            &observer_rx,
+            &context,
+        );
+    }
+
+    Ok((context, report))
+}
+
+fn stub_network(action_wrapper: ActionWrapper, context: &Arc<Context>) -> ActionWrapper {
+    match action_wrapper.action() {
+        Action::InitNetwork(settings) => ActionWrapper::new(Action::InitNetwork(NetworkSettings {
+            p2p_config: context.p2p_config.clone(),
+            handler: ignoring_net_handler(),
+            ..settings.clone()
+        })),
+        _ => action_wrapper,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::test_context,
+        network::entry_with_header::EntryWithHeader,
+        nucleus::state::{CachedValidationResult, ValidationCacheKey},
+    };
+    use holochain_core_types::{chain_header::test_chain_header, entry::test_entry};
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use tempfile::tempdir;
+
+    fn test_actions() -> Vec<ActionWrapper> {
+        let entry_with_header = EntryWithHeader {
+            entry: test_entry(),
+            header: test_chain_header(),
+        };
+        vec![
+            ActionWrapper::new(Action::Hold(entry_with_header.clone())),
+            ActionWrapper::new(Action::CacheValidationResult((
+                ValidationCacheKey {
+                    entry_address: test_entry().address(),
+                    header_address: test_chain_header().address(),
+                    dna_address: Address::from("dna"),
+                },
+                CachedValidationResult::Valid,
+            ))),
+            ActionWrapper::new(Action::Ping),
+        ]
+    }
+
+    /// Reduces the test actions in an instance that journals them to the given path
+    fn write_test_journal(path: &Path, checkpoint_interval: u64) -> Arc<Context> {
+        let mut context = (*test_context("jane", None)).clone();
+        context.action_journal = Some(Arc::new(std::sync::Mutex::new(
+            ActionJournal::new(path, checkpoint_interval).unwrap(),
+        )));
+        let mut instance = Instance::new(Arc::new(context.clone()));
+        let context = instance.setup_for_replay(Arc::new(context));
+        let (_observer_tx, observer_rx) = unbounded();
+        for action_wrapper in test_actions() {
+            instance.process_action(&action_wrapper, Vec::new(), &observer_rx, &context);
+        }
+        flush(&context);
+        context
+    }
+
+    fn flush(context: &Arc<Context>) {
+        let journal = context.action_journal.as_ref().unwrap();
+        journal.lock().unwrap().flush().unwrap();
+    }
+
+    #[test]
+    fn journal_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("journal");
+        let context = write_test_journal(&path, 2);
+
+        let records = read_journal(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records.iter().map(|r| r.sequence).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            records
+                .iter()
+                .map(|r| r.action_wrapper.action().clone())
+                .collect::<Vec<_>>(),
+            test_actions()
+                .iter()
+                .map(|a| a.action().clone())
+                .collect::<Vec<_>>()
+        );
+        assert!(records[0].checkpoint.is_none());
+        assert!(records[1].checkpoint.is_some());
+        assert!(records[2].checkpoint.is_none());
+
+        // Reopening continues the sequence
+        let mut journal = ActionJournal::new(&path, 2).unwrap();
+        journal
+            .append(
+                &ActionWrapper::new(Action::Ping),
+                &*context.state().unwrap(),
+            )
+            .unwrap();
+        journal.flush().unwrap();
+        let records = read_journal(&path).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[3].sequence, 3);
+        assert!(records[3].checkpoint.is_some());
+    }
+
+    #[test]
+    fn torn_last_record_is_dropped() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("journal");
+        let context = write_test_journal(&path, 2);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"sequence\":3,\"action_wra").unwrap();
+        assert_eq!(read_journal(&path).unwrap().len(), 3);
+
+        let mut journal = ActionJournal::new(&path, 2).unwrap();
+        journal
+            .append(
+                &ActionWrapper::new(Action::Ping),
+                &*context.state().unwrap(),
+            )
+            .unwrap();
+        journal.flush().unwrap();
+        let records = read_journal(&path).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[3].sequence, 3);
+
+        // A broken record in the middle is an error
+        let raw = fs::read_to_string(&path).unwrap();
+        fs::write(&path, format!("garbage\n{}", raw)).unwrap();
+        assert!(read_journal(&path).is_err());
+    }
+
+    #[test]
+    fn replay_reproduces_checkpoints() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("journal");
+        let original_context = write_test_journal(&path, 1);
+
+        let (replayed_context, report) = replay_journal(&path, test_context("jane", None)).unwrap();
+        assert_eq!(report.actions_replayed, 3);
+        assert_eq!(report.checkpoints_matched, 3);
+        assert!(report.is_consistent());
+        assert_eq!(
+            state_digest(&*replayed_context.state().unwrap()).unwrap(),
+            state_digest(&*original_context.state().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn replay_reports_mismatching_checkpoints() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("journal");
+        write_test_journal(&path, 1);
+
+        // Drop the first action, so that the state diverges from the journaled checkpoints
+        let records = read_journal(&path).unwrap();
+        let lines: Vec<String> = records
+            .iter()
+            .skip(1)
+            .map(|record| serde_json::to_string(record).unwrap())
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let (_, report) = replay_journal(&path, test_context("jane", None)).unwrap();
+        assert_eq!(report.actions_replayed, 2);
+        assert!(!report.is_consistent());
+        assert_eq!(report.mismatches[0].sequence, 1);
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     instance::dispatch_action,
 };
 use futures::{future::Future, task::Poll};
-use holochain_core_types::{entry::Entry, error::HolochainError};
+use holochain_core_types::{entry::Entry, error::HolochainError, signature::Provenance};
 use holochain_persistence_api::cas::content::Address;
 use std::{pin::Pin, sync::Arc};
 
@@ -18,11 +18,27 @@ pub async fn commit_entry(
     entry: Entry,
     maybe_link_update_delete: Option<Address>,
     context: &Arc<Context>,
+) -> Result<Address, HolochainError> {
+    await!(commit_entry_with_provenances(
+        entry,
+        maybe_link_update_delete,
+        vec![],
+        context
+    ))
+}
+
+/// Like `commit_entry()` but adds the given provenances (i.e. signatures of other
+/// agents, as in countersigned entries) to the new chain header next to our own.
+pub async fn commit_entry_with_provenances(
+    entry: Entry,
+    maybe_link_update_delete: Option<Address>,
+    provenances: Vec<Provenance>,
+    context: &Arc<Context>,
 ) -> Result<Address, HolochainError> {
     let action_wrapper = ActionWrapper::new(Action::Commit((
         entry.clone(),
         maybe_link_update_delete,
-        vec![],
+        provenances,
     )));
     dispatch_action(context.action_channel(), action_wrapper.clone());
     await!(CommitFuture {
@@ -0,0 +1,91 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    agent::state::{ActionResponse, ChainLock},
+    context::Context,
+    instance::dispatch_action,
+};
+use futures::{future::Future, task::Poll};
+use holochain_core_types::error::HolochainError;
+use holochain_persistence_api::cas::content::Address;
+use std::{pin::Pin, sync::Arc, thread, time::SystemTime};
+
+/// LockChain Action Creator
+/// Locks the source chain for a countersignature of the entry with the given address,
+/// started by the given initiator. Until the lock gets released with `unlock_chain()` or
+/// `rollback_chain()`, or `expires_at` passes, only that entry can be committed.
+/// An expiring lock gets rolled back, so a commit of the entry that nobody finished
+/// the countersignature for gets dropped again.
+///
+/// Returns a future that resolves to the taken lock, which is needed to release it again,
+/// or to an error if the chain is already locked.
+pub async fn lock_chain(
+    entry_address: Address,
+    initiator: Address,
+    expires_at: SystemTime,
+    context: &Arc<Context>,
+) -> Result<ChainLock, HolochainError> {
+    let lock = ChainLock::new(entry_address, initiator);
+    let action_wrapper = ActionWrapper::new(Action::LockChain(lock.clone()));
+    dispatch_action(context.action_channel(), action_wrapper.clone());
+    await!(LockChainFuture {
+        context: context.clone(),
+        action: action_wrapper,
+    })?;
+
+    let context_inner = context.clone();
+    let expired_lock = lock.clone();
+    thread::Builder::new()
+        .name(format!("chain_lock_timeout/{}", lock.entry_address))
+        .spawn(move || {
+            if let Ok(remaining) = expires_at.duration_since(SystemTime::now()) {
+                thread::sleep(remaining);
+            }
+            dispatch_action(
+                context_inner.action_channel(),
+                ActionWrapper::new(Action::RollbackChain(expired_lock)),
+            );
+        })
+        .expect("Could not spawn thread for chain lock timeout");
+    Ok(lock)
+}
+
+/// UnlockChain Action Creator
+/// Releases the given chain lock. Does nothing if the chain is not locked with that lock
+/// anymore, so the chain might have been locked again for the same entry.
+pub fn unlock_chain(lock: ChainLock, context: &Arc<Context>) {
+    let action_wrapper = ActionWrapper::new(Action::UnlockChain(lock));
+    dispatch_action(context.action_channel(), action_wrapper);
+}
+
+/// LockChainFuture resolves to the result of the LockChain action
+pub struct LockChainFuture {
+    context: Arc<Context>,
+    action: ActionWrapper,
+}
+
+impl Future for LockChainFuture {
+    type Output = Result<(), HolochainError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context) -> Poll<Self::Output> {
+        if let Some(err) = self.context.action_channel_error("LockChainFuture") {
+            return Poll::Ready(Err(err));
+        }
+        //
+        // TODO: connect the waker to state updates for performance reasons
+        // See: https://github.com/holochain/holochain-rust/issues/314
+        //
+        cx.waker().clone().wake();
+        match self
+            .context
+            .state()
+            .unwrap()
+            .agent()
+            .actions()
+            .get(&self.action)
+        {
+            Some(ActionResponse::LockChain(result)) => Poll::Ready(result.clone()),
+            Some(_) => unreachable!(),
+            None => Poll::Pending,
+        }
+    }
+}
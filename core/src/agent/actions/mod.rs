@@ -1 +1,3 @@
 pub mod commit;
+pub mod lock_chain;
+pub mod rollback_chain;
@@ -0,0 +1,17 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    agent::state::ChainLock,
+    context::Context,
+    instance::dispatch_action_and_wait,
+};
+use std::sync::Arc;
+
+/// RollbackChain Action Creator
+/// Releases the given chain lock and moves the top of the source chain back to where it
+/// was when the lock was taken, dropping a commit of the locked entry.
+/// Does nothing if the chain is not locked with that lock anymore.
+/// Blocks until the action got reduced.
+pub fn rollback_chain(lock: ChainLock, context: &Arc<Context>) {
+    let action_wrapper = ActionWrapper::new(Action::RollbackChain(lock));
+    dispatch_action_and_wait(context.clone(), action_wrapper);
+}
@@ -3,6 +3,7 @@ use holochain_core_types::{
     chain_header::ChainHeader,
     entry::entry_type::EntryType,
     error::RibosomeErrorCode::{self, *},
+    time::Iso8601,
 };
 use holochain_persistence_api::cas::{
     content::{Address, AddressableContent},
@@ -35,6 +36,10 @@ pub struct ChainStoreQueryOptions {
     pub start: usize,
     pub limit: usize,
     pub headers: bool,
+    /// Stop at the first header committed before this time.
+    pub since: Option<Iso8601>,
+    /// Stop when reaching the header with this address (which is not included).
+    pub until_header: Option<Address>,
 }
 
 #[derive(Debug)]
@@ -98,6 +103,21 @@ impl ChainStore {
         };
         let headers = options.headers;
 
+        // Since the chain is walked from the newest header backwards, both bounds
+        // end the iteration instead of filtering, so older headers never get loaded.
+        let since = options.since;
+        let until_header = options.until_header;
+        let in_bounds = |header: &ChainHeader| {
+            since
+                .as_ref()
+                .map(|since| header.timestamp() >= since)
+                .unwrap_or(true)
+                && until_header
+                    .as_ref()
+                    .map(|until_header| header.address() != *until_header)
+                    .unwrap_or(true)
+        };
+
         let vector = match entry_type_names {
             // Vec<Address> or Vec<ChainHeader>
             [] | [""] | ["**"] => {
@@ -105,6 +125,7 @@ impl ChainStore {
                 if headers {
                     ChainStoreQueryResult::Headers(
                         self.iter(start_chain_header)
+                            .take_while(&in_bounds)
                             .skip(start)
                             .take(limit)
                             .map(|header| header.to_owned())
@@ -113,6 +134,7 @@ impl ChainStore {
                 } else {
                     ChainStoreQueryResult::Addresses(
                         self.iter(start_chain_header)
+                            .take_while(&in_bounds)
                             .skip(start)
                             .take(limit)
                             .map(|header| header.entry_address().to_owned())
@@ -120,8 +142,9 @@ impl ChainStore {
                     )
                 }
             }
-            [one] if !is_glob_str(one) => {
-                // Single EntryType without "glob" pattern; uses .iter_type()
+            [one] if !is_glob_str(one) && until_header.is_none() => {
+                // Single EntryType without "glob" pattern; uses .iter_type().  It only visits
+                // headers of that type, so it can't be used to stop at some other header.
                 let entry_type = match EntryType::from_str(&one) {
                     Ok(inner) => inner,
                     Err(..) => return Err(UnknownEntryType),
@@ -129,6 +152,7 @@ impl ChainStore {
                 if headers {
                     ChainStoreQueryResult::Headers(
                         self.iter_type(start_chain_header, &entry_type)
+                            .take_while(&in_bounds)
                             .skip(start)
                             .take(limit)
                             .map(|header| header.to_owned())
@@ -137,6 +161,7 @@ impl ChainStore {
                 } else {
                     ChainStoreQueryResult::Addresses(
                         self.iter_type(start_chain_header, &entry_type)
+                            .take_while(&in_bounds)
                             .skip(start)
                             .take(limit)
                             .map(|header| header.entry_address().to_owned())
@@ -163,6 +188,7 @@ impl ChainStore {
                 if headers {
                     ChainStoreQueryResult::Headers(
                         self.iter(start_chain_header)
+                            .take_while(&in_bounds)
                             .filter(|header| {
                                 globset.matches(header.entry_type().to_string()).len() > 0
                             })
@@ -174,6 +200,7 @@ impl ChainStore {
                 } else {
                     ChainStoreQueryResult::Addresses(
                         self.iter(start_chain_header)
+                            .take_while(&in_bounds)
                             .filter(|header| {
                                 globset.matches(header.entry_type().to_string()).len() > 0
                             })
@@ -307,7 +334,7 @@ pub mod tests {
             entry_type::{test_entry_type_b, AppEntryType},
             test_entry, test_entry_b, test_entry_c, Entry,
         },
-        time::test_iso_8601,
+        time::{test_iso_8601, Iso8601},
     };
     use holochain_json_api::json::{JsonString, RawString};
     use holochain_persistence_api::cas::content::AddressableContent;
@@ -533,6 +560,7 @@ pub mod tests {
                     start: 0,
                     limit: 1,
                     headers: false,
+                    ..Default::default()
                 },
             )
             .unwrap()
@@ -791,6 +819,75 @@ pub mod tests {
         }
     }
 
+    #[test]
+    /// show that query() stops at the since and until_header bounds
+    fn bounded_query_test() {
+        let chain_store = test_chain_store();
+
+        let chain_header_a = test_chain_header();
+        let entry = test_entry_b();
+        let chain_header_b = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &test_provenances("sig-b"),
+            &Some(chain_header_a.address()),
+            &None,
+            &None,
+            &Iso8601::new(10, 0),
+        );
+        let entry = test_entry_c();
+        let chain_header_c = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &test_provenances("sig-c"),
+            &Some(chain_header_b.address()),
+            &Some(chain_header_b.address()),
+            &None,
+            &Iso8601::new(20, 0),
+        );
+
+        let storage = chain_store.content_storage.clone();
+        for chain_header in &[&chain_header_a, &chain_header_b, &chain_header_c] {
+            (*storage.write().unwrap())
+                .add(*chain_header)
+                .expect("could not add header to cas");
+        }
+
+        let found = match chain_store
+            .query(
+                &Some(chain_header_c.clone()),
+                &[],
+                ChainStoreQueryOptions {
+                    headers: true,
+                    since: Some(Iso8601::new(10, 0)),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        {
+            ChainStoreQueryResult::Headers(headers) => headers,
+            other => panic!("Unexpected query value {:?}", other),
+        };
+        assert_eq!(vec![chain_header_c.clone(), chain_header_b.clone()], found);
+
+        // A single EntryType query has to stop at the bound as well
+        let found = match chain_store
+            .query(
+                &Some(chain_header_c.clone()),
+                &vec![test_entry_type_b().to_string().as_ref()],
+                ChainStoreQueryOptions {
+                    until_header: Some(chain_header_b.address()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        {
+            ChainStoreQueryResult::Addresses(addresses) => addresses,
+            other => panic!("Unexpected query value {:?}", other),
+        };
+        assert_eq!(vec![chain_header_c.entry_address().clone()], found);
+    }
+
     use globset::{Glob, GlobBuilder, GlobSetBuilder};
 
     #[test]
@@ -38,6 +38,34 @@ pub struct AgentState {
     chain_store: ChainStore,
     top_chain_header: Option<ChainHeader>,
     initial_agent_address: Address,
+    /// Lock taken while a countersignature is pending.
+    /// While set, only the locked entry can be committed, and only once.
+    chain_lock: Option<ChainLock>,
+    /// The top of the chain when the lock was taken, to roll back to if the
+    /// countersignature fails after the locked entry got committed
+    top_chain_header_before_lock: Option<ChainHeader>,
+}
+
+/// A lock of the source chain for the countersignature of an entry.
+/// Every lock gets a unique token so that releasing an old lock (e.g. when its timeout hits)
+/// can't release a newer one taken for the same entry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainLock {
+    pub entry_address: Address,
+    /// The agent that started the countersignature, the only one allowed to
+    /// make us commit, publish or abort it
+    pub initiator: Address,
+    pub token: snowflake::ProcessUniqueId,
+}
+
+impl ChainLock {
+    pub fn new(entry_address: Address, initiator: Address) -> Self {
+        ChainLock {
+            entry_address,
+            initiator,
+            token: snowflake::ProcessUniqueId::new(),
+        }
+    }
 }
 
 impl AgentState {
@@ -48,6 +76,8 @@ impl AgentState {
             chain_store,
             top_chain_header: None,
             initial_agent_address,
+            chain_lock: None,
+            top_chain_header_before_lock: None,
         }
     }
 
@@ -61,6 +91,8 @@ impl AgentState {
             chain_store,
             top_chain_header: chain_header,
             initial_agent_address,
+            chain_lock: None,
+            top_chain_header_before_lock: None,
         }
     }
 
@@ -78,6 +110,11 @@ impl AgentState {
         self.top_chain_header.clone()
     }
 
+    /// The lock the chain is currently locked with, if any
+    pub fn chain_lock(&self) -> Option<ChainLock> {
+        self.chain_lock.clone()
+    }
+
     pub fn iter_chain(&self) -> ChainStoreIterator {
         self.chain_store.iter(&self.top_chain_header)
     }
@@ -169,6 +206,7 @@ pub enum ActionResponse {
     FetchEntry(Option<Entry>),
     GetLinks(Result<Vec<Address>, HolochainError>),
     LinkEntries(Result<Entry, HolochainError>),
+    LockChain(Result<(), HolochainError>),
 }
 
 pub fn create_new_chain_header(
@@ -224,6 +262,29 @@ fn reduce_commit_entry(
     let action = action_wrapper.action();
     let (entry, maybe_link_update_delete, provenances) = unwrap_to!(action => Action::Commit);
 
+    if let Some(ref lock) = agent_state.chain_lock {
+        let error = if lock.entry_address != entry.address() {
+            Some(format!(
+                "Source chain is locked while a countersignature for entry {} is pending",
+                lock.entry_address
+            ))
+        } else if agent_state.top_chain_header != agent_state.top_chain_header_before_lock {
+            Some(format!(
+                "Countersigned entry {} is already committed",
+                lock.entry_address
+            ))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            agent_state.actions.insert(
+                action_wrapper.clone(),
+                ActionResponse::Commit(Err(HolochainError::ErrorGeneric(error))),
+            );
+            return;
+        }
+    }
+
     let result = create_new_chain_header(
         &entry,
         agent_state,
@@ -238,6 +299,8 @@ fn reduce_commit_entry(
         Ok((chain_header, entry.address()))
     })
     .and_then(|(chain_header, address)| {
+        // A countersigned entry keeps the chain locked until it gets published
+        // or rolled back
         agent_state.top_chain_header = Some(chain_header);
         Ok(address)
     });
@@ -247,10 +310,73 @@ fn reduce_commit_entry(
         .insert(action_wrapper.clone(), ActionResponse::Commit(result));
 }
 
+/// Locks the chain with the lock given in the LockChain action,
+/// unless it is already locked.
+fn reduce_lock_chain(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let lock = unwrap_to!(action => Action::LockChain);
+
+    let result = match agent_state.chain_lock {
+        Some(ref current) => Err(HolochainError::ErrorGeneric(format!(
+            "Source chain is already locked for a countersignature of entry {}",
+            current.entry_address
+        ))),
+        None => {
+            agent_state.chain_lock = Some(lock.clone());
+            agent_state.top_chain_header_before_lock = agent_state.top_chain_header.clone();
+            Ok(())
+        }
+    };
+
+    agent_state
+        .actions
+        .insert(action_wrapper.clone(), ActionResponse::LockChain(result));
+}
+
+/// Releases the chain lock if it is the lock given in the UnlockChain action.
+/// A commit of the locked entry stays on the chain.
+fn reduce_unlock_chain(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let lock = unwrap_to!(action => Action::UnlockChain);
+
+    if agent_state.chain_lock.as_ref() == Some(lock) {
+        agent_state.chain_lock = None;
+        agent_state.top_chain_header_before_lock = None;
+    }
+}
+
+/// Releases the chain lock if it is the lock given in the RollbackChain action and
+/// moves the top of the chain back to where it was when the lock was taken, which drops
+/// a commit of the locked entry. Nothing else can be committed while locked.
+fn reduce_rollback_chain(
+    agent_state: &mut AgentState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let lock = unwrap_to!(action => Action::RollbackChain);
+
+    if agent_state.chain_lock.as_ref() == Some(lock) {
+        agent_state.top_chain_header = agent_state.top_chain_header_before_lock.take();
+        agent_state.chain_lock = None;
+    }
+}
+
 /// maps incoming action to the correct handler
 fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<AgentReduceFn> {
     match action_wrapper.action() {
         Action::Commit(_) => Some(reduce_commit_entry),
+        Action::LockChain(_) => Some(reduce_lock_chain),
+        Action::UnlockChain(_) => Some(reduce_unlock_chain),
+        Action::RollbackChain(_) => Some(reduce_rollback_chain),
         _ => None,
     }
 }
@@ -335,6 +461,121 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// test that a locked chain only accepts the commit of the entry it is locked for
+    fn test_chain_lock() {
+        let netname = Some("test_chain_lock");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id.address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+
+        let other_lock = ChainLock::new("other entry".into(), "initiator".into());
+        let lock = ActionWrapper::new(Action::LockChain(other_lock.clone()));
+        reduce_lock_chain(&mut agent_state, &state, &lock);
+        assert_eq!(
+            agent_state.actions().get(&lock),
+            Some(&ActionResponse::LockChain(Ok(())))
+        );
+        assert_eq!(agent_state.chain_lock(), Some(other_lock.clone()));
+
+        let second_lock = ActionWrapper::new(Action::LockChain(ChainLock::new(
+            expected_entry_address(),
+            "initiator".into(),
+        )));
+        reduce_lock_chain(&mut agent_state, &state, &second_lock);
+        assert!(match agent_state.actions().get(&second_lock) {
+            Some(ActionResponse::LockChain(Err(_))) => true,
+            _ => false,
+        });
+
+        let commit = test_action_wrapper_commit();
+        reduce_commit_entry(&mut agent_state, &state, &commit);
+        assert!(match agent_state.actions().get(&commit) {
+            Some(ActionResponse::Commit(Err(_))) => true,
+            _ => false,
+        });
+        assert_eq!(agent_state.top_chain_header(), None);
+
+        let unlock = ActionWrapper::new(Action::UnlockChain(other_lock));
+        reduce_unlock_chain(&mut agent_state, &state, &unlock);
+        assert_eq!(agent_state.chain_lock(), None);
+
+        let lock = ActionWrapper::new(Action::LockChain(ChainLock::new(
+            expected_entry_address(),
+            "initiator".into(),
+        )));
+        reduce_lock_chain(&mut agent_state, &state, &lock);
+        let commit = test_action_wrapper_commit();
+        reduce_commit_entry(&mut agent_state, &state, &commit);
+        assert_eq!(
+            agent_state.actions().get(&commit),
+            Some(&test_action_response_commit()),
+        );
+        // the lock is kept until the countersigned entry got published or rolled back
+        assert!(agent_state.chain_lock().is_some());
+
+        let second_commit = test_action_wrapper_commit();
+        reduce_commit_entry(&mut agent_state, &state, &second_commit);
+        assert!(match agent_state.actions().get(&second_commit) {
+            Some(ActionResponse::Commit(Err(_))) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    /// test that releasing an old lock does not release a newer lock for the same entry
+    fn test_stale_chain_lock_does_not_unlock() {
+        let netname = Some("test_stale_chain_lock_does_not_unlock");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id.address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+
+        let old_lock = ChainLock::new(expected_entry_address(), "initiator".into());
+        let lock = ActionWrapper::new(Action::LockChain(old_lock.clone()));
+        reduce_lock_chain(&mut agent_state, &state, &lock);
+        let unlock = ActionWrapper::new(Action::UnlockChain(old_lock.clone()));
+        reduce_unlock_chain(&mut agent_state, &state, &unlock);
+        assert_eq!(agent_state.chain_lock(), None);
+
+        let new_lock = ChainLock::new(expected_entry_address(), "initiator".into());
+        let lock = ActionWrapper::new(Action::LockChain(new_lock.clone()));
+        reduce_lock_chain(&mut agent_state, &state, &lock);
+
+        // e.g. the timeout of the old lock hits
+        let stale_unlock = ActionWrapper::new(Action::UnlockChain(old_lock));
+        reduce_unlock_chain(&mut agent_state, &state, &stale_unlock);
+        assert_eq!(agent_state.chain_lock(), Some(new_lock));
+    }
+
+    #[test]
+    /// test that a rollback drops the commit made under the given lock and releases it
+    fn test_rollback_chain() {
+        let netname = Some("test_rollback_chain");
+        let context = test_context("bob", netname);
+        let mut agent_state = test_agent_state(Some(context.agent_id.address()));
+        let state = State::new_with_agent(context, agent_state.clone());
+
+        let chain_lock = ChainLock::new(expected_entry_address(), "initiator".into());
+        let lock = ActionWrapper::new(Action::LockChain(chain_lock.clone()));
+        reduce_lock_chain(&mut agent_state, &state, &lock);
+        let commit = test_action_wrapper_commit();
+        reduce_commit_entry(&mut agent_state, &state, &commit);
+        let committed_header = agent_state
+            .top_chain_header()
+            .expect("Commit must create a header");
+
+        let other_lock = ChainLock::new(expected_entry_address(), "initiator".into());
+        let other_rollback = ActionWrapper::new(Action::RollbackChain(other_lock));
+        reduce_rollback_chain(&mut agent_state, &state, &other_rollback);
+        assert_eq!(agent_state.top_chain_header(), Some(committed_header));
+        assert_eq!(agent_state.chain_lock(), Some(chain_lock.clone()));
+
+        let rollback = ActionWrapper::new(Action::RollbackChain(chain_lock));
+        reduce_rollback_chain(&mut agent_state, &state, &rollback);
+        assert_eq!(agent_state.top_chain_header(), None);
+        assert_eq!(agent_state.chain_lock(), None);
+    }
+
     #[test]
     /// test response to json
     fn test_commit_response_to_json() {
@@ -5,7 +5,12 @@ use crate::{
 use holochain_core_types::{agent::AgentId, entry::Entry, link::link_data::LinkData};
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
 use serde::Serialize;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
 #[derive(Clone, Debug, Serialize)]
 pub struct ConsistencySignal<E: Serialize> {
@@ -31,6 +36,15 @@ impl<E: Serialize> ConsistencySignal<E> {
             .collect();
         Self { event, pending }
     }
+
+    pub fn event(&self) -> &E {
+        &self.event
+    }
+
+    /// Events that are expected to follow this one, and which group of nodes will emit them.
+    pub fn pending(&self) -> &[PendingConsistency<E>] {
+        &self.pending
+    }
 }
 
 impl From<ConsistencySignalE> for ConsistencySignal<String> {
@@ -72,12 +86,24 @@ pub enum ConsistencyEvent {
 }
 
 #[derive(Clone, Debug, Serialize)]
-struct PendingConsistency<E: Serialize> {
+pub struct PendingConsistency<E: Serialize> {
     event: E,
     group: ConsistencyGroup,
 }
 
-#[derive(Clone, Debug, Serialize)]
+impl<E: Serialize> PendingConsistency<E> {
+    pub fn event(&self) -> &E {
+        &self.event
+    }
+
+    pub fn group(&self) -> &ConsistencyGroup {
+        &self.group
+    }
+}
+
+/// Which nodes a pending consistency event is expected from:
+/// the node that emitted the causing event (Source) or the nodes holding the entry (Validators).
+#[derive(Clone, Debug, Serialize, PartialEq, Eq, Hash)]
 pub enum ConsistencyGroup {
     Source,
     Validators,
@@ -201,3 +227,304 @@ impl ConsistencyModel {
         }
     }
 }
+
+/// How long awaiting consistency blocks before giving up, unless told otherwise.
+pub const DEFAULT_CONSISTENCY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest timeout remote callers (e.g. of the `consistency/await` RPC method) can ask for,
+/// so that they cannot keep conductor threads blocked indefinitely.
+pub const MAX_CONSISTENCY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Consistency is only assumed once no signals came in for this long, since the signals of
+/// an action can still be on their way when the zome call that caused it returns.
+pub const CONSISTENCY_QUIET_PERIOD: Duration = Duration::from_millis(300);
+
+/// Events still missing after this long are dropped, so that events which are never going
+/// to be observed (e.g. because the validators are on another conductor) don't pile up.
+pub const CONSISTENCY_RETENTION: Duration = Duration::from_secs(600);
+
+/// Maximum number of events kept that were observed before (or without) their cause.
+/// The tracker gets fed all consistency signals, even if they are not sent to interfaces,
+/// so the oldest of those events are dropped once there are more.
+pub const CONSISTENCY_MAX_UNEXPECTED: usize = 1000;
+
+const CONSISTENCY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Follows the consistency signals of several sources (i.e. instances or agents)
+/// and keeps the events that are still expected.
+/// Events expected from the `Source` group have to come from the source that caused them,
+/// events expected from `Validators` can come from any source.
+#[derive(Default)]
+pub struct ConsistencyTracker {
+    pending: Vec<(String, PendingConsistency<String>, Instant)>,
+    /// Oldest first
+    unexpected: VecDeque<(String, String, Instant)>,
+    last_observed: HashMap<String, Instant>,
+}
+
+fn matches(group: &ConsistencyGroup, expected_from: &str, source: &str) -> bool {
+    *group == ConsistencyGroup::Validators || expected_from == source
+}
+
+impl ConsistencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a signal that the given source emitted.
+    pub fn observe(&mut self, source: &str, signal: &ConsistencySignal<String>) {
+        let now = Instant::now();
+        self.last_observed.insert(source.to_string(), now);
+        self.pending
+            .retain(|(_, _, since)| now.duration_since(*since) < CONSISTENCY_RETENTION);
+        while self
+            .unexpected
+            .front()
+            .map(|(_, _, since)| now.duration_since(*since) >= CONSISTENCY_RETENTION)
+            .unwrap_or(false)
+        {
+            self.unexpected.pop_front();
+        }
+
+        let event = signal.event();
+        match self
+            .pending
+            .iter()
+            .position(|(from, p, _)| p.event() == event && matches(p.group(), from, source))
+        {
+            Some(index) => {
+                self.pending.remove(index);
+            }
+            // Effects can arrive before their causes, so keep them around to be matched later.
+            None => {
+                self.unexpected
+                    .push_back((source.to_string(), event.clone(), now));
+                if self.unexpected.len() > CONSISTENCY_MAX_UNEXPECTED {
+                    self.unexpected.pop_front();
+                }
+            }
+        }
+
+        for pending in signal.pending() {
+            match self.unexpected.iter().position(|(emitter, e, _)| {
+                e == pending.event() && matches(pending.group(), source, emitter)
+            }) {
+                Some(index) => {
+                    self.unexpected.remove(index);
+                }
+                None => self
+                    .pending
+                    .push((source.to_string(), pending.clone(), now)),
+            }
+        }
+    }
+
+    /// True if no events caused by the given source, or by any source if `None`, are missing.
+    pub fn is_consistent(&self, source: Option<&str>) -> bool {
+        self.pending
+            .iter()
+            .all(|(from, _, _)| source.map(|source| source != from).unwrap_or(true))
+    }
+
+    /// Like `is_consistent` but also requires that the given source (or any source if `None`)
+    /// emitted no signals for `CONSISTENCY_QUIET_PERIOD`, so that signals still in flight
+    /// are not missed. Other sources being busy does not keep a source from settling.
+    pub fn is_settled(&self, source: Option<&str>) -> bool {
+        let quiet = |last: &Instant| last.elapsed() >= CONSISTENCY_QUIET_PERIOD;
+        self.is_consistent(source)
+            && match source {
+                Some(source) => self.last_observed.get(source).map(quiet).unwrap_or(true),
+                None => self.last_observed.values().all(quiet),
+            }
+    }
+
+    /// Events that are still expected, together with the source that caused them.
+    pub fn pending_events(&self) -> Vec<(String, String)> {
+        self.pending
+            .iter()
+            .map(|(from, p, _)| (from.clone(), p.event().clone()))
+            .collect()
+    }
+
+    /// The subset of `pending_events` that is expected from the validators holding an entry.
+    /// Only the sources fed to this tracker are observed, so these events never arrive if
+    /// those validators run on other conductors.
+    pub fn pending_validator_events(&self) -> Vec<(String, String)> {
+        self.pending
+            .iter()
+            .filter(|(_, p, _)| *p.group() == ConsistencyGroup::Validators)
+            .map(|(from, p, _)| (from.clone(), p.event().clone()))
+            .collect()
+    }
+}
+
+/// Blocks until all events caused by the given source (or by any source if `None`) have been
+/// observed by the tracker, which is fed from another thread.
+/// Fails with the events that are still missing after `timeout`.
+pub fn await_consistency(
+    tracker: &Arc<Mutex<ConsistencyTracker>>,
+    source: Option<&str>,
+    timeout: Duration,
+) -> Result<(), Vec<(String, String)>> {
+    await_consistency_with(|| (), tracker, source, timeout)
+}
+
+/// Like `await_consistency` but calls `feed` before every check,
+/// for callers that need to pass the signals to the tracker themselves.
+pub fn await_consistency_with<F: FnMut()>(
+    mut feed: F,
+    tracker: &Arc<Mutex<ConsistencyTracker>>,
+    source: Option<&str>,
+    timeout: Duration,
+) -> Result<(), Vec<(String, String)>> {
+    let start = Instant::now();
+    loop {
+        feed();
+        {
+            let tracker = tracker.lock().unwrap();
+            if tracker.is_settled(source) {
+                return Ok(());
+            }
+            if start.elapsed() >= timeout {
+                return Err(tracker
+                    .pending_events()
+                    .into_iter()
+                    .filter(|(from, _)| source.map(|source| source == from).unwrap_or(true))
+                    .collect());
+            }
+        }
+        thread::sleep(CONSISTENCY_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn publish(address: &str) -> ConsistencySignal<String> {
+        ConsistencySignal::new_pending(
+            ConsistencyEvent::Publish(Address::from(address)),
+            ConsistencyGroup::Validators,
+            vec![ConsistencyEvent::Hold(Address::from(address))],
+        )
+        .into()
+    }
+
+    fn hold(address: &str) -> ConsistencySignal<String> {
+        ConsistencySignal::new_terminal(ConsistencyEvent::Hold(Address::from(address))).into()
+    }
+
+    fn validation(address: &str, done: bool) -> ConsistencySignal<String> {
+        let address = Address::from(address);
+        if done {
+            ConsistencySignal::new_terminal(ConsistencyEvent::RemovePendingValidation(address))
+                .into()
+        } else {
+            ConsistencySignal::new_pending(
+                ConsistencyEvent::AddPendingValidation(address.clone()),
+                ConsistencyGroup::Source,
+                vec![ConsistencyEvent::RemovePendingValidation(address)],
+            )
+            .into()
+        }
+    }
+
+    #[test]
+    fn tracker_matches_validator_effects_from_any_source() {
+        let mut tracker = ConsistencyTracker::new();
+        tracker.observe("alice", &publish("Qmentry"));
+        assert!(!tracker.is_consistent(None));
+        tracker.observe("bob", &hold("Qmentry"));
+        assert!(tracker.is_consistent(None));
+
+        // effects observed before their cause
+        tracker.observe("bob", &hold("Qmother"));
+        tracker.observe("alice", &publish("Qmother"));
+        assert!(tracker.is_consistent(None));
+    }
+
+    #[test]
+    fn tracker_matches_source_effects_only_from_the_source() {
+        let mut tracker = ConsistencyTracker::new();
+        tracker.observe("alice", &validation("Qmentry", false));
+        tracker.observe("bob", &validation("Qmentry", true));
+        assert_eq!(
+            tracker.pending_events().len(),
+            1,
+            "bob can't finish alice's validation"
+        );
+        assert!(tracker.is_consistent(Some("bob")));
+        assert!(!tracker.is_consistent(Some("alice")));
+        tracker.observe("alice", &validation("Qmentry", true));
+        assert!(tracker.is_consistent(None));
+    }
+
+    #[test]
+    fn tracker_settles_per_source() {
+        let mut tracker = ConsistencyTracker::new();
+        tracker.observe("alice", &validation("Qmentry", false));
+        tracker.observe("alice", &validation("Qmentry", true));
+        thread::sleep(CONSISTENCY_QUIET_PERIOD);
+        tracker.observe("bob", &publish("Qmother"));
+        assert!(tracker.is_settled(Some("alice")));
+        assert!(!tracker.is_settled(Some("bob")));
+        assert!(!tracker.is_settled(None));
+    }
+
+    #[test]
+    fn tracker_bounds_unexpected_events() {
+        let mut tracker = ConsistencyTracker::new();
+        for i in 0..CONSISTENCY_MAX_UNEXPECTED + 1 {
+            tracker.observe("bob", &hold(&format!("Qm{}", i)));
+        }
+        assert_eq!(tracker.unexpected.len(), CONSISTENCY_MAX_UNEXPECTED);
+        // The oldest one got dropped, so its cause is expected again
+        tracker.observe("alice", &publish("Qm0"));
+        assert!(!tracker.is_consistent(Some("alice")));
+        tracker.observe("alice", &publish("Qm1"));
+        assert_eq!(tracker.pending_events().len(), 1);
+    }
+
+    #[test]
+    fn tracker_tells_validator_events_apart() {
+        let mut tracker = ConsistencyTracker::new();
+        tracker.observe("alice", &publish("Qmentry"));
+        tracker.observe("alice", &validation("Qmother", false));
+        assert_eq!(tracker.pending_events().len(), 2);
+        let from_validators = tracker.pending_validator_events();
+        assert_eq!(from_validators.len(), 1);
+        assert!(tracker.pending_events().contains(&from_validators[0]));
+    }
+
+    #[test]
+    fn await_consistency_waits_for_effects() {
+        let tracker = Arc::new(Mutex::new(ConsistencyTracker::new()));
+        tracker
+            .lock()
+            .unwrap()
+            .observe("alice", &publish("Qmentry"));
+
+        let result = await_consistency(&tracker, Some("alice"), Duration::from_millis(100));
+        assert_eq!(
+            result,
+            Err(vec![(
+                "alice".to_string(),
+                serde_json::to_string(&ConsistencyEvent::Hold(Address::from("Qmentry"))).unwrap()
+            )])
+        );
+
+        let feeding_tracker = tracker.clone();
+        let feeder = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            feeding_tracker
+                .lock()
+                .unwrap()
+                .observe("bob", &hold("Qmentry"));
+        });
+        assert_eq!(
+            await_consistency(&tracker, Some("alice"), Duration::from_secs(5)),
+            Ok(())
+        );
+        feeder.join().unwrap();
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     action::{Action, ActionWrapper},
+    action_journal::ActionJournal,
     conductor_api::ConductorApi,
     instance::Observer,
     nucleus::actions::get_entry::get_entry_from_cas,
@@ -42,6 +43,19 @@ use std::{
 #[cfg(test)]
 use test_utils::mock_signing::mock_conductor_api;
 
+/// Configures how an instance treats agents it knows warrants against.
+/// By default warranted agents are treated like any other agent, warrants only get surfaced
+/// in get_entry and get_links results.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct WarrantPolicy {
+    /// Ignore direct messages sent by warranted agents
+    #[serde(default)]
+    pub refuse_direct_messages: bool,
+    /// Ignore any data published or gossiped by warranted agents, or authored by them
+    #[serde(default)]
+    pub refuse_gossip: bool,
+}
+
 /// Context holds the components that parts of a Holochain instance need in order to operate.
 /// This includes components that are injected from the outside like persister
 /// but also the store of the instance that gets injected before passing on the context
@@ -62,6 +76,9 @@ pub struct Context {
     pub(crate) signal_tx: Option<Sender<Signal>>,
     pub(crate) instance_is_alive: Arc<AtomicBool>,
     pub state_dump_logging: bool,
+    pub warrant_policy: WarrantPolicy,
+    /// If set, every reduced action gets appended to this journal
+    pub action_journal: Option<Arc<Mutex<ActionJournal>>>,
 }
 
 impl Context {
@@ -122,6 +139,8 @@ impl Context {
             )),
             instance_is_alive: Arc::new(AtomicBool::new(true)),
             state_dump_logging,
+            warrant_policy: WarrantPolicy::default(),
+            action_journal: None,
         }
     }
 
@@ -153,6 +172,8 @@ impl Context {
             conductor_api: ConductorApi::new(Self::test_check_conductor_api(None, agent_id)),
             instance_is_alive: Arc::new(AtomicBool::new(true)),
             state_dump_logging,
+            warrant_policy: WarrantPolicy::default(),
+            action_journal: None,
         })
     }
 
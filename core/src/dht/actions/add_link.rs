@@ -1,7 +1,7 @@
 use crate::{
     action::{Action, ActionWrapper},
     context::Context,
-    instance::dispatch_action,
+    instance::{clear_action_response, dispatch_action},
 };
 use futures::{future::Future, task::Poll};
 use holochain_core_types::{error::HolochainError, link::link_data::LinkData};
@@ -44,8 +44,10 @@ impl Future for AddLinkFuture {
         cx.waker().clone().wake();
         if let Some(state) = self.context.state() {
             match state.dht().actions().get(&self.action) {
-                Some(Ok(_)) => Poll::Ready(Ok(())),
-                Some(Err(e)) => Poll::Ready(Err(e.clone())),
+                Some(result) => {
+                    clear_action_response(self.context.action_channel(), &self.action);
+                    Poll::Ready(result.clone().map(|_| ()))
+                }
                 None => Poll::Pending,
             }
         } else {
@@ -1,7 +1,7 @@
 use crate::{
     action::{Action, ActionWrapper},
     context::Context,
-    instance::dispatch_action,
+    instance::{clear_action_response, dispatch_action},
 };
 
 use futures::{future::Future, task::Poll};
@@ -46,8 +46,10 @@ impl Future for UpdateEntryFuture {
         cx.waker().clone().wake();
         if let Some(state) = self.context.state() {
             match state.dht().actions().get(&self.action) {
-                Some(Ok(address)) => Poll::Ready(Ok(address.clone())),
-                Some(Err(e)) => Poll::Ready(Err(e.clone())),
+                Some(result) => {
+                    clear_action_response(self.context.action_channel(), &self.action);
+                    Poll::Ready(result.clone())
+                }
                 None => Poll::Pending,
             }
         } else {
@@ -13,6 +13,7 @@ use holochain_core_types::{
     eav::{Attribute, EaviQuery, EntityAttributeValueIndex},
     entry::Entry,
     error::{HcResult, HolochainError},
+    kv::atomically,
     link::Link,
 };
 
@@ -30,7 +31,7 @@ pub(crate) enum LinkModification {
 
 /// Used as the inner function for both commit and hold reducers
 pub(crate) fn reduce_store_entry_inner(store: &mut DhtStore, entry: &Entry) -> HcResult<()> {
-    match (*store.content_storage().write()?).add(entry) {
+    atomically(|| match (*store.content_storage().write()?).add(entry) {
         Ok(()) => create_crud_status_eav(&entry.address(), CrudStatus::Live).map(|status_eav| {
             (*store.meta_storage().write()?)
                 .add_eavi(&status_eav)
@@ -40,7 +41,7 @@ pub(crate) fn reduce_store_entry_inner(store: &mut DhtStore, entry: &Entry) -> H
                 })
         })?,
         Err(e) => Err(format!("err/dht: dht::reduce_store_entry_inner() FAILED {:?}", e).into()),
-    }
+    })
 }
 
 pub(crate) fn reduce_add_remove_link_inner(
@@ -73,14 +74,16 @@ pub(crate) fn reduce_update_entry_inner(
     old_address: &Address,
     new_address: &Address,
 ) -> HcResult<Address> {
-    // Update crud-status
-    let new_status_eav = create_crud_status_eav(old_address, CrudStatus::Modified)?;
-    (*store.meta_storage().write()?).add_eavi(&new_status_eav)?;
-    // add link from old to new
-    let crud_link_eav = create_crud_link_eav(old_address, new_address)?;
-    (*store.meta_storage().write()?).add_eavi(&crud_link_eav)?;
+    atomically(|| {
+        // Update crud-status
+        let new_status_eav = create_crud_status_eav(old_address, CrudStatus::Modified)?;
+        (*store.meta_storage().write()?).add_eavi(&new_status_eav)?;
+        // add link from old to new
+        let crud_link_eav = create_crud_link_eav(old_address, new_address)?;
+        (*store.meta_storage().write()?).add_eavi(&crud_link_eav)?;
 
-    Ok(new_address.clone())
+        Ok(new_address.clone())
+    })
 }
 
 pub(crate) fn reduce_remove_entry_inner(
@@ -135,12 +138,14 @@ pub(crate) fn reduce_remove_entry_inner(
         .map_err(|_| HolochainError::ErrorGeneric("Could not create eav".into()))?;
     let meta_storage = &store.meta_storage().clone();
 
-    (*meta_storage.write()?).add_eavi(&new_status_eav)?;
+    atomically(|| {
+        (*meta_storage.write()?).add_eavi(&new_status_eav)?;
 
-    // Update crud-link
-    let crud_link_eav = create_crud_link_eav(latest_deleted_address, deletion_address)
-        .map_err(|_| HolochainError::ErrorGeneric(String::from("Could not create eav")))?;
-    (*meta_storage.write()?).add_eavi(&crud_link_eav)?;
+        // Update crud-link
+        let crud_link_eav = create_crud_link_eav(latest_deleted_address, deletion_address)
+            .map_err(|_| HolochainError::ErrorGeneric(String::from("Could not create eav")))?;
+        (*meta_storage.write()?).add_eavi(&crud_link_eav)?;
 
-    Ok(latest_deleted_address.clone())
+        Ok(latest_deleted_address.clone())
+    })
 }
@@ -44,6 +44,8 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<DhtReducer> {
         Action::RemoveEntry(_) => Some(reduce_remove_entry),
         Action::AddLink(_) => Some(reduce_add_link),
         Action::RemoveLink(_) => Some(reduce_remove_link),
+        Action::ClearActionResponse(_) => Some(reduce_clear_action_response),
+        Action::PruneActionResponses(_) => Some(reduce_prune_action_responses),
         _ => None,
     }
 }
@@ -55,7 +57,12 @@ pub(crate) fn reduce_commit_entry(
     let (entry, _, _) = unwrap_to!(action_wrapper.action() => Action::Commit);
     let mut new_store = (*old_store).clone();
     match reduce_store_entry_inner(&mut new_store, entry) {
-        Ok(()) => Some(new_store),
+        Ok(()) => {
+            if let Entry::Warrant(warrant) = entry {
+                new_store.add_warrant(warrant, &entry.address()).ok()?;
+            }
+            Some(new_store)
+        }
         Err(e) => {
             println!("{}", e);
             None
@@ -73,6 +80,9 @@ pub(crate) fn reduce_hold_entry(
         Ok(()) => {
             new_store.mark_entry_as_held(&entry);
             new_store.add_header_for_entry(&entry, &header).ok()?;
+            if let Entry::Warrant(warrant) = entry {
+                new_store.add_warrant(warrant, &entry.address()).ok()?;
+            }
             Some(new_store)
         }
         Err(e) => {
@@ -145,6 +155,33 @@ pub(crate) fn reduce_remove_entry(
     Some(new_store)
 }
 
+pub(crate) fn reduce_clear_action_response(
+    old_store: &DhtStore,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore> {
+    let id = unwrap_to!(action_wrapper.action() => Action::ClearActionResponse);
+    // Clear actions are reduced by the network as well, most of them don't concern the DHT
+    if !old_store.actions().contains(id) {
+        return None;
+    }
+    let mut new_store = (*old_store).clone();
+    new_store.actions_mut().remove(id);
+    Some(new_store)
+}
+
+pub(crate) fn reduce_prune_action_responses(
+    old_store: &DhtStore,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore> {
+    let cutoff = unwrap_to!(action_wrapper.action() => Action::PruneActionResponses);
+    if !old_store.actions().has_older_than(*cutoff) {
+        return None;
+    }
+    let mut new_store = (*old_store).clone();
+    new_store.actions_mut().remove_older_than(*cutoff);
+    Some(new_store)
+}
+
 #[allow(dead_code)]
 pub(crate) fn reduce_get_links(
     _old_store: &DhtStore,
@@ -171,8 +208,9 @@ pub mod tests {
         agent::{test_agent_id, test_agent_id_with_name},
         chain_header::test_chain_header,
         eav::Attribute,
-        entry::{test_entry, test_sys_entry, Entry},
+        entry::{test_entry, test_sys_entry, warrant::Warrant, Entry},
         link::{link_data::LinkData, Link, LinkActionKind},
+        signature::{Provenance, Signature},
     };
     use holochain_persistence_api::cas::content::AddressableContent;
     use std::convert::TryFrom;
@@ -406,6 +444,11 @@ pub mod tests {
         let result = new_dht_store.actions().get(&action).unwrap();
 
         assert!(result.is_err());
+
+        // The response is dropped once the future has read it
+        let clear = ActionWrapper::new(Action::ClearActionResponse(*action.id()));
+        let cleared_dht_store = reduce(new_dht_store, &clear);
+        assert!(cleared_dht_store.actions().get(&action).is_none());
     }
 
     #[test]
@@ -433,4 +476,40 @@ pub mod tests {
         assert_eq!(&entry, &result_entry,);
     }
 
+    #[test]
+    pub fn reduce_hold_warrant_test() {
+        let context = test_context("bill", None);
+        let store = test_store(context.clone());
+
+        let invalid_header = test_chain_header();
+        let author = invalid_header.provenances()[0].source();
+        assert!(!store.dht().is_warranted(&author));
+
+        let warrant = Entry::Warrant(Warrant::new(
+            test_entry(),
+            invalid_header.clone(),
+            "FAIL wat".to_string(),
+            Provenance::new(context.agent_id.address(), Signature::from("sig")),
+        ));
+        let entry_wh = EntryWithHeader {
+            entry: warrant.clone(),
+            header: test_chain_header(),
+        };
+
+        let new_dht_store =
+            reduce_hold_entry(&store.dht(), &ActionWrapper::new(Action::Hold(entry_wh)))
+                .expect("there should be a new store for holding a warrant");
+
+        assert!(new_dht_store.is_warranted(&author));
+        assert_eq!(
+            new_dht_store.get_warrants(&author).unwrap(),
+            vec![warrant.address()]
+        );
+        assert_eq!(
+            new_dht_store.get_warrants_for_headers(&[invalid_header]),
+            vec![warrant.address()]
+        );
+        assert!(!new_dht_store.is_warranted(&context.agent_id.address()));
+    }
+
 }
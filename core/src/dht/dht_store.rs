@@ -1,10 +1,11 @@
-use crate::action::ActionWrapper;
+use crate::action::ActionResponses;
 use holochain_core_types::{
     chain_header::ChainHeader,
     crud_status::CrudStatus,
     eav::{Attribute, EaviQuery, EntityAttributeValueIndex},
-    entry::Entry,
+    entry::{warrant::Warrant, Entry},
     error::HolochainError,
+    kv::atomically,
 };
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::{
@@ -20,7 +21,7 @@ use crate::state::StateWrapper;
 use holochain_json_api::error::JsonResult;
 use holochain_persistence_api::cas::content::Content;
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::BTreeSet,
     convert::TryFrom,
     sync::{Arc, RwLock},
 };
@@ -37,7 +38,9 @@ pub struct DhtStore {
     /// All the entries that the network has told us to hold
     holding_list: Vec<Address>,
 
-    actions: HashMap<ActionWrapper, Result<Address, HolochainError>>,
+    /// The results of actions that are waited for.
+    /// Results get removed once they were read, or when they expire.
+    actions: ActionResponses<Result<Address, HolochainError>>,
 }
 
 impl PartialEq for DhtStore {
@@ -119,7 +122,7 @@ impl DhtStore {
             content_storage,
             meta_storage,
             holding_list: Vec::new(),
-            actions: HashMap::new(),
+            actions: ActionResponses::new(),
         }
     }
 
@@ -166,7 +169,8 @@ impl DhtStore {
                 Attribute::LinkTag(_, _)
                 | Attribute::RemovedLink(_, _)
                 | Attribute::CrudLink
-                | Attribute::CrudStatus => true,
+                | Attribute::CrudStatus
+                | Attribute::Warrant => true,
                 _ => false,
             }),
             None.into(),
@@ -221,9 +225,70 @@ impl DhtStore {
             &Attribute::EntryHeader,
             &header.address(),
         )?;
-        self.content_storage().write().unwrap().add(header)?;
-        self.meta_storage().write().unwrap().add_eavi(&eavi)?;
-        Ok(())
+        atomically(|| {
+            self.content_storage().write().unwrap().add(header)?;
+            self.meta_storage().write().unwrap().add_eavi(&eavi)?;
+            Ok(())
+        })
+    }
+
+    /// Add meta data that references the given warrant from every agent it is issued against
+    pub fn add_warrant(
+        &self,
+        warrant: &Warrant,
+        warrant_address: &Address,
+    ) -> Result<(), HolochainError> {
+        atomically(|| {
+            for agent in warrant.warranted_agents() {
+                let eavi =
+                    EntityAttributeValueIndex::new(&agent, &Attribute::Warrant, warrant_address)?;
+                self.meta_storage().write().unwrap().add_eavi(&eavi)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Get the addresses of all warrants we know of against the given agent
+    pub fn get_warrants(&self, agent: &Address) -> Result<Vec<Address>, HolochainError> {
+        Ok(self
+            .meta_storage
+            .read()?
+            .fetch_eavi(&EaviQuery::new(
+                Some(agent.to_owned()).into(),
+                Some(Attribute::Warrant).into(),
+                None.into(),
+                IndexFilter::LatestByAttribute,
+                None,
+            ))?
+            .into_iter()
+            .map(|eavi| eavi.value())
+            .collect())
+    }
+
+    /// Get the addresses of all warrants we know of against any of the agents
+    /// that signed one of the given headers
+    pub fn get_warrants_for_headers(&self, headers: &[ChainHeader]) -> Vec<Address> {
+        headers
+            .iter()
+            .flat_map(|header| {
+                header
+                    .provenances()
+                    .iter()
+                    .map(|provenance| provenance.source())
+            })
+            .collect::<BTreeSet<Address>>()
+            .iter()
+            .flat_map(|agent| self.get_warrants(agent).unwrap_or_default())
+            .collect::<BTreeSet<Address>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns true if we know of any warrant against the given agent
+    pub fn is_warranted(&self, agent: &Address) -> bool {
+        self.get_warrants(agent)
+            .map(|warrants| !warrants.is_empty())
+            .unwrap_or(false)
     }
 
     pub fn mark_entry_as_held(&mut self, entry: &Entry) {
@@ -242,12 +307,10 @@ impl DhtStore {
     pub(crate) fn meta_storage(&self) -> Arc<RwLock<dyn EntityAttributeValueStorage<Attribute>>> {
         self.meta_storage.clone()
     }
-    pub fn actions(&self) -> &HashMap<ActionWrapper, Result<Address, HolochainError>> {
+    pub fn actions(&self) -> &ActionResponses<Result<Address, HolochainError>> {
         &self.actions
     }
-    pub(crate) fn actions_mut(
-        &mut self,
-    ) -> &mut HashMap<ActionWrapper, Result<Address, HolochainError>> {
+    pub(crate) fn actions_mut(&mut self) -> &mut ActionResponses<Result<Address, HolochainError>> {
         &mut self.actions
     }
 }
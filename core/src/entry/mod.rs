@@ -64,6 +64,7 @@ pub mod tests {
             EntryType::ChainMigrate,
             EntryType::CapTokenClaim,
             EntryType::CapTokenGrant,
+            EntryType::Warrant,
         ]
     }
 
@@ -76,6 +76,15 @@ impl Instance {
         context
     }
 
+    /// Attaches this instance's state and the context's persister to the context like
+    /// inner_setup() does, but without starting the action loop or scheduled jobs,
+    /// so that only actions passed to process_action() change the state.
+    /// Used to replay action journals.
+    pub(crate) fn setup_for_replay(&mut self, context: Arc<Context>) -> Arc<Context> {
+        self.persister = Some(context.persister.clone());
+        self.initialize_context(context)
+    }
+
     /// This is calling inner_setup and running the initialization workflow which makes sure that
     /// the chain gets initialized if dna is Some.
     /// If dna is None it is assumed the chain is already initialized, i.e. we are loading a chain.
@@ -244,6 +253,17 @@ impl Instance {
             *state = new_state;
         }
 
+        if let Some(ref journal) = context.action_journal {
+            let state = self.state();
+            if let Err(e) = journal.lock().unwrap().append(action_wrapper, &state) {
+                log_error!(
+                    context,
+                    "instance/process_action: could not journal action: {:?}",
+                    e
+                );
+            }
+        }
+
         if let Err(e) = self.save() {
             log_error!(
                 context,
@@ -387,6 +407,18 @@ pub fn dispatch_action(action_channel: &Sender<ActionWrapper>, action_wrapper: A
     lax_send_sync(action_channel.clone(), action_wrapper, "dispatch_action");
 }
 
+/// Makes network and DHT state drop their response to the given action.
+/// Futures call this once they have read the response they were waiting for.
+pub fn clear_action_response(
+    action_channel: &Sender<ActionWrapper>,
+    action_wrapper: &ActionWrapper,
+) {
+    dispatch_action(
+        action_channel,
+        ActionWrapper::new(Action::ClearActionResponse(*action_wrapper.id())),
+    );
+}
+
 #[cfg(test)]
 pub mod tests {
     use self::tempfile::tempdir;
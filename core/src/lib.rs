@@ -12,6 +12,8 @@ extern crate test_utils;
 extern crate unwrap_to;
 #[macro_use]
 extern crate num_derive;
+#[macro_use]
+extern crate strum_macros;
 
 #[macro_use]
 extern crate holochain_wasm_utils;
@@ -27,6 +29,7 @@ extern crate logging;
 #[macro_use]
 pub mod macros;
 pub mod action;
+pub mod action_journal;
 pub mod agent;
 pub mod consistency;
 pub mod context;
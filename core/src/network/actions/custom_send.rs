@@ -18,9 +18,25 @@ pub async fn custom_send(
     custom_direct_message: CustomDirectMessage,
     timeout: Timeout,
     context: Arc<Context>,
+) -> Result<String, HolochainError> {
+    await!(send_direct_message_and_await_response(
+        to_agent,
+        DirectMessage::Custom(custom_direct_message),
+        timeout,
+        context
+    ))
+}
+
+/// Sends the given DirectMessage and waits for the response to show up in
+/// NetworkState::custom_direct_message_replys (or the timeout to hit).
+/// Used for all messages that get answered with a string payload.
+pub async fn send_direct_message_and_await_response(
+    to_agent: Address,
+    direct_message: DirectMessage,
+    timeout: Timeout,
+    context: Arc<Context>,
 ) -> Result<String, HolochainError> {
     let id = ProcessUniqueId::new().to_string();
-    let direct_message = DirectMessage::Custom(custom_direct_message);
     let direct_message_data = DirectMessageData {
         address: to_agent,
         message: direct_message,
@@ -32,13 +48,13 @@ pub async fn custom_send(
     let context_inner = context.clone();
     let id_inner = id.clone();
     thread::Builder::new()
-        .name(format!("custom_send_timeout/{}", id))
+        .name(format!("direct_message_timeout/{}", id))
         .spawn(move || {
             thread::sleep(timeout.into());
             let action_wrapper = ActionWrapper::new(Action::SendDirectMessageTimeout(id_inner));
             dispatch_action(context_inner.action_channel(), action_wrapper.clone());
         })
-        .expect("Could not spawn thread for custom_send timeout");
+        .expect("Could not spawn thread for direct message timeout");
 
     await!(SendResponseFuture {
         context: context.clone(),
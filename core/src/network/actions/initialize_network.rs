@@ -6,6 +6,7 @@ use crate::{
 };
 use futures::{task::Poll, Future};
 use holochain_core_types::error::HcResult;
+use holochain_net::p2p_config::P2pConfig;
 #[cfg(test)]
 use holochain_persistence_api::cas::content::Address;
 use std::{pin::Pin, sync::Arc};
@@ -15,7 +16,7 @@ pub async fn initialize_network(context: &Arc<Context>) -> HcResult<()> {
     let (dna_address, agent_id) = await!(get_dna_and_agent(context))?;
     let handler = create_handler(&context, dna_address.to_string());
     let network_settings = NetworkSettings {
-        p2p_config: context.p2p_config.clone(),
+        p2p_config: p2p_config_for_dna(context),
         dna_address,
         agent_id: agent_id.clone(),
         handler,
@@ -38,7 +39,7 @@ pub async fn initialize_network_with_spoofed_dna(
     let (_, agent_id) = await!(get_dna_and_agent(context))?;
     let handler = create_handler(&context, dna_address.to_string());
     let network_settings = NetworkSettings {
-        p2p_config: context.p2p_config.clone(),
+        p2p_config: p2p_config_for_dna(context),
         dna_address,
         agent_id,
         handler,
@@ -51,6 +52,16 @@ pub async fn initialize_network_with_spoofed_dna(
     })
 }
 
+/// The conductor's network config with the DHT parameters of the instance's DNA applied.
+fn p2p_config_for_dna(context: &Arc<Context>) -> P2pConfig {
+    let dht_config = context
+        .state()
+        .and_then(|state| state.nucleus().dna())
+        .map(|dna| dna.dht)
+        .unwrap_or_default();
+    context.p2p_config.clone().with_dht_config(&dht_config)
+}
+
 pub struct InitNetworkFuture {
     context: Arc<Context>,
 }
@@ -1,7 +1,7 @@
 use crate::{
     action::{Action, ActionWrapper},
     context::Context,
-    instance::dispatch_action,
+    instance::{clear_action_response, dispatch_action},
     network::actions::ActionResponse,
 };
 use futures::{future::Future, task::Poll};
@@ -47,10 +47,10 @@ impl Future for PublishFuture {
         //
         cx.waker().clone().wake();
         match state.actions().get(&self.action) {
-            Some(ActionResponse::Publish(result)) => match result {
-                Ok(address) => Poll::Ready(Ok(address.to_owned())),
-                Err(error) => Poll::Ready(Err(error.clone())),
-            },
+            Some(ActionResponse::Publish(result)) => {
+                clear_action_response(self.context.action_channel(), &self.action);
+                Poll::Ready(result.clone())
+            }
             _ => Poll::Pending,
         }
     }
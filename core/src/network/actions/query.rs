@@ -35,6 +35,13 @@ pub async fn query(
     method: QueryMethod,
     timeout: Timeout,
 ) -> HcResult<NetworkQueryResult> {
+    // The DNA can set its own default timeout for queries
+    let timeout = context
+        .state()
+        .and_then(|state| state.nucleus().dna())
+        .map(|dna| dna.dht.query_timeout(timeout.clone()))
+        .unwrap_or(timeout);
+
     let (key, payload) = match method {
         QueryMethod::Entry(address) => {
             let key = GetEntryKey {
@@ -2,7 +2,8 @@ use holochain_persistence_api::cas::content::Address;
 
 use holochain_json_api::{error::JsonError, json::JsonString};
 
-use holochain_core_types::validation::ValidationPackage;
+use holochain_core_types::{entry::Entry, signature::Provenance, validation::ValidationPackage};
+use std::time::SystemTime;
 
 /// This is direct message that got created by the zome code through hdk::send().
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
@@ -16,6 +17,30 @@ pub struct CustomDirectMessage {
     pub payload: Result<String, String>,
 }
 
+/// Sent by the initiator of a countersignature to every counterparty.
+/// Asks the counterparty to validate the entry, lock its source chain and
+/// respond with its signature of the entry's address.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
+pub struct CountersignRequest {
+    pub entry: Entry,
+    /// Signatures collected so far, at least the initiator's
+    pub provenances: Vec<Provenance>,
+    /// All agents that have to sign, initiator excluded
+    pub counterparties: Vec<Address>,
+    /// When the whole countersignature has to be done by. Counterparties keep their
+    /// chains locked until then, plus a grace period for the CountersignPublish to arrive.
+    pub deadline: SystemTime,
+}
+
+/// Sent by the initiator after it has committed the countersigned entry.
+/// Makes counterparties commit the same entry with all provenances, but neither publish it
+/// nor unlock their chains before the initiator sends a CountersignPublish.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
+pub struct CountersignCommit {
+    pub entry: Entry,
+    pub provenances: Vec<Provenance>,
+}
+
 /// These are the different kinds of (low-level, i.e. non-app)
 /// node-to-node messages that can be send between Holochain nodes.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, DefaultJson)]
@@ -35,4 +60,24 @@ pub enum DirectMessage {
     /// Option<> since there has to be a way to respond saying
     /// "I can't"
     ValidationPackage(Option<ValidationPackage>),
+
+    /// Asks a counterparty to countersign an entry.
+    CountersignRequest(CountersignRequest),
+
+    /// Tells a counterparty to commit the countersigned entry.
+    CountersignCommit(CountersignCommit),
+
+    /// Tells a counterparty that all counterparties committed the countersigned entry
+    /// with the given address, so it can publish it and unlock its chain.
+    CountersignPublish(Address),
+
+    /// Tells a counterparty that the countersignature for the entry
+    /// with the given address failed so it can roll back its commit and unlock its chain.
+    CountersignAbort(Address),
+
+    /// Response of a counterparty to any of the four messages above.
+    /// Holds the JSON serialized provenance as response to a CountersignRequest,
+    /// the entry address as response to a CountersignCommit
+    /// and an empty string as response to a CountersignPublish or CountersignAbort.
+    CountersignResponse(Result<String, String>),
 }
@@ -333,6 +333,7 @@ fn get_meta_aspects(
             Attribute::LinkTag(_, _) => true,
             Attribute::RemovedLink(_, _) => true,
             Attribute::CrudLink => true,
+            Attribute::Warrant => true,
             _ => false,
         })
         .map(|eavi| {
@@ -364,6 +365,10 @@ fn get_meta_aspects(
                     value_entry.entry_with_meta.entry,
                     header,
                 )),
+                Attribute::Warrant => {
+                    let warrant = unwrap_to!(value_entry.entry_with_meta.entry => Entry::Warrant);
+                    Ok(EntryAspect::Warrant(warrant.clone(), header))
+                }
                 _ => unreachable!(),
             }
         })
@@ -77,6 +77,7 @@ fn get_links(
                         } else {
                             None
                         };
+                        let warrants = entry_with_meta_and_headers.warrants;
                         entry_with_meta_and_headers
                             .entry
                             .map(|single_entry| match single_entry {
@@ -86,6 +87,7 @@ fn get_links(
                                     link_add.link().target().clone(),
                                     tag.clone(),
                                     maybe_entry_headers,
+                                    warrants,
                                 )),
                                 Entry::LinkRemove(link_remove) => Ok(GetLinkData::new(
                                     link_add_address.clone(),
@@ -93,6 +95,7 @@ fn get_links(
                                     link_remove.0.link().target().clone(),
                                     tag.clone(),
                                     maybe_entry_headers,
+                                    warrants,
                                 )),
                                 _ => Err(HolochainError::ErrorGeneric(
                                     "Wrong entry type for Link content".to_string(),
@@ -138,9 +141,11 @@ fn get_entry(context: &Arc<Context>, address: Address) -> Option<EntryWithMetaAn
                     entry_with_meta_opt
                         .map(|entry_with_meta| {
                             if entry_with_meta.entry.entry_type().can_publish(&context) {
+                                let warrants = state.dht().get_warrants_for_headers(&headers);
                                 Some(EntryWithMetaAndHeader {
                                     entry_with_meta: entry_with_meta.clone(),
                                     headers,
+                                    warrants,
                                 })
                             } else {
                                 None
@@ -4,6 +4,10 @@ use crate::{
     instance::dispatch_action,
     network::direct_message::DirectMessage,
     workflows::{
+        countersign_entry::{
+            handle_countersign_abort, handle_countersign_commit, handle_countersign_publish,
+            handle_countersign_request,
+        },
         handle_custom_direct_message::handle_custom_direct_message,
         respond_validation_package_request::respond_validation_package_request,
     },
@@ -36,6 +40,19 @@ pub fn handle_send_message(message_data: DirectMessageData, context: Arc<Context
         }
     };
 
+    if context.warrant_policy.refuse_direct_messages
+        && context
+            .state()
+            .map(|state| state.dht().is_warranted(&message_data.from_agent_id))
+            .unwrap_or(false)
+    {
+        log_warn!(context,
+            "net/handle_send_message: Refusing direct message from warranted agent {}",
+            message_data.from_agent_id,
+        );
+        return;
+    }
+
     match message {
         DirectMessage::Custom(custom_direct_message) => {
             thread::Builder::new()
@@ -82,6 +99,65 @@ pub fn handle_send_message(message_data: DirectMessageData, context: Arc<Context
         DirectMessage::ValidationPackage(_) => log_error!(context, 
             "net: Got DirectMessage::ValidationPackage as initial message. This should not happen.",
         ),
+        DirectMessage::CountersignRequest(request) => {
+            thread::Builder::new()
+                .name(format!(
+                    "countersign_request/{}",
+                    ProcessUniqueId::new().to_string()
+                ))
+                .spawn(move || {
+                    context.block_on(handle_countersign_request(
+                        message_data.from_agent_id,
+                        message_data.request_id,
+                        request,
+                        context.clone(),
+                    ));
+                })
+                .expect("Could not spawn thread for handling of countersign request");
+        }
+        DirectMessage::CountersignCommit(commit) => {
+            thread::Builder::new()
+                .name(format!(
+                    "countersign_commit/{}",
+                    ProcessUniqueId::new().to_string()
+                ))
+                .spawn(move || {
+                    context.block_on(handle_countersign_commit(
+                        message_data.from_agent_id,
+                        message_data.request_id,
+                        commit,
+                        context.clone(),
+                    ));
+                })
+                .expect("Could not spawn thread for handling of countersign commit");
+        }
+        DirectMessage::CountersignPublish(address) => {
+            thread::Builder::new()
+                .name(format!(
+                    "countersign_publish/{}",
+                    ProcessUniqueId::new().to_string()
+                ))
+                .spawn(move || {
+                    context.block_on(handle_countersign_publish(
+                        message_data.from_agent_id,
+                        message_data.request_id,
+                        address,
+                        context.clone(),
+                    ));
+                })
+                .expect("Could not spawn thread for handling of countersign publish");
+        }
+        DirectMessage::CountersignAbort(address) => {
+            context.block_on(handle_countersign_abort(
+                message_data.from_agent_id,
+                message_data.request_id,
+                address,
+                context.clone(),
+            ));
+        }
+        DirectMessage::CountersignResponse(_) => log_error!(context,
+            "net: Got DirectMessage::CountersignResponse as initial message. This should not happen.",
+        ),
     };
 }
 
@@ -147,5 +223,27 @@ pub fn handle_send_message_result(message_data: DirectMessageData, context: Arc<
                 ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
             dispatch_action(context.action_channel(), action_wrapper.clone());
         }
+        DirectMessage::CountersignResponse(result) => {
+            if initial_message.is_none() {
+                log_error!(context, "net: Received a countersign response but could not find message ID in history. Not able to process.");
+                return;
+            }
+
+            let action_wrapper = ActionWrapper::new(Action::HandleCustomSendResponse((
+                message_data.request_id.clone(),
+                result,
+            )));
+            dispatch_action(context.action_channel(), action_wrapper.clone());
+
+            let action_wrapper =
+                ActionWrapper::new(Action::ResolveDirectConnection(message_data.request_id));
+            dispatch_action(context.action_channel(), action_wrapper.clone());
+        }
+        DirectMessage::CountersignRequest(_)
+        | DirectMessage::CountersignCommit(_)
+        | DirectMessage::CountersignPublish(_)
+        | DirectMessage::CountersignAbort(_) => log_error!(context,
+            "net: Got a countersign message as a response. This should not happen.",
+        ),
     };
 }
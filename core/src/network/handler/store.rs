@@ -9,16 +9,31 @@ use crate::{
 };
 use holochain_core_types::entry::{deletion_entry::DeletionEntry, Entry};
 use holochain_json_api::json::JsonString;
-use holochain_persistence_api::cas::content::AddressableContent;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
 use lib3h_protocol::data_types::StoreEntryAspectData;
 use snowflake::ProcessUniqueId;
-use std::{convert::TryInto, sync::Arc, thread};
+use std::{convert::TryFrom, iter, sync::Arc, thread};
 
 /// The network requests us to store (i.e. hold) the given entry aspect data.
 pub fn handle_store(dht_data: StoreEntryAspectData, context: Arc<Context>) {
     let aspect_json =
         JsonString::from_json(&String::from_utf8(dht_data.entry_aspect.aspect).unwrap());
-    if let Ok(aspect) = aspect_json.clone().try_into() {
+    if let Ok(aspect) = EntryAspect::try_from(aspect_json.clone()) {
+        if context.warrant_policy.refuse_gossip {
+            let dht = context.state().unwrap().dht();
+            let provider = Address::from(dht_data.provider_agent_id.to_string());
+            if let Some(agent) = iter::once(provider)
+                .chain(aspect.header().provenances().iter().map(|p| p.source()))
+                .find(|agent| dht.is_warranted(agent))
+            {
+                log_warn!(context,
+                    "net/handle_store: Refusing {:?} from warranted agent {}",
+                    aspect,
+                    agent
+                );
+                return;
+            }
+        }
         match aspect {
             EntryAspect::Content(entry, header) => {
                 log_debug!(context, "net/handle: handle_store: Got EntryAspect::Content. processing...");
@@ -101,6 +116,28 @@ pub fn handle_store(dht_data: StoreEntryAspectData, context: Arc<Context>) {
                     })
                     .expect("Could not spawn thread for storing EntryAspect::Update");
             }
+            EntryAspect::Warrant(warrant, header) => {
+                log_debug!(context, "net/handle: handle_store: Got EntryAspect::Warrant. processing...");
+                let entry = Entry::Warrant(warrant);
+                if entry.address() != *header.entry_address() {
+                    log_error!(context, "net/handle: handle_store: Got EntryAspect::Warrant with non-matching Warrant and ChainHeader! Hash of content in header does not match content! Ignoring.");
+                    return;
+                }
+                let entry_with_header = EntryWithHeader { entry, header };
+                thread::Builder::new()
+                    .name(format!(
+                        "store_warrant/{}",
+                        ProcessUniqueId::new().to_string()
+                    ))
+                    .spawn(move || {
+                        if let Err(error) = context
+                            .block_on(hold_entry_workflow(&entry_with_header, context.clone()))
+                        {
+                            log_error!(context, "net/handle_store: {}", error)
+                        }
+                    })
+                    .expect("Could not spawn thread for storing EntryAspect::Warrant");
+            }
             EntryAspect::Deletion(header) => {
                 log_debug!(context, 
                     "net/handle: handle_store: Got EntryAspect::Deletion. processing...",
@@ -0,0 +1,69 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    network::state::NetworkState,
+    state::State,
+};
+
+pub fn reduce_clear_action_response(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let id = unwrap_to!(action => Action::ClearActionResponse);
+
+    network_state.actions.remove(id);
+}
+
+pub fn reduce_prune_action_responses(
+    network_state: &mut NetworkState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let cutoff = unwrap_to!(action => Action::PruneActionResponses);
+
+    network_state.actions.remove_older_than(*cutoff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::test_context, network::actions::ActionResponse, state::test_store,
+    };
+    use holochain_core_types::error::HolochainError;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn clear_and_prune_action_responses() {
+        let context = test_context("alice", None);
+        let store = test_store(context);
+        let mut network_state = NetworkState::new();
+        let publish = ActionWrapper::new(Action::Publish("address".into()));
+        network_state.actions.insert(
+            publish.clone(),
+            ActionResponse::Publish(Err(HolochainError::Timeout)),
+        );
+
+        let clear = ActionWrapper::new(Action::ClearActionResponse(*publish.id()));
+        reduce_clear_action_response(&mut network_state, &store, &clear);
+        assert!(network_state.actions().get(&publish).is_none());
+
+        network_state.actions.insert(
+            publish.clone(),
+            ActionResponse::Publish(Err(HolochainError::Timeout)),
+        );
+        let prune = ActionWrapper::new(Action::PruneActionResponses(
+            SystemTime::now() - Duration::from_secs(60),
+        ));
+        reduce_prune_action_responses(&mut network_state, &store, &prune);
+        assert!(network_state.actions().get(&publish).is_some());
+
+        let prune = ActionWrapper::new(Action::PruneActionResponses(
+            SystemTime::now() + Duration::from_secs(60),
+        ));
+        reduce_prune_action_responses(&mut network_state, &store, &prune);
+        assert!(network_state.actions().is_empty());
+    }
+}
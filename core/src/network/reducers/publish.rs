@@ -119,6 +119,40 @@ fn publish_link_meta(
     )
 }
 
+/// Send to network a publish request for a warrant to the agent entries of all warranted agents
+fn publish_warrant_meta(
+    network_state: &mut NetworkState,
+    entry_with_header: &EntryWithHeader,
+) -> Result<(), HolochainError> {
+    let warrant = match &entry_with_header.entry {
+        Entry::Warrant(warrant) => warrant,
+        _ => {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Received bad entry type. Expected Entry::Warrant received {:?}",
+                entry_with_header.entry,
+            )));
+        }
+    };
+    for agent in warrant.warranted_agents() {
+        send(
+            network_state,
+            Lib3hClientProtocol::PublishEntry(ProvidedEntryData {
+                space_address: network_state.dna_address.clone().unwrap(),
+                provider_agent_id: network_state.agent_id.clone().unwrap().into(),
+                entry: EntryData {
+                    entry_address: agent,
+                    aspect_list: vec![EntryAspect::Warrant(
+                        warrant.clone(),
+                        entry_with_header.header.clone(),
+                    )
+                    .into()],
+                },
+            }),
+        )?;
+    }
+    Ok(())
+}
+
 fn reduce_publish_inner(
     network_state: &mut NetworkState,
     root_state: &State,
@@ -156,6 +190,8 @@ fn reduce_publish_inner(
                 None => Ok(()),
             }
         }),
+        EntryType::Warrant => publish_entry(network_state, &entry_with_header)
+            .and_then(|_| publish_warrant_meta(network_state, &entry_with_header)),
         _ => Err(HolochainError::NotImplemented(
             format!("reduce_publish_inner not implemented for {}", entry_with_header.entry.entry_type()),
         )),
@@ -1,5 +1,5 @@
 use crate::{
-    action::{ActionWrapper, QueryKey},
+    action::{ActionResponses, QueryKey},
     network::{actions::ActionResponse, direct_message::DirectMessage,query::NetworkQueryResult},
 };
 use boolinator::*;
@@ -12,7 +12,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-type Actions = HashMap<ActionWrapper, ActionResponse>;
+type Actions = ActionResponses<ActionResponse>;
 
 /// This represents the state of a get_validation_package network process:
 /// None: process started, but no response yet from the network
@@ -26,9 +26,8 @@ type GetResults = Option<Result<NetworkQueryResult, HolochainError>>;
 
 #[derive(Clone, Debug)]
 pub struct NetworkState {
-    /// every action and the result of that action
-    // @TODO this will blow up memory, implement as some kind of dropping/FIFO with a limit?
-    // @see https://github.com/holochain/holochain-rust/issues/166
+    /// The results of actions that are waited for.
+    /// Results get removed once they were read, or when they expire.
     pub actions: Actions,
     pub network: Arc<Mutex<Option<P2pNetwork>>>,
     pub dna_address: Option<Address>,
@@ -59,7 +58,7 @@ impl PartialEq for NetworkState {
 impl NetworkState {
     pub fn new() -> Self {
         NetworkState {
-            actions: HashMap::new(),
+            actions: ActionResponses::new(),
             network: Arc::new(Mutex::new(None)),
             dna_address: None,
             agent_id: None,
@@ -72,8 +71,8 @@ impl NetworkState {
         }
     }
 
-    pub fn actions(&self) -> Actions {
-        self.actions.clone()
+    pub fn actions(&self) -> &Actions {
+        &self.actions
     }
 
     pub fn initialized(&self) -> Result<(), HolochainError> {
@@ -1,6 +1,10 @@
 use crate::{
     action::{Action, ActionWrapper},
-    agent::{self, find_chain_header},
+    agent::{
+        self,
+        chain_store::{ChainStoreQueryOptions, ChainStoreQueryResult},
+        find_chain_header,
+    },
     context::Context,
     entry::CanPublish,
     nucleus::ribosome::callback::{
@@ -18,7 +22,7 @@ use holochain_core_types::{
     validation::{ValidationPackage, ValidationPackageDefinition::*},
 };
 use snowflake;
-use std::{convert::TryInto, pin::Pin, sync::Arc, thread, vec::Vec};
+use std::{convert::TryInto, pin::Pin, str::FromStr, sync::Arc, thread, vec::Vec};
 use crate::state::StateWrapper;
 
 pub async fn build_validation_package<'a>(
@@ -65,6 +69,10 @@ pub async fn build_validation_package<'a>(
         EntryType::AgentId => {
             // FIXME
         }
+
+        EntryType::Warrant => {
+            // Warrants are validated natively and only need the header
+        }
         _ => {
             return Err(HolochainError::ValidationFailed(format!(
                 "Attempted to validate system entry type {:?}",
@@ -157,6 +165,57 @@ pub async fn build_validation_package<'a>(
                                 package.source_chain_headers = Some(headers);
                                 package
                             }
+                            ChainEntriesOfTypes(entry_type_names) => {
+                                let mut package = ValidationPackage::only_header(entry_header);
+                                let headers = query_chain_headers_before_header(
+                                    &context,
+                                    &package.chain_header,
+                                    &entry_type_names,
+                                    ChainStoreQueryOptions::default(),
+                                )?;
+                                package.source_chain_entries =
+                                    Some(public_chain_entries_from_headers(&context, &headers));
+                                package
+                            }
+                            LastHeaders(count) => {
+                                let mut package = ValidationPackage::only_header(entry_header);
+                                package.source_chain_headers =
+                                    Some(last_headers_before_header(
+                                        &context,
+                                        &package.chain_header,
+                                        count,
+                                    )?);
+                                package
+                            }
+                            TimeWindow(period) => {
+                                let mut package = ValidationPackage::only_header(entry_header);
+                                let since = (package.chain_header.timestamp() - period)?;
+                                let headers = query_chain_headers_before_header(
+                                    &context,
+                                    &package.chain_header,
+                                    &[],
+                                    ChainStoreQueryOptions {
+                                        since: Some(since),
+                                        ..Default::default()
+                                    },
+                                )?;
+                                package.source_chain_entries =
+                                    Some(public_chain_entries_from_headers(&context, &headers));
+                                package.source_chain_headers = Some(headers);
+                                package
+                            }
+                            SinceLastEntryOfType(entry_type_name) => {
+                                let mut package = ValidationPackage::only_header(entry_header);
+                                let headers = chain_headers_since_last_entry_of_type(
+                                    &context,
+                                    &package.chain_header,
+                                    &entry_type_name,
+                                )?;
+                                package.source_chain_entries =
+                                    Some(public_chain_entries_from_headers(&context, &headers));
+                                package.source_chain_headers = Some(headers);
+                                package
+                            }
                             Custom(string) => {
                                 let mut package = ValidationPackage::only_header(entry_header);
                                 package.custom = Some(string);
@@ -218,6 +277,84 @@ fn all_chain_headers_before_header(
     chain.iter(&Some(header.clone())).skip(1).collect()
 }
 
+// query the chain before the given header (which is not included), so that only the
+// selected headers get loaded instead of the whole chain
+fn query_chain_headers_before_header(
+    context: &Arc<Context>,
+    header: &ChainHeader,
+    entry_type_names: &[String],
+    options: ChainStoreQueryOptions,
+) -> Result<Vec<ChainHeader>, HolochainError> {
+    let chain = context.state().unwrap().agent().chain_store();
+    let previous_header = chain.iter(&Some(header.clone())).nth(1);
+    if previous_header.is_none() {
+        return Ok(Vec::new());
+    }
+    let names: Vec<&str> = entry_type_names.iter().map(AsRef::as_ref).collect();
+    match chain.query(
+        &previous_header,
+        names.as_slice(),
+        ChainStoreQueryOptions {
+            headers: true,
+            ..options
+        },
+    ) {
+        Ok(ChainStoreQueryResult::Headers(headers)) => Ok(headers),
+        Ok(ChainStoreQueryResult::Addresses(_)) => unreachable!(),
+        Err(code) => Err(HolochainError::ErrorGeneric(format!(
+            "Could not query source chain for validation package: {}",
+            code.to_string()
+        ))),
+    }
+}
+
+/// The given number of headers preceding the given header, latest first.
+/// (A query limit of 0 means no limit, so 0 headers have to be handled here.)
+fn last_headers_before_header(
+    context: &Arc<Context>,
+    header: &ChainHeader,
+    count: usize,
+) -> Result<Vec<ChainHeader>, HolochainError> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    query_chain_headers_before_header(
+        context,
+        header,
+        &[],
+        ChainStoreQueryOptions {
+            limit: count,
+            ..Default::default()
+        },
+    )
+}
+
+/// All headers before the given one, back to and including the last one of the given type.
+fn chain_headers_since_last_entry_of_type(
+    context: &Arc<Context>,
+    header: &ChainHeader,
+    entry_type_name: &str,
+) -> Result<Vec<ChainHeader>, HolochainError> {
+    let entry_type = EntryType::from_str(entry_type_name).map_err(|_| {
+        HolochainError::ErrorGeneric(format!("Unknown entry type '{}'", entry_type_name))
+    })?;
+    let chain = context.state().unwrap().agent().chain_store();
+    let previous_header = chain.iter(&Some(header.clone())).nth(1);
+    let until_header = chain
+        .iter_type(&previous_header, &entry_type)
+        .next()
+        .and_then(|last_of_type| last_of_type.link());
+    query_chain_headers_before_header(
+        context,
+        header,
+        &[],
+        ChainStoreQueryOptions {
+            until_header,
+            ..Default::default()
+        },
+    )
+}
+
 /// ValidationPackageFuture resolves to the ValidationPackage or a HolochainError.
 pub struct ValidationPackageFuture {
     context: Arc<Context>,
@@ -381,6 +518,115 @@ mod tests {
         assert_eq!(maybe_validation_package.unwrap(), expected);
     }
 
+    #[test]
+    fn test_building_validation_package_entries_of_types() {
+        let (_instance, context) = instance(None);
+
+        commit(test_entry_package_entry(), &context);
+        commit(test_entry_package_chain_full(), &context);
+
+        // commit entry to build validation package for
+        let chain_header = commit(test_entry_package_entries_of_types(), &context);
+
+        let maybe_validation_package = context.block_on(build_validation_package(
+            &test_entry_package_entries_of_types(),
+            context.clone(),
+            &vec![],
+        ));
+        assert!(maybe_validation_package.is_ok());
+
+        let expected = ValidationPackage {
+            chain_header,
+            source_chain_entries: Some(vec![test_entry_package_entry()]),
+            source_chain_headers: None,
+            custom: None,
+        };
+
+        assert_eq!(maybe_validation_package.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_building_validation_package_last_headers() {
+        let (_instance, context) = instance(None);
+
+        commit(test_entry_package_entry(), &context);
+        commit(test_entry_package_chain_full(), &context);
+
+        // commit entry to build validation package for
+        let chain_header = commit(test_entry_package_last_headers(), &context);
+
+        let maybe_validation_package = context.block_on(build_validation_package(
+            &test_entry_package_last_headers(),
+            context.clone(),
+            &vec![],
+        ));
+        assert!(maybe_validation_package.is_ok());
+
+        let expected = ValidationPackage {
+            chain_header: chain_header.clone(),
+            source_chain_entries: None,
+            source_chain_headers: Some(
+                all_chain_headers_before_header(&context, &chain_header)
+                    .into_iter()
+                    .take(2)
+                    .collect(),
+            ),
+            custom: None,
+        };
+
+        assert_eq!(maybe_validation_package.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_last_headers_before_header() {
+        let (_instance, context) = instance(None);
+
+        commit(test_entry_package_entry(), &context);
+        let chain_header = commit(test_entry_package_last_headers(), &context);
+        let all_headers = all_chain_headers_before_header(&context, &chain_header);
+        assert!(all_headers.len() > 2);
+
+        assert_eq!(
+            last_headers_before_header(&context, &chain_header, 0).unwrap(),
+            Vec::new()
+        );
+        assert_eq!(
+            last_headers_before_header(&context, &chain_header, 2).unwrap(),
+            all_headers.into_iter().take(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_building_validation_package_since_last_entry_of_type() {
+        let (_instance, context) = instance(None);
+
+        commit(test_entry_package_chain_entries(), &context);
+        let last_of_type = commit(test_entry_package_entry(), &context);
+        let later = commit(test_entry_package_chain_full(), &context);
+
+        // commit entry to build validation package for
+        let chain_header = commit(test_entry_package_since_last_entry_of_type(), &context);
+
+        let maybe_validation_package = context.block_on(build_validation_package(
+            &test_entry_package_since_last_entry_of_type(),
+            context.clone(),
+            &vec![],
+        ));
+        assert!(maybe_validation_package.is_ok());
+
+        let expected = ValidationPackage {
+            chain_header,
+            source_chain_entries: Some(vec![
+                test_entry_package_chain_full(),
+                test_entry_package_entry(),
+            ]),
+            source_chain_headers: Some(vec![later, last_of_type]),
+            custom: None,
+        };
+
+        assert_eq!(maybe_validation_package.unwrap(), expected);
+    }
+
     // test can make validation package with empty chain
     #[test]
     fn test_all_chain_headers_before_header_empty_chain() {
@@ -0,0 +1,64 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    context::Context,
+    instance::dispatch_action,
+    network::entry_with_header::EntryWithHeader,
+    nucleus::{
+        state::{CachedValidationResult, ValidationCacheKey},
+        validation::{ValidationError, ValidationResult},
+    },
+};
+use holochain_persistence_api::cas::content::AddressableContent;
+use std::sync::Arc;
+
+/// The key under which the outcome of validating the given entry and header is cached.
+/// Returns None as long as the DNA address is not known.
+pub fn validation_cache_key(
+    entry_with_header: &EntryWithHeader,
+    context: &Arc<Context>,
+) -> Option<ValidationCacheKey> {
+    let dna_address = context.state()?.network().dna_address.clone()?;
+    Some(ValidationCacheKey::new(
+        entry_with_header.entry.address(),
+        entry_with_header.header.address(),
+        dna_address,
+    ))
+}
+
+pub fn cached_validation_result(
+    key: &ValidationCacheKey,
+    context: &Arc<Context>,
+) -> Option<CachedValidationResult> {
+    context.state()?.nucleus().cached_validation_result(key)
+}
+
+/// Dispatches a CacheValidationResult action for conclusive validation results.
+/// Results that could change when validating again later are ignored.
+pub fn cache_validation_result(
+    key: ValidationCacheKey,
+    result: &ValidationResult,
+    context: &Arc<Context>,
+) {
+    let cached_result = match result {
+        Ok(()) => CachedValidationResult::Valid,
+        Err(ValidationError::Fail(reason)) => CachedValidationResult::Invalid(reason.clone()),
+        Err(_) => return,
+    };
+    dispatch_action(
+        context.action_channel(),
+        ActionWrapper::new(Action::CacheValidationResult((key, cached_result))),
+    );
+}
+
+/// Marks a cached validation outcome as used again,
+/// so that it doesn't get evicted before outcomes that nobody asked for.
+pub fn refresh_cached_validation_result(
+    key: ValidationCacheKey,
+    cached_result: CachedValidationResult,
+    context: &Arc<Context>,
+) {
+    dispatch_action(
+        context.action_channel(),
+        ActionWrapper::new(Action::CacheValidationResult((key, cached_result))),
+    );
+}
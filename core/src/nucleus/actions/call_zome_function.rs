@@ -30,7 +30,7 @@ use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
 use snowflake::ProcessUniqueId;
 use std::{pin::Pin, sync::Arc, thread};
 
-#[derive(Clone, Debug, PartialEq, Hash, Serialize)]
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ExecuteZomeFnResponse {
     call: ZomeFnCall,
     result: ZomeFnResult,
@@ -1,5 +1,6 @@
 pub mod add_pending_validation;
 pub mod build_validation_package;
+pub mod cache_validation_result;
 pub mod call_init;
 pub mod call_zome_function;
 pub mod get_entry;
@@ -79,6 +80,21 @@ pub mod tests {
             .unwrap()
             .entry_types
             .insert("package_chain_full".into(), EntryTypeDef::new());
+        dna.zomes
+            .get_mut("test_zome")
+            .unwrap()
+            .entry_types
+            .insert("package_entries_of_types".into(), EntryTypeDef::new());
+        dna.zomes
+            .get_mut("test_zome")
+            .unwrap()
+            .entry_types
+            .insert("package_last_headers".into(), EntryTypeDef::new());
+        dna.zomes
+            .get_mut("test_zome")
+            .unwrap()
+            .entry_types
+            .insert("package_since_last_entry_of_type".into(), EntryTypeDef::new());
 
         dna
     }
@@ -115,6 +131,21 @@ pub mod tests {
         Entry::App("package_chain_full".into(), "test value".into())
     }
 
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn test_entry_package_entries_of_types() -> Entry {
+        Entry::App("package_entries_of_types".into(), "test value".into())
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn test_entry_package_last_headers() -> Entry {
+        Entry::App("package_last_headers".into(), "test value".into())
+    }
+
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn test_entry_package_since_last_entry_of_type() -> Entry {
+        Entry::App("package_since_last_entry_of_type".into(), "test value".into())
+    }
+
     #[cfg_attr(tarpaulin, skip)]
     pub fn commit(entry: Entry, context: &Arc<Context>) -> ChainHeader {
         let chain = context.state().unwrap().agent().chain_store();
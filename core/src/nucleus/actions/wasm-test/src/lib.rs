@@ -136,6 +136,81 @@ define_zome! {
                 hdk::ValidationPackageDefinition::ChainFull
             },
 
+            validation: | validation_data: hdk::EntryValidationData<TestEntryType>| {
+                 match validation_data
+                {
+                   EntryValidationData::Create{entry:test_entry,validation_data:_} =>
+                   {
+
+                        (test_entry.stuff != "FAIL")
+                        .ok_or_else(|| "FAIL content is not allowed".to_string())
+                   }
+                   _ =>{
+                       Err("Failed to validate with wrong entry type".to_string())
+                   }
+                }
+            }
+        ),
+
+        entry!(
+            name: "package_entries_of_types",
+            description: "asdfda",
+            sharing: Sharing::Public,
+
+            validation_package: || {
+                hdk::ValidationPackageDefinition::ChainEntriesOfTypes(vec!["package_entry".to_string()])
+            },
+
+            validation: | validation_data: hdk::EntryValidationData<TestEntryType>| {
+                 match validation_data
+                {
+                   EntryValidationData::Create{entry:test_entry,validation_data:_} =>
+                   {
+
+                        (test_entry.stuff != "FAIL")
+                        .ok_or_else(|| "FAIL content is not allowed".to_string())
+                   }
+                   _ =>{
+                       Err("Failed to validate with wrong entry type".to_string())
+                   }
+                }
+            }
+        ),
+
+        entry!(
+            name: "package_last_headers",
+            description: "asdfda",
+            sharing: Sharing::Public,
+
+            validation_package: || {
+                hdk::ValidationPackageDefinition::LastHeaders(2)
+            },
+
+            validation: | validation_data: hdk::EntryValidationData<TestEntryType>| {
+                 match validation_data
+                {
+                   EntryValidationData::Create{entry:test_entry,validation_data:_} =>
+                   {
+
+                        (test_entry.stuff != "FAIL")
+                        .ok_or_else(|| "FAIL content is not allowed".to_string())
+                   }
+                   _ =>{
+                       Err("Failed to validate with wrong entry type".to_string())
+                   }
+                }
+            }
+        ),
+
+        entry!(
+            name: "package_since_last_entry_of_type",
+            description: "asdfda",
+            sharing: Sharing::Public,
+
+            validation_package: || {
+                hdk::ValidationPackageDefinition::SinceLastEntryOfType("package_entry".to_string())
+            },
+
             validation: | validation_data: hdk::EntryValidationData<TestEntryType>| {
                  match validation_data
                 {
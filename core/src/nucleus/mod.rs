@@ -25,7 +25,7 @@ use snowflake;
 use std::sync::Arc;
 
 /// Struct holding data for requesting the execution of a Zome function (ExecutionZomeFunction Action)
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ZomeFnCall {
     id: snowflake::ProcessUniqueId,
     pub zome_name: String,
@@ -4,10 +4,14 @@ use crate::{
     state::State,
 };
 use holochain_persistence_api::cas::content::AddressableContent;
+use std::{sync::Arc, time::SystemTime};
 
 /// Reduce AddPendingValidation Action.
 /// Inserts boxed EntryWithHeader and dependencies into state, referenced with
 /// the entry's address.
+/// If the entry was pending already and its retry was due, this counts as another
+/// failed attempt and the next retry gets scheduled with a longer delay.
+/// Retries triggered by an arriving dependency before that keep the attempts and schedule.
 #[allow(unknown_lints)]
 #[allow(clippy::needless_pass_by_value)]
 pub fn reduce_add_pending_validation(
@@ -19,10 +23,24 @@ pub fn reduce_add_pending_validation(
     let pending = unwrap_to!(action => Action::AddPendingValidation);
     let address = pending.entry_with_header.entry.address();
     let workflow = pending.workflow.clone();
-    state.pending_validations.insert(
-        PendingValidationKey::new(address, workflow),
-        pending.clone(),
-    );
+    let key = PendingValidationKey::new(address, workflow);
+    let now = SystemTime::now();
+    let mut pending = (**pending).clone();
+    match state.pending_validations.get(&key) {
+        // Retried early because a dependency arrived, so this was no scheduled attempt
+        Some(previous) if !previous.is_due(now) => {
+            pending.attempts = previous.attempts;
+            pending.next_retry = previous.next_retry;
+        }
+        previous => {
+            let attempts = previous
+                .map(|previous| previous.attempts)
+                .unwrap_or(0)
+                .saturating_add(1);
+            pending.schedule_retry(attempts, now);
+        }
+    }
+    state.pending_validations.insert(key, Arc::new(pending));
 }
 
 #[cfg(test)]
@@ -37,7 +55,8 @@ pub mod tests {
     };
     use holochain_core_types::{chain_header::test_chain_header, entry::Entry};
     use holochain_json_api::json::RawString;
-    use std::sync::Arc;
+    use holochain_persistence_api::cas::content::Address;
+    use std::time::Duration;
 
     #[test]
     fn test_reduce_add_pending_validation() {
@@ -52,20 +71,55 @@ pub mod tests {
         };
 
         let action_wrapper = ActionWrapper::new(Action::AddPendingValidation(Arc::new(
-            PendingValidationStruct {
+            PendingValidationStruct::new(
                 entry_with_header,
-                dependencies: Vec::new(),
-                workflow: ValidatingWorkflow::HoldEntry,
-            },
+                Vec::new(),
+                ValidatingWorkflow::HoldEntry,
+            ),
         )));
 
         reduce_add_pending_validation(&mut state, &root_state, &action_wrapper);
 
-        assert!(state
-            .pending_validations
-            .contains_key(&PendingValidationKey::new(
-                entry.address(),
-                ValidatingWorkflow::HoldEntry
-            )));
+        let key = PendingValidationKey::new(entry.address(), ValidatingWorkflow::HoldEntry);
+        assert_eq!(state.pending_validations.get(&key).unwrap().attempts, 1);
+
+        // adding it again once its retry is due counts as another attempt and backs off further
+        let first_retry = state.pending_validations.get(&key).unwrap().next_retry;
+        let mut due = (**state.pending_validations.get(&key).unwrap()).clone();
+        due.next_retry = Some(SystemTime::now() - Duration::from_secs(1));
+        state.pending_validations.insert(key.clone(), Arc::new(due));
+        reduce_add_pending_validation(&mut state, &root_state, &action_wrapper);
+        let pending = state.pending_validations.get(&key).unwrap();
+        assert_eq!(pending.attempts, 2);
+        assert!(pending.next_retry > first_retry);
+    }
+
+    #[test]
+    fn test_reduce_add_pending_validation_woken_by_dependency() {
+        let context = test_context("jimmy", None);
+        let mut state = test_nucleus_state();
+        let root_state = test_store(context);
+
+        let entry = Entry::App("package_entry".into(), RawString::from("test value").into());
+        let action_wrapper = ActionWrapper::new(Action::AddPendingValidation(Arc::new(
+            PendingValidationStruct::new(
+                EntryWithHeader {
+                    entry: entry.clone(),
+                    header: test_chain_header(),
+                },
+                vec![Address::from("still missing")],
+                ValidatingWorkflow::HoldEntry,
+            ),
+        )));
+
+        reduce_add_pending_validation(&mut state, &root_state, &action_wrapper);
+        let key = PendingValidationKey::new(entry.address(), ValidatingWorkflow::HoldEntry);
+        let first_retry = state.pending_validations.get(&key).unwrap().next_retry;
+
+        // still pending after an early retry: neither an attempt nor a new backoff
+        reduce_add_pending_validation(&mut state, &root_state, &action_wrapper);
+        let pending = state.pending_validations.get(&key).unwrap();
+        assert_eq!(pending.attempts, 1);
+        assert_eq!(pending.next_retry, first_retry);
     }
 }
@@ -0,0 +1,50 @@
+use crate::{
+    action::{Action, ActionWrapper},
+    nucleus::state::NucleusState,
+    state::State,
+};
+
+/// Reduce CacheValidationResult Action.
+/// Stores the outcome of a finished validation in the validation cache,
+/// referenced by entry, header and DNA address.
+#[allow(unknown_lints)]
+#[allow(clippy::needless_pass_by_value)]
+pub fn reduce_cache_validation_result(
+    state: &mut NucleusState,
+    _root_state: &State,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let (key, result) = unwrap_to!(action => Action::CacheValidationResult).clone();
+    state.validation_cache.insert(key, result);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        instance::tests::test_context,
+        nucleus::state::{tests::test_nucleus_state, CachedValidationResult, ValidationCacheKey},
+        state::test_store,
+    };
+
+    #[test]
+    fn test_reduce_cache_validation_result() {
+        let context = test_context("jimmy", None);
+        let mut state = test_nucleus_state();
+        let root_state = test_store(context);
+
+        let key = ValidationCacheKey::new("entry".into(), "header".into(), "dna".into());
+        let action_wrapper = ActionWrapper::new(Action::CacheValidationResult((
+            key.clone(),
+            CachedValidationResult::Valid,
+        )));
+
+        reduce_cache_validation_result(&mut state, &root_state, &action_wrapper);
+
+        assert_eq!(
+            state.cached_validation_result(&key),
+            Some(CachedValidationResult::Valid)
+        );
+    }
+}
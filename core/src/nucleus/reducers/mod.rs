@@ -1,4 +1,5 @@
 mod add_pending_validation;
+mod cache_validation_result;
 pub mod init_application;
 mod remove_pending_validation;
 pub mod return_initialization_result;
@@ -11,6 +12,7 @@ use crate::{
     nucleus::{
         reducers::{
             add_pending_validation::reduce_add_pending_validation,
+            cache_validation_result::reduce_cache_validation_result,
             init_application::reduce_initialize_chain,
             remove_pending_validation::reduce_remove_pending_validation,
             return_initialization_result::reduce_return_initialization_result,
@@ -32,6 +34,7 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NucleusReduceFn> {
     match action_wrapper.action() {
         Action::AddPendingValidation(_) => Some(reduce_add_pending_validation),
         Action::RemovePendingValidation(_) => Some(reduce_remove_pending_validation),
+        Action::CacheValidationResult(_) => Some(reduce_cache_validation_result),
         Action::ReturnInitializationResult(_) => Some(reduce_return_initialization_result),
         Action::InitializeChain(_) => Some(reduce_initialize_chain),
         Action::ReturnZomeFunctionResult(_) => Some(reduce_return_zome_function_result),
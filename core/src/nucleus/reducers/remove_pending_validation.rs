@@ -53,11 +53,11 @@ pub mod tests {
         };
 
         let action_wrapper = ActionWrapper::new(Action::AddPendingValidation(Arc::new(
-            PendingValidationStruct {
+            PendingValidationStruct::new(
                 entry_with_header,
-                dependencies: Vec::new(),
-                workflow: ValidatingWorkflow::HoldEntry,
-            },
+                Vec::new(),
+                ValidatingWorkflow::HoldEntry,
+            ),
         )));
 
         reduce_add_pending_validation(&mut nucleus_state, &state, &action_wrapper);
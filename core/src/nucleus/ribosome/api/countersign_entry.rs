@@ -0,0 +1,40 @@
+use crate::{
+    nucleus::ribosome::{api::ZomeApiResult, Runtime},
+    workflows::countersign_entry::countersign_entry,
+};
+use holochain_core_types::error::HolochainError;
+use holochain_wasm_utils::api_serialization::{
+    commit_entry::CommitEntryResult, countersign_entry::CountersignEntryArgs,
+};
+use std::convert::TryFrom;
+use wasmi::{RuntimeArgs, RuntimeValue};
+
+/// ZomeApiFunction::CountersignEntry function code
+/// args: [0] encoded MemoryAllocation as u64
+/// Expected complex argument: CountersignEntryArgs
+/// Returns an HcApiReturnCode as I64
+pub fn invoke_countersign_entry(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult {
+    let context = runtime.context()?;
+    // deserialize args
+    let args_str = runtime.load_json_string_from_args(&args);
+    let countersign_args = match CountersignEntryArgs::try_from(args_str.clone()) {
+        Ok(input) => input,
+        Err(error) => {
+            log_error!(context,
+                "zome: invoke_countersign_entry failed to deserialize arguments: {:?} with error {:?}",
+                args_str, error
+            );
+            return ribosome_error_code!(ArgumentDeserializationFailed);
+        }
+    };
+
+    // Wait for all counterparties to sign and the entry to be committed
+    let result: Result<CommitEntryResult, HolochainError> = context.block_on(countersign_entry(
+        countersign_args.entry,
+        countersign_args.counterparties,
+        countersign_args.timeout,
+        context.clone(),
+    ));
+
+    runtime.store_result(result)
+}
@@ -166,13 +166,13 @@ pub mod tests {
         );
         let expected_1 = JsonString::from_json(
             &(format!(
-                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}}]}}","error":"null"}}"#,
+                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}}]}}","error":"null"}}"#,
                 entry_addresses[1], "test-tag", entry_addresses[2], "test-tag",
             ) + "\u{0}"),
         );
         let expected_2 = JsonString::from_json(
             &(format!(
-               r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}}]}}","error":"null"}}"#,
+               r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}}]}}","error":"null"}}"#,
                 entry_addresses[2], "test-tag", entry_addresses[1], "test-tag",
             ) + "\u{0}"),
         );
@@ -289,13 +289,13 @@ pub mod tests {
         );
         let expected_1 = JsonString::from_json(
             &(format!(
-                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}}]}}","error":"null"}}"#,
+                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}}]}}","error":"null"}}"#,
                 entry_addresses[1], "test-tag1", entry_addresses[2], "test-tag2",
             ) + "\u{0}"),
         );
         let expected_2 = JsonString::from_json(
             &(format!(
-               r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}}]}}","error":"null"}}"#,
+               r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}}]}}","error":"null"}}"#,
                 entry_addresses[2], "test-tag2", entry_addresses[1], "test-tag1",
             ) + "\u{0}"),
         );
@@ -337,7 +337,7 @@ pub mod tests {
         );
         let expected = JsonString::from_json(
             &(format!(
-                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}}]}}","error":"null"}}"#,
+                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}}]}}","error":"null"}}"#,
                 entry_addresses[1], "test-tag1",
             ) + "\u{0}"),
         );
@@ -373,7 +373,7 @@ pub mod tests {
         );
         let expected = JsonString::from_json(
             &(format!(
-                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}}]}}","error":"null"}}"#,
+                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}}]}}","error":"null"}}"#,
                 entry_addresses[1], "test-tag",
             ) + "\u{0}"),
         );
@@ -409,7 +409,7 @@ pub mod tests {
         );
         let expected = JsonString::from_json(
             &(format!(
-                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\"}}]}}","error":"null"}}"#,
+                r#"{{"ok":true,"value":"{{\"links\":[{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}},{{\"address\":\"{}\",\"headers\":[],\"tag\":\"{}\",\"status\":\"live\",\"warrants\":[]}}]}}","error":"null"}}"#,
                 entry_addresses[1], "test-tag1", entry_addresses[1], "test-tag2",
             ) + "\u{0}"),
         );
@@ -3,6 +3,7 @@
 
 pub mod call;
 pub mod commit;
+pub mod countersign_entry;
 pub mod debug;
 pub mod emit_signal;
 pub mod entry_address;
@@ -31,6 +32,7 @@ use crate::nucleus::ribosome::{
         call::invoke_call,
         capabilities::{invoke_commit_capability_claim, invoke_commit_capability_grant},
         commit::invoke_commit_app_entry,
+        countersign_entry::invoke_countersign_entry,
         crypto::invoke_crypto,
         debug::invoke_debug,
         emit_signal::invoke_emit_signal,
@@ -154,6 +156,10 @@ link_zome_api! {
     
     ///send a meta
     "hc_meta",Meta,invoke_meta;
+
+    /// Commit an app entry to the source chains of this and other agents,
+    /// each signing it, after negotiating with them via direct messages
+    "hc_countersign_entry", CountersignEntry, invoke_countersign_entry;
 }
 
 #[cfg(test)]
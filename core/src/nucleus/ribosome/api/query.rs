@@ -84,6 +84,7 @@ pub fn invoke_query(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult
                     start: query.options.start,
                     limit: query.options.limit,
                     headers: query.options.headers,
+                    ..Default::default()
                 },
             )
         }
@@ -96,6 +97,7 @@ pub fn invoke_query(runtime: &mut Runtime, args: &RuntimeArgs) -> ZomeApiResult
                     start: query.options.start,
                     limit: query.options.limit,
                     headers: query.options.headers,
+                    ..Default::default()
                 },
             )
         }
@@ -15,7 +15,11 @@ use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
 };
 use snowflake;
-use std::{collections::HashMap, convert::TryFrom, fmt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fmt,
+};
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, DefaultJson)]
 pub enum NucleusStatus {
@@ -90,6 +94,176 @@ impl PendingValidationKey {
     }
 }
 
+/// Identifies the outcome of validating an entry.
+/// Validating the same entry with the same header against the same DNA always has the
+/// same outcome, so it only needs to happen once.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ValidationCacheKey {
+    pub entry_address: Address,
+    pub header_address: Address,
+    pub dna_address: Address,
+}
+
+impl ValidationCacheKey {
+    pub fn new(entry_address: Address, header_address: Address, dna_address: Address) -> Self {
+        ValidationCacheKey {
+            entry_address,
+            header_address,
+            dna_address,
+        }
+    }
+}
+
+impl Serialize for ValidationCacheKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!(
+            "{}__{}__{}",
+            self.entry_address, self.header_address, self.dna_address
+        ))
+    }
+}
+
+struct ValidationCacheKeyStringVisitor;
+impl<'de> Visitor<'de> for ValidationCacheKeyStringVisitor {
+    type Value = ValidationCacheKey;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a ValidationCacheKey in the format '<entry>__<header>__<dna>'")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        let parts: Vec<&str> = value.split("__").collect();
+        match parts.as_slice() {
+            [entry_address, header_address, dna_address] => Ok(ValidationCacheKey::new(
+                entry_address.to_string().into(),
+                header_address.to_string().into(),
+                dna_address.to_string().into(),
+            )),
+            _ => Err(Error::custom(format!(
+                "Invalid ValidationCacheKey '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ValidationCacheKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(ValidationCacheKeyStringVisitor)
+    }
+}
+
+/// A conclusive validation outcome as it is kept in the validation cache.
+/// Outcomes that might change on a later try (i.e. missing dependencies) are never cached.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum CachedValidationResult {
+    Valid,
+    Invalid(String),
+}
+
+/// How many validation outcomes the validation cache keeps at most.
+pub const VALIDATION_CACHE_CAPACITY: usize = 10_000;
+
+/// The validation cache, bounded to its `capacity` most recently used outcomes.
+/// An outcome counts as used when it gets cached or read back by the hold entry workflow.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationCache {
+    capacity: usize,
+    results: HashMap<ValidationCacheKey, (CachedValidationResult, u64)>,
+    // Keys by the tick of their last use, least recently used first
+    recency: BTreeMap<u64, ValidationCacheKey>,
+    next_tick: u64,
+}
+
+impl Default for ValidationCache {
+    fn default() -> Self {
+        ValidationCache::with_capacity(VALIDATION_CACHE_CAPACITY)
+    }
+}
+
+impl ValidationCache {
+    pub fn new() -> Self {
+        ValidationCache::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ValidationCache {
+            capacity,
+            results: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_tick: 0,
+        }
+    }
+
+    pub fn get(&self, key: &ValidationCacheKey) -> Option<&CachedValidationResult> {
+        self.results.get(key).map(|(result, _)| result)
+    }
+
+    /// Stores the outcome as the most recently used one and evicts the least
+    /// recently used outcomes that don't fit anymore.
+    pub fn insert(&mut self, key: ValidationCacheKey, result: CachedValidationResult) {
+        if let Some((_, tick)) = self.results.remove(&key) {
+            self.recency.remove(&tick);
+        }
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        self.recency.insert(tick, key.clone());
+        self.results.insert(key, (result, tick));
+
+        while self.results.len() > self.capacity {
+            let oldest_tick = match self.recency.keys().next() {
+                Some(tick) => *tick,
+                None => break,
+            };
+            if let Some(oldest_key) = self.recency.remove(&oldest_tick) {
+                self.results.remove(&oldest_key);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ValidationCacheKey, &CachedValidationResult)> {
+        self.results.iter().map(|(key, (result, _))| (key, result))
+    }
+}
+
+/// Snapshots only hold the outcomes, so after loading them
+/// their recency is just the order they got read in.
+impl From<HashMap<ValidationCacheKey, CachedValidationResult>> for ValidationCache {
+    fn from(results: HashMap<ValidationCacheKey, CachedValidationResult>) -> Self {
+        let mut cache = ValidationCache::new();
+        for (key, result) in results {
+            cache.insert(key, result);
+        }
+        cache
+    }
+}
+
+impl From<&ValidationCache> for HashMap<ValidationCacheKey, CachedValidationResult> {
+    fn from(cache: &ValidationCache) -> Self {
+        cache
+            .iter()
+            .map(|(key, result)| (key.clone(), result.clone()))
+            .collect()
+    }
+}
+
 /// The state-slice for the Nucleus.
 /// Holds the dynamic parts of the DNA, i.e. zome calls and validation requests.
 #[derive(Clone, Debug, PartialEq, Default)]
@@ -97,6 +271,7 @@ pub struct NucleusState {
     // Persisted fields:
     pub status: NucleusStatus,
     pub pending_validations: HashMap<PendingValidationKey, PendingValidation>,
+    pub validation_cache: ValidationCache,
 
     // Transient fields:
     pub dna: Option<Dna>, //DNA is transient here because it is stored in the chain and gets
@@ -121,6 +296,7 @@ impl NucleusState {
             validation_results: HashMap::new(),
             validation_packages: HashMap::new(),
             pending_validations: HashMap::new(),
+            validation_cache: ValidationCache::new(),
         }
     }
 
@@ -154,6 +330,13 @@ impl NucleusState {
         }
     }
 
+    pub fn cached_validation_result(
+        &self,
+        key: &ValidationCacheKey,
+    ) -> Option<CachedValidationResult> {
+        self.validation_cache.get(key).cloned()
+    }
+
     // Getters
     pub fn dna(&self) -> Option<Dna> {
         self.dna.clone()
@@ -167,6 +350,8 @@ impl NucleusState {
 pub struct NucleusStateSnapshot {
     pub status: NucleusStatus,
     pub pending_validations: HashMap<PendingValidationKey, PendingValidation>,
+    #[serde(default)]
+    pub validation_cache: HashMap<ValidationCacheKey, CachedValidationResult>,
 }
 
 impl From<&StateWrapper> for NucleusStateSnapshot {
@@ -174,6 +359,7 @@ impl From<&StateWrapper> for NucleusStateSnapshot {
         NucleusStateSnapshot {
             status: state.nucleus().status(),
             pending_validations: state.nucleus().pending_validations.clone(),
+            validation_cache: (&state.nucleus().validation_cache).into(),
         }
     }
 }
@@ -187,6 +373,7 @@ impl From<NucleusStateSnapshot> for NucleusState {
             validation_results: HashMap::new(),
             validation_packages: HashMap::new(),
             pending_validations: snapshot.pending_validations,
+            validation_cache: snapshot.validation_cache.into(),
         }
     }
 }
@@ -209,11 +396,60 @@ impl AddressableContent for NucleusStateSnapshot {
 #[cfg(test)]
 pub mod tests {
 
-    use super::NucleusState;
+    use super::*;
 
     /// dummy nucleus state
     pub fn test_nucleus_state() -> NucleusState {
         NucleusState::new()
     }
 
+    #[test]
+    fn validation_cache_survives_snapshot_round_trip() {
+        let mut state = test_nucleus_state();
+        let key = ValidationCacheKey::new("entry".into(), "header".into(), "dna".into());
+        state.validation_cache.insert(
+            key.clone(),
+            CachedValidationResult::Invalid("FAIL".to_string()),
+        );
+
+        let snapshot = NucleusStateSnapshot {
+            status: state.status(),
+            pending_validations: state.pending_validations.clone(),
+            validation_cache: (&state.validation_cache).into(),
+        };
+        let restored = NucleusStateSnapshot::try_from_content(&snapshot.content())
+            .expect("Could not restore snapshot");
+        let restored_state = NucleusState::from(restored);
+
+        assert_eq!(
+            restored_state.cached_validation_result(&key),
+            Some(CachedValidationResult::Invalid("FAIL".to_string()))
+        );
+    }
+
+    #[test]
+    fn validation_cache_evicts_least_recently_used_outcome() {
+        let mut cache = ValidationCache::with_capacity(2);
+        let key = |name: &str| ValidationCacheKey::new(name.into(), "header".into(), "dna".into());
+
+        cache.insert(key("first"), CachedValidationResult::Valid);
+        cache.insert(key("second"), CachedValidationResult::Valid);
+        // Using the first outcome again makes the second one the least recently used
+        cache.insert(key("first"), CachedValidationResult::Valid);
+        cache.insert(key("third"), CachedValidationResult::Valid);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key("first")).is_some());
+        assert!(cache.get(&key("second")).is_none());
+        assert!(cache.get(&key("third")).is_some());
+    }
+
+    #[test]
+    fn snapshot_without_validation_cache_can_be_loaded() {
+        let snapshot = NucleusStateSnapshot::try_from(JsonString::from_json(
+            r#"{"status":"New","pending_validations":{}}"#,
+        ))
+        .expect("Could not load old snapshot");
+        assert!(snapshot.validation_cache.is_empty());
+    }
 }
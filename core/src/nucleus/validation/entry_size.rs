@@ -0,0 +1,43 @@
+use crate::nucleus::validation::{ValidationError, ValidationResult};
+use holochain_core_types::{dna::dht::DhtConfig, entry::Entry};
+
+use holochain_persistence_api::cas::content::AddressableContent;
+
+/// Fails entries whose serialized content is bigger than the maximum entry size
+/// the DNA sets.
+pub fn validate_entry_size(entry: &Entry, dht_config: &DhtConfig) -> ValidationResult {
+    match dht_config.max_entry_size {
+        Some(max_entry_size) => {
+            let size = String::from(entry.content()).len();
+            if size > max_entry_size {
+                Err(ValidationError::Fail(format!(
+                    "Entry size of {} bytes exceeds the maximum of {} bytes",
+                    size, max_entry_size
+                )))
+            } else {
+                Ok(())
+            }
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::entry::test_entry;
+
+    #[test]
+    fn rejects_entries_over_the_max_size() {
+        let entry = test_entry();
+        let size = String::from(entry.content()).len();
+        let dht_config = |max_entry_size| DhtConfig {
+            max_entry_size: Some(max_entry_size),
+            ..Default::default()
+        };
+
+        assert_eq!(validate_entry_size(&entry, &DhtConfig::default()), Ok(()));
+        assert_eq!(validate_entry_size(&entry, &dht_config(size)), Ok(()));
+        assert!(validate_entry_size(&entry, &dht_config(size - 1)).is_err());
+    }
+}
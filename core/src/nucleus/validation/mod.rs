@@ -8,16 +8,18 @@ use holochain_core_types::{
 };
 use holochain_persistence_api::cas::content::Address;
 
-use std::sync::Arc;
+use std::{future::Future, pin::Pin, sync::Arc};
 
 mod agent_entry;
 mod app_entry;
+mod entry_size;
 mod header_address;
 mod link_entry;
 mod provenances;
 mod remove_entry;
+mod warrant_entry;
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 /// A failed validation.
 pub enum ValidationError {
     /// `Fail` means the validation function did run successfully and recognized the entry
@@ -67,7 +69,8 @@ impl From<ValidationError> for HolochainError {
 ///    the validation package.
 /// 2. Validates provenances given in the header by verifying the cryptographic signatures
 ///    against the source agent addresses.
-/// 3. Finally spawns a thread to run the type specific validation callback in a Ribosome.
+/// 3. Checks that the entry is not bigger than the maximum entry size set in the DNA.
+/// 4. Finally spawns a thread to run the type specific validation callback in a Ribosome.
 ///
 /// All of this actually happens in the functions of the sub modules. This function is the
 /// main validation entry point and, like a workflow, stays high-level.
@@ -80,6 +83,14 @@ pub async fn validate_entry(
     //check_entry_type(entry.entry_type(), context)?;
     header_address::validate_header_address(&entry, &validation_data.package.chain_header)?;
     provenances::validate_provenances(&validation_data)?;
+    if entry.entry_type() != EntryType::Dna {
+        let dht_config = context
+            .state()
+            .and_then(|state| state.nucleus().dna())
+            .map(|dna| dna.dht)
+            .unwrap_or_default();
+        entry_size::validate_entry_size(&entry, &dht_config)?;
+    }
 
     match entry.entry_type() {
         // DNA entries are not validated currently and always valid
@@ -126,10 +137,34 @@ pub async fn validate_entry(
         // chain headers always pass for now. In future this should check that the entry is valid
         EntryType::ChainHeader => Ok(()), 
 
+        EntryType::Warrant => await!(warrant_entry::validate_warrant_entry(
+            entry.clone(),
+            validation_data,
+            context
+        )),
+
         _ => Err(ValidationError::NotImplemented),
     }
 }
 
+/// validate_entry() behind a pointer, so that validating an entry can validate another one,
+/// like the entry a warrant was issued for.
+pub(crate) fn validate_entry_boxed<'a>(
+    entry: Entry,
+    link: Option<Address>,
+    validation_data: ValidationData,
+    context: &'a Arc<Context>,
+) -> Pin<Box<dyn Future<Output = ValidationResult> + 'a>> {
+    Box::pin(validate_entry(entry, link, validation_data, context))
+}
+
+/// Checks that the given header is a header of the given entry and that the signatures of
+/// all its provenances verify, i.e. that the agents named in the header really authored it.
+pub fn validate_entry_header(entry: &Entry, header: &ChainHeader) -> ValidationResult {
+    header_address::validate_header_address(entry, header)?;
+    provenances::validate_header_provenances(header)
+}
+
 pub fn entry_to_validation_data(
     context: Arc<Context>,
     entry: &Entry,
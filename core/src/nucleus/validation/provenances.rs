@@ -1,10 +1,13 @@
 use crate::nucleus::validation::{ValidationError, ValidationResult};
 use boolinator::Boolinator;
-use holochain_core_types::validation::ValidationData;
+use holochain_core_types::{chain_header::ChainHeader, validation::ValidationData};
 use holochain_dpki::utils::Verify;
 
 pub fn validate_provenances(validation_data: &ValidationData) -> ValidationResult {
-    let header = &validation_data.package.chain_header;
+    validate_header_provenances(&validation_data.package.chain_header)
+}
+
+pub fn validate_header_provenances(header: &ChainHeader) -> ValidationResult {
     header
         .provenances()
         .iter()
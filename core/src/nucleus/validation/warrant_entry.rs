@@ -0,0 +1,91 @@
+use crate::{
+    context::Context,
+    network::entry_with_header::EntryWithHeader,
+    nucleus::validation::{
+        validate_entry_boxed, validate_entry_header, ValidationError, ValidationResult,
+    },
+    workflows::validation_package,
+};
+use boolinator::Boolinator;
+use holochain_core_types::{
+    entry::Entry,
+    validation::{EntryLifecycle, ValidationData},
+};
+use holochain_dpki::utils::Verify;
+use holochain_persistence_api::cas::content::AddressableContent;
+use std::sync::Arc;
+
+/// Warrants get validated natively, without any zome callback:
+/// 1. The warrant's header has to be the header of the warranted entry and the signatures
+///    of all its provenances have to verify, so nobody can get warranted with a forged header.
+/// 2. The warrant has to be committed by the validator that issued it.
+/// 3. The validator's signature over the warrant has to verify.
+/// 4. The warranted entry has to fail validation with the warranted header, so that
+///    nobody can get an honest agent censored by warranting a valid entry.
+///    As long as the validation package of the warranted entry can't be fetched,
+///    the warrant stays pending.
+pub async fn validate_warrant_entry(
+    entry: Entry,
+    validation_data: ValidationData,
+    context: &Arc<Context>,
+) -> ValidationResult {
+    let warrant = unwrap_to!(entry => Entry::Warrant);
+
+    validate_entry_header(warrant.invalid_entry(), warrant.header())?;
+
+    let validator = warrant.validator();
+    validation_data
+        .package
+        .chain_header
+        .provenances()
+        .iter()
+        .any(|provenance| provenance.source() == validator.source())
+        .ok_or_else(|| {
+            ValidationError::Fail(format!(
+                "Warrant was issued by {} but not committed by them",
+                validator.source()
+            ))
+        })?;
+
+    match validator.verify(warrant.signed_payload()) {
+        Ok(true) => (),
+        Ok(false) => return Err(ValidationError::Fail(format!(
+            "Warrant signature of validator {} invalid",
+            validator.source()
+        ))),
+        Err(_) => return Err(ValidationError::Fail(format!(
+            "Warrant signature of validator {} failed to verify public signing key. Key might be invalid.",
+            validator.source()
+        ))),
+    }
+
+    let warranted = EntryWithHeader {
+        entry: warrant.invalid_entry().clone(),
+        header: warrant.header().clone(),
+    };
+    let mut package = await!(validation_package(&warranted, context.clone()))
+        .ok()
+        .and_then(|maybe_package| maybe_package)
+        .ok_or_else(|| ValidationError::UnresolvedDependencies(vec![warranted.header.address()]))?;
+    // The header's signatures got verified above, so it is the one to validate against,
+    // whatever header the author sent along with the package
+    package.chain_header = warranted.header.clone();
+
+    let warranted_result = await!(validate_entry_boxed(
+        warranted.entry.clone(),
+        None,
+        ValidationData {
+            package,
+            lifecycle: EntryLifecycle::Dht,
+        },
+        context
+    ));
+    match warranted_result {
+        Ok(()) => Err(ValidationError::Fail(format!(
+            "Warranted entry {} is valid",
+            warranted.entry.address()
+        ))),
+        Err(ValidationError::Fail(_)) => Ok(()),
+        Err(error) => Err(error),
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
-    agent::state::{AgentStateSnapshot, AGENT_SNAPSHOT_ADDRESS},
+    agent::state::{AgentState, AgentStateSnapshot, AGENT_SNAPSHOT_ADDRESS},
     context::Context,
-    nucleus::state::{NucleusStateSnapshot, NUCLEUS_SNAPSHOT_ADDRESS},
+    nucleus::state::{NucleusState, NucleusStateSnapshot, NUCLEUS_SNAPSHOT_ADDRESS},
     state::State,
 };
 use holochain_core_types::error::HolochainError;
@@ -12,10 +12,19 @@ use holochain_persistence_api::cas::{
 };
 
 use crate::{
-    dht::dht_store::{DhtStoreSnapshot, DHT_STORE_SNAPSHOT_ADDRESS},
+    dht::dht_store::{DhtStore, DhtStoreSnapshot, DHT_STORE_SNAPSHOT_ADDRESS},
     state::StateWrapper,
 };
-use std::sync::{Arc, RwLock};
+use holochain_json_api::{
+    error::{JsonError, JsonResult},
+    json::JsonString,
+};
+use multihash::Hash;
+use serde_json::{self, Value};
+use std::{
+    convert::TryFrom,
+    sync::{Arc, RwLock},
+};
 
 /// trait that defines the persistence functionality that holochain_core requires
 pub trait Persister: Send {
@@ -54,40 +63,30 @@ impl Persister for SimplePersister {
     }
     fn load(&self, context: Arc<Context>) -> Result<Option<State>, HolochainError> {
         let lock = &*self.storage.clone();
-        let store = lock.read().unwrap();
-
-        let agent_snapshot: Option<AgentStateSnapshot> = store
-            .fetch(&Address::from(AGENT_SNAPSHOT_ADDRESS))?
-            .map(|s: Content| {
-                AgentStateSnapshot::try_from_content(&s)
-                    .expect("could not load AgentStateSnapshot from content")
-            });
-
-        let nucleus_snapshot: Option<NucleusStateSnapshot> = store
-            .fetch(&Address::from(NUCLEUS_SNAPSHOT_ADDRESS))?
-            .map(|s: Content| {
-                NucleusStateSnapshot::try_from_content(&s)
-                    .expect("could not load NucleusStateSnapshot from content")
-            });
-
-        let dht_store_snapshot: Option<DhtStoreSnapshot> = store
-            .fetch(&Address::from(DHT_STORE_SNAPSHOT_ADDRESS))?
-            .map(|s: Content| {
-                DhtStoreSnapshot::try_from_content(&s)
-                    .expect("could not load DhtStoreSnapshot from content")
-            });
-
-        if agent_snapshot.is_none() || nucleus_snapshot.is_none() || dht_store_snapshot.is_none() {
-            return Ok(None);
-        }
+        let store = lock
+            .read()
+            .map_err(|_| HolochainError::new("Could not get read lock on storage"))?;
+
+        let agent_snapshot = match store.fetch(&Address::from(AGENT_SNAPSHOT_ADDRESS))? {
+            Some(content) => AgentStateSnapshot::try_from_content(&content)?,
+            None => return Ok(None),
+        };
+        let nucleus_snapshot = match store.fetch(&Address::from(NUCLEUS_SNAPSHOT_ADDRESS))? {
+            Some(content) => NucleusStateSnapshot::try_from_content(&content)?,
+            None => return Ok(None),
+        };
+        let dht_store_snapshot = match store.fetch(&Address::from(DHT_STORE_SNAPSHOT_ADDRESS))? {
+            Some(content) => DhtStoreSnapshot::try_from_content(&content)?,
+            None => return Ok(None),
+        };
 
-        Ok(State::try_from_snapshots(
+        State::try_from_snapshots(
             context,
-            agent_snapshot.unwrap(),
-            nucleus_snapshot.unwrap(),
-            dht_store_snapshot.unwrap(),
+            agent_snapshot,
+            nucleus_snapshot,
+            dht_store_snapshot,
         )
-        .ok())
+        .map(Some)
     }
 }
 
@@ -97,14 +96,404 @@ impl SimplePersister {
     }
 }
 
+/// Version of the format in which TransactionalPersister stores state.
+/// Version 1 is the format written by SimplePersister: one snapshot per state slice,
+/// each at a fixed address.
+pub const STATE_SCHEMA_VERSION: u32 = 2;
+
+pub static STATE_SNAPSHOT_HEAD_ADDRESS: &'static str = "StateSnapshotHead";
+pub static STATE_SNAPSHOT_PART_ADDRESS: &'static str = "StateSnapshotPart";
+
+/// A migration turns the raw snapshots of one schema version into those of the next.
+/// `MIGRATIONS[n]` migrates from version `n + 1` to version `n + 2`.
+type Migration = fn(RawSnapshots) -> Result<RawSnapshots, HolochainError>;
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
+/// Version 2 only changed how snapshots are stored, not their format.
+fn migrate_v1_to_v2(snapshots: RawSnapshots) -> Result<RawSnapshots, HolochainError> {
+    Ok(snapshots)
+}
+
+/// The state slice snapshots as plain JSON, so that migrations can work on formats
+/// the current snapshot types don't deserialize from anymore.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawSnapshots {
+    pub agent: Value,
+    pub nucleus: Value,
+    pub dht: Value,
+}
+
+impl RawSnapshots {
+    /// Applies all migrations needed to bring snapshots of the given schema version
+    /// to STATE_SCHEMA_VERSION.
+    pub fn migrate(self, from_version: u32) -> Result<Self, HolochainError> {
+        if from_version == 0 || from_version > STATE_SCHEMA_VERSION {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Can not migrate state snapshots of unknown schema version {}",
+                from_version
+            )));
+        }
+        MIGRATIONS[(from_version - 1) as usize..]
+            .iter()
+            .try_fold(self, |snapshots, migration| migration(snapshots))
+    }
+
+    fn into_state(self, context: Arc<Context>) -> Result<State, HolochainError> {
+        let agent: AgentStateSnapshot = serde_json::from_value(self.agent).map_err(|e| {
+            HolochainError::ErrorGeneric(format!("Could not load AgentStateSnapshot: {}", e))
+        })?;
+        let nucleus: NucleusStateSnapshot = serde_json::from_value(self.nucleus).map_err(|e| {
+            HolochainError::ErrorGeneric(format!("Could not load NucleusStateSnapshot: {}", e))
+        })?;
+        let dht: DhtStoreSnapshot = serde_json::from_value(self.dht).map_err(|e| {
+            HolochainError::ErrorGeneric(format!("Could not load DhtStoreSnapshot: {}", e))
+        })?;
+        State::try_from_snapshots(context, agent, nucleus, dht)
+    }
+}
+
+/// Points to the slot a state slice snapshot got written to,
+/// together with the hash of the snapshot for verification.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PartRef {
+    pub slot: u8,
+    pub hash: Address,
+}
+
+/// The record that makes a set of state slice snapshots current.
+/// Heads alternate between two addresses by sequence number so that a torn head write
+/// leaves the previous head intact.
+#[derive(Clone, Debug, Deserialize, Serialize, DefaultJson, PartialEq)]
+pub struct StateSnapshotHead {
+    pub schema_version: u32,
+    pub sequence: u64,
+    pub agent: PartRef,
+    pub nucleus: PartRef,
+    pub dht: PartRef,
+}
+
+// Addresses must not contain slashes since file based storages use them as file names.
+fn head_address(slot: u64) -> Address {
+    format!("{}-{}", STATE_SNAPSHOT_HEAD_ADDRESS, slot).into()
+}
+
+impl AddressableContent for StateSnapshotHead {
+    fn content(&self) -> Content {
+        self.to_owned().into()
+    }
+
+    fn try_from_content(content: &Content) -> JsonResult<Self> {
+        Self::try_from(content.to_owned())
+    }
+
+    fn address(&self) -> Address {
+        head_address(self.sequence % 2)
+    }
+}
+
+/// A state slice snapshot stored in one of its two slots.
+#[derive(Clone, Debug, Deserialize, Serialize, DefaultJson, PartialEq)]
+struct SnapshotPart {
+    slot: Address,
+    snapshot: Value,
+}
+
+fn part_address(name: &str, slot: u8) -> Address {
+    format!("{}-{}-{}", STATE_SNAPSHOT_PART_ADDRESS, name, slot).into()
+}
+
+fn part_hash(snapshot: &Value) -> Address {
+    Address::encode_from_str(&snapshot.to_string(), Hash::SHA2256)
+}
+
+impl AddressableContent for SnapshotPart {
+    fn content(&self) -> Content {
+        self.to_owned().into()
+    }
+
+    fn try_from_content(content: &Content) -> JsonResult<Self> {
+        Self::try_from(content.to_owned())
+    }
+
+    fn address(&self) -> Address {
+        self.slot.clone()
+    }
+}
+
+/// The state slices of the last save.
+/// Reducers keep the Arc of a slice they don't change, so comparing pointers tells which
+/// slices need to be serialized and hashed again.
+#[derive(Clone)]
+struct SavedSlices {
+    agent: Arc<AgentState>,
+    nucleus: Arc<NucleusState>,
+    dht: Arc<DhtStore>,
+}
+
+/// Persister that stores state transactionally and only writes what changed.
+/// Slices that did not change since the last save don't even get serialized.
+///
+/// Each state slice snapshot has two slots. A save writes changed snapshots into the slot
+/// the current head does not point to and commits them by writing a new head, so a crash
+/// in the middle of a save leaves the previous head and its snapshots untouched.
+/// Loading picks the newest head whose snapshots verify against their hashes and migrates
+/// them from the head's schema version, falling back to the SimplePersister format if no
+/// head exists.
+#[derive(Clone)]
+pub struct TransactionalPersister {
+    storage: Arc<RwLock<dyn ContentAddressableStorage>>,
+    head: Option<StateSnapshotHead>,
+    saved: Option<SavedSlices>,
+}
+
+impl PartialEq for TransactionalPersister {
+    fn eq(&self, other: &TransactionalPersister) -> bool {
+        (&*self.storage.read().unwrap()).get_id() == (&*other.storage.read().unwrap()).get_id()
+    }
+}
+
+impl TransactionalPersister {
+    pub fn new(storage: Arc<RwLock<dyn ContentAddressableStorage>>) -> Self {
+        TransactionalPersister {
+            storage,
+            head: None,
+            saved: None,
+        }
+    }
+
+    /// Keeps the previous snapshot of a slice that did not change since the last save and
+    /// writes a new one otherwise.
+    fn save_part<F>(
+        store: &mut dyn ContentAddressableStorage,
+        name: &str,
+        previous: Option<&PartRef>,
+        unchanged: bool,
+        snapshot: F,
+    ) -> Result<PartRef, HolochainError>
+    where
+        F: FnOnce() -> Result<Value, HolochainError>,
+    {
+        match previous {
+            Some(previous) if unchanged => Ok(previous.clone()),
+            _ => Self::write_part(store, name, previous, snapshot()?),
+        }
+    }
+
+    fn write_part(
+        store: &mut dyn ContentAddressableStorage,
+        name: &str,
+        previous: Option<&PartRef>,
+        snapshot: Value,
+    ) -> Result<PartRef, HolochainError> {
+        let hash = part_hash(&snapshot);
+        if let Some(previous) = previous {
+            if previous.hash == hash {
+                return Ok(previous.clone());
+            }
+        }
+        let slot = previous.map(|part| 1 - part.slot).unwrap_or(0);
+        store.add(&SnapshotPart {
+            slot: part_address(name, slot),
+            snapshot,
+        })?;
+        Ok(PartRef { slot, hash })
+    }
+
+    fn read_part(
+        store: &dyn ContentAddressableStorage,
+        name: &str,
+        part: &PartRef,
+    ) -> Result<Value, HolochainError> {
+        let content = store
+            .fetch(&part_address(name, part.slot))?
+            .ok_or_else(|| HolochainError::ErrorGeneric(format!("Missing {} snapshot", name)))?;
+        let snapshot = SnapshotPart::try_from_content(&content)?.snapshot;
+        if part_hash(&snapshot) != part.hash {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Hash mismatch in {} snapshot",
+                name
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    fn read_snapshots(
+        store: &dyn ContentAddressableStorage,
+        head: &StateSnapshotHead,
+    ) -> Result<RawSnapshots, HolochainError> {
+        Ok(RawSnapshots {
+            agent: Self::read_part(store, "agent", &head.agent)?,
+            nucleus: Self::read_part(store, "nucleus", &head.nucleus)?,
+            dht: Self::read_part(store, "dht", &head.dht)?,
+        })
+    }
+
+    /// Returns the newest head whose snapshots are all intact, together with them.
+    /// Ok(None) means no head got written yet, an error that heads exist but none of them
+    /// is usable.
+    fn latest_head(
+        store: &dyn ContentAddressableStorage,
+    ) -> Result<Option<(StateSnapshotHead, RawSnapshots)>, HolochainError> {
+        let mut heads = Vec::new();
+        let mut errors = Vec::new();
+        for slot in 0..2 {
+            match store.fetch(&head_address(slot)) {
+                Ok(Some(content)) => match StateSnapshotHead::try_from_content(&content) {
+                    Ok(head) => heads.push(head),
+                    Err(error) => errors.push(HolochainError::from(error).to_string()),
+                },
+                Ok(None) => (),
+                Err(error) => errors.push(HolochainError::from(error).to_string()),
+            }
+        }
+        if heads.is_empty() && errors.is_empty() {
+            return Ok(None);
+        }
+
+        heads.sort_by_key(|head| std::cmp::Reverse(head.sequence));
+        for head in heads {
+            if head.schema_version > STATE_SCHEMA_VERSION {
+                return Err(HolochainError::ErrorGeneric(format!(
+                    "State snapshot has schema version {} but only versions up to {} are supported",
+                    head.schema_version, STATE_SCHEMA_VERSION
+                )));
+            }
+            match Self::read_snapshots(store, &head) {
+                Ok(snapshots) => return Ok(Some((head, snapshots))),
+                Err(error) => errors.push(error.to_string()),
+            }
+        }
+        Err(HolochainError::ErrorGeneric(format!(
+            "No consistent state snapshot found: {}",
+            errors.join("; ")
+        )))
+    }
+
+    /// Reads the three snapshots SimplePersister writes, which are schema version 1.
+    fn read_legacy_snapshots(
+        store: &dyn ContentAddressableStorage,
+    ) -> Result<Option<RawSnapshots>, HolochainError> {
+        let read = |address: &str| -> Result<Option<Value>, HolochainError> {
+            match store.fetch(&Address::from(address))? {
+                Some(content) => Ok(Some(serde_json::from_str(&String::from(content))?)),
+                None => Ok(None),
+            }
+        };
+        match (
+            read(AGENT_SNAPSHOT_ADDRESS)?,
+            read(NUCLEUS_SNAPSHOT_ADDRESS)?,
+            read(DHT_STORE_SNAPSHOT_ADDRESS)?,
+        ) {
+            (Some(agent), Some(nucleus), Some(dht)) => Ok(Some(RawSnapshots {
+                agent,
+                nucleus,
+                dht,
+            })),
+            (None, None, None) => Ok(None),
+            _ => Err(HolochainError::ErrorGeneric(
+                "Incomplete state snapshots of schema version 1".to_string(),
+            )),
+        }
+    }
+}
+
+impl Persister for TransactionalPersister {
+    fn save(&mut self, state: &StateWrapper) -> Result<(), HolochainError> {
+        let lock = &*self.storage.clone();
+        let mut store = lock
+            .try_write()
+            .map_err(|_| HolochainError::new("Could not get write lock on storage"))?;
+
+        if self.head.is_none() {
+            // Whatever is stored must not get overwritten before a new head is committed.
+            self.head = Self::latest_head(&*store)
+                .ok()
+                .and_then(|latest| latest.map(|(head, _)| head));
+        }
+        let previous = self.head.as_ref();
+
+        let slices = SavedSlices {
+            agent: state.agent(),
+            nucleus: state.nucleus(),
+            dht: state.dht(),
+        };
+        let saved = self.saved.as_ref();
+        let agent = Self::save_part(
+            &mut *store,
+            "agent",
+            previous.map(|head| &head.agent),
+            saved.map_or(false, |saved| Arc::ptr_eq(&saved.agent, &slices.agent)),
+            || Ok(serde_json::to_value(AgentStateSnapshot::from(state))?),
+        )?;
+        let nucleus = Self::save_part(
+            &mut *store,
+            "nucleus",
+            previous.map(|head| &head.nucleus),
+            saved.map_or(false, |saved| Arc::ptr_eq(&saved.nucleus, &slices.nucleus)),
+            || Ok(serde_json::to_value(NucleusStateSnapshot::from(state))?),
+        )?;
+        let dht = Self::save_part(
+            &mut *store,
+            "dht",
+            previous.map(|head| &head.dht),
+            saved.map_or(false, |saved| Arc::ptr_eq(&saved.dht, &slices.dht)),
+            || Ok(serde_json::to_value(DhtStoreSnapshot::from(state))?),
+        )?;
+
+        if let Some(previous) = previous {
+            if previous.schema_version == STATE_SCHEMA_VERSION
+                && previous.agent == agent
+                && previous.nucleus == nucleus
+                && previous.dht == dht
+            {
+                self.saved = Some(slices);
+                return Ok(());
+            }
+        }
+        let head = StateSnapshotHead {
+            schema_version: STATE_SCHEMA_VERSION,
+            sequence: previous.map(|head| head.sequence + 1).unwrap_or(0),
+            agent,
+            nucleus,
+            dht,
+        };
+        store.add(&head)?;
+        self.head = Some(head);
+        self.saved = Some(slices);
+        Ok(())
+    }
+
+    fn load(&self, context: Arc<Context>) -> Result<Option<State>, HolochainError> {
+        let lock = &*self.storage.clone();
+        let store = lock
+            .read()
+            .map_err(|_| HolochainError::new("Could not get read lock on storage"))?;
+
+        let (schema_version, snapshots) = match Self::latest_head(&*store)? {
+            Some((head, snapshots)) => (head.schema_version, snapshots),
+            None => match Self::read_legacy_snapshots(&*store)? {
+                Some(snapshots) => (1, snapshots),
+                None => return Ok(None),
+            },
+        };
+        snapshots
+            .migrate(schema_version)?
+            .into_state(context)
+            .map(Some)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use self::tempfile::tempdir;
     use crate::{
+        action::{Action, ActionWrapper},
+        agent::state::ChainLock,
         instance::tests::test_context_with_agent_state,
-        persister::{Persister, SimplePersister},
+        persister::*,
     };
+    use holochain_persistence_file::cas::file::FilesystemStorage;
     use std::fs::File;
     use tempfile;
 
@@ -127,4 +516,161 @@ mod tests {
         // need to fix this so `persitance.load()` takes a networks or something
         assert_ne!(state.network(), state_from_file.network());
     }
+
+    #[test]
+    fn transactional_persistence_round_trip() {
+        let context = test_context_with_agent_state(None);
+        let mut persister = TransactionalPersister::new(context.dht_storage.clone());
+        assert!(persister.load(context.clone()).unwrap().is_none());
+
+        let state = context.state().unwrap().clone();
+        persister.save(&state).unwrap();
+        let loaded = persister.load(context.clone()).unwrap().unwrap();
+        assert_eq!(state.agent(), loaded.agent());
+        assert_eq!(state.nucleus(), loaded.nucleus());
+        assert_eq!(state.dht(), loaded.dht());
+
+        // saving unchanged state must not commit a new head
+        persister.save(&state).unwrap();
+        assert_eq!(persister.head.as_ref().unwrap().sequence, 0);
+
+        // a fresh persister picks up where the stored heads left off
+        let mut other = TransactionalPersister::new(context.dht_storage.clone());
+        other.save(&state).unwrap();
+        assert_eq!(other.head, persister.head);
+    }
+
+    #[test]
+    fn transactional_persistence_round_trip_on_filesystem() {
+        let dir = tempdir().unwrap();
+        let storage = FilesystemStorage::new(dir.path().to_str().unwrap()).unwrap();
+        let storage: Arc<RwLock<dyn ContentAddressableStorage>> = Arc::new(RwLock::new(storage));
+        let context = test_context_with_agent_state(None);
+        let state = context.state().unwrap().clone();
+
+        let mut persister = TransactionalPersister::new(storage.clone());
+        persister.save(&state).unwrap();
+        let loaded = TransactionalPersister::new(storage)
+            .load(context.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.agent(), loaded.agent());
+        assert_eq!(state.nucleus(), loaded.nucleus());
+        assert_eq!(state.dht(), loaded.dht());
+    }
+
+    #[test]
+    fn only_changed_slices_get_written() {
+        let context = test_context_with_agent_state(None);
+        let mut persister = TransactionalPersister::new(context.dht_storage.clone());
+        let state = context.state().unwrap().clone();
+        persister.save(&state).unwrap();
+        let head = persister.head.clone().unwrap();
+
+        // only touches the agent slice
+        let locked = state.reduce(ActionWrapper::new(Action::LockChain(ChainLock::new(
+            "entry".into(),
+            "initiator".into(),
+        ))));
+        persister.save(&locked).unwrap();
+        let new_head = persister.head.clone().unwrap();
+        assert_eq!(new_head.sequence, head.sequence + 1);
+        assert_ne!(new_head.agent, head.agent);
+        assert_eq!(new_head.nucleus, head.nucleus);
+        assert_eq!(new_head.dht, head.dht);
+    }
+
+    #[test]
+    fn falls_back_to_previous_head_if_newest_is_torn() {
+        let context = test_context_with_agent_state(None);
+        let mut persister = TransactionalPersister::new(context.dht_storage.clone());
+        let state = context.state().unwrap().clone();
+        persister.save(&state).unwrap();
+        let head = persister.head.clone().unwrap();
+
+        // simulate a crash while writing the next head
+        context
+            .dht_storage
+            .write()
+            .unwrap()
+            .add(&SnapshotPart {
+                slot: head_address(1),
+                snapshot: Value::String("torn".to_string()),
+            })
+            .unwrap();
+
+        let loaded = persister.load(context.clone()).unwrap().unwrap();
+        assert_eq!(state.agent(), loaded.agent());
+
+        let store = context.dht_storage.read().unwrap();
+        let (latest, _) = TransactionalPersister::latest_head(&*store)
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest, head);
+    }
+
+    #[test]
+    fn load_returns_error_instead_of_panicking() {
+        let context = test_context_with_agent_state(None);
+        let persister = TransactionalPersister::new(context.dht_storage.clone());
+        context
+            .dht_storage
+            .write()
+            .unwrap()
+            .add(&SnapshotPart {
+                slot: head_address(0),
+                snapshot: Value::Null,
+            })
+            .unwrap();
+        assert!(persister.load(context.clone()).is_err());
+
+        let mut head = StateSnapshotHead {
+            schema_version: STATE_SCHEMA_VERSION + 1,
+            sequence: 1,
+            agent: PartRef {
+                slot: 0,
+                hash: Address::from("agent"),
+            },
+            nucleus: PartRef {
+                slot: 0,
+                hash: Address::from("nucleus"),
+            },
+            dht: PartRef {
+                slot: 0,
+                hash: Address::from("dht"),
+            },
+        };
+        context.dht_storage.write().unwrap().add(&head).unwrap();
+        assert!(persister.load(context.clone()).is_err());
+
+        // hashes don't match any stored snapshot
+        head.schema_version = STATE_SCHEMA_VERSION;
+        context.dht_storage.write().unwrap().add(&head).unwrap();
+        assert!(persister.load(context.clone()).is_err());
+    }
+
+    #[test]
+    fn migrates_snapshots_of_simple_persister() {
+        let context = test_context_with_agent_state(None);
+        let state = context.state().unwrap().clone();
+        SimplePersister::new(context.dht_storage.clone())
+            .save(&state)
+            .unwrap();
+
+        let loaded = TransactionalPersister::new(context.dht_storage.clone())
+            .load(context.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.agent(), loaded.agent());
+        assert_eq!(state.nucleus(), loaded.nucleus());
+        assert_eq!(state.dht(), loaded.dht());
+
+        let snapshots = RawSnapshots {
+            agent: serde_json::to_value(AgentStateSnapshot::from(&state)).unwrap(),
+            nucleus: serde_json::to_value(NucleusStateSnapshot::from(&state)).unwrap(),
+            dht: serde_json::to_value(DhtStoreSnapshot::from(&state)).unwrap(),
+        };
+        assert_eq!(snapshots.clone().migrate(1).unwrap(), snapshots);
+        assert!(snapshots.migrate(STATE_SCHEMA_VERSION + 1).is_err());
+    }
 }
@@ -0,0 +1,34 @@
+use crate::{
+    action::{Action, ActionWrapper, ACTION_RESPONSE_TTL},
+    context::Context,
+    instance::dispatch_action,
+};
+use std::{sync::Arc, time::SystemTime};
+
+/// Drops responses to actions that no future has picked up within ACTION_RESPONSE_TTL.
+pub fn prune_action_responses(context: Arc<Context>) {
+    let cutoff = match SystemTime::now().checked_sub(ACTION_RESPONSE_TTL) {
+        Some(cutoff) => cutoff,
+        None => return,
+    };
+
+    let expired = context
+        .state()
+        .map(|state| {
+            state.network().actions().has_older_than(cutoff)
+                || state.dht().actions().has_older_than(cutoff)
+        })
+        .unwrap_or(false);
+
+    if expired {
+        log_debug!(
+            context,
+            "scheduled_jobs/prune_action_responses: dropping responses older than {:?}",
+            ACTION_RESPONSE_TTL
+        );
+        dispatch_action(
+            context.action_channel(),
+            ActionWrapper::new(Action::PruneActionResponses(cutoff)),
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod action_responses;
 pub mod pending_validations;
 pub mod state_dump;
 
@@ -11,5 +12,6 @@ pub fn create_callback(context: Arc<Context>) -> impl 'static + FnMut() + Sync +
             state_dump::state_dump(context.clone());
         }
         pending_validations::run_pending_validations(context.clone());
+        action_responses::prune_action_responses(context.clone());
     }
 }
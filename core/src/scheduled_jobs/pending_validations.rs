@@ -1,7 +1,9 @@
 use crate::{
     context::Context,
     network::entry_with_header::EntryWithHeader,
-    nucleus::actions::remove_pending_validation::remove_pending_validation,
+    nucleus::{
+        actions::remove_pending_validation::remove_pending_validation, state::PendingValidationKey,
+    },
     workflows::{hold_entry::hold_entry_workflow, hold_link::hold_link_workflow},
 };
 use holochain_core_types::error::HolochainError;
@@ -13,7 +15,14 @@ use crate::workflows::{
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
 use snowflake::ProcessUniqueId;
-use std::{convert::TryFrom, fmt, sync::Arc, thread};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime},
+};
 
 pub type PendingValidation = Arc<PendingValidationStruct>;
 
@@ -66,11 +75,70 @@ impl fmt::Display for ValidatingWorkflow {
     }
 }
 
+/// Delay before the first retry of a pending validation.
+/// Every failed retry doubles the delay until it reaches MAX_RETRY_DELAY.
+pub const RETRY_DELAY_BASE: Duration = Duration::from_secs(10);
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+/// After this many attempts a pending validation is only retried when one of its
+/// dependencies arrives, until it gets purged.
+pub const MAX_VALIDATION_ATTEMPTS: u32 = 30;
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, DefaultJson)]
 pub struct PendingValidationStruct {
     pub entry_with_header: EntryWithHeader,
     pub dependencies: Vec<Address>,
     pub workflow: ValidatingWorkflow,
+    /// How often validating this entry has been tried without a conclusive result
+    #[serde(default)]
+    pub attempts: u32,
+    /// When to retry next, unless a dependency arrives earlier.
+    /// None once MAX_VALIDATION_ATTEMPTS is reached.
+    #[serde(default)]
+    pub next_retry: Option<SystemTime>,
+}
+
+impl PendingValidationStruct {
+    pub fn new(
+        entry_with_header: EntryWithHeader,
+        dependencies: Vec<Address>,
+        workflow: ValidatingWorkflow,
+    ) -> Self {
+        PendingValidationStruct {
+            entry_with_header,
+            dependencies,
+            workflow,
+            attempts: 0,
+            next_retry: None,
+        }
+    }
+
+    /// Records another failed attempt and schedules the next retry with exponential backoff,
+    /// unless the maximum number of attempts is reached.
+    pub fn schedule_retry(&mut self, attempts: u32, now: SystemTime) {
+        self.attempts = attempts.min(MAX_VALIDATION_ATTEMPTS);
+        self.next_retry = if self.attempts < MAX_VALIDATION_ATTEMPTS {
+            Some(now + retry_delay(self.attempts))
+        } else {
+            None
+        };
+    }
+
+    pub fn is_due(&self, now: SystemTime) -> bool {
+        self.attempts < MAX_VALIDATION_ATTEMPTS
+            && self
+                .next_retry
+                .map(|next_retry| next_retry <= now)
+                .unwrap_or(true)
+    }
+}
+
+/// RETRY_DELAY_BASE * 2^(attempts - 1), capped at MAX_RETRY_DELAY
+pub fn retry_delay(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(31);
+    RETRY_DELAY_BASE
+        .checked_mul(1 << exponent)
+        .map(|delay| delay.min(MAX_RETRY_DELAY))
+        .unwrap_or(MAX_RETRY_DELAY)
 }
 
 fn retry_validation(pending: PendingValidation, context: Arc<Context>) {
@@ -113,6 +181,7 @@ fn retry_validation(pending: PendingValidation, context: Arc<Context>) {
         .expect("Could not spawn thread for retry_validation");
 }
 
+/// Retries all pending validations whose backoff delay has passed.
 pub fn run_pending_validations(context: Arc<Context>) {
     let pending_validations = context
         .state()
@@ -121,12 +190,140 @@ pub fn run_pending_validations(context: Arc<Context>) {
         .pending_validations
         .clone();
 
-    pending_validations.iter().for_each(|(_, pending)| {
+    let now = SystemTime::now();
+    pending_validations
+        .iter()
+        .filter(|(_, pending)| pending.is_due(now))
+        .for_each(|(_, pending)| {
+            log_debug!(context,
+                "scheduled_jobs/run_pending_validations: found pending validation for {}: {} (attempt {})",
+                pending.entry_with_header.entry.entry_type(),
+                pending.entry_with_header.entry.address(),
+                pending.attempts + 1
+            );
+            retry_validation(pending.clone(), context.clone());
+        });
+}
+
+/// Retries all pending validations that are waiting for the given address right away,
+/// regardless of their backoff delay.
+/// Gets called when an entry arrives that other validations might depend on.
+pub fn run_pending_validations_waiting_for(address: &Address, context: Arc<Context>) {
+    let waiting = waiting_for(
+        &context.state().unwrap().nucleus().pending_validations,
+        address,
+    );
+
+    for pending in waiting {
         log_debug!(context,
-            "scheduled_jobs/run_pending_validations: found pending validation for {}: {}",
-            pending.entry_with_header.entry.entry_type(),
+            "scheduled_jobs/run_pending_validations: dependency {} arrived, retrying {}",
+            address,
             pending.entry_with_header.entry.address()
         );
-        retry_validation(pending.clone(), context.clone());
-    });
+        retry_validation(pending, context.clone());
+    }
+}
+
+/// The pending validations that have the given address among their dependencies.
+fn waiting_for(
+    pending_validations: &HashMap<PendingValidationKey, PendingValidation>,
+    address: &Address,
+) -> Vec<PendingValidation> {
+    pending_validations
+        .values()
+        .filter(|pending| pending.dependencies.contains(address))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::{
+        chain_header::test_chain_header,
+        entry::{test_entry, test_entry_b, Entry},
+    };
+
+    #[test]
+    fn retry_delay_doubles_up_to_max() {
+        assert_eq!(retry_delay(1), RETRY_DELAY_BASE);
+        assert_eq!(retry_delay(2), RETRY_DELAY_BASE * 2);
+        assert_eq!(retry_delay(4), RETRY_DELAY_BASE * 8);
+        assert_eq!(retry_delay(20), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u32::max_value()), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn pending_validation_is_due_after_backoff() {
+        let mut pending = PendingValidationStruct::new(
+            EntryWithHeader {
+                entry: test_entry(),
+                header: test_chain_header(),
+            },
+            Vec::new(),
+            ValidatingWorkflow::HoldEntry,
+        );
+        let now = SystemTime::now();
+        assert!(pending.is_due(now));
+
+        pending.schedule_retry(3, now);
+        assert_eq!(pending.attempts, 3);
+        assert!(!pending.is_due(now));
+        assert!(pending.is_due(now + retry_delay(3)));
+    }
+
+    #[test]
+    fn pending_validation_stops_being_due_at_max_attempts() {
+        let mut pending = PendingValidationStruct::new(
+            EntryWithHeader {
+                entry: test_entry(),
+                header: test_chain_header(),
+            },
+            Vec::new(),
+            ValidatingWorkflow::HoldEntry,
+        );
+        let now = SystemTime::now();
+
+        pending.schedule_retry(u32::max_value(), now);
+        assert_eq!(pending.attempts, MAX_VALIDATION_ATTEMPTS);
+        assert_eq!(pending.next_retry, None);
+        assert!(!pending.is_due(now + MAX_RETRY_DELAY));
+    }
+
+    #[test]
+    fn only_validations_waiting_for_an_arrived_dependency_get_woken() {
+        let dependency = Address::from("dependency");
+        let pending_for = |entry: Entry, dependencies: Vec<Address>| {
+            let mut pending = PendingValidationStruct::new(
+                EntryWithHeader {
+                    entry,
+                    header: test_chain_header(),
+                },
+                dependencies,
+                ValidatingWorkflow::HoldEntry,
+            );
+            // not due yet, so only the arriving dependency can trigger a retry
+            pending.schedule_retry(1, SystemTime::now());
+            Arc::new(pending)
+        };
+        let waiting = pending_for(test_entry(), vec![dependency.clone()]);
+        let unrelated = pending_for(test_entry_b(), vec![Address::from("other")]);
+
+        let mut pending_validations = HashMap::new();
+        for pending in vec![waiting.clone(), unrelated] {
+            pending_validations.insert(
+                PendingValidationKey::new(
+                    pending.entry_with_header.entry.address(),
+                    pending.workflow.clone(),
+                ),
+                pending,
+            );
+        }
+
+        assert_eq!(
+            waiting_for(&pending_validations, &dependency),
+            vec![waiting]
+        );
+        assert!(waiting_for(&pending_validations, &Address::from("nothing")).is_empty());
+    }
 }
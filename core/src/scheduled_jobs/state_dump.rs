@@ -97,6 +97,8 @@ Running zome calls: {calls:?}
 Pending validations:
 {validations}
 --------------------
+Cached validation results: {cached_validations}
+--------------------
 
 Network:
 --------
@@ -105,19 +107,26 @@ Running query flows: {flows:?}
 Running VALIDATION PACKAGE requests: {validation_packages:?}
 ------------------------------------
 Running DIRECT MESSAGES: {direct_messages:?}
+------------------------------------
+Unread action responses: {network_action_responses}
 
 Dht:
 ====
 Holding:
 {holding_list}
+--------
+Unread action responses: {dht_action_responses}
 --------
     "#,
         source_chain = source_chain_strings.join("\n\n"),
         calls = dump.running_calls,
         validations = pending_validation_strings.join("\n"),
+        cached_validations = dump.cached_validations,
         flows = dump.query_flows,
         validation_packages = dump.validation_package_flows,
         direct_messages = dump.direct_message_flows,
+        network_action_responses = dump.network_action_responses,
+        dht_action_responses = dump.dht_action_responses,
         holding_list = holding_strings.join("\n")
     );
 
@@ -1,11 +1,12 @@
 use crate::nucleus::ZomeFnCall;
-use crate::action::QueryKey;
+use crate::nucleus::state::PendingValidationKey;
+use crate::action::{Action, ActionWrapper, QueryKey};
 use holochain_core_types::{entry::Entry, error::HolochainError};
 use holochain_persistence_api::cas::content::{Address, AddressableContent};
 use crate::network::direct_message::DirectMessage;
-use crate::scheduled_jobs::pending_validations::ValidatingWorkflow;
+use crate::scheduled_jobs::pending_validations::{PendingValidation, ValidatingWorkflow};
 use crate::context::Context;
-use std::{convert::TryInto, sync::Arc};
+use std::{convert::TryInto, sync::Arc, time::SystemTime};
 use holochain_core_types::chain_header::ChainHeader;
 
 #[derive(Serialize)]
@@ -13,6 +14,20 @@ pub struct PendingValidationDump{
     pub address: Address,
     pub dependencies: Vec<Address>,
     pub workflow: ValidatingWorkflow,
+    pub attempts: u32,
+    pub next_retry: Option<SystemTime>,
+}
+
+impl PendingValidationDump {
+    pub fn new(key: &PendingValidationKey, pending_validation: &PendingValidation) -> Self {
+        PendingValidationDump {
+            address: key.address.clone(),
+            workflow: key.workflow.clone(),
+            dependencies: pending_validation.dependencies.clone(),
+            attempts: pending_validation.attempts,
+            next_retry: pending_validation.next_retry,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -22,12 +37,27 @@ pub struct StateDump {
     pub validation_package_flows: Vec<Address>,
     pub direct_message_flows: Vec<(String, DirectMessage)>,
     pub pending_validations: Vec<PendingValidationDump>,
+    /// Number of validation outcomes the validation cache holds
+    pub cached_validations: usize,
     pub held_entries: Vec<Address>,
     pub source_chain: Vec<ChainHeader>,
+    /// Number of action responses the network state holds that no future has read yet
+    pub network_action_responses: usize,
+    /// Number of action responses the DHT store holds that no future has read yet
+    pub dht_action_responses: usize,
 }
 
 impl From<Arc<Context>> for StateDump {
     fn from(context: Arc<Context>) -> StateDump {
+        StateDump::new(context, true)
+    }
+}
+
+impl StateDump {
+    /// Creates a dump of the instance's current state.
+    /// Walking the whole source chain gets expensive for long chains, so it can be left out
+    /// and fetched page by page with source_chain_page() instead.
+    pub fn new(context: Arc<Context>, include_source_chain: bool) -> StateDump {
         let (agent, nucleus, network, dht) = {
             let state_lock = context.state().expect("No state?!");
             (
@@ -38,8 +68,16 @@ impl From<Arc<Context>> for StateDump {
             )
         };
 
-        let source_chain: Vec<ChainHeader> = agent.iter_chain().collect();
-        let source_chain: Vec<ChainHeader> = source_chain.into_iter().rev().collect();
+        // Counted first, since building the other fields moves out of network
+        let network_action_responses = network.actions().len();
+        let dht_action_responses = dht.actions().len();
+
+        let source_chain: Vec<ChainHeader> = if include_source_chain {
+            let source_chain: Vec<ChainHeader> = agent.iter_chain().collect();
+            source_chain.into_iter().rev().collect()
+        } else {
+            Vec::new()
+        };
 
         let running_calls: Vec<ZomeFnCall> = nucleus
             .zome_calls
@@ -73,22 +111,100 @@ impl From<Arc<Context>> for StateDump {
 
         let pending_validations = nucleus
             .pending_validations
-            .into_iter()
+            .iter()
             .map(|(pending_validation_key, pending_validation)| {
-                PendingValidationDump {
-                    address: pending_validation_key.address,
-                    workflow: pending_validation_key.workflow,
-                    dependencies: pending_validation.dependencies.clone(),
-                }
+                PendingValidationDump::new(pending_validation_key, pending_validation)
             })
             .collect::<Vec<PendingValidationDump>>();
 
+        let cached_validations = nucleus.validation_cache.len();
+
         let held_entries = dht.get_all_held_entry_addresses().clone();
 
         StateDump {
             running_calls, query_flows, validation_package_flows, direct_message_flows,
-            pending_validations, held_entries, source_chain
+            pending_validations, cached_validations, held_entries, source_chain,
+            network_action_responses, dht_action_responses,
+        }
+    }
+}
+
+/// Returns up to `limit` headers of the agent's source chain, newest first,
+/// after skipping the `offset` newest ones.
+/// Headers only link to their predecessor, so the skipped headers still get loaded
+/// from the CAS on the way down: a page costs `offset + limit` lookups.
+pub fn source_chain_page(
+    context: &Arc<Context>,
+    offset: usize,
+    limit: usize,
+) -> Result<Vec<ChainHeader>, HolochainError> {
+    let agent = context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("No state".to_string()))?
+        .agent();
+    Ok(agent.iter_chain().skip(offset).take(limit).collect())
+}
+
+/// An incremental change of an instance's state.
+/// Changes are derived from the actions the instance reduces, so that a live inspector can
+/// follow a running instance without polling for whole state dumps.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "change_type", content = "data")]
+pub enum StateChange {
+    /// Any action that got reduced
+    Action {
+        id: String,
+        action_type: String,
+    },
+    EntryCommitted(Address),
+    EntryHeld(Address),
+    PendingValidationAdded {
+        address: Address,
+        workflow: ValidatingWorkflow,
+        dependencies: Vec<Address>,
+    },
+    PendingValidationRemoved {
+        address: Address,
+        workflow: ValidatingWorkflow,
+    },
+    QueryFlowOpened(QueryKey),
+    QueryFlowClosed(QueryKey),
+}
+
+impl StateChange {
+    /// All changes the given action makes to the state.
+    pub fn from_action(action_wrapper: &ActionWrapper) -> Vec<StateChange> {
+        let mut changes = vec![StateChange::Action {
+            id: action_wrapper.id().to_string(),
+            action_type: <&'static str>::from(action_wrapper.action()).to_string(),
+        }];
+        match action_wrapper.action() {
+            Action::Commit((entry, _, _)) => {
+                changes.push(StateChange::EntryCommitted(entry.address()))
+            }
+            Action::Hold(entry_with_header) => {
+                changes.push(StateChange::EntryHeld(entry_with_header.entry.address()))
+            }
+            Action::AddPendingValidation(pending) => {
+                changes.push(StateChange::PendingValidationAdded {
+                    address: pending.entry_with_header.entry.address(),
+                    workflow: pending.workflow.clone(),
+                    dependencies: pending.dependencies.clone(),
+                })
+            }
+            Action::RemovePendingValidation((address, workflow)) => {
+                changes.push(StateChange::PendingValidationRemoved {
+                    address: address.clone(),
+                    workflow: workflow.clone(),
+                })
+            }
+            Action::Query((key, _)) => changes.push(StateChange::QueryFlowOpened(key.clone())),
+            Action::HandleQuery((_, key)) | Action::QueryTimeout(key) => {
+                changes.push(StateChange::QueryFlowClosed(key.clone()))
+            }
+            _ => (),
         }
+        changes
     }
 }
 
@@ -120,4 +236,59 @@ pub fn address_to_content_and_type(
     } else {
         Ok((String::from("UNKNOWN"), raw_content.to_string()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{agent::state::ChainLock, network::entry_with_header::EntryWithHeader};
+    use holochain_core_types::{chain_header::test_chain_header, entry::test_entry};
+
+    #[test]
+    fn test_state_changes_from_hold_action() {
+        let action_wrapper = ActionWrapper::new(Action::Hold(EntryWithHeader {
+            entry: test_entry(),
+            header: test_chain_header(),
+        }));
+        let changes = StateChange::from_action(&action_wrapper);
+        assert_eq!(changes.len(), 2);
+        match &changes[0] {
+            StateChange::Action { id, action_type } => {
+                assert_eq!(id, &action_wrapper.id().to_string());
+                assert_eq!(action_type, "Hold");
+            }
+            other => panic!("Expected an Action change, got {:?}", other),
+        }
+        match &changes[1] {
+            StateChange::EntryHeld(address) => assert_eq!(address, &test_entry().address()),
+            other => panic!("Expected an EntryHeld change, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_action_type_matches_serialized_tag() {
+        let lock = ChainLock::new(test_entry().address(), "initiator".into());
+        let actions = vec![
+            Action::Ping,
+            Action::ShutdownNetwork,
+            Action::LockChain(lock.clone()),
+            Action::UnlockChain(lock.clone()),
+            Action::RollbackChain(lock),
+            Action::Publish(test_entry().address()),
+        ];
+        for action in actions {
+            let serialized = serde_json::to_value(&action).unwrap();
+            assert_eq!(
+                Some(<&'static str>::from(&action)),
+                serialized["action_type"].as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn test_state_changes_from_other_action() {
+        let changes =
+            StateChange::from_action(&ActionWrapper::new(Action::Publish(test_entry().address())));
+        assert_eq!(changes.len(), 1);
+    }
+}
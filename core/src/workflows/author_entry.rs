@@ -1,5 +1,5 @@
 use crate::{
-    agent::actions::commit::commit_entry,
+    agent::actions::commit::commit_entry_with_provenances,
     context::Context,
     network::actions::{
         publish::publish,
@@ -31,6 +31,23 @@ pub async fn author_entry<'a>(
     context: &'a Arc<Context>,
     provenances: &'a Vec<Provenance>,
 ) -> Result<CommitEntryResult, HolochainError> {
+    let addr = await!(commit_authored_entry(
+        entry,
+        maybe_link_update_delete,
+        context,
+        provenances
+    ))?;
+    await!(publish_authored_entry(entry, context))?;
+    Ok(CommitEntryResult::new(addr))
+}
+
+/// The first half of author_entry(): validates and commits the entry without publishing it.
+pub async fn commit_authored_entry<'a>(
+    entry: &'a Entry,
+    maybe_link_update_delete: Option<Address>,
+    context: &'a Arc<Context>,
+    provenances: &'a Vec<Provenance>,
+) -> Result<Address, HolochainError> {
     let address = entry.address();
     log_debug!(context,
         "workflow/authoring_entry: {} with content: {:?}",
@@ -74,12 +91,22 @@ pub async fn author_entry<'a>(
         "workflow/authoring_entry/{}: committing...",
         address
     );
-    let addr = await!(commit_entry(
+    let addr = await!(commit_entry_with_provenances(
         entry.clone(),
         maybe_link_update_delete,
+        provenances.clone(),
         &context
     ))?;
     log_debug!(context, "workflow/authoring_entry/{}: committed", address);
+    Ok(addr)
+}
+
+/// The second half of author_entry(): publishes a committed entry and its header.
+pub async fn publish_authored_entry<'a>(
+    entry: &'a Entry,
+    context: &'a Arc<Context>,
+) -> Result<(), HolochainError> {
+    let address = entry.address();
 
     // 4. Publish the valid entry to DHT. This will call Hold to itself
     if entry.entry_type().can_publish(context) {
@@ -103,8 +130,8 @@ pub async fn author_entry<'a>(
     log_debug!(context, "debug/workflow/authoring_entry/{}: publishing header...", address);
     await!(publish_header_entry(entry.address(), &context))?;
     log_debug!(context, "debug/workflow/authoring_entry/{}: header published!", address);
-    
-    Ok(CommitEntryResult::new(addr))
+
+    Ok(())
 }
 
 #[cfg(test)]
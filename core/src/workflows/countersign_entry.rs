@@ -0,0 +1,567 @@
+use crate::{
+    action::{Action, ActionWrapper, DirectMessageData},
+    agent::{
+        actions::{
+            lock_chain::{lock_chain, unlock_chain},
+            rollback_chain::rollback_chain,
+        },
+        state::ChainLock,
+    },
+    context::Context,
+    instance::dispatch_action,
+    network::{
+        actions::custom_send::send_direct_message_and_await_response,
+        direct_message::{CountersignCommit, CountersignRequest, DirectMessage},
+    },
+    nucleus::{
+        actions::{
+            build_validation_package::build_validation_package,
+            get_entry::get_entry_from_agent_chain,
+        },
+        validation::validate_entry,
+    },
+    workflows::author_entry::{commit_authored_entry, publish_authored_entry},
+};
+use holochain_core_types::{
+    entry::Entry,
+    error::HolochainError,
+    signature::{Provenance, Signature},
+    time::Timeout,
+    validation::{EntryLifecycle, ValidationData},
+};
+use holochain_dpki::utils::Verify;
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::{
+    commit_entry::CommitEntryResult, crypto::CryptoMethod,
+};
+use std::{
+    convert::TryFrom,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// Signs the address of the given entry with this agent's key
+fn sign_entry(entry: &Entry, context: &Arc<Context>) -> Result<Provenance, HolochainError> {
+    let signature = context
+        .conductor_api
+        .execute(entry.address().to_string(), CryptoMethod::Sign)?;
+    Ok(Provenance::new(
+        context.agent_id.address(),
+        Signature::from(signature),
+    ))
+}
+
+/// Checks that the given provenances contain a valid signature of the entry from the given agent
+fn check_signed_by(
+    entry: &Entry,
+    provenances: &[Provenance],
+    agent: &Address,
+) -> Result<(), HolochainError> {
+    let signed = provenances
+        .iter()
+        .filter(|provenance| provenance.source() == *agent)
+        .any(|provenance| {
+            provenance
+                .verify(entry.address().to_string())
+                .unwrap_or(false)
+        });
+    if signed {
+        Ok(())
+    } else {
+        Err(HolochainError::ValidationFailed(format!(
+            "Entry {} is missing a valid signature from agent {}",
+            entry.address(),
+            agent
+        )))
+    }
+}
+
+/// Counterparties keep their chains locked this long past the deadline of a
+/// countersignature, so that the CountersignPublish the initiator sends once the last
+/// commit got acknowledged still reaches them before their locks get rolled back.
+pub const COUNTERSIGN_PUBLISH_GRACE: Duration = Duration::from_secs(30);
+
+/// The time left until the given deadline, as timeout for the next message
+fn time_left(deadline: SystemTime) -> Result<Timeout, HolochainError> {
+    deadline
+        .duration_since(SystemTime::now())
+        .map(|left| Timeout::new(left.as_millis() as usize))
+        .map_err(|_| HolochainError::Timeout)
+}
+
+/// Sends a countersign message to the given counterparty and waits for its response,
+/// at most until the given deadline.
+async fn send_before_deadline(
+    counterparty: Address,
+    message: DirectMessage,
+    deadline: SystemTime,
+    context: Arc<Context>,
+) -> Result<String, HolochainError> {
+    let timeout = time_left(deadline)?;
+    await!(send_direct_message_and_await_response(
+        counterparty,
+        message,
+        timeout,
+        context
+    ))
+}
+
+/// Tells all counterparties to roll back and unlock, and rolls back our own chain.
+/// Counterparties that can't be reached roll back by themselves once their locks expire.
+async fn abort_countersignature<'a>(
+    counterparties: &'a [Address],
+    lock: ChainLock,
+    deadline: SystemTime,
+    context: &'a Arc<Context>,
+) {
+    for counterparty in counterparties {
+        let _ = await!(send_before_deadline(
+            counterparty.clone(),
+            DirectMessage::CountersignAbort(lock.entry_address.clone()),
+            deadline + COUNTERSIGN_PUBLISH_GRACE,
+            context.clone()
+        ));
+    }
+    rollback_chain(lock, context);
+}
+
+/// Countersigns the given entry with all given counterparties and commits it
+/// to our and their source chains, each with all provenances in the chain header.
+///
+/// This is a three phase protocol over direct messages, all of which has to happen
+/// within the given timeout:
+/// 1. Our chain gets locked and every counterparty receives a CountersignRequest.
+///    Counterparties validate the entry, lock their chains until the deadline and respond
+///    with their signature.
+/// 2. With all signatures collected we commit the entry and send a CountersignCommit to
+///    every counterparty which makes them commit it, too. Nobody publishes yet and all
+///    chains stay locked.
+/// 3. Once every counterparty acknowledged its commit, every counterparty receives a
+///    CountersignPublish, and everybody publishes the entry and unlocks their chain.
+///
+/// If phase 1 or 2 fails, all counterparties get sent a CountersignAbort and everybody
+/// rolls back their commit, if any, and unlocks. Counterparties that don't hear from us
+/// roll back by themselves shortly after the deadline.
+/// Failures in phase 3 can't be rolled back anymore, but get returned as errors, too.
+pub async fn countersign_entry(
+    entry: Entry,
+    counterparties: Vec<Address>,
+    timeout: Timeout,
+    context: Arc<Context>,
+) -> Result<CommitEntryResult, HolochainError> {
+    let address = entry.address();
+    if !entry.entry_type().is_app() {
+        return Err(HolochainError::ErrorGeneric(
+            "Only app entries can be countersigned".to_string(),
+        ));
+    }
+    let own_address = context.agent_id.address();
+    if counterparties.is_empty() || counterparties.contains(&own_address) {
+        return Err(HolochainError::ErrorGeneric(
+            "Countersigning needs at least one counterparty other than ourselves".to_string(),
+        ));
+    }
+
+    let deadline = SystemTime::now() + Duration::from(timeout);
+    let lock = await!(lock_chain(
+        address.clone(),
+        own_address,
+        deadline + COUNTERSIGN_PUBLISH_GRACE,
+        &context
+    ))?;
+
+    // 1. Collect all signatures
+    let request = CountersignRequest {
+        entry: entry.clone(),
+        provenances: vec![sign_entry(&entry, &context)?],
+        counterparties: counterparties.clone(),
+        deadline,
+    };
+    let mut counter_provenances = Vec::new();
+    for counterparty in counterparties.iter() {
+        let result = await!(send_before_deadline(
+            counterparty.clone(),
+            DirectMessage::CountersignRequest(request.clone()),
+            deadline,
+            context.clone()
+        ))
+        .and_then(|response| {
+            let provenance = Provenance::try_from(JsonString::from_json(&response))?;
+            check_signed_by(&entry, &[provenance.clone()], counterparty)?;
+            Ok(provenance)
+        });
+        match result {
+            Ok(provenance) => counter_provenances.push(provenance),
+            Err(error) => {
+                log_warn!(
+                    context,
+                    "workflow/countersign_entry/{}: counterparty {} did not sign: {}",
+                    address,
+                    counterparty,
+                    error
+                );
+                await!(abort_countersignature(&counterparties, lock, deadline, &context));
+                return Err(error);
+            }
+        }
+    }
+
+    // 2. Commit everywhere, without publishing
+    if let Err(error) = await!(commit_authored_entry(
+        &entry,
+        None,
+        &context,
+        &counter_provenances
+    )) {
+        await!(abort_countersignature(&counterparties, lock, deadline, &context));
+        return Err(error);
+    }
+    let mut all_provenances = request.provenances.clone();
+    all_provenances.extend(counter_provenances);
+    let commit = CountersignCommit {
+        entry: entry.clone(),
+        provenances: all_provenances,
+    };
+    for counterparty in counterparties.iter() {
+        if let Err(error) = await!(send_before_deadline(
+            counterparty.clone(),
+            DirectMessage::CountersignCommit(commit.clone()),
+            deadline,
+            context.clone()
+        ))
+        .and_then(|_| time_left(deadline))
+        {
+            log_warn!(
+                context,
+                "workflow/countersign_entry/{}: counterparty {} did not commit: {}",
+                address,
+                counterparty,
+                error
+            );
+            await!(abort_countersignature(&counterparties, lock, deadline, &context));
+            return Err(error);
+        }
+    }
+
+    // 3. Everybody committed, so publish everywhere
+    unlock_chain(lock, &context);
+    let mut failed = Vec::new();
+    for counterparty in counterparties.iter() {
+        if let Err(error) = await!(send_before_deadline(
+            counterparty.clone(),
+            DirectMessage::CountersignPublish(address.clone()),
+            deadline + COUNTERSIGN_PUBLISH_GRACE,
+            context.clone()
+        )) {
+            log_error!(
+                context,
+                "workflow/countersign_entry/{}: counterparty {} failed to publish: {}",
+                address,
+                counterparty,
+                error
+            );
+            failed.push(counterparty.to_string());
+        }
+    }
+    await!(publish_authored_entry(&entry, &context))?;
+    if !failed.is_empty() {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Countersigned entry {} got committed, but counterparties {} failed to publish it",
+            address,
+            failed.join(", ")
+        )));
+    }
+
+    Ok(CommitEntryResult::new(address))
+}
+
+fn respond(
+    to_agent: Address,
+    msg_id: String,
+    result: Result<String, HolochainError>,
+    context: &Arc<Context>,
+) {
+    let direct_message_data = DirectMessageData {
+        address: to_agent,
+        message: DirectMessage::CountersignResponse(result.map_err(|e| e.to_string())),
+        msg_id,
+        is_response: true,
+    };
+    let action_wrapper = ActionWrapper::new(Action::SendDirectMessage(direct_message_data));
+    dispatch_action(context.action_channel(), action_wrapper);
+}
+
+/// Counterparty side of phase 1: validates the requested entry against our chain,
+/// locks our chain for the initiator and responds with our signature.
+pub async fn handle_countersign_request(
+    from_agent_id: Address,
+    msg_id: String,
+    request: CountersignRequest,
+    context: Arc<Context>,
+) {
+    let result = await!(sign_countersign_request(&from_agent_id, request, &context));
+    respond(from_agent_id, msg_id, result, &context);
+}
+
+async fn sign_countersign_request<'a>(
+    from_agent_id: &'a Address,
+    request: CountersignRequest,
+    context: &'a Arc<Context>,
+) -> Result<String, HolochainError> {
+    let entry = request.entry.clone();
+    if !request.counterparties.contains(&context.agent_id.address()) {
+        return Err(HolochainError::ErrorGeneric(
+            "We are not a counterparty of this countersignature".to_string(),
+        ));
+    }
+    time_left(request.deadline)?;
+    check_signed_by(&entry, &request.provenances, from_agent_id)?;
+
+    let package = await!(build_validation_package(
+        &entry,
+        context.clone(),
+        &request.provenances
+    ))?;
+    await!(validate_entry(
+        entry.clone(),
+        None,
+        ValidationData {
+            package,
+            lifecycle: EntryLifecycle::Chain,
+        },
+        context
+    ))?;
+
+    let lock = await!(lock_chain(
+        entry.address(),
+        from_agent_id.clone(),
+        request.deadline + COUNTERSIGN_PUBLISH_GRACE,
+        context
+    ))?;
+    match sign_entry(&entry, context) {
+        Ok(provenance) => Ok(String::from(JsonString::from(provenance))),
+        Err(error) => {
+            unlock_chain(lock, context);
+            Err(error)
+        }
+    }
+}
+
+/// Counterparty side of phase 2: commits the countersigned entry with all provenances.
+/// The chain stays locked until the initiator tells us to publish or abort.
+pub async fn handle_countersign_commit(
+    from_agent_id: Address,
+    msg_id: String,
+    commit: CountersignCommit,
+    context: Arc<Context>,
+) {
+    let result = await!(commit_countersigned_entry(&from_agent_id, commit, &context));
+    respond(from_agent_id, msg_id, result, &context);
+}
+
+async fn commit_countersigned_entry<'a>(
+    from_agent_id: &'a Address,
+    commit: CountersignCommit,
+    context: &'a Arc<Context>,
+) -> Result<String, HolochainError> {
+    let entry = commit.entry.clone();
+    let lock = initiator_lock(&entry.address(), from_agent_id, context)?;
+    check_signed_by(&entry, &commit.provenances, from_agent_id)?;
+    let own_address = context.agent_id.address();
+    let other_provenances: Vec<Provenance> = commit
+        .provenances
+        .into_iter()
+        .filter(|provenance| provenance.source() != own_address)
+        .collect();
+    match await!(commit_authored_entry(
+        &entry,
+        None,
+        context,
+        &other_provenances
+    )) {
+        Ok(address) => Ok(address.to_string()),
+        Err(error) => {
+            rollback_chain(lock, context);
+            Err(error)
+        }
+    }
+}
+
+/// Counterparty side of phase 3: publishes the committed countersigned entry
+/// and unlocks our chain.
+pub async fn handle_countersign_publish(
+    from_agent_id: Address,
+    msg_id: String,
+    entry_address: Address,
+    context: Arc<Context>,
+) {
+    let result = await!(publish_countersigned_entry(
+        &from_agent_id,
+        &entry_address,
+        &context
+    ));
+    respond(from_agent_id, msg_id, result, &context);
+}
+
+async fn publish_countersigned_entry<'a>(
+    from_agent_id: &'a Address,
+    entry_address: &'a Address,
+    context: &'a Arc<Context>,
+) -> Result<String, HolochainError> {
+    let lock = initiator_lock(entry_address, from_agent_id, context)?;
+    let committed = context
+        .state()?
+        .agent()
+        .top_chain_header()
+        .filter(|header| header.entry_address() == entry_address)
+        .is_some();
+    let entry = if committed {
+        get_entry_from_agent_chain(context, entry_address)?
+    } else {
+        None
+    }
+    .ok_or_else(|| {
+        HolochainError::ErrorGeneric(format!(
+            "Countersigned entry {} is not committed",
+            entry_address
+        ))
+    })?;
+    unlock_chain(lock, context);
+    await!(publish_authored_entry(&entry, context))?;
+    Ok(String::new())
+}
+
+/// The lock our chain currently has for a countersignature of the given entry,
+/// if the given agent is the one that initiated it
+fn initiator_lock(
+    entry_address: &Address,
+    from_agent_id: &Address,
+    context: &Arc<Context>,
+) -> Result<ChainLock, HolochainError> {
+    let lock = context
+        .state()?
+        .agent()
+        .chain_lock()
+        .filter(|lock| lock.entry_address == *entry_address)
+        .ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!(
+                "No countersignature pending for entry {}",
+                entry_address
+            ))
+        })?;
+    if lock.initiator != *from_agent_id {
+        return Err(HolochainError::ErrorGeneric(format!(
+            "Only the initiator {} of the countersignature for entry {} can finish it",
+            lock.initiator, entry_address
+        )));
+    }
+    Ok(lock)
+}
+
+/// Counterparty side of an aborted countersignature:
+/// rolls back our commit of the entry, if any, and unlocks our chain.
+pub async fn handle_countersign_abort(
+    from_agent_id: Address,
+    msg_id: String,
+    entry_address: Address,
+    context: Arc<Context>,
+) {
+    let result = initiator_lock(&entry_address, &from_agent_id, &context).map(|lock| {
+        rollback_chain(lock, &context);
+        String::new()
+    });
+    respond(from_agent_id, msg_id, result, &context);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{countersign_entry, initiator_lock};
+    use crate::{agent::actions::lock_chain::lock_chain, nucleus::actions::tests::*};
+    use holochain_core_types::{entry::test_entry_with_value, time::Timeout};
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use std::{
+        thread,
+        time::{self, Duration, SystemTime},
+    };
+
+    #[test]
+    /// test that a countersigned entry ends up on both chains, signed by both agents
+    fn test_countersign_entry() {
+        let mut dna = test_dna();
+        dna.uuid = "test_countersign_entry".to_string();
+        let netname = Some("test_countersign_entry, the network");
+        let (_instance1, context1) = instance_by_name("jill", dna.clone(), netname);
+        let (_instance2, context2) = instance_by_name("jack", dna, netname);
+
+        let entry = test_entry_with_value("{\"stuff\":\"countersigned\"}");
+        let address = context1
+            .block_on(countersign_entry(
+                entry.clone(),
+                vec![context2.agent_id.address()],
+                Timeout::new(10000),
+                context1.clone(),
+            ))
+            .expect("Could not countersign entry")
+            .address();
+        assert_eq!(address, entry.address());
+
+        for context in vec![&context1, &context2] {
+            let state = context.state().unwrap();
+            let header = state
+                .agent()
+                .get_most_recent_header_for_entry(&entry)
+                .expect("Countersigned entry must be on both chains");
+            let sources: Vec<_> = header.provenances().iter().map(|p| p.source()).collect();
+            assert!(sources.contains(&context1.agent_id.address()));
+            assert!(sources.contains(&context2.agent_id.address()));
+            assert_eq!(state.agent().chain_lock(), None);
+        }
+    }
+
+    #[test]
+    /// test that countersigning fails and leaves the chain unlocked if the counterparty does not answer
+    fn test_countersign_entry_with_unreachable_counterparty() {
+        let mut dna = test_dna();
+        dna.uuid = "test_countersign_entry_with_unreachable_counterparty".to_string();
+        let netname = Some("test_countersign_entry_with_unreachable_counterparty, the network");
+        let (_instance1, context1) = instance_by_name("jill", dna, netname);
+
+        let entry = test_entry_with_value("{\"stuff\":\"countersigned\"}");
+        assert!(context1
+            .block_on(countersign_entry(
+                entry.clone(),
+                vec!["unknown agent".into()],
+                Timeout::new(1000),
+                context1.clone(),
+            ))
+            .is_err());
+        // unlocking happens asynchronously
+        thread::sleep(time::Duration::from_millis(500));
+        let state = context1.state().unwrap();
+        assert_eq!(state.agent().get_most_recent_header_for_entry(&entry), None);
+        assert_eq!(state.agent().chain_lock(), None);
+    }
+
+    #[test]
+    /// test that only the initiator of a countersignature can commit, publish or abort it
+    fn test_only_initiator_can_finish_countersignature() {
+        let mut dna = test_dna();
+        dna.uuid = "test_only_initiator_can_finish_countersignature".to_string();
+        let netname = Some("test_only_initiator_can_finish_countersignature, the network");
+        let (_instance, context) = instance_by_name("jack", dna, netname);
+
+        let address = test_entry_with_value("{\"stuff\":\"countersigned\"}").address();
+        let lock = context
+            .block_on(lock_chain(
+                address.clone(),
+                "jill".into(),
+                SystemTime::now() + Duration::from_secs(60),
+                &context,
+            ))
+            .expect("Could not lock chain");
+
+        assert!(initiator_lock(&address, &"mallory".into(), &context).is_err());
+        assert_eq!(initiator_lock(&address, &"jill".into(), &context), Ok(lock));
+    }
+}
@@ -42,16 +42,22 @@ pub async fn get_entry_with_meta_workflow<'a>(
         let entry = maybe_entry_with_meta.ok_or_else(|| HolochainError::ErrorGeneric(
             "Could not get entry".to_string(),
         ))?;
-        match context
-            .state()
-            .ok_or_else(|| HolochainError::ErrorGeneric(
-                "Could not get state".to_string(),
-            ))?
-            .get_headers(address.clone())
-        {
-            Ok(headers) => Ok(Some(EntryWithMetaAndHeader {
+        let maybe_headers_and_warrants = {
+            let state = context
+                .state()
+                .ok_or_else(|| HolochainError::ErrorGeneric(
+                    "Could not get state".to_string(),
+                ))?;
+            state.get_headers(address.clone()).map(|headers| {
+                let warrants = state.dht().get_warrants_for_headers(&headers);
+                (headers, warrants)
+            })
+        };
+        match maybe_headers_and_warrants {
+            Ok((headers, warrants)) => Ok(Some(EntryWithMetaAndHeader {
                 entry_with_meta: entry.clone(),
                 headers,
+                warrants,
             })),
             Err(_) => {
                 let response = await!(network::actions::query::query(
@@ -100,6 +106,20 @@ pub async fn get_entry_result_workflow<'a>(
                 }
             }
 
+            // Collect warrants the answering node and we know of against the entry's authors
+            let mut warrants = entry_with_meta_and_headers.warrants.clone();
+            warrants.extend(
+                context
+                    .state()
+                    .ok_or_else(|| HolochainError::ErrorGeneric(
+                        "Could not get state".to_string(),
+                    ))?
+                    .dht()
+                    .get_warrants_for_headers(&entry_with_meta_and_headers.headers),
+            );
+            warrants.sort();
+            warrants.dedup();
+
             // Add entry
             let headers: Vec<ChainHeader> = if args.options.headers {
                 entry_with_meta_and_headers.headers
@@ -107,6 +127,7 @@ pub async fn get_entry_result_workflow<'a>(
                 Vec::new()
             };
             entry_result.push(&entry_with_meta_and_headers.entry_with_meta, headers);
+            entry_result.add_warrants(warrants);
 
             if args.options.status_request == StatusRequestKind::Initial {
                 break;
@@ -42,6 +42,7 @@ pub async fn get_link_result_workflow<'a>(
                     headers: get_entry_crud.headers.unwrap_or_default(),
                     status: get_entry_crud.crud_status,
                     tag: get_entry_crud.tag.clone(),
+                    warrants: get_entry_crud.warrants,
                 })
                 .collect::<Vec<LinksResult>>();
 
@@ -3,13 +3,22 @@ use crate::{
     dht::actions::hold::hold_entry,
     network::entry_with_header::EntryWithHeader,
     nucleus::{
-        actions::add_pending_validation::add_pending_validation, validation::validate_entry,
+        actions::{
+            add_pending_validation::add_pending_validation,
+            cache_validation_result::{
+                cache_validation_result, cached_validation_result, refresh_cached_validation_result,
+                validation_cache_key,
+            },
+        },
+        state::{CachedValidationResult, ValidationCacheKey},
+        validation::validate_entry,
     },
 };
 
 use crate::{
-    nucleus::validation::ValidationError, scheduled_jobs::pending_validations::ValidatingWorkflow,
-    workflows::validation_package,
+    nucleus::validation::ValidationError,
+    scheduled_jobs::pending_validations::{run_pending_validations_waiting_for, ValidatingWorkflow},
+    workflows::{issue_warrant::issue_warrant, validation_package},
 };
 use holochain_core_types::{
     error::HolochainError,
@@ -23,6 +32,56 @@ use std::sync::Arc;
 pub async fn hold_entry_workflow(
     entry_with_header: &EntryWithHeader,
     context: Arc<Context>,
+) -> Result<(), HolochainError> {
+    // 1. Check if we have validated this entry with this header before
+    let cache_key = validation_cache_key(entry_with_header, &context);
+    let cached_result = cache_key
+        .as_ref()
+        .and_then(|key| cached_validation_result(key, &context));
+    // Keep outcomes that get asked for again in the bounded cache
+    if let (Some(key), Some(result)) = (cache_key.clone(), cached_result.clone()) {
+        refresh_cached_validation_result(key, result, &context);
+    }
+
+    match cached_result {
+        Some(CachedValidationResult::Invalid(reason)) => {
+            log_debug!(context, "workflow/hold_entry: Entry {} is already known to be invalid",
+                entry_with_header.entry.address(),
+            );
+            return Err(HolochainError::ValidationFailed(reason));
+        }
+        Some(CachedValidationResult::Valid) => {
+            log_debug!(context, "workflow/hold_entry: Entry {} is already known to be valid, skipping validation",
+                entry_with_header.entry.address(),
+            );
+        }
+        // 2. Otherwise validate it
+        None => await!(validate_entry_to_hold(entry_with_header, cache_key, context.clone()))?,
+    }
+
+    log_debug!(context,
+        "workflow/hold_entry: is valid! {}",
+        entry_with_header.entry.address()
+    );
+
+    // 3. If valid store the entry in the local DHT shard
+    await!(hold_entry(entry_with_header, context.clone()))?;
+
+    log_debug!(context,
+        "workflow/hold_entry: HOLDING: {}",
+        entry_with_header.entry.address()
+    );
+
+    // 4. Wake up validations that were waiting for this entry
+    run_pending_validations_waiting_for(&entry_with_header.entry.address(), context.clone());
+
+    Ok(())
+}
+
+async fn validate_entry_to_hold<'a>(
+    entry_with_header: &'a EntryWithHeader,
+    cache_key: Option<ValidationCacheKey>,
+    context: Arc<Context>,
 ) -> Result<(), HolochainError> {
     // 1. Get hold of validation package
     let maybe_validation_package = await!(validation_package(&entry_with_header, context.clone()))
@@ -52,6 +111,10 @@ pub async fn hold_entry_workflow(
     })?;
     log_debug!(context, "workflow/hold_entry: got validation package");
 
+    // Warrants name the agents of the header we received, so only issue one if the
+    // entry got validated with that header
+    let validated_received_header = validation_package.chain_header == entry_with_header.header;
+
     // 2. Create validation data struct
     let validation_data = ValidationData {
         package: validation_package,
@@ -59,13 +122,31 @@ pub async fn hold_entry_workflow(
     };
 
     // 3. Validate the entry
-    await!(validate_entry(
+    let validation_result = await!(validate_entry(
         entry_with_header.entry.clone(),
         None,
         validation_data,
         &context
-    ))
-    .map_err(|err| {
+    ));
+
+    // 4. Remember conclusive results so we don't have to validate again
+    if let Some(key) = cache_key {
+        cache_validation_result(key, &validation_result, &context);
+    }
+
+    // 5. If the entry is invalid, let others know by issuing a warrant
+    if let Err(ValidationError::Fail(reason)) = &validation_result {
+        if !validated_received_header {
+            log_debug!(context, "workflow/hold_entry: Source sent a different header than the one we got, not issuing a warrant");
+        } else if let Err(error) = await!(issue_warrant(entry_with_header, reason.clone(), &context)) {
+            log_error!(context, "workflow/hold_entry: Could not issue warrant for {}: {}",
+                entry_with_header.entry.address(),
+                error,
+            );
+        }
+    }
+
+    validation_result.map_err(|err| {
         if let ValidationError::UnresolvedDependencies(dependencies) = &err {
             log_debug!(context, "workflow/hold_entry: {} could not be validated due to unresolved dependencies and will be tried later. List of missing dependencies: {:?}",
                 entry_with_header.entry.address(),
@@ -85,22 +166,7 @@ pub async fn hold_entry_workflow(
             );
             HolochainError::from(err)
         }
-    })?;
-
-    log_debug!(context,
-        "workflow/hold_entry: is valid! {}",
-        entry_with_header.entry.address()
-    );
-
-    // 3. If valid store the entry in the local DHT shard
-    await!(hold_entry(entry_with_header, context.clone()))?;
-
-    log_debug!(context,
-        "workflow/hold_entry: HOLDING: {}",
-        entry_with_header.entry.address()
-    );
-
-    Ok(())
+    })
 }
 
 #[cfg(test)]
@@ -8,7 +8,9 @@ use crate::{
         actions::add_pending_validation::add_pending_validation, validation::ValidationError,
     },
     scheduled_jobs::pending_validations::ValidatingWorkflow,
-    workflows::{hold_entry::hold_entry_workflow, validation_package},
+    workflows::{
+        hold_entry::hold_entry_workflow, issue_warrant::issue_warrant, validation_package,
+    },
 };
 use holochain_core_types::{
     entry::Entry,
@@ -58,6 +60,10 @@ pub async fn hold_link_workflow(
     })?;
     log_debug!(context, "workflow/hold_link: got validation package");
 
+    // Warrants name the agents of the header we received, so only issue one if the
+    // entry got validated with that header
+    let validated_received_header = validation_package.chain_header == entry_with_header.header;
+
     // 2. Create validation data struct
     let validation_data = ValidationData {
         package: validation_package,
@@ -66,13 +72,26 @@ pub async fn hold_link_workflow(
 
     // 3. Validate the entry
     log_debug!(context, "workflow/hold_link: validate...");
-    await!(validate_entry(
+    let validation_result = await!(validate_entry(
         entry_with_header.entry.clone(),
         None,
         validation_data,
         &context
-    ))
-    .map_err(|err| {
+    ));
+
+    // 4. If the link is invalid, let others know by issuing a warrant
+    if let Err(ValidationError::Fail(reason)) = &validation_result {
+        if !validated_received_header {
+            log_debug!(context, "workflow/hold_link: Source sent a different header than the one we got, not issuing a warrant");
+        } else if let Err(error) = await!(issue_warrant(entry_with_header, reason.clone(), &context)) {
+            log_error!(context, "workflow/hold_link: Could not issue warrant for {:?}: {}",
+                entry_with_header.entry,
+                error,
+            );
+        }
+    }
+
+    validation_result.map_err(|err| {
         if let ValidationError::UnresolvedDependencies(dependencies) = &err {
             log_debug!(context, "workflow/hold_link: Link could not be validated due to unresolved dependencies and will be tried later. List of missing dependencies: {:?}", dependencies);
             add_pending_validation(
@@ -93,15 +112,15 @@ pub async fn hold_link_workflow(
     })?;
     log_debug!(context, "workflow/hold_link: is valid!");
 
-    // 3. If valid store the entry in the local DHT shard
+    // 5. If valid store the entry in the local DHT shard
     await!(add_link(&link_add, &context))?;
     log_debug!(context, "workflow/hold_link: added! {:?}", link);
 
-    //4. store link_add entry so we have all we need to respond to get links queries without any other network look-up
+    //6. store link_add entry so we have all we need to respond to get links queries without any other network look-up
     await!(hold_entry_workflow(&entry_with_header, context.clone()))?;
     log_debug!(context, "workflow/hold_entry: added! {:?}", entry_with_header);
 
-    //5. Link has been added to EAV and LinkAdd Entry has been stored on the dht
+    //7. Link has been added to EAV and LinkAdd Entry has been stored on the dht
     Ok(())
 }
 
@@ -0,0 +1,211 @@
+use crate::{
+    context::Context, network::entry_with_header::EntryWithHeader,
+    nucleus::validation::validate_entry_header, workflows::author_entry::author_entry,
+};
+use holochain_core_types::{
+    entry::{warrant::Warrant, Entry},
+    error::HolochainError,
+    signature::{Provenance, Signature},
+};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::crypto::CryptoMethod;
+use std::sync::Arc;
+
+/// Creates a warrant for an entry that failed validation, signs it with our key,
+/// commits it to our source chain and publishes it.
+/// Publishing a warrant also sends it to the agent entry of every agent that signed
+/// the invalid entry's header, so other nodes learn about them.
+///
+/// Only headers that belong to the entry and carry valid signatures of all their provenances
+/// get warranted. Otherwise anybody could get agents warranted by sending around invalid
+/// entries with forged headers naming them.
+///
+/// Since signatures are deterministic, issuing a warrant for the same entry, header and
+/// validation error twice results in the same warrant entry, which only gets committed once.
+pub async fn issue_warrant<'a>(
+    entry_with_header: &'a EntryWithHeader,
+    validation_error: String,
+    context: &'a Arc<Context>,
+) -> Result<Address, HolochainError> {
+    let entry = &entry_with_header.entry;
+    let header = &entry_with_header.header;
+    validate_entry_header(entry, header).map_err(|error| {
+        HolochainError::ErrorGeneric(format!(
+            "Refusing to issue a warrant for entry {} with an invalid header: {:?}",
+            entry.address(),
+            error
+        ))
+    })?;
+
+    let payload = Warrant::signing_payload(&entry.address(), &header.address(), &validation_error);
+    let signature = context.conductor_api.execute(payload, CryptoMethod::Sign)?;
+    let warrant = Warrant::new(
+        entry.clone(),
+        header.clone(),
+        validation_error,
+        Provenance::new(context.agent_id.address(), Signature::from(signature)),
+    );
+
+    if warrant
+        .warranted_agents()
+        .contains(&context.agent_id.address())
+    {
+        return Err(HolochainError::ErrorGeneric(
+            "Refusing to issue a warrant against ourselves".to_string(),
+        ));
+    }
+
+    let warrant_entry = Entry::Warrant(warrant);
+    if context
+        .state()
+        .ok_or_else(|| HolochainError::ErrorGeneric("Could not get state".to_string()))?
+        .agent()
+        .get_most_recent_header_for_entry(&warrant_entry)
+        .is_some()
+    {
+        log_debug!(
+            context,
+            "workflow/issue_warrant: warrant {} already issued",
+            warrant_entry.address()
+        );
+        return Ok(warrant_entry.address());
+    }
+
+    log_info!(
+        context,
+        "workflow/issue_warrant: issuing warrant {} for invalid entry {}",
+        warrant_entry.address(),
+        entry.address()
+    );
+    let result = await!(author_entry(&warrant_entry, None, context, &Vec::new()))?;
+    Ok(result.address())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::issue_warrant;
+    use crate::{
+        network::entry_with_header::EntryWithHeader, nucleus::actions::tests::*,
+        workflows::author_entry::author_entry,
+    };
+    use holochain_core_types::{
+        chain_header::ChainHeader,
+        entry::test_entry_with_value,
+        signature::{Provenance, Signature},
+        time::test_iso_8601,
+    };
+    use holochain_persistence_api::cas::content::AddressableContent;
+    use std::{thread, time};
+
+    #[test]
+    /// test that a warrant ends up on the validator's chain and gets published to the
+    /// agent entry of the warranted author
+    fn test_issue_warrant() {
+        let mut dna = test_dna();
+        dna.uuid = "test_issue_warrant".to_string();
+        let netname = Some("test_issue_warrant, the network");
+        let (_instance1, context1) = instance_by_name("jill", dna.clone(), netname);
+        let (_instance2, context2) = instance_by_name("jack", dna, netname);
+
+        let entry = test_entry_with_value("{\"stuff\":\"invalid\"}");
+        context1
+            .block_on(author_entry(&entry, None, &context1, &vec![]))
+            .unwrap();
+        let header = context1
+            .state()
+            .unwrap()
+            .agent()
+            .get_most_recent_header_for_entry(&entry)
+            .expect("There must be a header in the author's source chain after commit");
+        let entry_with_header = EntryWithHeader { entry, header };
+
+        assert!(context1
+            .block_on(issue_warrant(
+                &entry_with_header,
+                "FAIL test".to_string(),
+                &context1
+            ))
+            .is_err());
+
+        let warrant_address = context2
+            .block_on(issue_warrant(
+                &entry_with_header,
+                "FAIL test".to_string(),
+                &context2,
+            ))
+            .expect("Could not issue warrant");
+        let jill = context1.agent_id.address();
+        assert_eq!(
+            context2.state().unwrap().dht().get_warrants(&jill).unwrap(),
+            vec![warrant_address.clone()]
+        );
+
+        // issuing the same warrant again does not commit it twice
+        let chain_length = context2.state().unwrap().agent().iter_chain().count();
+        assert_eq!(
+            context2
+                .block_on(issue_warrant(
+                    &entry_with_header,
+                    "FAIL test".to_string(),
+                    &context2,
+                ))
+                .unwrap(),
+            warrant_address
+        );
+        assert_eq!(
+            context2.state().unwrap().agent().iter_chain().count(),
+            chain_length
+        );
+
+        let mut warranted = false;
+        let mut tries = 0;
+        while !warranted && tries < 10 {
+            tries = tries + 1;
+            warranted = context1.state().unwrap().dht().is_warranted(&jill);
+            if !warranted {
+                thread::sleep(time::Duration::from_millis(1000));
+            }
+        }
+        assert!(warranted);
+    }
+
+    #[test]
+    /// test that an invalid entry sent with a forged header does not get its supposed
+    /// author warranted
+    fn test_no_warrant_for_forged_header() {
+        let mut dna = test_dna();
+        dna.uuid = "test_no_warrant_for_forged_header".to_string();
+        let netname = Some("test_no_warrant_for_forged_header, the network");
+        let (_instance1, context1) = instance_by_name("jill", dna.clone(), netname);
+        let (_instance2, context2) = instance_by_name("jack", dna, netname);
+
+        let entry = test_entry_with_value("{\"stuff\":\"invalid\"}");
+        let jill = context1.agent_id.address();
+        let forged_header = ChainHeader::new(
+            &entry.entry_type(),
+            &entry.address(),
+            &[Provenance::new(jill.clone(), Signature::from("forged"))],
+            &None,
+            &None,
+            &None,
+            &test_iso_8601(),
+        );
+        let entry_with_header = EntryWithHeader {
+            entry,
+            header: forged_header,
+        };
+
+        assert!(context2
+            .block_on(issue_warrant(
+                &entry_with_header,
+                "FAIL test".to_string(),
+                &context2,
+            ))
+            .is_err());
+        assert_eq!(
+            context2.state().unwrap().dht().get_warrants(&jill).unwrap(),
+            Vec::new()
+        );
+        assert!(!context2.state().unwrap().dht().is_warranted(&jill));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod application;
 pub mod author_entry;
+pub mod countersign_entry;
 pub mod get_entry_result;
 pub mod get_link_result;
 pub mod get_links_count;
@@ -8,6 +9,7 @@ pub mod hold_entry;
 pub mod hold_entry_remove;
 pub mod hold_entry_update;
 pub mod hold_link;
+pub mod issue_warrant;
 pub mod remove_link;
 pub mod respond_validation_package_request;
 
@@ -86,7 +88,7 @@ async fn try_make_local_validation_package(
 /// Gets hold of the validation package for the given entry.
 /// First tries to create it locally and if that fails will try to get the
 /// validation package from the source.
-async fn validation_package(
+pub(crate) async fn validation_package(
     entry_with_header: &EntryWithHeader,
     context: Arc<Context>,
 ) -> Result<Option<ValidationPackage>, HolochainError> {
@@ -0,0 +1,17 @@
+extern crate cbindgen;
+
+use std::env;
+
+/// Regenerates include/core_api_c_binding.h from the crate's `extern "C"` functions.
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    match cbindgen::generate(&crate_dir) {
+        Ok(bindings) => {
+            bindings.write_to_file("include/core_api_c_binding.h");
+        }
+        // Don't fail the build over the header, the checked in one keeps working.
+        Err(error) => println!("cargo:warning=Could not generate C header: {}", error),
+    }
+}
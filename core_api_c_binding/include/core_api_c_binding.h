@@ -1,26 +1,147 @@
-//
-// Created by Nicolas Luck on 25.06.18.
-//
-
 #ifndef HOLOCHAIN_RUST_HC_CORE_C_BINDING_H
 #define HOLOCHAIN_RUST_HC_CORE_C_BINDING_H
 
+/* Generated by cbindgen from core_api_c_binding, do not edit by hand. */
+
+#include <stdbool.h>
 #include <stdint.h>
 #include "../../dna_c_binding/include/dna_c_binding.h"
+typedef void Holochain;
+
+typedef enum HolochainErrorCode {
+  HOLOCHAIN_ERROR_CODE_OK = 0,
+  /**
+   * A required pointer argument was null
+   */
+  HOLOCHAIN_ERROR_CODE_NULL_ARGUMENT = 1,
+  /**
+   * A string argument was not valid UTF-8 or a returned string contained a null byte
+   */
+  HOLOCHAIN_ERROR_CODE_INVALID_STRING = 2,
+  /**
+   * The configuration could not be parsed or is inconsistent
+   */
+  HOLOCHAIN_ERROR_CODE_INVALID_CONFIG = 3,
+  /**
+   * No conductor is running, or it failed to boot
+   */
+  HOLOCHAIN_ERROR_CODE_CONDUCTOR_NOT_RUNNING = 4,
+  /**
+   * There is no instance with the given ID
+   */
+  HOLOCHAIN_ERROR_CODE_UNKNOWN_INSTANCE = 5,
+  /**
+   * An argument (e.g. JSON parameters or a provenance) was malformed
+   */
+  HOLOCHAIN_ERROR_CODE_INVALID_ARGUMENT = 6,
+  /**
+   * The zome function could not be called or returned an error from the Ribosome
+   */
+  HOLOCHAIN_ERROR_CODE_ZOME_CALL_FAILED = 7,
+  /**
+   * An admin function failed
+   */
+  HOLOCHAIN_ERROR_CODE_ADMIN_FAILED = 8,
+  /**
+   * A call did not finish in time
+   */
+  HOLOCHAIN_ERROR_CODE_TIMEOUT = 9,
+  /**
+   * Rust code panicked
+   */
+  HOLOCHAIN_ERROR_CODE_PANIC = 10,
+  /**
+   * Any other error
+   */
+  HOLOCHAIN_ERROR_CODE_UNSPECIFIED = 11,
+} HolochainErrorCode;
+
+/**
+ * A booted conductor, created with `holochain_conductor_boot()`
+ * and freed with `holochain_conductor_free()`.
+ */
+typedef struct HolochainConductor HolochainConductor;
+
+typedef char *CStrPtr;
+
+/**
+ * Called with the JSON of every signal the conductor emits, from a thread of the library.
+ * Trace and consistency signals are only passed on if enabled in the config's `signals` section.
+ */
+typedef void (*HolochainSignalCallback)(void *user_data, const char *signal_json);
+
 #ifdef __cplusplus
 extern "C" {
-#endif
+#endif // __cplusplus
 
-typedef void Holochain;
-extern Holochain *holochain_new(Dna*, const char* storage_path);
-extern Holochain *holochain_load(const char* storage_path);
-extern bool holochain_start(Holochain*);
-extern bool holochain_stop(Holochain*);
-extern char* holochain_call(Holochain*, const char* zome, const char* capability, const char* function, const char* parameters);
+CStrPtr holochain_call(Holochain *ptr, CStrPtr zome, CStrPtr token, CStrPtr function, CStrPtr parameters);
 
-#ifdef __cplusplus
-}
-#endif
+/**
+ * Calls an admin function of the conductor, using the same methods and parameters
+ * as admin interfaces (e.g. `admin/instance/add`, `admin/dna/install_from_file`,
+ * `debug/state_dump`). `params_json` has to be a JSON object, or null for no parameters.
+ * The JSON result gets written to `*out`, which has to be freed with `holochain_string_free()`.
+ */
+HolochainErrorCode holochain_conductor_admin(HolochainConductor *conductor, const char *method, const char *params_json, char **out);
+
+/**
+ * Blocks until all effects of the actions of the given instance, or of all instances if
+ * `instance_id` is null, have been observed, or fails with `Timeout` after `timeout_ms`.
+ * Calling this right after a zome call returned waits for all of that call's effects.
+ */
+HolochainErrorCode holochain_conductor_await_consistency(HolochainConductor *conductor, const char *instance_id, uint64_t timeout_ms);
 
+/**
+ * Boots a conductor from the given TOML configuration: unlocks the agent keys, creates
+ * and starts all instances and starts the configured interfaces and UI servers.
+ * Replaces any conductor booted before.
+ * On success, `*out` points to the handle to pass to the other conductor functions.
+ */
+HolochainErrorCode holochain_conductor_boot(const char *toml, HolochainConductor **out);
+
+/**
+ * Calls a zome function of the given instance and writes its JSON result to `*out`,
+ * which has to be freed with `holochain_string_free()`.
+ *
+ * `token` is the capability token to call with, the instance's public token if null.
+ * If `provenance_source` and `provenance_signature` are given, the call is made on behalf
+ * of that agent, otherwise the instance's agent signs the call.
+ */
+HolochainErrorCode holochain_conductor_call(HolochainConductor *conductor, const char *instance_id, const char *zome, const char *function, const char *args_json, const char *token, const char *provenance_source, const char *provenance_signature, char **out);
+
+/**
+ * Shuts the conductor down and frees the handle.
+ * A conductor that replaced this handle's one keeps running.
+ */
+void holochain_conductor_free(HolochainConductor *conductor);
+
+/**
+ * Sets the function that receives the conductor's signals, replacing any set before.
+ * Pass null to stop receiving signals. `user_data` gets passed to the callback unchanged.
+ */
+HolochainErrorCode holochain_conductor_set_signal_callback(HolochainConductor *conductor, HolochainSignalCallback callback, void *user_data);
+
+/**
+ * Returns the message of the last error that happened on the calling thread, or null.
+ * The string is owned by the library and valid until the next failing call on this thread.
+ */
+const char *holochain_last_error_message(void);
+
+Holochain *holochain_load(CStrPtr storage_path);
+
+Holochain *holochain_new(Dna *ptr, CStrPtr storage_path);
+
+bool holochain_start(Holochain *ptr);
+
+bool holochain_stop(Holochain *ptr);
+
+/**
+ * Frees a string returned by the conductor functions.
+ */
+void holochain_string_free(char *s);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
 
-#endif //HOLOCHAIN_RUST_HC_CORE_C_BINDING_H
+#endif /* HOLOCHAIN_RUST_HC_CORE_C_BINDING_H */
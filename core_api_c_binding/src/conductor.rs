@@ -0,0 +1,477 @@
+//! C API for embedding a full conductor, configured through TOML like the `holochain` binary.
+//!
+//! The conductor gets mounted as the process-wide singleton that the admin functions rely on,
+//! so there can only be one running conductor per process. Booting another one replaces it,
+//! and the handle of the replaced conductor can then only be freed.
+//! All functions return a `HolochainErrorCode`, see the `error` module.
+
+use crossbeam_channel::RecvTimeoutError;
+use error::{
+    optional_string_arg, set_holochain_error, set_last_error, string_arg, write_string_result,
+    HolochainErrorCode,
+};
+use holochain_conductor_api::{
+    conductor::{base::Conductor, CONDUCTOR},
+    config::{load_configuration, Configuration},
+    interface::ConductorApiBuilder,
+};
+use holochain_core::{
+    consistency::await_consistency,
+    nucleus::actions::call_zome_function::make_cap_request_for_call,
+    signal::{signal_channel, Signal, SignalReceiver},
+};
+use holochain_core_types::{dna::capabilities::CapabilityRequest, signature::Signature};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::Address;
+use std::{
+    ffi::CString,
+    os::raw::{c_char, c_void},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Called with the JSON of every signal the conductor emits, from a thread of the library.
+/// Trace and consistency signals are only passed on if enabled in the config's `signals` section.
+pub type HolochainSignalCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, signal_json: *const c_char)>;
+
+#[derive(Clone, Copy)]
+struct SignalHandler {
+    callback: unsafe extern "C" fn(*mut c_void, *const c_char),
+    user_data: *mut c_void,
+}
+
+// The caller is responsible for user_data being usable from the signal thread.
+unsafe impl Send for SignalHandler {}
+
+/// A booted conductor, created with `holochain_conductor_boot()`
+/// and freed with `holochain_conductor_free()`.
+pub struct HolochainConductor {
+    id: usize,
+    signal_handler: Arc<Mutex<Option<SignalHandler>>>,
+    signal_thread_kill_switch: Arc<AtomicBool>,
+    signal_thread: Option<thread::JoinHandle<()>>,
+}
+
+static NEXT_HANDLE_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// ID of the handle whose conductor is mounted, 0 if none is.
+/// Only changed while holding the `CONDUCTOR` lock.
+static MOUNTED_HANDLE_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn guarded<F: FnOnce() -> HolochainErrorCode>(f: F) -> HolochainErrorCode {
+    catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| set_last_error(HolochainErrorCode::Panic, "Conductor library panicked"))
+}
+
+macro_rules! try_code {
+    ($result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(code) => return code,
+        }
+    };
+}
+
+/// Runs `f` on the mounted conductor if it is the one booted with the handle `id`.
+fn with_conductor<T, F>(id: usize, f: F) -> Result<T, HolochainErrorCode>
+where
+    F: FnOnce(&mut Conductor) -> Result<T, HolochainErrorCode>,
+{
+    match *CONDUCTOR.lock().unwrap() {
+        Some(ref mut conductor) if MOUNTED_HANDLE_ID.load(Ordering::SeqCst) == id => f(conductor),
+        Some(_) => Err(set_last_error(
+            HolochainErrorCode::ConductorNotRunning,
+            "The conductor of this handle got replaced by a newer one",
+        )),
+        None => Err(set_last_error(
+            HolochainErrorCode::ConductorNotRunning,
+            "No conductor is running",
+        )),
+    }
+}
+
+/// Unmounts the conductor if it is the one booted with the handle `id`.
+fn take_conductor(id: usize) -> Option<Conductor> {
+    let mut mounted = CONDUCTOR.lock().unwrap();
+    if MOUNTED_HANDLE_ID.load(Ordering::SeqCst) == id {
+        MOUNTED_HANDLE_ID.store(0, Ordering::SeqCst);
+        mounted.take()
+    } else {
+        None
+    }
+}
+
+/// Unlocks the agent keys and starts everything the mounted conductor is configured with,
+/// like the `holochain` binary does.
+fn boot_mounted(id: usize, config: Configuration) -> Result<Configuration, HolochainErrorCode> {
+    with_conductor(id, |conductor| {
+        let config_error = |error: String| set_last_error(HolochainErrorCode::InvalidConfig, error);
+        for agent in conductor.config().agents.iter() {
+            conductor
+                .check_load_key_for_agent(&agent.id)
+                .map_err(config_error)?;
+        }
+        conductor.boot_from_config().map_err(config_error)?;
+        conductor.start_all_instances().map_err(|error| {
+            set_last_error(HolochainErrorCode::ConductorNotRunning, error.to_string())
+        })?;
+        conductor.start_all_interfaces();
+        conductor
+            .start_all_static_servers()
+            .map_err(|error| set_last_error(HolochainErrorCode::ConductorNotRunning, error))?;
+        Ok(config)
+    })
+}
+
+fn spawn_signal_thread(
+    config: Configuration,
+    receiver: SignalReceiver,
+    handler: Arc<Mutex<Option<SignalHandler>>>,
+    kill_switch: Arc<AtomicBool>,
+) -> thread::JoinHandle<()> {
+    thread::Builder::new()
+        .name("c_binding_signals".to_string())
+        .spawn(move || {
+            while !kill_switch.load(Ordering::Relaxed) {
+                let signal = match receiver.recv_timeout(Duration::from_millis(100)) {
+                    Ok(signal) => signal,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    // The conductor is gone, e.g. replaced by a newer one.
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                let wanted = match signal {
+                    Signal::Trace(_) => config.signals.trace,
+                    Signal::Consistency(_) => config.signals.consistency,
+                    Signal::User(_) => true,
+                };
+                if !wanted {
+                    continue;
+                }
+                // Don't hold the lock during the callback, so it can set another one.
+                let current_handler = *handler.lock().unwrap();
+                if let Some(handler) = current_handler {
+                    if let Ok(json) = CString::new(String::from(JsonString::from(signal))) {
+                        unsafe { (handler.callback)(handler.user_data, json.as_ptr()) };
+                    }
+                }
+            }
+        })
+        .expect("Must be able to spawn thread")
+}
+
+/// Boots a conductor from the given TOML configuration: unlocks the agent keys, creates
+/// and starts all instances and starts the configured interfaces and UI servers.
+/// Replaces any conductor booted before.
+/// On success, `*out` points to the handle to pass to the other conductor functions.
+#[no_mangle]
+pub unsafe extern "C" fn holochain_conductor_boot(
+    toml: *const c_char,
+    out: *mut *mut HolochainConductor,
+) -> HolochainErrorCode {
+    guarded(|| {
+        if out.is_null() {
+            return set_last_error(HolochainErrorCode::NullArgument, "out must not be null");
+        }
+        let toml = try_code!(string_arg("toml", toml));
+
+        // The signal channel has to be in place before the instances get created.
+        let (signal_tx, signal_rx) = signal_channel();
+        let config = try_code!(load_configuration::<Configuration>(&toml)
+            .map_err(|error| set_holochain_error(HolochainErrorCode::InvalidConfig, error)));
+        let conductor = Conductor::from_config(config.clone()).with_signal_channel(signal_tx);
+        let id = NEXT_HANDLE_ID.fetch_add(1, Ordering::SeqCst);
+        let replaced = {
+            let mut mounted = CONDUCTOR.lock().unwrap();
+            MOUNTED_HANDLE_ID.store(id, Ordering::SeqCst);
+            mounted.replace(conductor)
+        };
+        drop(replaced);
+        let config = match boot_mounted(id, config) {
+            Ok(config) => config,
+            Err(code) => {
+                drop(take_conductor(id));
+                return code;
+            }
+        };
+
+        let signal_handler = Arc::new(Mutex::new(None));
+        let kill_switch = Arc::new(AtomicBool::new(false));
+        let signal_thread = spawn_signal_thread(
+            config,
+            signal_rx,
+            signal_handler.clone(),
+            kill_switch.clone(),
+        );
+        *out = Box::into_raw(Box::new(HolochainConductor {
+            id,
+            signal_handler,
+            signal_thread_kill_switch: kill_switch,
+            signal_thread: Some(signal_thread),
+        }));
+        HolochainErrorCode::Ok
+    })
+}
+
+/// Shuts the conductor down and frees the handle.
+/// A conductor that replaced this handle's one keeps running.
+#[no_mangle]
+pub unsafe extern "C" fn holochain_conductor_free(conductor: *mut HolochainConductor) {
+    if conductor.is_null() {
+        return;
+    }
+    let mut conductor = Box::from_raw(conductor);
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        drop(take_conductor(conductor.id));
+        conductor
+            .signal_thread_kill_switch
+            .store(true, Ordering::Relaxed);
+        if let Some(signal_thread) = conductor.signal_thread.take() {
+            let _ = signal_thread.join();
+        }
+    }));
+}
+
+/// Sets the function that receives the conductor's signals, replacing any set before.
+/// Pass null to stop receiving signals. `user_data` gets passed to the callback unchanged.
+#[no_mangle]
+pub unsafe extern "C" fn holochain_conductor_set_signal_callback(
+    conductor: *mut HolochainConductor,
+    callback: HolochainSignalCallback,
+    user_data: *mut c_void,
+) -> HolochainErrorCode {
+    if conductor.is_null() {
+        return set_last_error(
+            HolochainErrorCode::NullArgument,
+            "conductor must not be null",
+        );
+    }
+    *(*conductor).signal_handler.lock().unwrap() = callback.map(|callback| SignalHandler {
+        callback,
+        user_data,
+    });
+    HolochainErrorCode::Ok
+}
+
+/// Calls a zome function of the given instance and writes its JSON result to `*out`,
+/// which has to be freed with `holochain_string_free()`.
+///
+/// `token` is the capability token to call with, the instance's public token if null.
+/// If `provenance_source` and `provenance_signature` are given, the call is made on behalf
+/// of that agent, otherwise the instance's agent signs the call.
+#[no_mangle]
+pub unsafe extern "C" fn holochain_conductor_call(
+    conductor: *mut HolochainConductor,
+    instance_id: *const c_char,
+    zome: *const c_char,
+    function: *const c_char,
+    args_json: *const c_char,
+    token: *const c_char,
+    provenance_source: *const c_char,
+    provenance_signature: *const c_char,
+    out: *mut *mut c_char,
+) -> HolochainErrorCode {
+    guarded(|| {
+        if conductor.is_null() {
+            return set_last_error(
+                HolochainErrorCode::NullArgument,
+                "conductor must not be null",
+            );
+        }
+        let instance_id = try_code!(string_arg("instance_id", instance_id));
+        let zome = try_code!(string_arg("zome", zome));
+        let function = try_code!(string_arg("function", function));
+        let args = try_code!(string_arg("args_json", args_json));
+        let token = try_code!(optional_string_arg("token", token));
+        let provenance = match (
+            try_code!(optional_string_arg("provenance_source", provenance_source)),
+            try_code!(optional_string_arg(
+                "provenance_signature",
+                provenance_signature
+            )),
+        ) {
+            (Some(source), Some(signature)) => Some((source, signature)),
+            (None, None) => None,
+            _ => {
+                return set_last_error(
+                    HolochainErrorCode::InvalidArgument,
+                    "provenance_source and provenance_signature have to be given together",
+                )
+            }
+        };
+
+        // Don't block the conductor while the zome function runs.
+        let instance = try_code!(with_conductor((*conductor).id, |conductor| conductor
+            .instances()
+            .get(&instance_id)
+            .cloned()
+            .ok_or_else(|| set_last_error(
+                HolochainErrorCode::UnknownInstance,
+                format!("No instance with ID {}", instance_id)
+            ))));
+        let mut holochain = instance.write().unwrap();
+        let context = try_code!(holochain.context().map_err(|error| set_last_error(
+            HolochainErrorCode::ConductorNotRunning,
+            error.to_string()
+        )));
+        let token = match token {
+            Some(token) => Address::from(token),
+            None => try_code!(context.get_public_token().map_err(|error| {
+                set_holochain_error(HolochainErrorCode::InvalidArgument, error)
+            })),
+        };
+        let cap_request = match provenance {
+            Some((source, signature)) => {
+                CapabilityRequest::new(token, Address::from(source), Signature::from(signature))
+            }
+            None => {
+                make_cap_request_for_call(context, token, &function, JsonString::from_json(&args))
+            }
+        };
+
+        match holochain.call(&zome, cap_request, &function, &args) {
+            Ok(result) => write_string_result(out, String::from(result)),
+            Err(error) => set_last_error(HolochainErrorCode::ZomeCallFailed, error.to_string()),
+        }
+    })
+}
+
+/// Calls an admin function of the conductor, using the same methods and parameters
+/// as admin interfaces (e.g. `admin/instance/add`, `admin/dna/install_from_file`,
+/// `debug/state_dump`). `params_json` has to be a JSON object, or null for no parameters.
+/// The JSON result gets written to `*out`, which has to be freed with `holochain_string_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn holochain_conductor_admin(
+    conductor: *mut HolochainConductor,
+    method: *const c_char,
+    params_json: *const c_char,
+    out: *mut *mut c_char,
+) -> HolochainErrorCode {
+    guarded(|| {
+        if conductor.is_null() {
+            return set_last_error(
+                HolochainErrorCode::NullArgument,
+                "conductor must not be null",
+            );
+        }
+        let method = try_code!(string_arg("method", method));
+        let params = match try_code!(optional_string_arg("params_json", params_json)) {
+            Some(params) => try_code!(serde_json::from_str::<serde_json::Value>(&params).map_err(
+                |error| set_last_error(
+                    HolochainErrorCode::InvalidArgument,
+                    format!("params_json is not valid JSON: {}", error)
+                )
+            )),
+            None => json!({}),
+        };
+
+        // The admin functions lock the conductor themselves.
+        try_code!(with_conductor((*conductor).id, |_| Ok(())));
+        let handler = ConductorApiBuilder::new()
+            .with_admin_dna_functions()
+            .with_admin_ui_functions()
+            .with_debug_functions(String::from("c_binding"))
+            .spawn();
+        let request = json!({"jsonrpc": "2.0", "id": 0, "method": method, "params": params});
+        let response = try_code!(handler
+            .handle_request_sync(&request.to_string())
+            .ok_or_else(|| set_last_error(
+                HolochainErrorCode::AdminFailed,
+                "Admin function returned no response"
+            )));
+        let mut response: serde_json::Value = try_code!(serde_json::from_str(&response)
+            .map_err(|error| set_last_error(HolochainErrorCode::AdminFailed, error.to_string())));
+        match response.get("error") {
+            Some(error) => set_last_error(
+                HolochainErrorCode::AdminFailed,
+                error["message"].as_str().unwrap_or("Admin function failed"),
+            ),
+            None => write_string_result(out, response["result"].take().to_string()),
+        }
+    })
+}
+
+/// Blocks until all effects of the actions of the given instance, or of all instances if
+/// `instance_id` is null, have been observed, or fails with `Timeout` after `timeout_ms`.
+/// Calling this right after a zome call returned waits for all of that call's effects.
+#[no_mangle]
+pub unsafe extern "C" fn holochain_conductor_await_consistency(
+    conductor: *mut HolochainConductor,
+    instance_id: *const c_char,
+    timeout_ms: u64,
+) -> HolochainErrorCode {
+    guarded(|| {
+        if conductor.is_null() {
+            return set_last_error(
+                HolochainErrorCode::NullArgument,
+                "conductor must not be null",
+            );
+        }
+        let instance_id = try_code!(optional_string_arg("instance_id", instance_id));
+        // Wait without holding the conductor lock, so the instances can keep working.
+        let tracker = try_code!(with_conductor((*conductor).id, |conductor| Ok(
+            conductor.consistency_tracker()
+        )));
+        let timeout = Duration::from_millis(timeout_ms);
+        try_code!(
+            await_consistency(&tracker, instance_id.as_ref().map(String::as_str), timeout).map_err(
+                |pending| set_last_error(
+                    HolochainErrorCode::Timeout,
+                    format!(
+                        "Instances not consistent after {:?}, still waiting for: {:?}",
+                        timeout, pending
+                    )
+                )
+            )
+        );
+        HolochainErrorCode::Ok
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::ptr;
+
+    #[test]
+    fn boot_fails_with_invalid_config() {
+        let toml = CString::new("this is not toml").unwrap();
+        let mut conductor: *mut HolochainConductor = ptr::null_mut();
+        assert_eq!(
+            unsafe { holochain_conductor_boot(toml.as_ptr(), &mut conductor) },
+            HolochainErrorCode::InvalidConfig
+        );
+        assert!(conductor.is_null());
+    }
+
+    #[test]
+    fn functions_check_their_arguments() {
+        let mut out: *mut c_char = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                holochain_conductor_call(
+                    ptr::null_mut(),
+                    ptr::null(),
+                    ptr::null(),
+                    ptr::null(),
+                    ptr::null(),
+                    ptr::null(),
+                    ptr::null(),
+                    ptr::null(),
+                    &mut out,
+                ),
+                HolochainErrorCode::NullArgument
+            );
+            assert_eq!(
+                holochain_conductor_admin(ptr::null_mut(), ptr::null(), ptr::null(), &mut out),
+                HolochainErrorCode::NullArgument
+            );
+        }
+        assert!(out.is_null());
+    }
+}
@@ -0,0 +1,184 @@
+//! Error codes returned by the conductor functions of the C API.
+//!
+//! Every function that can fail returns a `HolochainErrorCode`. Results get written to
+//! out-parameters, and the message describing the last error is kept per thread
+//! and can be read with `holochain_last_error_message()`.
+
+use holochain_core_types::error::HolochainError;
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    ptr,
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HolochainErrorCode {
+    Ok = 0,
+    /// A required pointer argument was null
+    NullArgument = 1,
+    /// A string argument was not valid UTF-8 or a returned string contained a null byte
+    InvalidString = 2,
+    /// The configuration could not be parsed or is inconsistent
+    InvalidConfig = 3,
+    /// No conductor is running, or it failed to boot
+    ConductorNotRunning = 4,
+    /// There is no instance with the given ID
+    UnknownInstance = 5,
+    /// An argument (e.g. JSON parameters or a provenance) was malformed
+    InvalidArgument = 6,
+    /// The zome function could not be called or returned an error from the Ribosome
+    ZomeCallFailed = 7,
+    /// An admin function failed
+    AdminFailed = 8,
+    /// A call did not finish in time
+    Timeout = 9,
+    /// Rust code panicked
+    Panic = 10,
+    /// Any other error
+    Unspecified = 11,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Stores the message of the last error of this thread and returns its code.
+pub(crate) fn set_last_error<S: Into<String>>(
+    code: HolochainErrorCode,
+    message: S,
+) -> HolochainErrorCode {
+    let message = CString::new(message.into().replace('\0', ""))
+        .expect("Null bytes got removed from the message");
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+    code
+}
+
+/// Maps a HolochainError to the closest error code, falling back to the given one.
+pub(crate) fn set_holochain_error(
+    fallback: HolochainErrorCode,
+    error: HolochainError,
+) -> HolochainErrorCode {
+    let code = match error {
+        HolochainError::ConfigError(_) => HolochainErrorCode::InvalidConfig,
+        HolochainError::Timeout => HolochainErrorCode::Timeout,
+        _ => fallback,
+    };
+    set_last_error(code, error.to_string())
+}
+
+/// Reads a string argument, failing with the according error code.
+pub(crate) unsafe fn string_arg(
+    name: &str,
+    ptr: *const c_char,
+) -> Result<String, HolochainErrorCode> {
+    if ptr.is_null() {
+        return Err(set_last_error(
+            HolochainErrorCode::NullArgument,
+            format!("{} must not be null", name),
+        ));
+    }
+    CStr::from_ptr(ptr).to_str().map(String::from).map_err(|_| {
+        set_last_error(
+            HolochainErrorCode::InvalidString,
+            format!("{} is not valid UTF-8", name),
+        )
+    })
+}
+
+/// Like `string_arg` but treats null as not given.
+pub(crate) unsafe fn optional_string_arg(
+    name: &str,
+    ptr: *const c_char,
+) -> Result<Option<String>, HolochainErrorCode> {
+    if ptr.is_null() {
+        Ok(None)
+    } else {
+        string_arg(name, ptr).map(Some)
+    }
+}
+
+/// Hands a string to the caller, who has to free it with `holochain_string_free()`.
+pub(crate) unsafe fn write_string_result(
+    out: *mut *mut c_char,
+    value: String,
+) -> HolochainErrorCode {
+    if out.is_null() {
+        return set_last_error(HolochainErrorCode::NullArgument, "out must not be null");
+    }
+    match CString::new(value.trim_end_matches(char::from(0))) {
+        Ok(value) => {
+            *out = value.into_raw();
+            HolochainErrorCode::Ok
+        }
+        Err(_) => set_last_error(
+            HolochainErrorCode::InvalidString,
+            "Result contains a null byte",
+        ),
+    }
+}
+
+/// Returns the message of the last error that happened on the calling thread, or null.
+/// The string is owned by the library and valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn holochain_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map(|message| message.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+/// Frees a string returned by the conductor functions.
+#[no_mangle]
+pub unsafe extern "C" fn holochain_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        CString::from_raw(s);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn last_error_message_is_kept_per_thread() {
+        assert_eq!(
+            set_holochain_error(
+                HolochainErrorCode::Unspecified,
+                HolochainError::ConfigError("no agents".to_string())
+            ),
+            HolochainErrorCode::InvalidConfig
+        );
+        let message = unsafe { CStr::from_ptr(holochain_last_error_message()) };
+        assert!(message.to_str().unwrap().contains("no agents"));
+
+        std::thread::spawn(|| assert!(holochain_last_error_message().is_null()))
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn string_args_are_checked() {
+        unsafe {
+            assert_eq!(
+                string_arg("zome", ptr::null()),
+                Err(HolochainErrorCode::NullArgument)
+            );
+            assert_eq!(optional_string_arg("token", ptr::null()), Ok(None));
+            let zome = CString::new("blog").unwrap();
+            assert_eq!(string_arg("zome", zome.as_ptr()), Ok("blog".to_string()));
+
+            let mut out: *mut c_char = ptr::null_mut();
+            assert_eq!(
+                write_string_result(&mut out, "{}".to_string()),
+                HolochainErrorCode::Ok
+            );
+            assert_eq!(CStr::from_ptr(out).to_str().unwrap(), "{}");
+            holochain_string_free(out);
+        }
+    }
+}
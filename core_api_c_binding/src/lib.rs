@@ -1,8 +1,21 @@
+//! C API for embedding Holochain in native apps.
+//!
+//! Besides running a single `Holochain` instance with the `holochain_*` functions below,
+//! a full conductor can be booted from a TOML config with the `holochain_conductor_*`
+//! functions in the `conductor` module.
+//! The C header in `include/` gets generated from this crate with cbindgen on build.
 #![warn(unused_extern_crates)]
+extern crate crossbeam_channel;
 extern crate holochain_conductor_api;
 extern crate holochain_core;
 extern crate holochain_core_types;
+extern crate holochain_json_api;
 extern crate holochain_persistence_api;
+#[macro_use]
+extern crate serde_json;
+
+pub mod conductor;
+pub mod error;
 
 use holochain_conductor_api::{context_builder::ContextBuilder, Holochain};
 use holochain_core::{context::Context, logger::Logger};
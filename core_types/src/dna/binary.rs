@@ -0,0 +1,183 @@
+//! Compact binary DNA format.
+//!
+//! A `.dna.json` file embeds the WASM of every zome as base64 inside JSON which is slow
+//! to parse and big to distribute. The binary format stores the same DNA as:
+//!
+//! ```text
+//! magic "HCDNA" | format version (u8) | zlib compressed payload
+//! ```
+//!
+//! where the payload consists of:
+//!
+//! ```text
+//! manifest length (u32) | manifest
+//! section count (u32) | { zome name length (u32) | zome name | wasm length (u32) | wasm }*
+//! ```
+//!
+//! The manifest is the DNA's JSON with all zome code left empty, the WASM of each zome
+//! is stored raw in its own section. All numbers are little endian.
+//! Since the DNA gets rebuilt into the same `Dna` struct, its address is the same as for
+//! the JSON form.
+use crate::{
+    dna::{wasm::DnaWasm, Dna},
+    error::{HcResult, HolochainError},
+};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use holochain_json_api::json::JsonString;
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+};
+
+/// Bytes every binary DNA starts with
+pub const DNA_BINARY_MAGIC: &[u8] = b"HCDNA";
+/// Version of the binary layout written by `Dna::to_binary()`
+pub const DNA_BINARY_VERSION: u8 = 1;
+
+/// Returns true if the given bytes look like a binary DNA (as opposed to JSON)
+pub fn is_binary_dna(bytes: &[u8]) -> bool {
+    bytes.starts_with(DNA_BINARY_MAGIC)
+}
+
+fn format_error<S: Into<String>>(message: S) -> HolochainError {
+    HolochainError::SerializationError(format!("Invalid binary DNA: {}", message.into()))
+}
+
+fn write_section<W: Write>(writer: &mut W, bytes: &[u8]) -> HcResult<()> {
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_u32(bytes: &[u8], pos: &mut usize) -> HcResult<u32> {
+    let slice = bytes
+        .get(*pos..*pos + 4)
+        .ok_or_else(|| format_error("unexpected end of data"))?;
+    *pos += 4;
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(slice);
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_section<'a>(bytes: &'a [u8], pos: &mut usize) -> HcResult<&'a [u8]> {
+    let length = read_u32(bytes, pos)? as usize;
+    let section = bytes
+        .get(*pos..*pos + length)
+        .ok_or_else(|| format_error("unexpected end of data"))?;
+    *pos += length;
+    Ok(section)
+}
+
+impl Dna {
+    /// Serializes this DNA into the compact binary format described in the module docs.
+    pub fn to_binary(&self) -> HcResult<Vec<u8>> {
+        let mut manifest = self.clone();
+        for zome in manifest.zomes.values_mut() {
+            zome.code = DnaWasm::new();
+        }
+        let manifest = String::from(JsonString::from(manifest));
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        write_section(&mut encoder, manifest.as_bytes())?;
+        encoder.write_all(&(self.zomes.len() as u32).to_le_bytes())?;
+        for (zome_name, zome) in self.zomes.iter() {
+            write_section(&mut encoder, zome_name.as_bytes())?;
+            write_section(&mut encoder, &zome.code.code)?;
+        }
+
+        let mut binary = DNA_BINARY_MAGIC.to_vec();
+        binary.push(DNA_BINARY_VERSION);
+        binary.extend(encoder.finish()?);
+        Ok(binary)
+    }
+
+    /// Reads a DNA from the compact binary format described in the module docs.
+    pub fn from_binary(bytes: &[u8]) -> HcResult<Dna> {
+        if !is_binary_dna(bytes) {
+            return Err(format_error("missing header"));
+        }
+        let version = *bytes
+            .get(DNA_BINARY_MAGIC.len())
+            .ok_or_else(|| format_error("missing format version"))?;
+        if version != DNA_BINARY_VERSION {
+            return Err(format_error(format!(
+                "unsupported format version {}",
+                version
+            )));
+        }
+
+        let mut payload = Vec::new();
+        ZlibDecoder::new(&bytes[DNA_BINARY_MAGIC.len() + 1..])
+            .read_to_end(&mut payload)
+            .map_err(|e| format_error(format!("could not decompress: {}", e)))?;
+
+        let mut pos = 0;
+        let manifest = std::str::from_utf8(read_section(&payload, &mut pos)?)?;
+        let mut dna = Dna::try_from(JsonString::from_json(manifest))?;
+
+        let section_count = read_u32(&payload, &mut pos)?;
+        for _ in 0..section_count {
+            let zome_name = std::str::from_utf8(read_section(&payload, &mut pos)?)?;
+            let wasm = read_section(&payload, &mut pos)?;
+            let zome = dna.zomes.get_mut(zome_name).ok_or_else(|| {
+                format_error(format!(
+                    "WASM section for zome \"{}\" which is not in the manifest",
+                    zome_name
+                ))
+            })?;
+            zome.code = DnaWasm::from_bytes(wasm.to_vec());
+        }
+        Ok(dna)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::dna::zome::Zome;
+    use holochain_persistence_api::cas::content::AddressableContent;
+
+    fn test_dna() -> Dna {
+        let mut dna = Dna::new();
+        dna.name = "binary test".to_string();
+        dna.properties = json!({"answer": 42});
+        let mut zome = Zome::default();
+        zome.code = DnaWasm::from_bytes(vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]);
+        dna.zomes.insert("one".to_string(), zome.clone());
+        zome.code = DnaWasm::from_bytes(vec![1, 2, 3, 4, 5]);
+        dna.zomes.insert("two".to_string(), zome);
+        dna
+    }
+
+    #[test]
+    fn test_binary_roundtrip_keeps_address() {
+        let dna = test_dna();
+        let binary = dna.to_binary().unwrap();
+        assert!(is_binary_dna(&binary));
+
+        let restored = Dna::from_binary(&binary).unwrap();
+        assert_eq!(restored, dna);
+        assert_eq!(restored.address(), dna.address());
+        assert_eq!(*restored.zomes["two"].code.code, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_binary_is_smaller_than_json() {
+        let mut dna = test_dna();
+        dna.zomes.get_mut("one").unwrap().code = DnaWasm::from_bytes(vec![7; 10000]);
+        let json = String::from(JsonString::from(dna.clone()));
+        assert!(dna.to_binary().unwrap().len() < json.len());
+    }
+
+    #[test]
+    fn test_from_binary_rejects_invalid_input() {
+        assert!(Dna::from_binary(b"{\"name\": \"json\"}").is_err());
+
+        let mut binary = test_dna().to_binary().unwrap();
+        binary[DNA_BINARY_MAGIC.len()] = DNA_BINARY_VERSION + 1;
+        assert!(Dna::from_binary(&binary).is_err());
+
+        let binary = test_dna().to_binary().unwrap();
+        assert!(Dna::from_binary(&binary[..binary.len() / 2]).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::time::Timeout;
+use std::convert::TryFrom;
+
+/// DHT parameters of a DNA.
+///
+/// They are part of the DNA and therefore of its hash, so every agent of an app
+/// runs with the same settings. Parameters that are not set fall back to the defaults
+/// of the network backend and core. An empty `dht` object is left out of the serialized
+/// DNA so it doesn't change the hash of DNAs that don't set any parameters.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DhtConfig {
+    /// How many agents should hold each entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redundancy: Option<u32>,
+
+    /// Milliseconds between two rounds of gossip with other nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gossip_interval_ms: Option<u64>,
+
+    /// Milliseconds without gossip after which a node is considered gone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gossip_timeout_threshold_ms: Option<u64>,
+
+    /// Maximum size of the serialized content of an entry in bytes.
+    /// Entries that are bigger fail validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_entry_size: Option<usize>,
+
+    /// Timeout in milliseconds of get and get_links queries that don't set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_timeout_ms: Option<u64>,
+}
+
+impl DhtConfig {
+    pub fn is_default(&self) -> bool {
+        *self == DhtConfig::default()
+    }
+
+    /// The timeout to use for a query that was requested with the given one.
+    /// Only replaces the HDK's default timeout, so explicitly set ones are kept.
+    pub fn query_timeout(&self, requested: Timeout) -> Timeout {
+        match self.default_timeout_ms {
+            Some(timeout_ms) if requested == Timeout::default() => {
+                Timeout::new(usize::try_from(timeout_ms).unwrap_or_else(|_| usize::max_value()))
+            }
+            _ => requested,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn empty_config_is_not_serialized() {
+        let config: DhtConfig = serde_json::from_str("{}").unwrap();
+        assert!(config.is_default());
+
+        let config: DhtConfig =
+            serde_json::from_str(r#"{"redundancy": 5, "max_entry_size": 1024}"#).unwrap();
+        assert_eq!(config.redundancy, Some(5));
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"redundancy":5,"max_entry_size":1024}"#
+        );
+    }
+
+    #[test]
+    fn only_replaces_default_timeouts() {
+        let config = DhtConfig {
+            default_timeout_ms: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(config.query_timeout(Timeout::default()), Timeout::new(1000));
+        assert_eq!(config.query_timeout(Timeout::new(5)), Timeout::new(5));
+        let config: DhtConfig =
+            serde_json::from_str(r#"{"default_timeout_ms": 4294967296}"#).unwrap();
+        assert_eq!(config.default_timeout_ms, Some(4_294_967_296));
+        assert_eq!(
+            DhtConfig::default().query_timeout(Timeout::default()),
+            Timeout::default()
+        );
+    }
+}
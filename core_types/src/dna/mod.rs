@@ -24,8 +24,10 @@
 //! assert_eq!(name, dna2.name);
 //! ```
 
+pub mod binary;
 pub mod bridges;
 pub mod capabilities;
+pub mod dht;
 pub mod entry_types;
 pub mod fn_declarations;
 pub mod traits;
@@ -35,6 +37,7 @@ pub mod zome;
 use crate::{
     dna::{
         bridges::Bridge,
+        dht::DhtConfig,
         entry_types::EntryTypeDef,
         fn_declarations::{FnDeclaration, TraitFns},
     },
@@ -98,6 +101,10 @@ pub struct Dna {
     /// An array of zomes associated with your holochain application.
     #[serde(default)]
     pub zomes: BTreeMap<String, zome::Zome>,
+
+    /// Parameters of the DHT this DNA's agents share.
+    #[serde(default, skip_serializing_if = "DhtConfig::is_default")]
+    pub dht: DhtConfig,
 }
 
 impl AddressableContent for Dna {
@@ -121,6 +128,7 @@ impl Default for Dna {
             dna_spec_version: String::from("2.0"),
             properties: empty_object(),
             zomes: BTreeMap::new(),
+            dht: DhtConfig::default(),
         }
     }
 }
@@ -45,6 +45,7 @@ pub enum Attribute {
     RemovedLink(String, String),
     PendingEntry,
     Target,
+    Warrant,
 }
 
 impl Default for Attribute {
@@ -84,6 +85,7 @@ impl fmt::Display for Attribute {
             }
             Attribute::PendingEntry => write!(f, "pending-entry"),
             Attribute::Target => write!(f, "target"),
+            Attribute::Warrant => write!(f, "warrant"),
         }
     }
 }
@@ -117,6 +119,7 @@ impl TryFrom<&str> for Attribute {
                 "link_remove" => Ok(LinkRemove),
                 "pending-entry" => Ok(PendingEntry),
                 "target" => Ok(Target),
+                "warrant" => Ok(Warrant),
                 a => Err(AttributeError::Unrecognized(a.to_string())),
             }
         }
@@ -0,0 +1,539 @@
+//! An in-memory EAVI store that keeps secondary indices on entity, attribute and value
+//! next to the primary ordering by index, so that queries which constrain any of those
+//! only look at the matching records instead of scanning the whole store.
+
+use crate::eav::eavi::{Attribute, Entity, EntityAttributeValueIndex, Index, Value};
+use holochain_persistence_api::{
+    eav::{query::EaviQuery, storage::EntityAttributeValueStorage, IndexFilter},
+    error::{PersistenceError, PersistenceResult},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    hash::Hash,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+/// Number of records a streaming iterator takes from the store per lock acquisition.
+pub const EAVI_ITER_BATCH_SIZE: usize = 256;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Indices {
+    by_index: BTreeMap<Index, EntityAttributeValueIndex>,
+    by_entity: HashMap<Entity, BTreeSet<Index>>,
+    by_attribute: HashMap<Attribute, BTreeSet<Index>>,
+    by_value: HashMap<Value, BTreeSet<Index>>,
+}
+
+/// Once a query is narrowed down to this many records, the remaining indices are not
+/// consulted anymore and the query's filters get run on those records directly.
+const SMALL_SELECTION: usize = 32;
+
+/// Exact entity, attribute and value a query is known to be restricted to.
+///
+/// `EavFilter`s are opaque predicates, so without these the store has to run a filter on
+/// every key of an index to find the matching buckets. Keys given here get looked up
+/// directly instead. They have to agree with the query's filters.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EaviKeys {
+    pub entity: Option<Entity>,
+    pub attribute: Option<Attribute>,
+    pub value: Option<Value>,
+}
+
+/// Returns the union of the buckets whose key passes `check`,
+/// or None if that would not be smaller than `limit`.
+fn select<K: Eq + Hash + Clone>(
+    index: &HashMap<K, BTreeSet<Index>>,
+    check: impl Fn(K) -> bool,
+    limit: usize,
+) -> Option<BTreeSet<Index>> {
+    let buckets: Vec<&BTreeSet<Index>> = index
+        .iter()
+        .filter(|(key, _)| check((*key).clone()))
+        .map(|(_, bucket)| bucket)
+        .collect();
+    if buckets.iter().map(|bucket| bucket.len()).sum::<usize>() >= limit {
+        return None;
+    }
+    Some(buckets.into_iter().flatten().cloned().collect())
+}
+
+impl Indices {
+    /// Returns the first index starting from the given one that no record uses yet.
+    pub(crate) fn free_index(&self, mut index: Index) -> Index {
+        while self.by_index.contains_key(&index) {
+            index += 1;
+        }
+        index
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        mut eavi: EntityAttributeValueIndex,
+    ) -> EntityAttributeValueIndex {
+        let index = self.free_index(eavi.index());
+        eavi.set_index(index);
+        self.by_entity
+            .entry(eavi.entity())
+            .or_insert_with(BTreeSet::new)
+            .insert(index);
+        self.by_attribute
+            .entry(eavi.attribute())
+            .or_insert_with(BTreeSet::new)
+            .insert(index);
+        self.by_value
+            .entry(eavi.value())
+            .or_insert_with(BTreeSet::new)
+            .insert(index);
+        self.by_index.insert(index, eavi.clone());
+        eavi
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.by_index.len()
+    }
+
+    pub(crate) fn records(&self) -> impl Iterator<Item = &EntityAttributeValueIndex> {
+        self.by_index.values()
+    }
+
+    /// Picks the records a query could possibly match, looking up the given exact keys
+    /// first and then narrowing down with the other indices until the selection is small.
+    /// The query itself still has to be run on the result.
+    pub(crate) fn candidates(
+        &self,
+        query: &EaviQuery<Attribute>,
+        keys: &EaviKeys,
+    ) -> Vec<EntityAttributeValueIndex> {
+        let mut selected: Option<BTreeSet<Index>> = None;
+        let limit = |selected: &Option<BTreeSet<Index>>| {
+            selected
+                .as_ref()
+                .map(|indices| indices.len())
+                .unwrap_or_else(|| self.by_index.len())
+        };
+        let is_small = |selected: &Option<BTreeSet<Index>>| {
+            selected
+                .as_ref()
+                .map(|indices| indices.len() <= SMALL_SELECTION)
+                .unwrap_or(false)
+        };
+        let empty = BTreeSet::new();
+        // Tombstones are matched by their own attribute filter,
+        // so the attribute index can only narrow queries without one.
+        let use_attributes = query.tombstone().is_none();
+
+        if let IndexFilter::Range(start, end) = query.index() {
+            // An unbounded range does not narrow anything down
+            if start.is_some() || end.is_some() {
+                let start = start.unwrap_or(Index::min_value());
+                let end = end.unwrap_or(Index::max_value());
+                if start > end {
+                    return Vec::new();
+                }
+                selected = Some(self.by_index.range(start..=end).map(|(i, _)| *i).collect());
+            }
+        }
+
+        if let Some(ref entity) = keys.entity {
+            let bucket = self.by_entity.get(entity).unwrap_or(&empty);
+            selected = Some(intersect(selected, bucket));
+        }
+        if let Some(ref value) = keys.value {
+            let bucket = self.by_value.get(value).unwrap_or(&empty);
+            selected = Some(intersect(selected, bucket));
+        }
+        if let Some(ref attribute) = keys.attribute {
+            if use_attributes {
+                let bucket = self.by_attribute.get(attribute).unwrap_or(&empty);
+                selected = Some(intersect(selected, bucket));
+            }
+        }
+
+        if keys.entity.is_none() && !is_small(&selected) {
+            if let Some(indices) = select(
+                &self.by_entity,
+                |entity| query.entity().check(entity),
+                limit(&selected),
+            ) {
+                selected = Some(intersect(selected, &indices));
+            }
+        }
+        if keys.value.is_none() && !is_small(&selected) {
+            if let Some(indices) = select(
+                &self.by_value,
+                |value| query.value().check(value),
+                limit(&selected),
+            ) {
+                selected = Some(intersect(selected, &indices));
+            }
+        }
+        if use_attributes && keys.attribute.is_none() && !is_small(&selected) {
+            if let Some(indices) = select(
+                &self.by_attribute,
+                |attribute| query.attribute().check(attribute),
+                limit(&selected),
+            ) {
+                selected = Some(intersect(selected, &indices));
+            }
+        }
+
+        match selected {
+            Some(indices) => indices
+                .iter()
+                .filter_map(|index| self.by_index.get(index))
+                .cloned()
+                .collect(),
+            None => self.by_index.values().cloned().collect(),
+        }
+    }
+}
+
+fn intersect(selected: Option<BTreeSet<Index>>, indices: &BTreeSet<Index>) -> BTreeSet<Index> {
+    match selected {
+        Some(selected) => selected.intersection(indices).cloned().collect(),
+        None => indices.clone(),
+    }
+}
+
+/// EAVI storage with secondary indices on entity, attribute and value.
+///
+/// Queries get narrowed down to the records of the most selective index before the
+/// query is run on them, and index range queries only touch the records in that range.
+/// Use `iter` or `iter_range` to stream over the records in index order without
+/// copying the whole store.
+#[derive(Clone, Debug, Default)]
+pub struct EavIndexedMemoryStorage {
+    indices: Arc<RwLock<Indices>>,
+}
+
+impl EavIndexedMemoryStorage {
+    pub fn new() -> EavIndexedMemoryStorage {
+        Default::default()
+    }
+
+    fn read(&self) -> PersistenceResult<RwLockReadGuard<Indices>> {
+        self.indices
+            .read()
+            .map_err(|_| PersistenceError::ErrorGeneric("Could not read EAV indices".to_string()))
+    }
+
+    fn write(&self) -> PersistenceResult<RwLockWriteGuard<Indices>> {
+        self.indices
+            .write()
+            .map_err(|_| PersistenceError::ErrorGeneric("Could not write EAV indices".to_string()))
+    }
+
+    /// Number of records in the store.
+    pub fn len(&self) -> PersistenceResult<usize> {
+        Ok(self.read()?.len())
+    }
+
+    pub fn is_empty(&self) -> PersistenceResult<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Streams over all records in index order.
+    pub fn iter(&self) -> EaviIter {
+        self.iter_range(None, None)
+    }
+
+    /// Streams over the records with an index in the given inclusive range, in index order.
+    /// Records added while iterating show up if they fall behind the iterator's position.
+    pub fn iter_range(&self, start: Option<Index>, end: Option<Index>) -> EaviIter {
+        EaviIter {
+            indices: self.indices.clone(),
+            next: start.unwrap_or(Index::min_value()),
+            end: end.unwrap_or(Index::max_value()),
+            batch: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Like `fetch_eavi` but with the exact keys the query is restricted to,
+    /// which get looked up directly instead of being searched for.
+    pub fn fetch_eavi_by_keys(
+        &self,
+        query: &EaviQuery<Attribute>,
+        keys: &EaviKeys,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex>> {
+        let candidates = self.read()?.candidates(query, keys);
+        Ok(query.run(candidates.into_iter()))
+    }
+}
+
+impl EntityAttributeValueStorage<Attribute> for EavIndexedMemoryStorage {
+    fn add_eavi(
+        &mut self,
+        eav: &EntityAttributeValueIndex,
+    ) -> PersistenceResult<Option<EntityAttributeValueIndex>> {
+        Ok(Some(self.write()?.insert(eav.clone())))
+    }
+
+    fn fetch_eavi(
+        &self,
+        query: &EaviQuery<Attribute>,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex>> {
+        self.fetch_eavi_by_keys(query, &EaviKeys::default())
+    }
+}
+
+/// Iterator over the records of an `EavIndexedMemoryStorage`.
+/// Takes the store's lock only while fetching the next batch of records.
+pub struct EaviIter {
+    indices: Arc<RwLock<Indices>>,
+    next: Index,
+    end: Index,
+    batch: Vec<EntityAttributeValueIndex>,
+    done: bool,
+}
+
+impl EaviIter {
+    fn fetch_batch(&mut self) {
+        let indices = match self.indices.read() {
+            Ok(indices) => indices,
+            Err(_) => {
+                self.done = true;
+                return;
+            }
+        };
+        if self.next > self.end {
+            self.done = true;
+            return;
+        }
+        self.batch = indices
+            .by_index
+            .range(self.next..=self.end)
+            .take(EAVI_ITER_BATCH_SIZE)
+            .map(|(_, eavi)| eavi.clone())
+            .collect();
+        match self.batch.last() {
+            Some(last) if last.index() < Index::max_value() => self.next = last.index() + 1,
+            _ => self.done = true,
+        }
+        // pop() takes from the back
+        self.batch.reverse();
+    }
+}
+
+impl Iterator for EaviIter {
+    type Item = EntityAttributeValueIndex;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.batch.is_empty() && !self.done {
+            self.fetch_batch();
+        }
+        self.batch.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eav::eavi::tests::test_eav_storage;
+    use holochain_json_api::json::{JsonString, RawString};
+    use holochain_persistence_api::{
+        cas::{
+            content::{AddressableContent, ExampleAddressableContent},
+            storage::EavTestSuite,
+        },
+        eav::EavFilter,
+    };
+
+    fn content(s: &str) -> ExampleAddressableContent {
+        ExampleAddressableContent::try_from_content(&JsonString::from(RawString::from(s))).unwrap()
+    }
+
+    #[test]
+    fn indexed_eav_round_trip() {
+        EavTestSuite::test_round_trip(
+            EavIndexedMemoryStorage::new(),
+            content("foo"),
+            Attribute::LinkTag("abc".to_string(), "favourite-color".to_string()),
+            content("blue"),
+        )
+    }
+
+    #[test]
+    fn indexed_eav_one_to_many() {
+        EavTestSuite::test_one_to_many::<
+            ExampleAddressableContent,
+            Attribute,
+            EavIndexedMemoryStorage,
+        >(EavIndexedMemoryStorage::new(), &Attribute::default());
+    }
+
+    #[test]
+    fn indexed_eav_many_to_one() {
+        EavTestSuite::test_many_to_one::<
+            ExampleAddressableContent,
+            Attribute,
+            EavIndexedMemoryStorage,
+        >(EavIndexedMemoryStorage::new(), &Attribute::default());
+    }
+
+    #[test]
+    fn indexed_eav_range() {
+        EavTestSuite::test_range::<ExampleAddressableContent, Attribute, EavIndexedMemoryStorage>(
+            EavIndexedMemoryStorage::new(),
+            &Attribute::default(),
+        );
+    }
+
+    #[test]
+    fn indexed_eav_prefixes() {
+        EavTestSuite::test_multiple_attributes::<
+            ExampleAddressableContent,
+            Attribute,
+            EavIndexedMemoryStorage,
+        >(
+            EavIndexedMemoryStorage::new(),
+            vec!["a_", "b_", "c_", "d_"]
+                .into_iter()
+                .map(|p| Attribute::LinkTag(p.to_string() + "one_to_many", "".into()))
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn indexed_eav_matches_example_storage() {
+        let mut indexed = EavIndexedMemoryStorage::new();
+        let mut example = test_eav_storage();
+        let bases: Vec<_> = (0..5).map(|i| content(&format!("base{}", i))).collect();
+        let targets: Vec<_> = (0..20).map(|i| content(&format!("target{}", i))).collect();
+        for (i, target) in targets.iter().enumerate() {
+            let base = &bases[i % bases.len()];
+            let attribute = if i % 7 == 0 {
+                Attribute::RemovedLink("type".into(), "tag".into())
+            } else {
+                Attribute::LinkTag("type".into(), "tag".into())
+            };
+            let eavi = EntityAttributeValueIndex::new_with_index(
+                &base.address(),
+                &attribute,
+                &target.address(),
+                (i / 3) as i64,
+            )
+            .unwrap();
+            assert_eq!(
+                indexed.add_eavi(&eavi).unwrap(),
+                example.add_eavi(&eavi).unwrap()
+            );
+        }
+
+        let queries = vec![
+            EaviQuery::new(
+                Some(bases[1].address()).into(),
+                EavFilter::predicate(|attribute: Attribute| match attribute {
+                    Attribute::LinkTag(_, _) | Attribute::RemovedLink(_, _) => true,
+                    _ => false,
+                }),
+                None.into(),
+                IndexFilter::LatestByAttribute,
+                Some(EavFilter::single(Attribute::RemovedLink(
+                    "type".into(),
+                    "tag".into(),
+                ))),
+            ),
+            EaviQuery::new(
+                None.into(),
+                Some(Attribute::LinkTag("type".into(), "tag".into())).into(),
+                Some(targets[3].address()).into(),
+                IndexFilter::LatestByAttribute,
+                None,
+            ),
+            EaviQuery::new(
+                None.into(),
+                None.into(),
+                None.into(),
+                IndexFilter::Range(Some(2), Some(4)),
+                None,
+            ),
+            EaviQuery::default(),
+        ];
+        for query in queries {
+            assert_eq!(
+                indexed.fetch_eavi(&query).unwrap(),
+                example.fetch_eavi(&query).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn indexed_eav_looks_up_exact_keys() {
+        let mut storage = EavIndexedMemoryStorage::new();
+        let bases: Vec<_> = (0..3).map(|i| content(&format!("base{}", i))).collect();
+        let count = SMALL_SELECTION * 3;
+        for i in 0..count {
+            let eavi = EntityAttributeValueIndex::new_with_index(
+                &bases[i % bases.len()].address(),
+                &Attribute::LinkTag("type".into(), format!("tag{}", i % 2)),
+                &content(&format!("target{}", i)).address(),
+                i as i64,
+            )
+            .unwrap();
+            storage.add_eavi(&eavi).unwrap();
+        }
+
+        let attribute = Attribute::LinkTag("type".into(), "tag0".into());
+        let query = EaviQuery::new(
+            Some(bases[1].address()).into(),
+            Some(attribute.clone()).into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        );
+        let keys = EaviKeys {
+            entity: Some(bases[1].address()),
+            attribute: Some(attribute),
+            value: None,
+        };
+        let by_keys = storage.fetch_eavi_by_keys(&query, &keys).unwrap();
+        assert_eq!(by_keys, storage.fetch_eavi(&query).unwrap());
+        assert_eq!(by_keys.len(), count / 6);
+
+        let unknown = EaviKeys {
+            entity: Some(content("unknown").address()),
+            ..Default::default()
+        };
+        assert!(storage
+            .fetch_eavi_by_keys(&EaviQuery::default(), &unknown)
+            .unwrap()
+            .is_empty());
+
+        let unbounded = EaviQuery::new(
+            None.into(),
+            None.into(),
+            None.into(),
+            IndexFilter::Range(None, None),
+            None,
+        );
+        assert_eq!(storage.fetch_eavi(&unbounded).unwrap().len(), count);
+    }
+
+    #[test]
+    fn indexed_eav_streams_in_index_order() {
+        let mut storage = EavIndexedMemoryStorage::new();
+        let count = EAVI_ITER_BATCH_SIZE * 2 + 3;
+        for i in 0..count {
+            let eavi = EntityAttributeValueIndex::new_with_index(
+                &content("base").address(),
+                &Attribute::default(),
+                &content(&format!("target{}", i)).address(),
+                0,
+            )
+            .unwrap();
+            storage.add_eavi(&eavi).unwrap();
+        }
+        assert_eq!(storage.len().unwrap(), count);
+
+        let indices: Vec<Index> = storage.iter().map(|eavi| eavi.index()).collect();
+        assert_eq!(indices, (0..count as Index).collect::<Vec<_>>());
+
+        let range: Vec<Index> = storage
+            .iter_range(Some(10), Some(12))
+            .map(|eavi| eavi.index())
+            .collect();
+        assert_eq!(range, vec![10, 11, 12]);
+    }
+}
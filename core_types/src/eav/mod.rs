@@ -1,5 +1,6 @@
 pub mod eavi;
+pub mod indexed;
 pub mod query;
 pub mod storage;
 
-pub use self::{eavi::*, query::*, storage::*};
+pub use self::{eavi::*, indexed::*, query::*, storage::*};
@@ -60,6 +60,7 @@ pub enum EntryType {
     ChainMigrate,
     CapTokenGrant,
     CapTokenClaim,
+    Warrant,
 }
 
 impl From<AppEntryType> for EntryType {
@@ -117,6 +118,7 @@ impl FromStr for EntryType {
             sys_prefix!("chain_migrate") => EntryType::ChainMigrate,
             sys_prefix!("cap_token_claim") => EntryType::CapTokenClaim,
             sys_prefix!("cap_token_grant") => EntryType::CapTokenGrant,
+            sys_prefix!("warrant") => EntryType::Warrant,
             _ => EntryType::App(AppEntryType(s.into())),
         })
     }
@@ -136,6 +138,7 @@ impl From<EntryType> for String {
             EntryType::ChainMigrate => sys_prefix!("chain_migrate"),
             EntryType::CapTokenClaim => sys_prefix!("cap_token_claim"),
             EntryType::CapTokenGrant => sys_prefix!("cap_token_grant"),
+            EntryType::Warrant => sys_prefix!("warrant"),
         })
     }
 }
@@ -205,6 +208,7 @@ pub mod tests {
             EntryType::ChainMigrate,
             EntryType::CapTokenClaim,
             EntryType::CapTokenGrant,
+            EntryType::Warrant,
         ]
     }
 
@@ -243,6 +247,7 @@ pub mod tests {
             (sys_prefix!("chain_migrate"), EntryType::ChainMigrate),
             (sys_prefix!("cap_token_claim"), EntryType::CapTokenClaim),
             (sys_prefix!("cap_token_grant"), EntryType::CapTokenGrant),
+            (sys_prefix!("warrant"), EntryType::Warrant),
         ] {
             assert_eq!(
                 variant,
@@ -6,10 +6,12 @@
 pub mod cap_entries;
 pub mod deletion_entry;
 pub mod entry_type;
+pub mod warrant;
 
 use self::{
     cap_entries::{CapTokenClaim, CapTokenGrant},
     deletion_entry::DeletionEntry,
+    warrant::Warrant,
 };
 use agent::{test_agent_id, AgentId};
 use chain_header::ChainHeader;
@@ -77,6 +79,7 @@ pub enum Entry {
     ChainMigrate(ChainMigrate),
     CapTokenClaim(CapTokenClaim),
     CapTokenGrant(CapTokenGrant),
+    Warrant(Warrant),
 }
 
 impl Entry {
@@ -93,6 +96,7 @@ impl Entry {
             Entry::ChainMigrate(_) => EntryType::ChainMigrate,
             Entry::CapTokenClaim(_) => EntryType::CapTokenClaim,
             Entry::CapTokenGrant(_) => EntryType::CapTokenGrant,
+            Entry::Warrant(_) => EntryType::Warrant,
         }
     }
 }
@@ -136,6 +140,9 @@ pub struct EntryWithMeta {
 pub struct EntryWithMetaAndHeader {
     pub entry_with_meta: EntryWithMeta,
     pub headers: Vec<ChainHeader>,
+    /// Addresses of warrants known against any of the agents that signed the headers
+    #[serde(default)]
+    pub warrants: Vec<Address>,
 }
 
 /// dummy entry value
@@ -0,0 +1,123 @@
+use crate::{chain_header::ChainHeader, entry::Entry, signature::Provenance};
+use holochain_json_api::{error::JsonError, json::JsonString};
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+
+//-------------------------------------------------------------------------------------------------
+// Warrant
+//-------------------------------------------------------------------------------------------------
+
+/// A warrant is the proof that an agent authored an invalid entry.
+/// It gets created by the DHT node that failed to validate the entry and holds the invalid
+/// entry, its header and the reason why validation failed, signed by that validator.
+/// Warrants get published alongside the agent entry of every agent that signed the header
+/// of the invalid entry, so that other nodes can learn about misbehaving agents.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, DefaultJson)]
+pub struct Warrant {
+    invalid_entry: Box<Entry>,
+    header: ChainHeader,
+    validation_error: String,
+    validator: Provenance,
+}
+
+impl Warrant {
+    pub fn new(
+        invalid_entry: Entry,
+        header: ChainHeader,
+        validation_error: String,
+        validator: Provenance,
+    ) -> Self {
+        Warrant {
+            invalid_entry: Box::new(invalid_entry),
+            header,
+            validation_error,
+            validator,
+        }
+    }
+
+    /// The data a validator has to sign to issue a warrant for the given entry and header.
+    pub fn signing_payload(
+        entry_address: &Address,
+        header_address: &Address,
+        validation_error: &str,
+    ) -> String {
+        format!(
+            "warrant:{}:{}:{}",
+            entry_address, header_address, validation_error
+        )
+    }
+
+    pub fn invalid_entry(&self) -> &Entry {
+        &self.invalid_entry
+    }
+
+    pub fn header(&self) -> &ChainHeader {
+        &self.header
+    }
+
+    pub fn validation_error(&self) -> &String {
+        &self.validation_error
+    }
+
+    pub fn validator(&self) -> &Provenance {
+        &self.validator
+    }
+
+    /// The data the validator's signature of this warrant is expected to sign.
+    pub fn signed_payload(&self) -> String {
+        Warrant::signing_payload(
+            &self.invalid_entry.address(),
+            &self.header.address(),
+            &self.validation_error,
+        )
+    }
+
+    /// All agents that signed the header of the invalid entry.
+    pub fn warranted_agents(&self) -> Vec<Address> {
+        self.header
+            .provenances()
+            .iter()
+            .map(|provenance| provenance.source())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{
+        chain_header::test_chain_header,
+        entry::test_entry,
+        signature::{Provenance, Signature},
+    };
+
+    pub fn test_warrant() -> Warrant {
+        Warrant::new(
+            test_entry(),
+            test_chain_header(),
+            "FAIL wat".to_string(),
+            Provenance::new("validator".into(), Signature::from("sig")),
+        )
+    }
+
+    #[test]
+    fn warrant_smoke_test() {
+        let warrant = test_warrant();
+        assert_eq!(warrant.invalid_entry(), &test_entry());
+        assert_eq!(
+            warrant.warranted_agents(),
+            test_chain_header()
+                .provenances()
+                .iter()
+                .map(|p| p.source())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            warrant.signed_payload(),
+            format!(
+                "warrant:{}:{}:FAIL wat",
+                test_entry().address(),
+                test_chain_header().address()
+            )
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use crate::kv::KvStore;
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::{
+    cas::{
+        content::{Address, AddressableContent, Content},
+        storage::ContentAddressableStorage,
+    },
+    error::PersistenceResult,
+};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Content addressable storage backed by a [KvStore](struct.KvStore.html).
+/// Each `add` is committed as its own transaction, unless it happens inside
+/// [atomically](fn.atomically.html).
+#[derive(Clone, Debug)]
+pub struct KvStorage {
+    store: KvStore,
+}
+
+impl KvStorage {
+    pub fn new(store: KvStore) -> KvStorage {
+        KvStorage { store }
+    }
+
+    /// Opens (or creates) a store in the given directory and uses it as CAS.
+    pub fn open<P: AsRef<Path>>(path: P) -> PersistenceResult<KvStorage> {
+        Ok(KvStorage::new(KvStore::open(path)?))
+    }
+
+    pub fn store(&self) -> &KvStore {
+        &self.store
+    }
+}
+
+impl ContentAddressableStorage for KvStorage {
+    fn add(&mut self, content: &dyn AddressableContent) -> PersistenceResult<()> {
+        let mut transaction = self.store.transaction();
+        transaction.add_content(content);
+        transaction.commit().map(|_| ())
+    }
+
+    fn contains(&self, address: &Address) -> PersistenceResult<bool> {
+        self.store.contains_content(address)
+    }
+
+    fn fetch(&self, address: &Address) -> PersistenceResult<Option<Content>> {
+        Ok(self
+            .store
+            .fetch_content(address)?
+            .map(|content| JsonString::from_json(&content)))
+    }
+
+    fn get_id(&self) -> Uuid {
+        self.store.get_id()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::kv::tests::test_content;
+    use tempfile::tempdir;
+
+    #[test]
+    fn kv_cas_round_trip() {
+        let dir = tempdir().unwrap();
+        let mut cas = KvStorage::open(dir.path()).unwrap();
+        let content = test_content("foo");
+        assert!(!cas.contains(&content.address()).unwrap());
+        assert_eq!(cas.fetch(&content.address()).unwrap(), None);
+
+        cas.add(&content).unwrap();
+        assert!(cas.contains(&content.address()).unwrap());
+        assert_eq!(
+            cas.fetch(&content.address()).unwrap(),
+            Some(content.content())
+        );
+
+        let reopened = KvStorage::open(dir.path()).unwrap();
+        assert_eq!(
+            reopened.fetch(&content.address()).unwrap(),
+            Some(content.content())
+        );
+        assert_ne!(reopened.get_id(), cas.get_id());
+    }
+}
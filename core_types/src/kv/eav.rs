@@ -0,0 +1,164 @@
+use crate::{
+    eav::{Attribute, EaviKeys, EaviQuery, EntityAttributeValueIndex},
+    kv::KvStore,
+};
+use holochain_persistence_api::{
+    eav::storage::EntityAttributeValueStorage, error::PersistenceResult,
+};
+use std::{collections::BTreeSet, path::Path};
+
+/// EAV storage backed by a [KvStore](struct.KvStore.html), indexed the same way as
+/// [EavIndexedMemoryStorage](../eav/struct.EavIndexedMemoryStorage.html).
+/// Each `add_eavi` is committed as its own transaction, unless it happens inside
+/// [atomically](fn.atomically.html).
+#[derive(Clone, Debug)]
+pub struct EavKvStorage {
+    store: KvStore,
+}
+
+impl EavKvStorage {
+    pub fn new(store: KvStore) -> EavKvStorage {
+        EavKvStorage { store }
+    }
+
+    /// Opens (or creates) a store in the given directory and uses it as EAV storage.
+    pub fn open<P: AsRef<Path>>(path: P) -> PersistenceResult<EavKvStorage> {
+        Ok(EavKvStorage::new(KvStore::open(path)?))
+    }
+
+    pub fn store(&self) -> &KvStore {
+        &self.store
+    }
+
+    /// Like `fetch_eavi` but with the exact keys the query is restricted to,
+    /// which get looked up directly instead of being searched for.
+    pub fn fetch_eavi_by_keys(
+        &self,
+        query: &EaviQuery,
+        keys: &EaviKeys,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex>> {
+        let candidates = self.store.with_eav(|eav| eav.candidates(query, keys))?;
+        Ok(query.run(candidates.into_iter()))
+    }
+}
+
+impl EntityAttributeValueStorage<Attribute> for EavKvStorage {
+    fn add_eavi(
+        &mut self,
+        eav: &EntityAttributeValueIndex,
+    ) -> PersistenceResult<Option<EntityAttributeValueIndex>> {
+        let mut transaction = self.store.transaction();
+        transaction.add_eavi(eav);
+        Ok(transaction.commit()?.pop())
+    }
+
+    fn fetch_eavi(
+        &self,
+        query: &EaviQuery,
+    ) -> PersistenceResult<BTreeSet<EntityAttributeValueIndex>> {
+        self.fetch_eavi_by_keys(query, &EaviKeys::default())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::kv::tests::test_content;
+    use holochain_persistence_api::cas::{
+        content::ExampleAddressableContent, storage::EavTestSuite,
+    };
+    use tempfile::{tempdir, TempDir};
+
+    fn test_eav_kv_storage() -> (EavKvStorage, TempDir) {
+        let dir = tempdir().unwrap();
+        (EavKvStorage::open(dir.path()).unwrap(), dir)
+    }
+
+    #[test]
+    fn kv_eav_round_trip() {
+        let (storage, _dir) = test_eav_kv_storage();
+        EavTestSuite::test_round_trip(
+            storage,
+            test_content("foo"),
+            Attribute::LinkTag("abc".to_string(), "favourite-color".to_string()),
+            test_content("blue"),
+        )
+    }
+
+    #[test]
+    fn kv_eav_one_to_many() {
+        let (storage, _dir) = test_eav_kv_storage();
+        EavTestSuite::test_one_to_many::<ExampleAddressableContent, Attribute, EavKvStorage>(
+            storage,
+            &Attribute::default(),
+        );
+    }
+
+    #[test]
+    fn kv_eav_many_to_one() {
+        let (storage, _dir) = test_eav_kv_storage();
+        EavTestSuite::test_many_to_one::<ExampleAddressableContent, Attribute, EavKvStorage>(
+            storage,
+            &Attribute::default(),
+        );
+    }
+
+    #[test]
+    fn kv_eav_range() {
+        let (storage, _dir) = test_eav_kv_storage();
+        EavTestSuite::test_range::<ExampleAddressableContent, Attribute, EavKvStorage>(
+            storage,
+            &Attribute::default(),
+        );
+    }
+
+    #[test]
+    fn kv_eav_shares_store_with_cas() {
+        use crate::kv::KvStorage;
+        use holochain_persistence_api::cas::{
+            content::AddressableContent, storage::ContentAddressableStorage,
+        };
+
+        let (mut eav_storage, _dir) = test_eav_kv_storage();
+        let cas = KvStorage::new(eav_storage.store().clone());
+        let base = test_content("base");
+        let target = test_content("target");
+
+        let mut transaction = eav_storage.store().transaction();
+        transaction.add_content(&base).add_eavi(
+            &EntityAttributeValueIndex::new(
+                &base.address(),
+                &Attribute::default(),
+                &target.address(),
+            )
+            .unwrap(),
+        );
+        transaction.commit().unwrap();
+
+        assert!(cas.contains(&base.address()).unwrap());
+        let stored = eav_storage
+            .add_eavi(
+                &EntityAttributeValueIndex::new(
+                    &base.address(),
+                    &Attribute::default(),
+                    &base.address(),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        assert!(stored.is_some());
+        assert_eq!(
+            eav_storage
+                .fetch_eavi(&EaviQuery::new(
+                    Some(base.address()).into(),
+                    None.into(),
+                    None.into(),
+                    holochain_persistence_api::eav::IndexFilter::Range(None, None),
+                    None,
+                ))
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,611 @@
+//! An embedded, transactional key-value store that holds both content (CAS) and
+//! EAV meta data of an instance in a single append-only log.
+//!
+//! Every commit is one checksummed line in the log, written and synced in one go,
+//! so all writes of a transaction become durable together or not at all.
+//! On open, the log gets replayed into memory and a torn last commit is dropped.
+//! Use [KvStorage](struct.KvStorage.html) and [EavKvStorage](struct.EavKvStorage.html)
+//! to plug the store in as CAS and EAV storage, and [atomically](fn.atomically.html)
+//! to have all their writes of one operation go into a single commit.
+
+pub mod cas;
+pub mod eav;
+
+pub use self::{cas::KvStorage, eav::EavKvStorage};
+
+use crate::eav::{indexed::Indices, EntityAttributeValueIndex, Index};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::{
+    cas::content::{Address, AddressableContent},
+    error::{PersistenceError, PersistenceResult},
+};
+use multihash::Hash;
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use uuid::Uuid;
+
+/// File name of the commit log inside the store's directory.
+pub const KV_LOG_FILE: &str = "store.log";
+
+/// Minimum number of bytes taken up by overwritten records before the log gets compacted.
+/// Compaction also needs those dead bytes to make up more than half of the log.
+pub const KV_COMPACTION_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum KvWrite {
+    Content { address: Address, content: String },
+    Eavi(String),
+}
+
+fn kv_error<E: ToString>(error: E) -> PersistenceError {
+    PersistenceError::ErrorGeneric(format!("KV store: {}", error.to_string()))
+}
+
+fn checksum(payload: &str) -> String {
+    String::from(Address::encode_from_str(payload, Hash::SHA2256))
+}
+
+fn encode_commit(writes: &[KvWrite]) -> PersistenceResult<String> {
+    let payload = serde_json::to_string(writes).map_err(kv_error)?;
+    Ok(format!("{} {}\n", checksum(&payload), payload))
+}
+
+fn decode_commit(line: &[u8]) -> Option<Vec<KvWrite>> {
+    let line = std::str::from_utf8(line).ok()?;
+    let mut parts = line.splitn(2, ' ');
+    let sum = parts.next()?;
+    let payload = parts.next()?;
+    if checksum(payload) != sum {
+        return None;
+    }
+    serde_json::from_str(payload).ok()
+}
+
+#[derive(Debug)]
+struct KvInner {
+    path: PathBuf,
+    log: File,
+    content: HashMap<Address, String>,
+    eav: Indices,
+    /// Size of the log file.
+    log_bytes: u64,
+    /// Bytes in the log taken up by content that got overwritten since.
+    dead_bytes: u64,
+    /// Commits that are applied in memory but still held back by an
+    /// [atomically](fn.atomically.html) block.
+    unflushed: usize,
+}
+
+impl KvInner {
+    fn apply(&mut self, write: KvWrite) -> PersistenceResult<()> {
+        match write {
+            KvWrite::Content { address, content } => {
+                let address_bytes = String::from(address.clone()).len();
+                if let Some(old) = self.content.insert(address, content) {
+                    self.dead_bytes += (address_bytes + old.len()) as u64;
+                }
+            }
+            KvWrite::Eavi(content) => {
+                let eavi =
+                    EntityAttributeValueIndex::try_from_content(&JsonString::from_json(&content))
+                        .map_err(kv_error)?;
+                self.eav.insert(eavi);
+            }
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, writes: &[KvWrite]) -> PersistenceResult<()> {
+        let commit = encode_commit(writes)?;
+        self.log
+            .write_all(commit.as_bytes())
+            .and_then(|_| self.log.sync_data())
+            .map_err(kv_error)?;
+        self.log_bytes += commit.len() as u64;
+        Ok(())
+    }
+
+    /// Compacts the log once overwritten records make up most of it.
+    /// Never happens while commits are held back, since compaction would
+    /// write them out before the rest of their batch.
+    fn compact_if_needed(&mut self) -> PersistenceResult<()> {
+        if self.unflushed == 0
+            && self.dead_bytes >= KV_COMPACTION_THRESHOLD
+            && self.dead_bytes * 2 > self.log_bytes
+        {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the log with only the live records and atomically swaps it in.
+    fn compact(&mut self) -> PersistenceResult<()> {
+        let mut writes: Vec<KvWrite> = self
+            .content
+            .iter()
+            .map(|(address, content)| KvWrite::Content {
+                address: address.clone(),
+                content: content.clone(),
+            })
+            .collect();
+        writes.extend(
+            self.eav
+                .records()
+                .map(|eavi| KvWrite::Eavi(String::from(eavi.content()))),
+        );
+
+        let log_path = self.path.join(KV_LOG_FILE);
+        let compacted_path = self.path.join(format!("{}.compact", KV_LOG_FILE));
+        let commit = encode_commit(&writes)?;
+        let mut compacted = File::create(&compacted_path).map_err(kv_error)?;
+        compacted
+            .write_all(commit.as_bytes())
+            .and_then(|_| compacted.sync_all())
+            .map_err(kv_error)?;
+        fs::rename(&compacted_path, &log_path).map_err(kv_error)?;
+        self.log = OpenOptions::new()
+            .append(true)
+            .open(&log_path)
+            .map_err(kv_error)?;
+        self.log_bytes = commit.len() as u64;
+        self.dead_bytes = 0;
+        Ok(())
+    }
+}
+
+/// Handle to an embedded transactional store living in one directory.
+/// Clones share the same store.
+#[derive(Clone, Debug)]
+pub struct KvStore {
+    inner: Arc<RwLock<KvInner>>,
+    id: Uuid,
+}
+
+impl KvStore {
+    /// Opens the store in the given directory, creating it if needed,
+    /// and replays its log. A torn last commit from a crash gets truncated away;
+    /// a broken commit in the middle of the log is reported as an error.
+    pub fn open<P: AsRef<Path>>(path: P) -> PersistenceResult<KvStore> {
+        let path: PathBuf = path.as_ref().into();
+        fs::create_dir_all(&path).map_err(kv_error)?;
+        let log_path = path.join(KV_LOG_FILE);
+        let mut log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&log_path)
+            .map_err(kv_error)?;
+        let mut raw = Vec::new();
+        log.read_to_end(&mut raw).map_err(kv_error)?;
+
+        let mut inner = KvInner {
+            path,
+            log,
+            content: HashMap::new(),
+            eav: Indices::default(),
+            log_bytes: 0,
+            dead_bytes: 0,
+            unflushed: 0,
+        };
+        let mut valid_length = 0;
+        let mut rest = raw.as_slice();
+        while let Some(end) = rest.iter().position(|byte| *byte == b'\n') {
+            let line = &rest[..end];
+            match decode_commit(line) {
+                Some(writes) => {
+                    for write in writes {
+                        inner.apply(write)?;
+                    }
+                    valid_length += end + 1;
+                    rest = &rest[end + 1..];
+                }
+                None if rest[end + 1..].is_empty() => break,
+                None => {
+                    return Err(kv_error(format!(
+                        "corrupt commit at byte {} of {}",
+                        valid_length,
+                        log_path.display()
+                    )))
+                }
+            }
+        }
+        if valid_length < raw.len() {
+            inner
+                .log
+                .set_len(valid_length as u64)
+                .and_then(|_| inner.log.sync_all())
+                .map_err(kv_error)?;
+        }
+        inner.log_bytes = valid_length as u64;
+
+        Ok(KvStore {
+            inner: Arc::new(RwLock::new(inner)),
+            id: Uuid::new_v4(),
+        })
+    }
+
+    pub fn get_id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Starts a transaction. Nothing is written before `commit` is called on it.
+    pub fn transaction(&self) -> Transaction {
+        Transaction {
+            store: self,
+            content: Vec::new(),
+            eavis: Vec::new(),
+        }
+    }
+
+    /// Writes the log to a new file containing only live records.
+    /// Happens automatically once enough of the log is taken up by overwritten content.
+    pub fn compact(&self) -> PersistenceResult<()> {
+        self.inner.write().map_err(kv_error)?.compact()
+    }
+
+    fn flush(&self, writes: &[KvWrite], commits: usize) -> PersistenceResult<()> {
+        let mut inner = self.inner.write().map_err(kv_error)?;
+        inner.unflushed -= commits;
+        inner.append(writes)?;
+        inner.compact_if_needed()
+    }
+
+    pub(crate) fn fetch_content(&self, address: &Address) -> PersistenceResult<Option<String>> {
+        Ok(self
+            .inner
+            .read()
+            .map_err(kv_error)?
+            .content
+            .get(address)
+            .cloned())
+    }
+
+    pub(crate) fn contains_content(&self, address: &Address) -> PersistenceResult<bool> {
+        Ok(self
+            .inner
+            .read()
+            .map_err(kv_error)?
+            .content
+            .contains_key(address))
+    }
+
+    pub(crate) fn with_eav<T>(&self, f: impl FnOnce(&Indices) -> T) -> PersistenceResult<T> {
+        Ok(f(&self.inner.read().map_err(kv_error)?.eav))
+    }
+}
+
+/// A set of content and EAV writes that get committed atomically.
+pub struct Transaction<'a> {
+    store: &'a KvStore,
+    content: Vec<(Address, String)>,
+    eavis: Vec<EntityAttributeValueIndex>,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn add_content(&mut self, content: &dyn AddressableContent) -> &mut Self {
+        self.content
+            .push((content.address(), String::from(content.content())));
+        self
+    }
+
+    pub fn add_eavi(&mut self, eavi: &EntityAttributeValueIndex) -> &mut Self {
+        self.eavis.push(eavi.clone());
+        self
+    }
+
+    /// Writes all added records as one commit and returns the EAVIs as stored,
+    /// i.e. with indices moved past any collisions.
+    /// Inside an [atomically](fn.atomically.html) block the records are visible right away
+    /// but only get written to the log together with the block's other commits.
+    pub fn commit(self) -> PersistenceResult<Vec<EntityAttributeValueIndex>> {
+        let mut inner = self.store.inner.write().map_err(kv_error)?;
+
+        let mut writes: Vec<KvWrite> = self
+            .content
+            .into_iter()
+            .filter(|(address, content)| inner.content.get(address) != Some(content))
+            .map(|(address, content)| KvWrite::Content { address, content })
+            .collect();
+        let mut taken: BTreeSet<Index> = BTreeSet::new();
+        let mut stored = Vec::with_capacity(self.eavis.len());
+        for mut eavi in self.eavis {
+            let mut index = inner.eav.free_index(eavi.index());
+            while taken.contains(&index) {
+                index = inner.eav.free_index(index + 1);
+            }
+            taken.insert(index);
+            eavi.set_index(index);
+            writes.push(KvWrite::Eavi(String::from(eavi.content())));
+            stored.push(eavi);
+        }
+        if writes.is_empty() {
+            return Ok(stored);
+        }
+
+        if PendingCommits::hold_back(self.store, &writes) {
+            inner.unflushed += 1;
+        } else {
+            inner.append(&writes)?;
+        }
+        for write in writes {
+            inner.apply(write)?;
+        }
+        inner.compact_if_needed()?;
+        Ok(stored)
+    }
+}
+
+/// Commits held back by the current thread's [atomically](fn.atomically.html) block,
+/// merged per store.
+#[derive(Default)]
+struct PendingCommits {
+    depth: usize,
+    stores: Vec<(KvStore, Vec<KvWrite>, usize)>,
+}
+
+thread_local! {
+    static PENDING_COMMITS: RefCell<PendingCommits> = RefCell::new(PendingCommits::default());
+}
+
+impl PendingCommits {
+    /// Queues the writes if the current thread is inside an atomically block.
+    fn hold_back(store: &KvStore, writes: &[KvWrite]) -> bool {
+        PENDING_COMMITS.with(|pending| {
+            let mut pending = pending.borrow_mut();
+            if pending.depth == 0 {
+                return false;
+            }
+            match pending
+                .stores
+                .iter_mut()
+                .find(|(held, _, _)| held.id == store.id)
+            {
+                Some((_, held_writes, commits)) => {
+                    held_writes.extend_from_slice(writes);
+                    *commits += 1;
+                }
+                None => pending.stores.push((store.clone(), writes.to_vec(), 1)),
+            }
+            true
+        })
+    }
+
+    fn enter() {
+        PENDING_COMMITS.with(|pending| pending.borrow_mut().depth += 1);
+    }
+
+    /// Leaves one level of nesting and writes out everything held back
+    /// once the outermost block is left.
+    fn leave() -> PersistenceResult<()> {
+        let stores = PENDING_COMMITS.with(|pending| {
+            let mut pending = pending.borrow_mut();
+            pending.depth -= 1;
+            if pending.depth == 0 {
+                pending.stores.drain(..).collect()
+            } else {
+                Vec::new()
+            }
+        });
+        stores
+            .into_iter()
+            .map(|(store, writes, commits)| store.flush(&writes, commits))
+            .fold(Ok(()), |result, flushed| result.and(flushed))
+    }
+}
+
+/// Leaves the atomically block even if its closure panics.
+struct AtomicallyGuard {
+    left: bool,
+}
+
+impl AtomicallyGuard {
+    fn leave(mut self) -> PersistenceResult<()> {
+        self.left = true;
+        PendingCommits::leave()
+    }
+}
+
+impl Drop for AtomicallyGuard {
+    fn drop(&mut self) {
+        if !self.left {
+            let _ = PendingCommits::leave();
+        }
+    }
+}
+
+/// Runs `f` so that every KV store commit it makes on this thread, including each
+/// `add` and `add_eavi` of [KvStorage](struct.KvStorage.html) and
+/// [EavKvStorage](struct.EavKvStorage.html), is written as one commit per store
+/// when the outermost `atomically` returns. A crash in between loses all of them together.
+/// Writes are visible to readers of the store right away.
+/// Storages that are not backed by a KV store are not affected.
+pub fn atomically<T, E: From<PersistenceError>>(f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    PendingCommits::enter();
+    let guard = AtomicallyGuard { left: false };
+    let result = f();
+    let flushed = guard.leave();
+    let value = result?;
+    flushed?;
+    Ok(value)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::eav::{Attribute, EaviQuery};
+    use holochain_json_api::json::RawString;
+    use holochain_persistence_api::{
+        cas::{content::ExampleAddressableContent, storage::ContentAddressableStorage},
+        eav::storage::EntityAttributeValueStorage,
+    };
+    use tempfile::tempdir;
+
+    pub fn test_content(s: &str) -> ExampleAddressableContent {
+        ExampleAddressableContent::try_from_content(&JsonString::from(RawString::from(s))).unwrap()
+    }
+
+    fn test_eavi(base: &str, target: &str) -> EntityAttributeValueIndex {
+        EntityAttributeValueIndex::new_with_index(
+            &test_content(base).address(),
+            &Attribute::LinkTag("type".into(), "tag".into()),
+            &test_content(target).address(),
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn commit_survives_reopen() {
+        let dir = tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let stored = {
+            let mut transaction = store.transaction();
+            transaction
+                .add_content(&test_content("base"))
+                .add_content(&test_content("target"))
+                .add_eavi(&test_eavi("base", "target"))
+                .add_eavi(&test_eavi("base", "other"));
+            transaction.commit().unwrap()
+        };
+        assert_eq!(
+            stored.iter().map(|eavi| eavi.index()).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+
+        let reopened = KvStore::open(dir.path()).unwrap();
+        assert_eq!(
+            reopened
+                .fetch_content(&test_content("base").address())
+                .unwrap(),
+            Some(String::from(test_content("base").content()))
+        );
+        let records = reopened
+            .with_eav(|eav| eav.candidates(&EaviQuery::default(), &EaviKeys::default()))
+            .unwrap();
+        assert_eq!(records, stored);
+    }
+
+    #[test]
+    fn torn_commit_is_dropped() {
+        let dir = tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let mut transaction = store.transaction();
+        transaction.add_content(&test_content("kept"));
+        transaction.commit().unwrap();
+
+        let log_path = dir.path().join(KV_LOG_FILE);
+        let committed = fs::read_to_string(&log_path).unwrap();
+        let torn = encode_commit(&[KvWrite::Content {
+            address: test_content("lost").address(),
+            content: String::from(test_content("lost").content()),
+        }])
+        .unwrap();
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log.write_all(&torn.as_bytes()[..torn.len() / 2]).unwrap();
+
+        let reopened = KvStore::open(dir.path()).unwrap();
+        assert!(reopened
+            .contains_content(&test_content("kept").address())
+            .unwrap());
+        assert!(!reopened
+            .contains_content(&test_content("lost").address())
+            .unwrap());
+        assert_eq!(fs::read_to_string(&log_path).unwrap(), committed);
+    }
+
+    #[test]
+    fn torn_multi_byte_character_is_dropped() {
+        let dir = tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let mut transaction = store.transaction();
+        transaction.add_content(&test_content("kept"));
+        transaction.commit().unwrap();
+
+        let log_path = dir.path().join(KV_LOG_FILE);
+        let committed = fs::read_to_string(&log_path).unwrap();
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log.write_all(&"ü".as_bytes()[..1]).unwrap();
+
+        let reopened = KvStore::open(dir.path()).unwrap();
+        assert!(reopened
+            .contains_content(&test_content("kept").address())
+            .unwrap());
+        assert_eq!(fs::read_to_string(&log_path).unwrap(), committed);
+    }
+
+    #[test]
+    fn atomically_writes_one_commit_per_store() {
+        let dir = tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let mut cas = KvStorage::new(store.clone());
+        let mut eav = EavKvStorage::new(store.clone());
+        let log_path = dir.path().join(KV_LOG_FILE);
+
+        atomically(|| {
+            cas.add(&test_content("base"))?;
+            atomically(|| eav.add_eavi(&test_eavi("base", "target")))?;
+            assert!(store
+                .contains_content(&test_content("base").address())
+                .unwrap());
+            assert_eq!(fs::metadata(&log_path).unwrap().len(), 0);
+            Ok::<_, PersistenceError>(())
+        })
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&log_path).unwrap().lines().count(), 1);
+        let reopened = KvStore::open(dir.path()).unwrap();
+        assert!(reopened
+            .contains_content(&test_content("base").address())
+            .unwrap());
+        assert_eq!(reopened.with_eav(|eav| eav.len()).unwrap(), 1);
+    }
+
+    #[test]
+    fn corrupt_commit_in_the_middle_is_an_error() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join(KV_LOG_FILE);
+        let good = encode_commit(&[KvWrite::Eavi(String::from(
+            test_eavi("base", "target").content(),
+        ))])
+        .unwrap();
+        fs::write(&log_path, format!("garbage\n{}", good)).unwrap();
+        assert!(KvStore::open(dir.path()).is_err());
+    }
+
+    #[test]
+    fn compaction_keeps_live_records() {
+        let dir = tempdir().unwrap();
+        let store = KvStore::open(dir.path()).unwrap();
+        let address = Address::from("slot");
+        for i in 0..3 {
+            let mut transaction = store.transaction();
+            transaction
+                .content
+                .push((address.clone(), format!("\"{}\"", i)));
+            transaction.add_eavi(&test_eavi("base", &i.to_string()));
+            transaction.commit().unwrap();
+        }
+        store.compact().unwrap();
+
+        let reopened = KvStore::open(dir.path()).unwrap();
+        assert_eq!(
+            reopened.fetch_content(&address).unwrap(),
+            Some("\"2\"".to_string())
+        );
+        assert_eq!(reopened.with_eav(|eav| eav.len()).unwrap(), 3);
+        assert_eq!(
+            fs::read_to_string(dir.path().join(KV_LOG_FILE))
+                .unwrap()
+                .lines()
+                .count(),
+            1
+        );
+    }
+}
@@ -9,6 +9,7 @@
 
 extern crate base64;
 extern crate chrono;
+extern crate flate2;
 extern crate futures;
 #[macro_use]
 extern crate lazy_static;
@@ -30,6 +31,9 @@ extern crate regex;
 extern crate maplit;
 extern crate hcid;
 extern crate lib3h_protocol;
+#[cfg(test)]
+extern crate tempfile;
+extern crate uuid;
 extern crate wasmi;
 pub mod chain_header;
 pub mod crud_status;
@@ -43,6 +47,7 @@ pub mod bits_n_pieces;
 pub mod chain_migrate;
 pub mod dna;
 pub mod hdk_version;
+pub mod kv;
 pub mod link;
 pub mod network;
 pub mod signature;
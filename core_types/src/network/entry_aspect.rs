@@ -1,4 +1,8 @@
-use crate::{chain_header::ChainHeader, entry::Entry, link::link_data::LinkData};
+use crate::{
+    chain_header::ChainHeader,
+    entry::{warrant::Warrant, Entry},
+    link::link_data::LinkData,
+};
 use chrono::{offset::FixedOffset, DateTime};
 use holochain_json_api::{error::JsonError, json::JsonString};
 use holochain_persistence_api::cas::content::{Address, AddressableContent, Content};
@@ -70,6 +74,13 @@ pub enum EntryAspect {
     // `Entry::Deletion(address)`.
     // Deletion(Address, ChainHeader),
     Deletion(ChainHeader),
+
+    // Meta item for a warrant against the agent whose address
+    // this gets published to.
+    // ChainHeader is the header of the warrant entry on the
+    // validator's source chain (not the header of the invalid
+    // entry which is part of the Warrant itself).
+    Warrant(Warrant, ChainHeader),
 }
 
 impl EntryAspect {
@@ -81,6 +92,7 @@ impl EntryAspect {
             EntryAspect::LinkRemove(_, _) => String::from("link_remove"),
             EntryAspect::Update(_, _) => String::from("update"),
             EntryAspect::Deletion(_) => String::from("deletion"),
+            EntryAspect::Warrant(_, _) => String::from("warrant"),
         }
     }
     pub fn header(&self) -> ChainHeader {
@@ -91,6 +103,7 @@ impl EntryAspect {
             EntryAspect::LinkRemove(_, header) => header.clone(),
             EntryAspect::Update(_, header) => header.clone(),
             EntryAspect::Deletion(header) => header.clone(),
+            EntryAspect::Warrant(_, header) => header.clone(),
         }
     }
 }
@@ -156,6 +169,13 @@ impl fmt::Debug for EntryAspect {
             EntryAspect::Deletion(header) => {
                 write!(f, "EntryAspect::Deletion({})", format_header(header))
             }
+            EntryAspect::Warrant(warrant, header) => write!(
+                f,
+                "EntryAspect::Warrant(against {:?} for {}, {})",
+                warrant.warranted_agents(),
+                warrant.invalid_entry().address(),
+                format_header(header)
+            ),
         }
     }
 }
@@ -20,6 +20,9 @@ pub struct GetLinkData {
     pub target: Value,
     pub tag: String,
     pub headers: Option<Vec<ChainHeader>>,
+    /// Addresses of warrants known against the author of the link
+    #[serde(default)]
+    pub warrants: Vec<Address>,
 }
 
 impl GetLinkData {
@@ -29,6 +32,7 @@ impl GetLinkData {
         target: Value,
         tag: String,
         headers: Option<Vec<ChainHeader>>,
+        warrants: Vec<Address>,
     ) -> GetLinkData {
         GetLinkData {
             address,
@@ -36,6 +40,7 @@ impl GetLinkData {
             target,
             tag,
             headers,
+            warrants,
         }
     }
 }
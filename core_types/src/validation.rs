@@ -10,6 +10,7 @@ use crate::{
     },
     error::HolochainError,
     link::link_data::LinkData,
+    time::Period,
 };
 
 use holochain_json_api::{error::JsonError, json::JsonString};
@@ -48,6 +49,17 @@ pub enum ValidationPackageDefinition {
     ChainHeaders,
     /// sending the whole chain: public entries and all headers
     ChainFull,
+    /// sending the public source chain entries of the given entry types only.
+    /// Entry type names can be glob patterns, as with `hdk::query`
+    ChainEntriesOfTypes(Vec<String>),
+    /// sending only the last N source chain headers before the entry
+    LastHeaders(usize),
+    /// sending the headers and public entries that were committed within
+    /// the given period before the entry
+    TimeWindow(Period),
+    /// sending the headers and public entries committed since (and including)
+    /// the author's last entry of the given type
+    SinceLastEntryOfType(String),
     /// sending something custom
     Custom(String),
 }
@@ -0,0 +1,42 @@
+use super::Dispatch;
+use error::ZomeApiResult;
+use holochain_core_types::{entry::Entry, time::Timeout};
+use holochain_persistence_api::cas::content::Address;
+use holochain_wasm_utils::api_serialization::{
+    commit_entry::CommitEntryResult, countersign_entry::CountersignEntryArgs,
+};
+
+/// Commits an entry to the source chains of the calling agent and all given counterparties
+/// at once, with the signatures of all of them attached to each chain header.
+/// Useful for transactions that need the consent of several agents, like a trade between a
+/// buyer and a seller.
+///
+/// The counterparties receive a direct message asking for their signature. Each of them
+/// validates the entry against their own chain (so validation rules decide whether an agent
+/// agrees to sign), locks their chain and responds with their signature. Once all signatures
+/// are collected the entry gets committed here and then on the counterparties' chains.
+/// Only once every counterparty has committed it, the entry gets published by everybody.
+/// While the countersignature is pending, no other entries can be committed by any of
+/// the involved agents.
+///
+/// The timeout covers collecting all signatures and commits. If any counterparty refuses
+/// or does not respond in time, all commits get rolled back, nothing gets published and all
+/// chains get unlocked. Counterparties that can't be reached roll back by themselves shortly
+/// after the timeout. An error is also returned if a counterparty fails to publish the
+/// entry, even though it is committed everywhere then.
+///
+/// Only app entries can be countersigned.
+/// Returns the address of the committed entry.
+pub fn countersign_entry(
+    entry: &Entry,
+    counterparties: Vec<Address>,
+    timeout: Timeout,
+) -> ZomeApiResult<Address> {
+    let result: CommitEntryResult =
+        Dispatch::CountersignEntry.with_input(CountersignEntryArgs {
+            entry: entry.clone(),
+            counterparties,
+            timeout,
+        })?;
+    Ok(result.address())
+}
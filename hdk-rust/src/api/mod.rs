@@ -22,6 +22,7 @@ mod bundle;
 mod call;
 mod capability;
 mod commit_entry;
+mod countersign_entry;
 mod debug;
 mod decrypt;
 mod emit_signal;
@@ -46,6 +47,7 @@ pub use self::{
     call::call,
     capability::{commit_capability_claim, commit_capability_grant},
     commit_entry::{commit_entry, commit_entry_result},
+    countersign_entry::countersign_entry,
     debug::debug,
     decrypt::decrypt,
     emit_signal::emit_signal,
@@ -91,6 +93,7 @@ macro_rules! def_api_fns {
                 self.with_input(JsonString::empty_object())
             }
 
+            #[cfg(not(all(feature = "mock", not(target_arch = "wasm32"))))]
             pub fn with_input<I: TryInto<JsonString>, O: TryFrom<JsonString>>(
                 &self,
                 input: I,
@@ -127,6 +130,22 @@ macro_rules! def_api_fns {
                     Err(ZomeApiError::from(result.error))
                 }
             }
+
+            /// Natively compiled zomes with the `mock` feature call the mock host instead.
+            #[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+            pub fn with_input<I: TryInto<JsonString>, O: TryFrom<JsonString>>(
+                &self,
+                input: I,
+            ) -> ZomeApiResult<O> {
+                let input: JsonString = input
+                    .try_into()
+                    .map_err(|_| ZomeApiError::Internal("Failed to serialize input".to_string()))?;
+                let output = crate::mock::dispatch(self, input)?;
+                let value = output.to_string();
+                output
+                    .try_into()
+                    .map_err(|_| ZomeApiError::from(format!("Failed to deserialize return value: {}", value)))
+            }
         }
 
         // Invokable functions in the Ribosome
@@ -195,6 +214,7 @@ def_api_fns! {
     hc_commit_capability_grant, CommitCapabilityGrant;
     hc_commit_capability_claim, CommitCapabilityClaim;
     hc_emit_signal, EmitSignal;
+    hc_countersign_entry, CountersignEntry;
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -75,7 +75,10 @@ pub struct ValidatingLinkDefinition {
 /// 4. native_type: `native_type` references a given Rust struct, which provides a clear schema for entries of this type.
 /// 5. validation_package: `validation_package` is a special identifier, which declares which data is required from peers
 ///      when attempting to validate entries of this type.
-///      Possible values are found within [ValidationPackageDefinition](ValidationPackageDefinition)
+///      Possible values are found within [ValidationPackageDefinition](ValidationPackageDefinition).
+///      Rules that need prior chain context should select only the part of the chain they need,
+///      i.e. by entry type, the last N headers, a time window or since the last entry of a type,
+///      rather than sending the whole chain with `ChainFull`.
 /// 6. validation: `validation` is a callback function which will be called any time that a
 ///      (DHT) node processes or stores this entry, triggered through actions such as [commit_entry](api::commit_entry()), [update_entry](api::update_entry()), [remove_entry](api::remove_entry()).
 ///      It always expects two arguments, the first of which is the entry attempting to be validated,
@@ -270,7 +273,8 @@ macro_rules! entry {
 /// 4. validation_package: Similar to entries, links have to be validated.
 ///        `validation_package` is a special identifier, which declares which data is required from peers
 ///         when attempting to validate entries of this type.
-///         Possible values are found within [ValidationPackageDefinition](ValidationPackageDefinition)
+///         Possible values are found within [ValidationPackageDefinition](ValidationPackageDefinition),
+///         including the filtered definitions that only select part of the author's chain.
 /// 5. validation: `validation` is a callback function which will be called any time that a
 ///         (DHT) node processes or stores a link of this kind, triggered through the link actions [link_entries](api::commit_entry()) and [remove_link](api::remove_link()).
 ///         It always expects three arguments, the first being the base and the second the target of the link.
@@ -31,6 +31,8 @@ pub extern crate holochain_persistence_api;
 pub extern crate holochain_wasm_utils;
 // #[macro_use]
 pub extern crate pretty_assertions;
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+extern crate regex;
 
 pub mod api;
 pub mod utils;
@@ -40,6 +42,8 @@ pub mod error;
 pub mod global_fns;
 pub mod init_globals;
 pub mod macros;
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+pub mod mock;
 
 pub use holochain_wasm_utils::api_serialization::{validation::*, THIS_INSTANCE};
 
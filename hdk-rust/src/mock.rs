@@ -0,0 +1,668 @@
+//! In-process mock of the Holochain host for unit-testing zome code natively.
+//!
+//! With the `mock` feature enabled, every `hdk::api` function that would call into the
+//! Ribosome through an `hc_*` extern gets handled by the [MockHost] of the current thread
+//! instead, as long as the zome is not compiled to WASM.
+//! So zome logic can be tested with plain `cargo test` (and a debugger) like this:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! hdk = { path = "...", features = ["mock"] }
+//! ```
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn can_create_post() {
+//!     hdk::mock::reset_mock_host();
+//!     let address = handle_create_post("hello".into()).unwrap();
+//!     assert_eq!(hdk::get_entry(&address).unwrap(), Some(post_entry("hello")));
+//! }
+//! ```
+//!
+//! The mock host keeps a local source chain and a fake DHT that holds every committed entry,
+//! including updates, removals and links. It does not run validation callbacks.
+//! Responses for `call`, `send` and `sign` can be programmed per test with
+//! [MockHost::on_call], [MockHost::on_send] and [MockHost::on_sign].
+//! Since `cargo test` runs each test on its own thread, each test gets its own host.
+//!
+//! The Zome API globals (`AGENT_ADDRESS` etc.) get initialized only once per process
+//! and always have the values of [MockHost::globals].
+
+use api::Dispatch;
+use error::{ZomeApiError, ZomeApiResult};
+use holochain_core_types::{
+    agent::AgentId,
+    chain_header::ChainHeader,
+    crud_status::CrudStatus,
+    entry::{
+        cap_entries::{CapTokenClaim, CapTokenGrant},
+        deletion_entry::DeletionEntry,
+        Entry, EntryWithMeta,
+    },
+    link::{link_data::LinkData, Link},
+    signature::{Provenance, Signature},
+    time::Iso8601,
+};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use holochain_wasm_utils::api_serialization::{
+    capabilities::{CommitCapabilityClaimArgs, CommitCapabilityGrantArgs},
+    commit_entry::{CommitEntryArgs, CommitEntryResult},
+    countersign_entry::CountersignEntryArgs,
+    crypto::{CryptoArgs, CryptoMethod},
+    emit_signal::EmitSignalArgs,
+    get_entry::{GetEntryArgs, GetEntryResult, StatusRequestKind},
+    get_links::{
+        GetLinksArgs, GetLinksResult, GetLinksResultCount, LinksResult, LinksStatusRequestKind,
+    },
+    link_entries::LinkEntriesArgs,
+    meta::{MetaArgs, MetaMethod, MetaResult},
+    send::SendArgs,
+    verify_signature::VerifySignatureArgs,
+    QueryArgs, QueryArgsNames, QueryResult, UpdateEntryArgs, ZomeApiGlobals, ZomeFnCallArgs,
+};
+use regex::Regex;
+use std::{cell::RefCell, collections::HashMap, convert::TryFrom};
+
+pub type CallHandler = Box<dyn FnMut(&ZomeFnCallArgs) -> ZomeApiResult<JsonString>>;
+pub type SendHandler = Box<dyn FnMut(&SendArgs) -> ZomeApiResult<String>>;
+pub type SignHandler = Box<dyn FnMut(&str) -> ZomeApiResult<String>>;
+
+/// Name of the agent the mock host acts as.
+pub const MOCK_AGENT_NAME: &str = "mock_agent";
+
+thread_local! {
+    static HOST: RefCell<MockHost> = RefCell::new(MockHost::new());
+}
+
+/// Runs the given closure with the mock host of the current thread.
+pub fn with_mock_host<R, F: FnOnce(&mut MockHost) -> R>(f: F) -> R {
+    HOST.with(|host| f(&mut host.borrow_mut()))
+}
+
+/// Replaces the mock host of the current thread with a fresh one.
+pub fn reset_mock_host() {
+    with_mock_host(|host| *host = MockHost::new())
+}
+
+struct MockLink {
+    link: Link,
+    link_add_address: Address,
+    status: CrudStatus,
+}
+
+pub struct MockHost {
+    agent_id: AgentId,
+    globals: ZomeApiGlobals,
+    chain: Vec<(ChainHeader, Entry)>,
+    dht: HashMap<Address, EntryWithMeta>,
+    links: Vec<MockLink>,
+    call_handler: Option<CallHandler>,
+    send_handler: Option<SendHandler>,
+    sign_handler: Option<SignHandler>,
+    debug_messages: Vec<String>,
+    signals: Vec<EmitSignalArgs>,
+}
+
+impl Default for MockHost {
+    fn default() -> Self {
+        MockHost::new()
+    }
+}
+
+/// What the mock host signs with if no sign handler is programmed.
+/// `verify_signature` accepts exactly these signatures.
+fn mock_signature(agent: &Address, payload: &str) -> String {
+    format!("mock-signature:{}:{}", agent, payload)
+}
+
+impl MockHost {
+    /// Creates a host whose chain holds the agent's id entry, like a chain after genesis.
+    pub fn new() -> Self {
+        let agent_id = AgentId::generate_fake(MOCK_AGENT_NAME);
+        let agent_address = agent_id.address();
+        let mut host = MockHost {
+            agent_id: agent_id.clone(),
+            globals: ZomeApiGlobals {
+                dna_name: String::from("mock_dna"),
+                dna_address: Address::from("QmMockDnaAddress"),
+                agent_id_str: JsonString::from(agent_id.clone()).to_string(),
+                agent_address: agent_address.clone(),
+                agent_initial_hash: agent_address.clone(),
+                agent_latest_hash: agent_address,
+                public_token: Address::from("QmMockPublicToken"),
+                cap_request: None,
+                properties: JsonString::empty_object(),
+            },
+            chain: Vec::new(),
+            dht: HashMap::new(),
+            links: Vec::new(),
+            call_handler: None,
+            send_handler: None,
+            sign_handler: None,
+            debug_messages: Vec::new(),
+            signals: Vec::new(),
+        };
+        host.commit(Entry::AgentId(agent_id), None);
+        host
+    }
+
+    pub fn globals(&self) -> &ZomeApiGlobals {
+        &self.globals
+    }
+
+    /// The source chain, oldest entry first.
+    pub fn chain(&self) -> &[(ChainHeader, Entry)] {
+        &self.chain
+    }
+
+    /// An entry as held by the fake DHT, with its CRUD status.
+    pub fn dht_entry(&self, address: &Address) -> Option<&EntryWithMeta> {
+        self.dht.get(address)
+    }
+
+    /// Puts an entry into the fake DHT without committing it, as if another agent had.
+    pub fn hold_entry(&mut self, entry: Entry) -> Address {
+        let address = entry.address();
+        self.dht.insert(
+            address.clone(),
+            EntryWithMeta {
+                entry,
+                crud_status: CrudStatus::Live,
+                maybe_link_update_delete: None,
+            },
+        );
+        address
+    }
+
+    pub fn debug_messages(&self) -> &[String] {
+        &self.debug_messages
+    }
+
+    pub fn signals(&self) -> &[EmitSignalArgs] {
+        &self.signals
+    }
+
+    /// Programs the responses to `hdk::call`. Without a handler, calls fail.
+    pub fn on_call<F>(&mut self, handler: F)
+    where
+        F: FnMut(&ZomeFnCallArgs) -> ZomeApiResult<JsonString> + 'static,
+    {
+        self.call_handler = Some(Box::new(handler));
+    }
+
+    /// Programs the responses to `hdk::send`. Without a handler, sending fails.
+    pub fn on_send<F>(&mut self, handler: F)
+    where
+        F: FnMut(&SendArgs) -> ZomeApiResult<String> + 'static,
+    {
+        self.send_handler = Some(Box::new(handler));
+    }
+
+    /// Programs the responses to `hdk::sign`.
+    /// Without a handler, the host returns a fake signature that `verify_signature` accepts.
+    pub fn on_sign<F>(&mut self, handler: F)
+    where
+        F: FnMut(&str) -> ZomeApiResult<String> + 'static,
+    {
+        self.sign_handler = Some(Box::new(handler));
+    }
+
+    fn commit(&mut self, entry: Entry, crud_link: Option<Address>) -> Address {
+        let address = entry.address();
+        let entry_type = entry.entry_type();
+        let previous = self.chain.last().map(|(header, _)| header.address());
+        let previous_same_type = self
+            .chain
+            .iter()
+            .rev()
+            .find(|(header, _)| *header.entry_type() == entry_type)
+            .map(|(header, _)| header.address());
+        let agent_address = self.agent_id.address();
+        let provenance = Provenance::new(
+            agent_address.clone(),
+            Signature::from(mock_signature(&agent_address, &address.to_string())),
+        );
+        let header = ChainHeader::new(
+            &entry_type,
+            &address,
+            &[provenance],
+            &previous,
+            &previous_same_type,
+            &crud_link,
+            &Iso8601::from(self.chain.len() as i64),
+        );
+        self.chain.push((header, entry.clone()));
+        if !self.dht.contains_key(&address) {
+            self.hold_entry(entry);
+        }
+        address
+    }
+
+    fn headers_for(&self, address: &Address) -> Vec<ChainHeader> {
+        self.chain
+            .iter()
+            .filter(|(header, _)| header.entry_address() == address)
+            .map(|(header, _)| header.clone())
+            .collect()
+    }
+
+    /// Follows updates to the latest version of an entry.
+    fn latest_address(&self, address: &Address) -> ZomeApiResult<Address> {
+        let mut address = address.clone();
+        loop {
+            let entry_with_meta = self.dht.get(&address).ok_or(ZomeApiError::HashNotFound)?;
+            match (
+                entry_with_meta.crud_status,
+                &entry_with_meta.maybe_link_update_delete,
+            ) {
+                (CrudStatus::Modified, Some(next)) => address = next.clone(),
+                _ => return Ok(address),
+            }
+        }
+    }
+
+    fn mark(&mut self, address: &Address, crud_status: CrudStatus, link: Address) {
+        if let Some(entry_with_meta) = self.dht.get_mut(address) {
+            entry_with_meta.crud_status = crud_status;
+            entry_with_meta.maybe_link_update_delete = Some(link);
+        }
+    }
+
+    fn get_entry(&self, args: &GetEntryArgs) -> GetEntryResult {
+        let kind = args.options.status_request.clone();
+        let mut result = GetEntryResult::new(kind.clone(), None);
+        let mut address = args.address.clone();
+        while let Some(entry_with_meta) = self.dht.get(&address) {
+            let headers = if args.options.headers {
+                self.headers_for(&address)
+            } else {
+                Vec::new()
+            };
+            result.push(entry_with_meta, headers);
+            match (
+                &kind,
+                entry_with_meta.crud_status,
+                &entry_with_meta.maybe_link_update_delete,
+            ) {
+                (StatusRequestKind::Initial, _, _) => break,
+                (_, CrudStatus::Modified, Some(next)) => address = next.clone(),
+                (StatusRequestKind::Latest, CrudStatus::Deleted, _) => {
+                    result.clear();
+                    break;
+                }
+                _ => break,
+            }
+        }
+        result
+    }
+
+    fn query(&self, args: &QueryArgs) -> QueryResult {
+        let names = match &args.entry_type_names {
+            QueryArgsNames::QueryName(name) => vec![name.clone()],
+            QueryArgsNames::QueryList(names) => names.clone(),
+        };
+        let limit = if args.options.limit == 0 {
+            usize::max_value()
+        } else {
+            args.options.limit
+        };
+        let matching = self
+            .chain
+            .iter()
+            .rev()
+            .filter(|(header, _)| {
+                names.is_empty()
+                    || names
+                        .iter()
+                        .any(|name| name == "*" || *name == header.entry_type().to_string())
+            })
+            .skip(args.options.start)
+            .take(limit)
+            .cloned();
+        match (args.options.headers, args.options.entries) {
+            (true, true) => QueryResult::HeadersWithEntries(matching.collect()),
+            (true, false) => QueryResult::Headers(matching.map(|(header, _)| header).collect()),
+            (false, true) => QueryResult::Entries(
+                matching
+                    .map(|(header, entry)| (header.entry_address().clone(), entry))
+                    .collect(),
+            ),
+            (false, false) => QueryResult::Addresses(
+                matching
+                    .map(|(header, _)| header.entry_address().clone())
+                    .collect(),
+            ),
+        }
+    }
+
+    fn link_entries(&mut self, args: &LinkEntriesArgs) -> Address {
+        let top_header = self.chain.last().map(|(header, _)| header.clone()).unwrap();
+        let link_add = LinkData::new_add(
+            &args.base,
+            &args.target,
+            &args.tag,
+            &args.link_type,
+            top_header,
+            self.agent_id.clone(),
+        );
+        let address = self.commit(Entry::LinkAdd(link_add), None);
+        self.links.push(MockLink {
+            link: args.to_link(),
+            link_add_address: address.clone(),
+            status: CrudStatus::Live,
+        });
+        address
+    }
+
+    fn remove_link(&mut self, args: &LinkEntriesArgs) {
+        let link = args.to_link();
+        let removed: Vec<Address> = self
+            .links
+            .iter_mut()
+            .filter(|mock_link| mock_link.link == link && mock_link.status == CrudStatus::Live)
+            .map(|mock_link| {
+                mock_link.status = CrudStatus::Deleted;
+                mock_link.link_add_address.clone()
+            })
+            .collect();
+        let top_header = self.chain.last().map(|(header, _)| header.clone()).unwrap();
+        let link_remove = LinkData::new_delete(
+            &args.base,
+            &args.target,
+            &args.tag,
+            &args.link_type,
+            top_header,
+            self.agent_id.clone(),
+        );
+        self.commit(Entry::LinkRemove((link_remove, removed)), None);
+    }
+
+    fn get_links(&self, args: &GetLinksArgs) -> ZomeApiResult<Vec<LinksResult>> {
+        let invalid = |_| ZomeApiError::Internal("Invalid link match regex".to_string());
+        let link_type = Regex::new(&args.link_type).map_err(invalid)?;
+        let tag = Regex::new(&args.tag).map_err(invalid)?;
+        Ok(self
+            .links
+            .iter()
+            .filter(|mock_link| {
+                mock_link.link.base() == &args.entry_address
+                    && link_type.is_match(mock_link.link.link_type())
+                    && tag.is_match(mock_link.link.tag())
+                    && match args.options.status_request {
+                        LinksStatusRequestKind::Live => mock_link.status == CrudStatus::Live,
+                        LinksStatusRequestKind::Deleted => mock_link.status == CrudStatus::Deleted,
+                        LinksStatusRequestKind::All => true,
+                    }
+            })
+            .map(|mock_link| LinksResult {
+                address: mock_link.link.target().clone(),
+                headers: if args.options.headers {
+                    self.headers_for(&mock_link.link_add_address)
+                } else {
+                    Vec::new()
+                },
+                tag: mock_link.link.tag().clone(),
+                status: mock_link.status,
+                warrants: Vec::new(),
+            })
+            .collect())
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        mock_signature(&self.agent_id.address(), payload)
+    }
+}
+
+/// Handles an API function the way the Ribosome would, with the mock host of the current thread.
+/// Handlers get taken out of the host while they run so they can use the API themselves.
+pub(crate) fn dispatch(function: &Dispatch, input: JsonString) -> ZomeApiResult<JsonString> {
+    match function {
+        Dispatch::Call => {
+            let args = ZomeFnCallArgs::try_from(input)?;
+            let mut handler = with_mock_host(|host| host.call_handler.take()).ok_or_else(|| {
+                ZomeApiError::Internal(format!(
+                    "Mock host has no call handler for {}/{}",
+                    args.zome_name, args.fn_name
+                ))
+            })?;
+            let result = handler(&args);
+            with_mock_host(|host| host.call_handler = Some(handler));
+            result
+        }
+        Dispatch::Send => {
+            let args = SendArgs::try_from(input)?;
+            let mut handler = with_mock_host(|host| host.send_handler.take()).ok_or_else(|| {
+                ZomeApiError::Internal(format!(
+                    "Mock host has no send handler for messages to {}",
+                    args.to_agent
+                ))
+            })?;
+            let result = handler(&args);
+            with_mock_host(|host| host.send_handler = Some(handler));
+            result.map(|response| JsonString::from_json(&response))
+        }
+        Dispatch::Crypto => {
+            let args = CryptoArgs::try_from(input)?;
+            match args.method {
+                CryptoMethod::Sign => match with_mock_host(|host| host.sign_handler.take()) {
+                    Some(mut handler) => {
+                        let result = handler(&args.payload);
+                        with_mock_host(|host| host.sign_handler = Some(handler));
+                        result
+                    }
+                    None => Ok(with_mock_host(|host| host.sign(&args.payload))),
+                }
+                .map(|signature| JsonString::from_json(&signature)),
+                CryptoMethod::Encrypt | CryptoMethod::Decrypt => {
+                    Err(ZomeApiError::FunctionNotImplemented)
+                }
+            }
+        }
+        _ => with_mock_host(|host| dispatch_to_host(host, function, input)),
+    }
+}
+
+fn dispatch_to_host(
+    host: &mut MockHost,
+    function: &Dispatch,
+    input: JsonString,
+) -> ZomeApiResult<JsonString> {
+    Ok(match function {
+        Dispatch::InitGlobals => JsonString::from(host.globals.clone()),
+        Dispatch::CommitEntry => {
+            let args = CommitEntryArgs::try_from(input)?;
+            JsonString::from(CommitEntryResult::new(host.commit(args.entry, None)))
+        }
+        Dispatch::CountersignEntry => {
+            let args = CountersignEntryArgs::try_from(input)?;
+            JsonString::from(CommitEntryResult::new(host.commit(args.entry, None)))
+        }
+        Dispatch::GetEntry => JsonString::from(host.get_entry(&GetEntryArgs::try_from(input)?)),
+        Dispatch::EntryAddress => JsonString::from(Entry::try_from(input)?.address()),
+        Dispatch::Query => JsonString::from(host.query(&QueryArgs::try_from(input)?)),
+        Dispatch::UpdateEntry => {
+            let args = UpdateEntryArgs::try_from(input)?;
+            let latest = host.latest_address(&args.address)?;
+            let address = host.commit(args.new_entry, Some(latest.clone()));
+            host.mark(&latest, CrudStatus::Modified, address.clone());
+            JsonString::from(address)
+        }
+        Dispatch::RemoveEntry => {
+            let latest = host.latest_address(&Address::try_from(input)?)?;
+            let deletion = Entry::Deletion(DeletionEntry::new(latest.clone()));
+            let address = host.commit(deletion, Some(latest.clone()));
+            host.mark(&latest, CrudStatus::Deleted, address.clone());
+            JsonString::from(address)
+        }
+        Dispatch::Debug => {
+            host.debug_messages.push(String::from(input));
+            JsonString::null()
+        }
+        Dispatch::VerifySignature => {
+            let args = VerifySignatureArgs::try_from(input)?;
+            let expected = mock_signature(&args.provenance.source(), &args.payload);
+            JsonString::from_json(
+                &(args.provenance.signature() == Signature::from(expected)).to_string(),
+            )
+        }
+        Dispatch::LinkEntries => {
+            JsonString::from(host.link_entries(&LinkEntriesArgs::try_from(input)?))
+        }
+        Dispatch::RemoveLink => {
+            host.remove_link(&LinkEntriesArgs::try_from(input)?);
+            JsonString::null()
+        }
+        Dispatch::GetLinks => JsonString::from(GetLinksResult::new(
+            host.get_links(&GetLinksArgs::try_from(input)?)?,
+        )),
+        Dispatch::GetLinksCount => JsonString::from(GetLinksResultCount {
+            count: host.get_links(&GetLinksArgs::try_from(input)?)?.len(),
+        }),
+        // Tests should not have to wait.
+        Dispatch::Sleep => JsonString::null(),
+        Dispatch::Meta => match MetaArgs::try_from(input)?.method {
+            MetaMethod::Version => {
+                JsonString::from(MetaResult::Version(env!("CARGO_PKG_VERSION").to_string()))
+            }
+            MetaMethod::Hash => return Err(ZomeApiError::FunctionNotImplemented),
+        },
+        Dispatch::CommitCapabilityGrant => {
+            let args = CommitCapabilityGrantArgs::try_from(input)?;
+            let grant =
+                CapTokenGrant::create(&args.id, args.cap_type, args.assignees, args.functions)?;
+            JsonString::from(host.commit(Entry::CapTokenGrant(grant), None))
+        }
+        Dispatch::CommitCapabilityClaim => {
+            let args = CommitCapabilityClaimArgs::try_from(input)?;
+            let claim = CapTokenClaim::new(args.id, args.grantor, args.token);
+            JsonString::from(host.commit(Entry::CapTokenClaim(claim), None))
+        }
+        Dispatch::EmitSignal => {
+            host.signals.push(EmitSignalArgs::try_from(input)?);
+            JsonString::null()
+        }
+        Dispatch::Call | Dispatch::Send | Dispatch::Crypto => unreachable!(),
+        Dispatch::SignOneTime
+        | Dispatch::KeystoreList
+        | Dispatch::KeystoreNewRandom
+        | Dispatch::KeystoreDeriveSeed
+        | Dispatch::KeystoreDeriveKey
+        | Dispatch::KeystoreSign
+        | Dispatch::KeystoreGetPublicKey => return Err(ZomeApiError::FunctionNotImplemented),
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use api;
+    use holochain_core_types::{entry::entry_type::AppEntryType, link::LinkMatch};
+
+    fn post(content: &str) -> Entry {
+        Entry::App(
+            AppEntryType::from("post"),
+            JsonString::from_json(&format!(r#"{{"content":"{}"}}"#, content)),
+        )
+    }
+
+    #[test]
+    fn mock_host_keeps_chain_and_dht() {
+        reset_mock_host();
+        let address = api::commit_entry(&post("hello")).unwrap();
+        assert_eq!(api::entry_address(&post("hello")).unwrap(), address);
+        assert_eq!(api::get_entry(&address).unwrap(), Some(post("hello")));
+
+        let updated = api::update_entry(post("hello again"), &address).unwrap();
+        assert_eq!(api::get_entry(&address).unwrap(), Some(post("hello again")));
+        assert_eq!(
+            api::get_entry_initial(&address).unwrap(),
+            Some(post("hello"))
+        );
+        assert_eq!(
+            api::query("post".into(), 0, 0).unwrap(),
+            vec![updated.clone(), address]
+        );
+
+        api::remove_entry(&updated).unwrap();
+        assert_eq!(api::get_entry(&updated).unwrap(), None);
+        with_mock_host(|host| {
+            assert_eq!(host.chain().len(), 5);
+            assert_eq!(
+                host.dht_entry(&updated).unwrap().crud_status,
+                CrudStatus::Deleted
+            );
+        });
+    }
+
+    #[test]
+    fn mock_host_links() {
+        reset_mock_host();
+        let base = api::commit_entry(&post("base")).unwrap();
+        let target = with_mock_host(|host| host.hold_entry(post("somebody else's")));
+        api::link_entries(&base, &target, "comments", "first").unwrap();
+
+        let links = api::get_links(&base, LinkMatch::Exactly("comments"), LinkMatch::Any).unwrap();
+        assert_eq!(links.addresses(), vec![target.clone()]);
+        assert_eq!(
+            api::get_links_and_load(&base, LinkMatch::Any, LinkMatch::Exactly("first"))
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.unwrap())
+                .collect::<Vec<_>>(),
+            vec![post("somebody else's")]
+        );
+
+        api::remove_link(&base, &target, "comments", "first").unwrap();
+        assert_eq!(
+            api::get_links_count(&base, LinkMatch::Any, LinkMatch::Any)
+                .unwrap()
+                .count,
+            0
+        );
+    }
+
+    #[test]
+    fn mock_host_programmable_responses() {
+        reset_mock_host();
+        assert!(api::call(
+            "instance",
+            "zome",
+            Address::from("token"),
+            "fn",
+            "{}".into()
+        )
+        .is_err());
+
+        with_mock_host(|host| {
+            host.on_call(|args| Ok(JsonString::from_json(&format!("\"{}\"", args.fn_name))));
+            host.on_send(|args| Ok(format!("pong: {}", args.payload)));
+        });
+        assert_eq!(
+            api::call(
+                "instance",
+                "zome",
+                Address::from("token"),
+                "ping",
+                "{}".into()
+            )
+            .unwrap(),
+            JsonString::from_json("\"ping\"")
+        );
+        assert_eq!(
+            api::send(Address::from("bob"), "ping".to_string(), Default::default()).unwrap(),
+            "pong: ping"
+        );
+
+        let signature = api::sign("payload").unwrap();
+        let provenance = Provenance::new(
+            with_mock_host(|host| host.globals().agent_address.clone()),
+            Signature::from(signature),
+        );
+        assert!(api::verify_signature(provenance.clone(), "payload").unwrap());
+        assert!(!api::verify_signature(provenance, "other payload").unwrap());
+
+        with_mock_host(|host| host.on_sign(|_| Ok("programmed".to_string())));
+        assert_eq!(api::sign("payload").unwrap(), "programmed");
+    }
+}
@@ -209,6 +209,11 @@ pub fn hc_emit_signal(_: RibosomeEncodingBits) -> RibosomeEncodingBits {
     RibosomeEncodedValue::Success.into()
 }
 
+#[no_mangle]
+pub fn hc_countersign_entry(_: RibosomeEncodingBits) -> RibosomeEncodingBits {
+    RibosomeEncodedValue::Success.into()
+}
+
 #[test]
 fn can_use_globals() {
     let (mut hc, _, _) = start_holochain_instance("can_use_globals", "alice");
@@ -369,12 +374,14 @@ fn can_roundtrip_links() {
             headers: Vec::new(),
             tag: "test-tag".into(),
             status: CrudStatus::Live,
+            warrants: Vec::new(),
         },
         LinksResult {
             address: entry_address_3.clone(),
             headers: Vec::new(),
             tag: "test-tag".into(),
             status: CrudStatus::Live,
+            warrants: Vec::new(),
         },
     ]));
     let expected_links = JsonString::from(expected_links);
@@ -389,12 +396,14 @@ fn can_roundtrip_links() {
                 headers: Vec::new(),
                 tag: "test-tag".into(),
                 status: CrudStatus::Live,
+                warrants: Vec::new(),
             },
             LinksResult {
                 address: entry_address_2.clone(),
                 headers: Vec::new(),
                 tag: "test-tag".into(),
                 status: CrudStatus::Live,
+                warrants: Vec::new(),
             },
         ]));
     let expected_links_reversed = JsonString::from(expected_links_reversed);
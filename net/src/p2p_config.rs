@@ -1,3 +1,4 @@
+use holochain_core_types::dna::dht::DhtConfig;
 use holochain_json_api::{error::JsonError, json::JsonString};
 use lib3h::engine::RealEngineConfig;
 use snowflake;
@@ -177,6 +178,49 @@ impl P2pConfig {
     }
 }
 
+/// DNA specific settings
+impl P2pConfig {
+    /// Applies the DHT parameters a DNA sets to the backend config. Parameters the DNA
+    /// doesn't set are left at the backend's own configuration.
+    pub fn with_dht_config(mut self, dht_config: &DhtConfig) -> Self {
+        match self.backend_config {
+            BackendConfig::Lib3h(ref mut config) => {
+                if let Some(interval) = dht_config.gossip_interval_ms {
+                    config.dht_gossip_interval = interval;
+                }
+                if let Some(threshold) = dht_config.gossip_timeout_threshold_ms {
+                    config.dht_timeout_threshold = threshold;
+                }
+                if let Some(redundancy) = dht_config.redundancy {
+                    // Keep whatever else is configured for the DHT
+                    let existing = serde_json::from_slice(&config.dht_custom_config);
+                    let mut custom_config = match existing {
+                        Ok(serde_json::Value::Object(custom_config)) => custom_config,
+                        _ => serde_json::Map::new(),
+                    };
+                    custom_config.insert("redundancy".into(), json!(redundancy));
+                    config.dht_custom_config = serde_json::Value::Object(custom_config)
+                        .to_string()
+                        .into_bytes();
+                }
+            }
+            BackendConfig::Json(serde_json::Value::Object(ref mut config)) => {
+                if let Some(interval) = dht_config.gossip_interval_ms {
+                    config.insert("dhtGossipInterval".into(), json!(interval));
+                }
+                if let Some(threshold) = dht_config.gossip_timeout_threshold_ms {
+                    config.insert("dhtTimeoutThreshold".into(), json!(threshold));
+                }
+                if let Some(redundancy) = dht_config.redundancy {
+                    config.insert("dhtRedundancy".into(), json!(redundancy));
+                }
+            }
+            BackendConfig::Json(_) => (),
+        }
+        self
+    }
+}
+
 /// end_user config
 impl P2pConfig {
     pub fn default_end_user_config() -> serde_json::Value {
@@ -268,6 +312,62 @@ mod tests {
         assert_eq!(p2p_config, P2pConfig::new_with_memory_backend(server_name));
     }
 
+    #[test]
+    fn it_applies_dht_config() {
+        let dht_config = DhtConfig {
+            redundancy: Some(3),
+            gossip_interval_ms: Some(1000),
+            ..Default::default()
+        };
+        let p2p_config = P2pConfig::default_ipc_uri(None).with_dht_config(&dht_config);
+        match p2p_config.backend_config {
+            BackendConfig::Json(config) => {
+                assert_eq!(config["dhtRedundancy"], json!(3));
+                assert_eq!(config["dhtGossipInterval"], json!(1000));
+                assert!(config.get("dhtTimeoutThreshold").is_none());
+            }
+            _ => panic!("Expected a JSON backend config"),
+        }
+
+        let p2p_config = P2pConfig::new_with_memory_backend("dht_test");
+        assert_eq!(
+            p2p_config.clone().with_dht_config(&DhtConfig::default()),
+            p2p_config
+        );
+    }
+
+    #[test]
+    fn it_merges_redundancy_into_the_lib3h_dht_config() {
+        let p2p_config = P2pConfig {
+            backend_kind: P2pBackendKind::LIB3H,
+            backend_config: BackendConfig::Lib3h(RealEngineConfig {
+                socket_type: "ws".into(),
+                tls_config: lib3h::transport_wss::TlsConfig::Unencrypted,
+                bootstrap_nodes: vec![],
+                work_dir: String::new(),
+                log_level: 'd',
+                bind_url: url::Url::parse("fixme://bind_url").unwrap(),
+                dht_custom_config: json!({ "neighbors": 8 }).to_string().into_bytes(),
+                dht_gossip_interval: 500,
+                dht_timeout_threshold: 3000,
+            }),
+            maybe_end_user_config: None,
+        };
+        let dht_config = DhtConfig {
+            redundancy: Some(3),
+            ..Default::default()
+        };
+        match p2p_config.with_dht_config(&dht_config).backend_config {
+            BackendConfig::Lib3h(config) => {
+                let custom_config: serde_json::Value =
+                    serde_json::from_slice(&config.dht_custom_config).unwrap();
+                assert_eq!(custom_config, json!({ "neighbors": 8, "redundancy": 3 }));
+                assert_eq!(config.dht_gossip_interval, 500);
+            }
+            _ => panic!("Expected a lib3h backend config"),
+        }
+    }
+
     #[test]
     fn it_should_fail_bad_backend_kind() {
         let res = P2pConfig::from_str(
@@ -9,6 +9,7 @@ extern crate serde_derive;
 extern crate holochain_json_derive;
 
 pub mod mock_signing;
+pub mod scenario;
 
 use crossbeam_channel::Receiver;
 use holochain_conductor_api::{context_builder::ContextBuilder, error::HolochainResult, Holochain};
@@ -496,8 +497,17 @@ pub fn start_holochain_instance<T: Into<String>>(
         entry_types.insert(EntryType::from("link_validator"), link_validator);
     }
 
+    start_holochain_instance_with_dna(dna, &agent_name.into())
+}
+
+/// Starts an instance of the given DNA for the given agent on the in-memory network
+/// that is named after the DNA's uuid, so instances of the same DNA can see each other.
+pub fn start_holochain_instance_with_dna(
+    dna: Dna,
+    agent_name: &str,
+) -> (Holochain, Arc<Mutex<TestLogger>>,SignalReceiver) {
     let (context, test_logger,signal_recieve) =
-        test_context_and_logger_with_network_name_and_signal(&agent_name.into(), Some(&dna.uuid));
+        test_context_and_logger_with_network_name_and_signal(agent_name, Some(&dna.uuid));
     let mut hc =
         Holochain::new(dna.clone(), context).expect("could not create new Holochain instance.");
 
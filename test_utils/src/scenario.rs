@@ -0,0 +1,213 @@
+//! Multi-agent test scenarios written in plain Rust.
+//!
+//! A [Scenario] starts one instance of a DNA per agent, all connected through the same
+//! in-memory network, lets tests call zome functions as any of these agents and waits
+//! for the network to settle by following the consistency signals the instances emit.
+//!
+//! ```rust,ignore
+//! let mut scenario = Scenario::from_env("my_scenario")?.with_agents(&["alice", "bob"]);
+//! let address = scenario.agent("alice").call("blog", "create_post", r#"{"content":"hi"}"#)?;
+//! scenario.await_consistency()?;
+//! let post: Post = scenario
+//!     .agent("bob")
+//!     .call_result("blog", "get_post", &format!(r#"{{"address":{}}}"#, address))?;
+//! ```
+//!
+//! `hc test --rust` runs `cargo test` in the app's test directory and sets
+//! `HC_SCENARIO_DNA_PATH` to the freshly packaged DNA, which [Scenario::from_env] reads.
+
+use crate::start_holochain_instance_with_dna;
+use hdk::error::ZomeApiResult;
+use holochain_common::env_vars::EnvVar;
+use holochain_conductor_api::{error::HolochainResult, Holochain};
+use holochain_core::{
+    consistency::{await_consistency_with, ConsistencyTracker, DEFAULT_CONSISTENCY_TIMEOUT},
+    logger::TestLogger,
+    nucleus::actions::call_zome_function::make_cap_request_for_call,
+    signal::{Signal, SignalReceiver},
+};
+use holochain_core_types::{dna::Dna, error::HolochainError};
+use holochain_json_api::json::JsonString;
+use holochain_persistence_api::cas::content::{Address, AddressableContent};
+use serde::de::DeserializeOwned;
+use std::{
+    convert::TryFrom,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// One agent of a scenario, running its own instance of the scenario's DNA.
+pub struct ScenarioAgent {
+    name: String,
+    holochain: Holochain,
+    logger: Arc<Mutex<TestLogger>>,
+    signals: SignalReceiver,
+}
+
+impl ScenarioAgent {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn holochain(&mut self) -> &mut Holochain {
+        &mut self.holochain
+    }
+
+    pub fn logger(&self) -> Arc<Mutex<TestLogger>> {
+        self.logger.clone()
+    }
+
+    /// The agent's address on the network.
+    pub fn address(&self) -> HolochainResult<Address> {
+        Ok(self.holochain.context()?.agent_id.address())
+    }
+
+    /// Calls a public zome function as this agent and returns its raw result.
+    pub fn call(&mut self, zome: &str, fn_name: &str, params: &str) -> HolochainResult<JsonString> {
+        let cap_request = {
+            let context = self.holochain.context()?;
+            let token = context.get_public_token().map_err(|_| {
+                HolochainError::ErrorGeneric(format!(
+                    "Agent {} has no public capability token",
+                    self.name
+                ))
+            })?;
+            make_cap_request_for_call(
+                context.clone(),
+                token,
+                fn_name,
+                JsonString::from_json(params),
+            )
+        };
+        self.holochain.call(zome, cap_request, fn_name, params)
+    }
+
+    /// Like `call` but parses the result as the `ZomeApiResult<T>` the zome function returned.
+    pub fn call_result<T: DeserializeOwned>(
+        &mut self,
+        zome: &str,
+        fn_name: &str,
+        params: &str,
+    ) -> HolochainResult<ZomeApiResult<T>> {
+        let result = self.call(zome, fn_name, params)?;
+        Ok(serde_json::from_str(&String::from(result)).map_err(HolochainError::from)?)
+    }
+}
+
+/// A set of agents running the same DNA on a shared in-memory network.
+pub struct Scenario {
+    dna: Dna,
+    agents: Vec<ScenarioAgent>,
+    tracker: Arc<Mutex<ConsistencyTracker>>,
+}
+
+impl Scenario {
+    /// Creates a scenario for the given DNA.
+    /// The DNA's uuid gets set to `name`, which also names the in-memory network,
+    /// so that scenarios running in parallel don't see each other.
+    pub fn new(name: &str, mut dna: Dna) -> Self {
+        dna.uuid = name.to_string();
+        Scenario {
+            dna,
+            agents: Vec::new(),
+            tracker: Arc::new(Mutex::new(ConsistencyTracker::new())),
+        }
+    }
+
+    /// Creates a scenario for the DNA in the given .dna.json file.
+    pub fn from_dna_file<P: AsRef<Path>>(name: &str, path: P) -> HolochainResult<Self> {
+        let contents = fs::read_to_string(path).map_err(HolochainError::from)?;
+        let dna = Dna::try_from(JsonString::from_json(&contents)).map_err(HolochainError::from)?;
+        Ok(Scenario::new(name, dna))
+    }
+
+    /// Creates a scenario for the DNA `hc test --rust` packaged.
+    pub fn from_env(name: &str) -> HolochainResult<Self> {
+        let path = EnvVar::ScenarioDnaPath.value().map_err(|_| {
+            HolochainError::ErrorGeneric(format!(
+                "{} is not set, run scenarios with `hc test --rust`",
+                EnvVar::ScenarioDnaPath.as_str()
+            ))
+        })?;
+        Scenario::from_dna_file(name, path)
+    }
+
+    pub fn dna(&self) -> &Dna {
+        &self.dna
+    }
+
+    /// Starts an instance for each of the given agents.
+    pub fn with_agents(mut self, names: &[&str]) -> Self {
+        for name in names {
+            self.add_agent(name);
+        }
+        self
+    }
+
+    /// Starts an instance for one more agent.
+    /// Panics if an agent with that name is already part of the scenario.
+    pub fn add_agent(&mut self, name: &str) -> &mut ScenarioAgent {
+        assert!(
+            self.agents.iter().all(|agent| agent.name != name),
+            "Agent {} is already part of the scenario",
+            name
+        );
+        let (holochain, logger, signals) =
+            start_holochain_instance_with_dna(self.dna.clone(), name);
+        self.agents.push(ScenarioAgent {
+            name: name.to_string(),
+            holochain,
+            logger,
+            signals,
+        });
+        self.agents.last_mut().unwrap()
+    }
+
+    /// Panics if there is no agent with the given name, as a test would.
+    pub fn agent(&mut self, name: &str) -> &mut ScenarioAgent {
+        self.agents
+            .iter_mut()
+            .find(|agent| agent.name == name)
+            .unwrap_or_else(|| panic!("Agent {} is not part of the scenario", name))
+    }
+
+    pub fn agents(&mut self) -> impl Iterator<Item = &mut ScenarioAgent> {
+        self.agents.iter_mut()
+    }
+
+    /// Feeds all signals the agents emitted so far into the tracker.
+    fn drain_signals(agents: &[ScenarioAgent], tracker: &Mutex<ConsistencyTracker>) {
+        for agent in agents.iter() {
+            while let Ok(signal) = agent.signals.try_recv() {
+                if let Signal::Consistency(signal) = signal {
+                    tracker.lock().unwrap().observe(&agent.name, &signal);
+                }
+            }
+        }
+    }
+
+    /// Blocks until every event the agents' actions caused has been observed,
+    /// or fails after `DEFAULT_CONSISTENCY_TIMEOUT`.
+    pub fn await_consistency(&mut self) -> Result<(), String> {
+        self.await_consistency_with_timeout(DEFAULT_CONSISTENCY_TIMEOUT)
+    }
+
+    pub fn await_consistency_with_timeout(&mut self, timeout: Duration) -> Result<(), String> {
+        let agents = &self.agents;
+        let tracker = &self.tracker;
+        await_consistency_with(
+            || Self::drain_signals(agents, tracker),
+            tracker,
+            None,
+            timeout,
+        )
+        .map_err(|pending| {
+            format!(
+                "Scenario did not become consistent within {:?}, still waiting for: {:?}",
+                timeout, pending
+            )
+        })
+    }
+}
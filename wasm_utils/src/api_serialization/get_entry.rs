@@ -80,6 +80,9 @@ pub struct GetEntryResultItem {
     pub meta: Option<EntryResultMeta>,
     pub entry: Option<Entry>,
     pub headers: Vec<ChainHeader>, // headers if requested in options
+    /// Addresses of warrants against agents that signed this entry's headers
+    #[serde(default)]
+    pub warrants: Vec<Address>,
 }
 impl GetEntryResultItem {
     pub fn new(maybe_entry_with_meta: Option<(&EntryWithMeta, Vec<ChainHeader>)>) -> Self {
@@ -92,11 +95,13 @@ impl GetEntryResultItem {
                 }),
                 entry: Some(entry_with_meta.entry.clone()),
                 headers,
+                warrants: Vec::new(),
             },
             _ => GetEntryResultItem {
                 meta: None,
                 entry: None,
                 headers: Vec::new(),
+                warrants: Vec::new(),
             },
         }
     }
@@ -187,6 +192,17 @@ impl GetEntryResult {
         };
     }
 
+    /// attaches the given warrant addresses to the most recently pushed item
+    pub fn add_warrants(&mut self, warrants: Vec<Address>) {
+        let maybe_item = match self.result {
+            GetEntryResultType::Single(ref mut item) => Some(item),
+            GetEntryResultType::All(ref mut history) => history.items.last_mut(),
+        };
+        if let Some(item) = maybe_item {
+            item.warrants.extend(warrants);
+        }
+    }
+
     /// returns the entry searched for.  Note that if the GetEntryOptions did not
     /// include a request for the entry value, this function will return None even if the
     /// entry was found.
@@ -259,6 +275,29 @@ mod tests {
         assert_eq!(result.latest(), Some(test_entry_b()));
     }
 
+    #[test]
+    fn test_get_entry_result_add_warrants() {
+        let mut result = GetEntryResult::new(StatusRequestKind::All, None);
+        for entry in vec![test_entry_a(), test_entry_b()] {
+            result.push(
+                &EntryWithMeta {
+                    entry,
+                    crud_status: CrudStatus::Live,
+                    maybe_link_update_delete: None,
+                },
+                vec![test_chain_header()],
+            );
+        }
+        result.add_warrants(vec![Address::from("warrant")]);
+        match result.result {
+            GetEntryResultType::All(history) => {
+                assert!(history.items[0].warrants.is_empty());
+                assert_eq!(history.items[1].warrants, vec![Address::from("warrant")]);
+            }
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn test_clear() {
         let mut result = GetEntryResult::new(StatusRequestKind::All, None);
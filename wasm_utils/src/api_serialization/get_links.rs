@@ -44,6 +44,9 @@ pub struct LinksResult {
     pub headers: Vec<ChainHeader>,
     pub tag: String,
     pub status: CrudStatus,
+    /// Addresses of warrants against the agent that created the link
+    #[serde(default)]
+    pub warrants: Vec<Address>,
 }
 
 #[derive(Deserialize, Clone, Serialize, Debug, DefaultJson)]
@@ -11,6 +11,7 @@ mod call;
 /// importing this module.
 pub mod capabilities;
 pub mod commit_entry;
+pub mod countersign_entry;
 pub mod crypto;
 pub mod emit_signal;
 pub mod get_entry;